@@ -0,0 +1,75 @@
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use lean_search::section3::{
+    beam_search_action, beam_search_with_nodes, bucket_beam_search_action,
+    bucket_beam_search_with_nodes, chokudai_search_action, chokudai_search_with_nodes,
+    greedy_action, MazeState, END_TURN,
+};
+
+// Throughput is per seed: greedy counts the moves it tries, and the searches the states they
+// really expand there, counted by one run before the measurement. Criterion divides them by
+// the time it measures, so it reports nodes per second.
+const SEEDS: [u64; 3] = [0, 1, 2];
+
+fn bench_greedy(c: &mut Criterion) {
+    let mut group = c.benchmark_group("greedy");
+    for seed in SEEDS {
+        let state = MazeState::with_seed(seed);
+        group.throughput(Throughput::Elements(state.legal_actions().len() as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(seed), &state, |b, state| {
+            b.iter(|| greedy_action(black_box(state)))
+        });
+    }
+    group.finish();
+}
+
+fn bench_beam_search(c: &mut Criterion) {
+    let (beam_width, beam_depth) = (5, END_TURN);
+    let mut group = c.benchmark_group("beam_search");
+    for seed in SEEDS {
+        let state = MazeState::with_seed(seed);
+        let (_, count) = beam_search_with_nodes(&state, beam_width, beam_depth);
+        group.throughput(Throughput::Elements(count.expanded as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(seed), &state, |b, state| {
+            b.iter(|| beam_search_action(black_box(state), beam_width, beam_depth))
+        });
+    }
+    group.finish();
+}
+
+// Same search with the beams in a bucket queue, for comparison with `beam_search`.
+fn bench_bucket_beam_search(c: &mut Criterion) {
+    let (beam_width, beam_depth) = (5, END_TURN);
+    let mut group = c.benchmark_group("bucket_beam_search");
+    for seed in SEEDS {
+        let state = MazeState::with_seed(seed);
+        let (_, count) = bucket_beam_search_with_nodes(&state, beam_width, beam_depth);
+        group.throughput(Throughput::Elements(count.expanded as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(seed), &state, |b, state| {
+            b.iter(|| bucket_beam_search_action(black_box(state), beam_width, beam_depth))
+        });
+    }
+    group.finish();
+}
+
+fn bench_chokudai_search(c: &mut Criterion) {
+    let (beam_width, beam_depth, beam_number) = (1, END_TURN as usize, 2);
+    let mut group = c.benchmark_group("chokudai_search");
+    for seed in SEEDS {
+        let state = MazeState::with_seed(seed);
+        let (_, count) = chokudai_search_with_nodes(&state, beam_width, beam_depth, beam_number);
+        group.throughput(Throughput::Elements(count.expanded as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(seed), &state, |b, state| {
+            b.iter(|| chokudai_search_action(black_box(state), beam_width, beam_depth, beam_number))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    bench_greedy,
+    bench_beam_search,
+    bench_bucket_beam_search,
+    bench_chokudai_search
+);
+criterion_main!(benches);
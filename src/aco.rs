@@ -0,0 +1,267 @@
+#![allow(unused)]
+
+use std::collections::HashMap;
+
+use rand::{distributions::WeightedIndex, prelude::Distribution, Rng};
+
+use crate::game::{Action, Outcome, ScoreType, SinglePlayerState};
+use crate::section3::MazeState;
+use crate::tsp::TspState;
+
+// Ant colony optimization over the lines of play of a single player game. Every ant walks
+// `depth` turns from the start, picking each move with probability proportional to
+// pheromone^alpha * (points it collects + 1)^beta; the pheromone sits on (node, move) edges,
+// where the node is whatever the caller says identifies a position, such as the cell of the
+// character or the city the salesman is in. After each iteration the pheromone evaporates by `evaporation` and the best ant of
+// the iteration and the best so far lay `score / best score` on every edge they used. Levels
+// never fall below `initial`, as in the MAX-MIN ant system, so no move is ever ruled out.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AcoConfig {
+    pub ants: usize,
+    pub iterations: usize,
+    pub depth: usize,
+    pub alpha: f64,
+    pub beta: f64,
+    pub evaporation: f64,
+    pub initial: f64,
+}
+
+impl Default for AcoConfig {
+    fn default() -> Self {
+        AcoConfig {
+            ants: 20,
+            iterations: 20,
+            depth: usize::MAX,
+            alpha: 1.0,
+            beta: 2.0,
+            evaporation: 0.1,
+            initial: 0.1,
+        }
+    }
+}
+
+// The score a line reaches and its moves.
+type Tour = (ScoreType, Vec<Action>);
+
+struct Colony {
+    pheromone: HashMap<(u64, Action), f64>,
+    initial: f64,
+}
+
+impl Colony {
+    fn level(&self, node: u64, action: Action) -> f64 {
+        self.pheromone
+            .get(&(node, action))
+            .copied()
+            .unwrap_or(self.initial)
+    }
+
+    fn evaporate(&mut self, evaporation: f64) {
+        for level in self.pheromone.values_mut() {
+            *level = (*level * (1.0 - evaporation)).max(self.initial);
+        }
+    }
+
+    fn deposit<S: SinglePlayerState>(
+        &mut self,
+        state: &S,
+        actions: &[Action],
+        amount: f64,
+        node: &impl Fn(&S) -> u64,
+    ) {
+        let mut state = state.clone();
+        for &action in actions {
+            *self
+                .pheromone
+                .entry((node(&state), action))
+                .or_insert(self.initial) += amount;
+            state.advance(action);
+        }
+    }
+}
+
+fn walk<S: SinglePlayerState>(
+    state: &S,
+    colony: &Colony,
+    config: &AcoConfig,
+    node: &impl Fn(&S) -> u64,
+    rng: &mut impl Rng,
+) -> Tour {
+    let mut state = state.clone();
+    let mut actions = vec![];
+    while actions.len() < config.depth && !state.is_done() {
+        let legal_actions = state.legal_actions();
+        if legal_actions.is_empty() {
+            break;
+        }
+        let here = node(&state);
+        let weights: Vec<f64> = legal_actions
+            .iter()
+            .map(|&action| {
+                let mut next_state = state.clone();
+                next_state.advance(action);
+                let gain = (next_state.score() - state.score()).max(0) as f64;
+                colony.level(here, action).powf(config.alpha) * (gain + 1.0).powf(config.beta)
+            })
+            .collect();
+        let action = match WeightedIndex::new(&weights) {
+            Ok(index) => legal_actions[index.sample(rng)],
+            Err(_) => legal_actions[rng.gen_range(0..legal_actions.len())],
+        };
+        state.advance(action);
+        actions.push(action);
+    }
+    (state.score(), actions)
+}
+
+// The best line the colony found, or the empty line if `state` is already over.
+pub fn ant_colony_plan_with<S: SinglePlayerState>(
+    state: &S,
+    config: &AcoConfig,
+    node: impl Fn(&S) -> u64,
+    rng: &mut impl Rng,
+) -> Tour {
+    let mut colony = Colony {
+        pheromone: HashMap::new(),
+        initial: config.initial,
+    };
+    let mut best: Tour = (state.score(), vec![]);
+    for _ in 0..config.iterations {
+        let Some(iteration_best) = (0..config.ants.max(1))
+            .map(|_| walk(state, &colony, config, &node, rng))
+            .max_by_key(|tour| tour.0)
+        else {
+            break;
+        };
+        if best.1.is_empty() || best.0 < iteration_best.0 {
+            best = iteration_best.clone();
+        }
+        colony.evaporate(config.evaporation);
+        let scale = best.0.max(1) as f64;
+        for tour in [&iteration_best, &best] {
+            colony.deposit(state, &tour.1, tour.0.max(0) as f64 / scale, &node);
+        }
+    }
+    best
+}
+
+// Plays the first move of the best line from the position of the character.
+pub fn ant_colony_action_with(
+    state: &MazeState,
+    config: &AcoConfig,
+    rng: &mut impl Rng,
+) -> Outcome {
+    let (_, actions) = ant_colony_plan_with(state, config, maze_node, rng);
+    actions.first().copied().into()
+}
+
+pub fn ant_colony_action(state: &MazeState, config: &AcoConfig) -> Outcome {
+    ant_colony_action_with(state, config, &mut rand::thread_rng())
+}
+
+// Cells as nodes: a move from a cell is reinforced whenever it was good there, whatever the
+// turn.
+pub fn maze_node(state: &MazeState) -> u64 {
+    ((state.character.y as u64) << 32) | state.character.x as u64
+}
+
+// Cities as nodes, so the pheromone sits on the legs between them as in the classic ant system.
+pub fn tsp_node(state: &TspState) -> u64 {
+    state.current() as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use super::*;
+    use crate::section3::{greedy_action, END_TURN};
+    use crate::tsp::CITY_NUMBER;
+
+    #[test]
+    fn test_ant_colony_plan() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let config = AcoConfig::default();
+        let (mut greedy, mut colony) = (0.0, 0.0);
+        for seed in 0..10 {
+            let state = MazeState::with_seed(seed);
+            let (score, actions) = ant_colony_plan_with(&state, &config, maze_node, &mut rng);
+            assert_eq!(actions.len(), END_TURN as usize);
+            // The plan scores what it promised when played.
+            let mut played = state;
+            for action in actions {
+                played.advance(action);
+            }
+            assert_eq!(played.game_score, score);
+            colony += score as f64;
+            let mut state = state;
+            while let Outcome::Action(action) = greedy_action(&state) {
+                state.advance(action);
+            }
+            greedy += state.game_score as f64;
+        }
+        println!(
+            "Greedy Score:\t{}\tAnt Colony Score:\t{}",
+            greedy / 10.0,
+            colony / 10.0
+        );
+        assert!(greedy < colony);
+    }
+
+    #[test]
+    fn test_ant_colony_tsp() {
+        let mut rng = StdRng::seed_from_u64(0);
+        // The legs score `MAX_LEG - length`, a narrower spread than the usual 1 / length, so
+        // the colony leans on it harder.
+        let config = AcoConfig {
+            beta: 12.0,
+            ..AcoConfig::default()
+        };
+        let (mut nearest, mut colony) = (0, 0);
+        for seed in 0..5 {
+            let state = TspState::with_seed(seed);
+            let (score, actions) = ant_colony_plan_with(&state, &config, tsp_node, &mut rng);
+            assert_eq!(actions.len(), CITY_NUMBER - 1);
+            let mut tour = state.clone();
+            for action in actions {
+                tour.advance(action);
+            }
+            assert_eq!(tour.score(), score);
+            colony += tour.length();
+            // Nearest neighbour: always the closest city not visited yet.
+            let mut tour = state;
+            while !tour.is_done() {
+                let here = tour.current();
+                let next = tour.legal_actions().into_iter();
+                let next = next.min_by_key(|&city| tour.distance(here, city)).unwrap();
+                tour.advance(next);
+            }
+            nearest += tour.length();
+        }
+        println!(
+            "Nearest Neighbour Length:\t{}\tAnt Colony Length:\t{}",
+            nearest, colony
+        );
+        assert!(colony < nearest);
+    }
+
+    #[test]
+    fn test_ant_colony_action() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let config = AcoConfig {
+            ants: 5,
+            iterations: 5,
+            depth: 10,
+            ..Default::default()
+        };
+        let mut state = MazeState::with_seed(0);
+        while !state.is_done() {
+            let legal_actions = state.legal_actions();
+            let action = ant_colony_action_with(&state, &config, &mut rng).unwrap();
+            assert!(legal_actions.contains(&action));
+            state.advance(action);
+        }
+        println!("Ant Colony Action Score:\t{}", state.game_score);
+        assert!(0 < state.game_score);
+    }
+}
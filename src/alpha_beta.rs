@@ -0,0 +1,599 @@
+#![allow(unused)]
+
+use alloc::{collections::BTreeMap, string::String, vec, vec::Vec};
+use core::{cmp::Reverse, fmt::Write, time::Duration};
+
+use crate::game::{
+    Action, ActionList, Clock, NodeCount, Outcome, ScoreType, TwoPlayerState, WinningStatus,
+};
+
+// Value of a won game, beyond every evaluation.
+pub const WIN_SCORE: ScoreType = 1_000_000_000;
+
+// Seen from the side to move, like everything else about two-player states.
+pub type Evaluation<'a, S> = dyn Fn(&S) -> ScoreType + 'a;
+
+#[derive(Debug, Clone, Default)]
+pub struct AlphaBetaResult {
+    pub value: ScoreType,
+    // The line both sides play if the evaluation is right, starting with the move to play.
+    pub pv: Vec<Action>,
+    // Deepest search that finished.
+    pub depth: usize,
+    pub count: NodeCount,
+}
+
+impl AlphaBetaResult {
+    pub fn action(&self) -> Outcome {
+        self.pv.first().copied().into()
+    }
+
+    // The evaluation and every position along the principal variation, for debugging what the
+    // search expects to happen.
+    pub fn render<S: TwoPlayerState + core::fmt::Display>(&self, state: &S) -> String {
+        let mut text = String::new();
+        let _ = writeln!(text, "{}", self);
+        let _ = writeln!(text, "{}", state);
+        let mut state = state.clone();
+        for (ply, &action) in self.pv.iter().enumerate() {
+            state.advance(action);
+            let _ = writeln!(text, "ply {}: action {}", ply + 1, action);
+            let _ = writeln!(text, "{}", state);
+        }
+        text
+    }
+}
+
+impl core::fmt::Display for AlphaBetaResult {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "depth: {}\tvalue: {}\tpv:", self.depth, self.value)?;
+        for action in self.pv.iter() {
+            write!(f, " {}", action)?;
+        }
+        Ok(())
+    }
+}
+
+// Which moves the search tries first, besides the last iteration's best move at the root.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MoveOrdering {
+    // Up to two moves per ply that recently caused a cutoff at that ply.
+    pub killers: bool,
+    // Moves that caused cutoffs anywhere, weighted by the square of the remaining depth.
+    pub history: bool,
+}
+
+impl Default for MoveOrdering {
+    fn default() -> Self {
+        MoveOrdering {
+            killers: true,
+            history: true,
+        }
+    }
+}
+
+struct Searcher<'e, S> {
+    evaluate: &'e Evaluation<'e, S>,
+    ordering: MoveOrdering,
+    // Tried first at the root: the best move of the last iteration, where it prunes the most.
+    root_hint: Option<Action>,
+    killers: Vec<[Option<Action>; 2]>,
+    history: Vec<u64>,
+    // The best move found for every position `key` has seen, tried first when it comes back.
+    key: Option<&'e dyn Fn(&S) -> u64>,
+    table: BTreeMap<u64, Action>,
+    quiescence: usize,
+    // Unquiet states at the horizon are searched on up to this ply.
+    max_ply: usize,
+    count: NodeCount,
+}
+
+impl<'e, S: TwoPlayerState> Searcher<'e, S> {
+    fn new(
+        evaluate: &'e Evaluation<'e, S>,
+        config: &AlphaBetaConfig,
+        key: Option<&'e dyn Fn(&S) -> u64>,
+    ) -> Self {
+        Searcher {
+            evaluate,
+            ordering: config.ordering,
+            root_hint: None,
+            killers: vec![],
+            history: vec![],
+            key,
+            table: BTreeMap::new(),
+            quiescence: config.quiescence,
+            max_ply: 0,
+            count: NodeCount::default(),
+        }
+    }
+
+    // Table or root move first, then the killers of `ply`, then by history; the sort is
+    // stable, so the game's own order breaks ties.
+    fn order(&self, state: &S, ply: usize, actions: &mut ActionList) {
+        let stored = self
+            .key
+            .and_then(|key| self.table.get(&key(state)).copied());
+        let first = self.root_hint.filter(|_| ply == 0).or(stored);
+        let killers = match self.killers.get(ply) {
+            Some(&killers) if self.ordering.killers => killers,
+            _ => [None; 2],
+        };
+        actions.sort_by_key(|&action| {
+            let rank = if Some(action) == first {
+                0
+            } else if killers.contains(&Some(action)) {
+                1
+            } else {
+                2
+            };
+            let history = match self.history.get(action) {
+                Some(&history) if self.ordering.history => history,
+                _ => 0,
+            };
+            (rank, Reverse(history))
+        });
+    }
+
+    fn record_cutoff(&mut self, action: Action, ply: usize, depth: usize) {
+        if self.ordering.killers {
+            if self.killers.len() <= ply {
+                self.killers.resize(ply + 1, [None; 2]);
+            }
+            let killers = &mut self.killers[ply];
+            if killers[0] != Some(action) {
+                killers[1] = killers[0];
+                killers[0] = Some(action);
+            }
+        }
+        if self.ordering.history {
+            if self.history.len() <= action {
+                self.history.resize(action + 1, 0);
+            }
+            self.history[action] += (depth * depth) as u64;
+        }
+    }
+}
+
+impl<S: TwoPlayerState> Searcher<'_, S> {
+    // Negamax with alpha-beta pruning; `pv` receives the best line below `state`.
+    fn search(
+        &mut self,
+        state: &S,
+        depth: usize,
+        ply: usize,
+        mut alpha: ScoreType,
+        beta: ScoreType,
+        pv: &mut Vec<Action>,
+    ) -> ScoreType {
+        pv.clear();
+        self.count.max_depth = self.count.max_depth.max(ply);
+        match state.winning_status() {
+            WinningStatus::Win => return WIN_SCORE,
+            WinningStatus::Lose => return -WIN_SCORE,
+            WinningStatus::Draw => return 0,
+            WinningStatus::None => {}
+        }
+        let unquiet = depth == 0 && ply < self.max_ply && !state.is_quiet();
+        let depth = if unquiet { 1 } else { depth };
+        if depth == 0 || state.is_done() {
+            return (self.evaluate)(state);
+        }
+        let mut legal_actions = state.legal_actions();
+        if legal_actions.is_empty() {
+            return -WIN_SCORE;
+        }
+        self.order(state, ply, &mut legal_actions);
+        self.count.expanded += 1;
+        let mut best = -WIN_SCORE - 1;
+        let mut line = vec![];
+        for action in legal_actions {
+            self.count.generated += 1;
+            let next_state = state.next_state(action);
+            let value = -self.search(&next_state, depth - 1, ply + 1, -beta, -alpha, &mut line);
+            if best < value {
+                best = value;
+                pv.clear();
+                pv.push(action);
+                pv.extend_from_slice(&line);
+            }
+            alpha = alpha.max(value);
+            if beta <= alpha {
+                self.count.pruned += 1;
+                self.record_cutoff(action, ply, depth);
+                break;
+            }
+        }
+        if let (Some(key), Some(&action)) = (self.key, pv.first()) {
+            self.table.insert(key(state), action);
+        }
+        best
+    }
+}
+
+// How each iteration of `iterative_deepening_with_config` sets its alpha-beta window.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Window {
+    Full,
+    // Starts at the last iteration's value plus or minus the width and widens it fourfold on
+    // the side that fails.
+    Aspiration(ScoreType),
+    // Null-window searches that close in on the value from the last iteration's guess.
+    Mtdf,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AlphaBetaConfig {
+    pub max_depth: usize,
+    pub time_limit: Duration,
+    pub window: Window,
+    pub ordering: MoveOrdering,
+    // How many plies past the horizon the search follows states that are not `is_quiet`.
+    pub quiescence: usize,
+}
+
+impl Default for AlphaBetaConfig {
+    fn default() -> Self {
+        AlphaBetaConfig {
+            max_depth: 64,
+            time_limit: Duration::from_millis(100),
+            window: Window::Full,
+            ordering: MoveOrdering::default(),
+            quiescence: 8,
+        }
+    }
+}
+
+impl<S: TwoPlayerState> Searcher<'_, S> {
+    // The value of `state` at `depth` with its principal variation, searched the way `window`
+    // says around `guess`.
+    fn search_root(
+        &mut self,
+        state: &S,
+        depth: usize,
+        window: Window,
+        guess: ScoreType,
+    ) -> (ScoreType, Vec<Action>) {
+        const LOW: ScoreType = -WIN_SCORE - 1;
+        const HIGH: ScoreType = WIN_SCORE + 1;
+        self.max_ply = depth + self.quiescence;
+        let mut pv = vec![];
+        match window {
+            Window::Full => {
+                let value = self.search(state, depth, 0, LOW, HIGH, &mut pv);
+                (value, pv)
+            }
+            Window::Aspiration(width) => {
+                let width = width.max(1);
+                let (mut alpha_width, mut beta_width) = (width, width);
+                loop {
+                    let alpha = (guess - alpha_width).max(LOW);
+                    let beta = (guess + beta_width).min(HIGH);
+                    let value = self.search(state, depth, 0, alpha, beta, &mut pv);
+                    if value <= alpha && LOW < alpha {
+                        alpha_width *= 4;
+                    } else if beta <= value && beta < HIGH {
+                        beta_width *= 4;
+                    } else {
+                        return (value, pv);
+                    }
+                }
+            }
+            Window::Mtdf => {
+                let (mut lower, mut upper) = (LOW, HIGH);
+                let mut value = guess;
+                let mut best_pv = vec![];
+                while lower < upper {
+                    let beta = if value == lower { value + 1 } else { value };
+                    value = self.search(state, depth, 0, beta - 1, beta, &mut pv);
+                    if value < beta {
+                        upper = value;
+                    } else {
+                        lower = value;
+                        // Only a search that failed high proved its first move.
+                        best_pv = pv.clone();
+                    }
+                }
+                if best_pv.is_empty() {
+                    best_pv = pv;
+                }
+                (value, best_pv)
+            }
+        }
+    }
+}
+
+pub fn alpha_beta<S: TwoPlayerState>(
+    state: &S,
+    depth: usize,
+    evaluate: &Evaluation<S>,
+) -> AlphaBetaResult {
+    let mut searcher = Searcher::new(evaluate, &AlphaBetaConfig::default(), None);
+    let (value, pv) = searcher.search_root(state, depth, Window::Full, 0);
+    AlphaBetaResult {
+        value,
+        pv,
+        depth,
+        count: searcher.count,
+    }
+}
+
+// Searches depth 1, 2, ... `max_depth` until `time_limit` has passed on `clock`, starting each
+// depth with the best move of the last one, and returns the deepest finished search.
+pub fn iterative_deepening<S: TwoPlayerState>(
+    state: &S,
+    max_depth: usize,
+    time_limit: Duration,
+    clock: &impl Clock,
+    evaluate: &Evaluation<S>,
+) -> AlphaBetaResult {
+    let config = AlphaBetaConfig {
+        max_depth,
+        time_limit,
+        ..AlphaBetaConfig::default()
+    };
+    iterative_deepening_with_config(state, &config, clock, evaluate)
+}
+
+// `iterative_deepening` whose iterations search the `config.window` around the value of the
+// last one. Narrow windows prune more and leave time for deeper iterations.
+pub fn iterative_deepening_with_config<S: TwoPlayerState>(
+    state: &S,
+    config: &AlphaBetaConfig,
+    clock: &impl Clock,
+    evaluate: &Evaluation<S>,
+) -> AlphaBetaResult {
+    let searcher = Searcher::new(evaluate, config, None);
+    deepen(state, config, clock, searcher)
+}
+
+// `iterative_deepening_with_config` that remembers the best move of every position by `key`,
+// a hash of the state, and tries it first whenever the position comes back: in the next
+// iteration or through a transposition.
+pub fn iterative_deepening_with_table<S: TwoPlayerState>(
+    state: &S,
+    config: &AlphaBetaConfig,
+    clock: &impl Clock,
+    evaluate: &Evaluation<S>,
+    key: &dyn Fn(&S) -> u64,
+) -> AlphaBetaResult {
+    let searcher = Searcher::new(evaluate, config, Some(key));
+    deepen(state, config, clock, searcher)
+}
+
+fn deepen<S: TwoPlayerState>(
+    state: &S,
+    config: &AlphaBetaConfig,
+    clock: &impl Clock,
+    mut searcher: Searcher<S>,
+) -> AlphaBetaResult {
+    let start = clock.now();
+    let mut result = AlphaBetaResult::default();
+    for depth in 1..=config.max_depth {
+        searcher.root_hint = result.pv.first().copied();
+        let guess = if depth == 1 {
+            (searcher.evaluate)(state)
+        } else {
+            result.value
+        };
+        let (value, pv) = searcher.search_root(state, depth, config.window, guess);
+        result = AlphaBetaResult {
+            value,
+            pv,
+            depth,
+            count: searcher.count,
+        };
+        if config.time_limit <= clock.now() - start || value.abs() == WIN_SCORE {
+            break;
+        }
+    }
+    result
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use smallvec::smallvec;
+
+    use super::*;
+    use crate::game::MockClock;
+    use crate::hashing::StateHash;
+    use crate::section5::AlternateMazeState;
+
+    fn minimax(state: &AlternateMazeState, depth: usize) -> ScoreType {
+        match state.winning_status() {
+            WinningStatus::Win => return WIN_SCORE,
+            WinningStatus::Lose => return -WIN_SCORE,
+            WinningStatus::Draw => return 0,
+            WinningStatus::None => {}
+        }
+        if depth == 0 {
+            return state.score();
+        }
+        state
+            .legal_actions()
+            .into_iter()
+            .map(|action| -minimax(&state.next_state(action), depth - 1))
+            .max()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_alpha_beta_matches_minimax() {
+        let evaluate = |state: &AlternateMazeState| state.score();
+        for seed in 0..20 {
+            let state = AlternateMazeState::with_seed(seed);
+            for depth in 1..=4 {
+                let result = alpha_beta(&state, depth, &evaluate);
+                assert_eq!(result.value, minimax(&state, depth));
+                assert_eq!(result.pv.len(), depth);
+                // The principal variation leads to the position the value came from.
+                let mut leaf = state;
+                for &action in result.pv.iter() {
+                    leaf.advance(action);
+                }
+                let sign = if depth % 2 == 0 { 1 } else { -1 };
+                assert_eq!(sign * minimax(&leaf, 0), result.value);
+            }
+        }
+    }
+
+    #[test]
+    fn test_windows_agree() {
+        let evaluate = |state: &AlternateMazeState| state.score();
+        for seed in 0..20 {
+            let state = AlternateMazeState::with_seed(seed);
+            for depth in 1..=4 {
+                let full = alpha_beta(&state, depth, &evaluate);
+                for window in [Window::Aspiration(1), Window::Aspiration(5), Window::Mtdf] {
+                    let config = AlphaBetaConfig {
+                        max_depth: depth,
+                        time_limit: Duration::from_secs(1),
+                        window,
+                        ..AlphaBetaConfig::default()
+                    };
+                    let result = iterative_deepening_with_config(
+                        &state,
+                        &config,
+                        &MockClock::new(),
+                        &evaluate,
+                    );
+                    assert_eq!(result.value, full.value, "{:?} at depth {}", window, depth);
+                    // Another first move is fine as long as it is worth as much.
+                    let action = result.action().unwrap();
+                    let rest = alpha_beta(&state.next_state(action), depth - 1, &evaluate);
+                    assert_eq!(-rest.value, full.value, "{:?} at depth {}", window, depth);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_move_ordering() {
+        let evaluate = |state: &AlternateMazeState| state.score();
+        let key = |state: &AlternateMazeState| state.state_hash();
+        let mut expanded = [0; 3];
+        for seed in 0..20 {
+            let state = AlternateMazeState::with_seed(seed);
+            let none = AlphaBetaConfig {
+                max_depth: 5,
+                time_limit: Duration::from_secs(1),
+                ordering: MoveOrdering {
+                    killers: false,
+                    history: false,
+                },
+                ..AlphaBetaConfig::default()
+            };
+            let ordered = AlphaBetaConfig {
+                ordering: MoveOrdering::default(),
+                ..none
+            };
+            let clock = MockClock::new();
+            let results = [
+                iterative_deepening_with_config(&state, &none, &clock, &evaluate),
+                iterative_deepening_with_config(&state, &ordered, &clock, &evaluate),
+                iterative_deepening_with_table(&state, &ordered, &clock, &evaluate, &key),
+            ];
+            for (i, result) in results.iter().enumerate() {
+                assert_eq!(result.value, results[0].value);
+                expanded[i] += result.count.expanded;
+            }
+        }
+        println!("Alpha-Beta Expanded:\t{:?}", expanded);
+        assert!(expanded[2] <= expanded[0]);
+    }
+
+    // Taking gains 1 but leaves the taker's piece hanging until the next move, and retaking a
+    // hanging piece gains 3. Material is seen from the side to move.
+    #[derive(Clone)]
+    struct Exchange {
+        rest: usize,
+        material: ScoreType,
+        hanging: bool,
+    }
+
+    impl TwoPlayerState for Exchange {
+        fn is_done(&self) -> bool {
+            self.rest == 0
+        }
+
+        fn advance(&mut self, action: Action) {
+            let gain = [0, 1, 3][action];
+            self.rest -= 1;
+            self.material = -(self.material + gain);
+            self.hanging = action == 1;
+        }
+
+        fn legal_actions(&self) -> ActionList {
+            if self.hanging {
+                smallvec![0, 1, 2]
+            } else {
+                smallvec![0, 1]
+            }
+        }
+
+        fn winning_status(&self) -> WinningStatus {
+            WinningStatus::None
+        }
+
+        fn is_quiet(&self) -> bool {
+            !self.hanging
+        }
+    }
+
+    #[test]
+    fn test_quiescence() {
+        let state = Exchange {
+            rest: 10,
+            material: 0,
+            hanging: false,
+        };
+        let evaluate = |state: &Exchange| state.material;
+        let config = AlphaBetaConfig {
+            max_depth: 1,
+            time_limit: Duration::from_secs(1),
+            quiescence: 0,
+            ..AlphaBetaConfig::default()
+        };
+        let clock = MockClock::new();
+        // At the horizon the take looks like a free gain.
+        let blind = iterative_deepening_with_config(&state, &config, &clock, &evaluate);
+        assert_eq!((blind.value, blind.action()), (1, Outcome::Action(1)));
+        let config = AlphaBetaConfig {
+            quiescence: 4,
+            ..config
+        };
+        let careful = iterative_deepening_with_config(&state, &config, &clock, &evaluate);
+        assert_eq!(careful.action(), Outcome::Action(0));
+        assert!(careful.value <= 0);
+        assert!(2 <= careful.count.max_depth);
+        assert_eq!(
+            alpha_beta(&state, 1, &evaluate).action(),
+            Outcome::Action(0)
+        );
+    }
+
+    #[test]
+    fn test_iterative_deepening() {
+        let evaluate = |state: &AlternateMazeState| state.score();
+        let state = AlternateMazeState::with_seed(0);
+        let full = iterative_deepening(
+            &state,
+            3,
+            Duration::from_secs(1),
+            &MockClock::new(),
+            &evaluate,
+        );
+        let direct = alpha_beta(&state, 3, &evaluate);
+        assert_eq!(
+            (full.value, &full.pv, full.depth),
+            (direct.value, &direct.pv, 3)
+        );
+        let clock = MockClock::ticking(Duration::from_millis(1));
+        let quick = iterative_deepening(&state, 3, Duration::from_millis(1), &clock, &evaluate);
+        assert_eq!(quick.depth, 1);
+        assert_eq!(quick.action(), Outcome::Action(quick.pv[0]));
+        let text = full.render(&state);
+        assert!(text.starts_with("depth: 3\tvalue: "));
+        assert_eq!(text.matches("ply ").count(), 3);
+    }
+}
@@ -0,0 +1,120 @@
+#![allow(unused)]
+
+use std::{borrow::Cow, fs::File, io, path::Path};
+
+use crate::learning::Features;
+
+// Palette indices: background, path, wall, character, then points 1..=9 from cold to hot.
+const BACKGROUND: u8 = 0;
+const PATH: u8 = 1;
+const WALL: u8 = 2;
+const CHARACTER: u8 = 3;
+const MAX_POINT: f64 = 9.0;
+#[rustfmt::skip]
+const PALETTE: [u8; 39] = [
+    24, 24, 24,
+    70, 70, 90,
+    128, 128, 128,
+    230, 30, 30,
+    0, 70, 160, 0, 110, 200, 0, 150, 230, 0, 190, 190, 230, 220, 0,
+    250, 190, 0, 250, 150, 0, 250, 100, 0, 200, 0, 120,
+];
+
+// Collects one frame per recorded turn and writes them as a looping GIF.
+pub struct GifRecorder {
+    cell_size: u16,
+    // Per frame, in 1/100 s.
+    delay: u16,
+    size: (u16, u16),
+    path: Vec<(usize, usize)>,
+    frames: Vec<Vec<u8>>,
+}
+
+impl GifRecorder {
+    pub fn new(cell_size: u16, delay: u16) -> GifRecorder {
+        GifRecorder {
+            cell_size: cell_size.max(1),
+            delay,
+            size: (0, 0),
+            path: vec![],
+            frames: vec![],
+        }
+    }
+
+    pub fn frame_number(&self) -> usize {
+        self.frames.len()
+    }
+
+    // Draws `state`; cells the character stood on in earlier frames are dimmed.
+    pub fn record(&mut self, state: &impl Features) {
+        let (height, width) = state.grid_size();
+        let cell = self.cell_size as usize;
+        self.size = ((width * cell) as u16, (height * cell) as u16);
+        let position = state.position();
+        let mut pixels = vec![BACKGROUND; height * width * cell * cell];
+        for y in 0..height {
+            for x in 0..width {
+                let point = state.point(y, x);
+                let color = if (y, x) == position {
+                    CHARACTER
+                } else if state.is_wall(y, x) {
+                    WALL
+                } else if 0.0 < point {
+                    CHARACTER + (point.min(MAX_POINT).ceil() as u8).max(1)
+                } else if self.path.contains(&(y, x)) {
+                    PATH
+                } else {
+                    BACKGROUND
+                };
+                for py in y * cell..(y + 1) * cell {
+                    let row = py * width * cell;
+                    pixels[row + x * cell..row + (x + 1) * cell].fill(color);
+                }
+            }
+        }
+        self.path.push(position);
+        self.frames.push(pixels);
+    }
+
+    pub fn write(&self, writer: impl io::Write) -> Result<(), gif::EncodingError> {
+        let (width, height) = self.size;
+        let mut encoder = gif::Encoder::new(writer, width, height, &PALETTE)?;
+        encoder.set_repeat(gif::Repeat::Infinite)?;
+        for pixels in self.frames.iter() {
+            let frame = gif::Frame {
+                width,
+                height,
+                delay: self.delay,
+                buffer: Cow::Borrowed(pixels),
+                ..Default::default()
+            };
+            encoder.write_frame(&frame)?;
+        }
+        Ok(())
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), gif::EncodingError> {
+        self.write(File::create(path)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::GameRunner;
+    use crate::section3::{greedy_action, MazeState};
+
+    #[test]
+    fn test_gif_recorder() {
+        let mut recorder = GifRecorder::new(4, 10);
+        recorder.record(&MazeState::with_seed(0));
+        GameRunner::new(MazeState::with_seed, greedy_action)
+            .on_turn(|state, _| recorder.record(state))
+            .run(0);
+        assert_eq!(recorder.frame_number(), 101);
+        let mut gif = vec![];
+        recorder.write(&mut gif).unwrap();
+        assert!(gif.starts_with(b"GIF89a"));
+        assert_eq!(gif.last(), Some(&0x3b));
+    }
+}
@@ -0,0 +1,145 @@
+#![allow(unused)]
+
+use alloc::{vec, vec::Vec};
+use core::ops::{Index, IndexMut};
+
+use crate::game::Action;
+
+// Handle to a node in an `Arena`; only valid for the arena that returned it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct NodeId(usize);
+
+impl NodeId {
+    pub fn index(self) -> usize {
+        self.0
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ArenaNode<T> {
+    pub parent: Option<NodeId>,
+    // The action that led here from `parent`.
+    pub action: Option<Action>,
+    pub value: T,
+}
+
+// Search nodes in one `Vec`, linked to their parents by index: no allocation per node, and a
+// plan is rebuilt by walking the links back from a leaf.
+#[derive(Debug, Clone)]
+pub struct Arena<T> {
+    nodes: Vec<ArenaNode<T>>,
+}
+
+impl<T> Arena<T> {
+    pub fn new() -> Arena<T> {
+        Arena { nodes: vec![] }
+    }
+
+    pub fn with_capacity(capacity: usize) -> Arena<T> {
+        Arena {
+            nodes: Vec::with_capacity(capacity),
+        }
+    }
+
+    pub fn root(&mut self, value: T) -> NodeId {
+        self.nodes.push(ArenaNode {
+            parent: None,
+            action: None,
+            value,
+        });
+        NodeId(self.nodes.len() - 1)
+    }
+
+    pub fn alloc(&mut self, parent: NodeId, action: Action, value: T) -> NodeId {
+        self.nodes.push(ArenaNode {
+            parent: Some(parent),
+            action: Some(action),
+            value,
+        });
+        NodeId(self.nodes.len() - 1)
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.nodes.capacity()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    // Drops every node but keeps the memory for the next search.
+    pub fn clear(&mut self) {
+        self.nodes.clear();
+    }
+
+    pub fn node(&self, id: NodeId) -> &ArenaNode<T> {
+        &self.nodes[id.0]
+    }
+
+    // Every value, in allocation order.
+    pub fn values(&self) -> impl Iterator<Item = &T> + '_ {
+        self.nodes.iter().map(|node| &node.value)
+    }
+
+    // `id` and its ancestors, leaf first.
+    pub fn ancestors(&self, id: NodeId) -> impl Iterator<Item = NodeId> + '_ {
+        core::iter::successors(Some(id), |&id| self.nodes[id.0].parent)
+    }
+
+    // Actions from the root down to `id`.
+    pub fn plan(&self, id: NodeId) -> Vec<Action> {
+        let mut plan: Vec<Action> = self
+            .ancestors(id)
+            .filter_map(|id| self.nodes[id.0].action)
+            .collect();
+        plan.reverse();
+        plan
+    }
+}
+
+impl<T> Default for Arena<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Index<NodeId> for Arena<T> {
+    type Output = T;
+
+    fn index(&self, id: NodeId) -> &T {
+        &self.nodes[id.0].value
+    }
+}
+
+impl<T> IndexMut<NodeId> for Arena<T> {
+    fn index_mut(&mut self, id: NodeId) -> &mut T {
+        &mut self.nodes[id.0].value
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_plan() {
+        let mut arena = Arena::new();
+        let root = arena.root(0);
+        let a = arena.alloc(root, 2, 1);
+        let b = arena.alloc(root, 3, 1);
+        let c = arena.alloc(b, 0, 2);
+        arena[c] += 10;
+        assert_eq!(arena[c], 12);
+        assert_eq!(arena.plan(c), vec![3, 0]);
+        assert_eq!(arena.plan(a), vec![2]);
+        assert!(arena.plan(root).is_empty());
+        assert_eq!(arena.ancestors(c).collect::<Vec<_>>(), vec![c, b, root]);
+        assert_eq!(arena.len(), 4);
+        arena.clear();
+        assert!(arena.is_empty());
+    }
+}
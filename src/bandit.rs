@@ -0,0 +1,169 @@
+#![allow(unused)]
+
+use rand::Rng;
+
+use crate::game::{Action, Outcome, TwoPlayerState};
+use crate::mcts::playout_with;
+
+// Flat Monte Carlo agents: every legal action is an arm whose reward is one random playout
+// after it. Nothing is built below the root, so they sit between `random_action` and MCTS in
+// both cost and strength.
+
+// The summed playout values and the number of pulls of one arm, from the mover's side.
+#[derive(Debug, Clone, Copy, Default)]
+struct Arm {
+    action: Action,
+    w: f64,
+    n: usize,
+}
+
+impl Arm {
+    fn mean(&self) -> f64 {
+        if self.n == 0 {
+            0.0
+        } else {
+            self.w / self.n as f64
+        }
+    }
+}
+
+fn pull<S: TwoPlayerState>(state: &S, arm: &mut Arm, rng: &mut impl Rng) {
+    // The playout is valued for the opponent, who moves next.
+    arm.w += 1.0 - playout_with(state.next_state(arm.action), rng);
+    arm.n += 1;
+}
+
+fn arms<S: TwoPlayerState>(state: &S) -> Vec<Arm> {
+    state
+        .legal_actions()
+        .into_iter()
+        .map(|action| Arm {
+            action,
+            ..Arm::default()
+        })
+        .collect()
+}
+
+// Pulls a random arm with probability `epsilon` and the best one so far otherwise, after one
+// pull of each, and plays the arm with the best mean.
+pub fn epsilon_greedy_action_with<S: TwoPlayerState>(
+    state: &S,
+    playout_number: usize,
+    epsilon: f64,
+    rng: &mut impl Rng,
+) -> Outcome {
+    let mut arms = arms(state);
+    if state.is_done() || arms.is_empty() {
+        return Outcome::NoAction;
+    }
+    let best = |arms: &[Arm]| {
+        (0..arms.len())
+            .max_by(|&a, &b| arms[a].mean().total_cmp(&arms[b].mean()))
+            .unwrap()
+    };
+    for i in 0..playout_number {
+        let arm = if i < arms.len() {
+            i
+        } else if rng.gen_bool(epsilon.clamp(0.0, 1.0)) {
+            rng.gen_range(0..arms.len())
+        } else {
+            best(&arms)
+        };
+        pull(state, &mut arms[arm], rng);
+    }
+    Outcome::Action(arms[best(&arms)].action)
+}
+
+pub fn epsilon_greedy_action<S: TwoPlayerState>(
+    state: &S,
+    playout_number: usize,
+    epsilon: f64,
+) -> Outcome {
+    epsilon_greedy_action_with(state, playout_number, epsilon, &mut rand::thread_rng())
+}
+
+// Pulls the arm with the highest mean + c * sqrt(2 ln N / n) and plays the most pulled one,
+// the root of an MCTS that never expands.
+pub fn ucb1_action_with<S: TwoPlayerState>(
+    state: &S,
+    playout_number: usize,
+    c: f64,
+    rng: &mut impl Rng,
+) -> Outcome {
+    let mut arms = arms(state);
+    if state.is_done() || arms.is_empty() {
+        return Outcome::NoAction;
+    }
+    for i in 0..playout_number {
+        let total = i as f64;
+        let score = |arm: &Arm| {
+            if arm.n == 0 {
+                return f64::INFINITY;
+            }
+            arm.mean() + c * (2.0 * total.ln() / arm.n as f64).sqrt()
+        };
+        let mut best = 0;
+        for j in 1..arms.len() {
+            if score(&arms[best]) < score(&arms[j]) {
+                best = j;
+            }
+        }
+        pull(state, &mut arms[best], rng);
+    }
+    arms.iter()
+        .max_by_key(|arm| arm.n)
+        .map(|arm| arm.action)
+        .into()
+}
+
+pub fn ucb1_action<S: TwoPlayerState>(state: &S, playout_number: usize, c: f64) -> Outcome {
+    ucb1_action_with(state, playout_number, c, &mut rand::thread_rng())
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use super::*;
+    use crate::section5::{random_action, AlternateMazeState};
+    use crate::tournament::play_match;
+
+    const GAME_NUMBER: usize = 100;
+
+    fn win_rate(agent: impl Fn(&AlternateMazeState) -> Outcome) -> f64 {
+        let mut points = 0.0;
+        for seed in 0..GAME_NUMBER as u64 {
+            let state = AlternateMazeState::with_seed(seed);
+            points += play_match(state, &agent, &random_action);
+            points += 1.0 - play_match(state, &random_action, &agent);
+        }
+        points / (2 * GAME_NUMBER) as f64
+    }
+
+    #[test]
+    fn test_epsilon_greedy_vs_random() {
+        let rate = win_rate(|s| epsilon_greedy_action(s, 100, 0.1));
+        println!("Epsilon-Greedy vs Random:\t{}", rate);
+        assert!(0.5 < rate);
+    }
+
+    #[test]
+    fn test_ucb1_vs_random() {
+        let rate = win_rate(|s| ucb1_action(s, 100, 1.0));
+        println!("UCB1 vs Random:\t{}", rate);
+        assert!(0.5 < rate);
+    }
+
+    #[test]
+    fn test_small_budgets() {
+        let state = AlternateMazeState::with_seed(0);
+        let mut rng = StdRng::seed_from_u64(0);
+        for playout_number in [0, 1] {
+            let legal_actions = state.legal_actions();
+            let action = epsilon_greedy_action_with(&state, playout_number, 0.5, &mut rng);
+            assert!(legal_actions.contains(&action.unwrap()));
+            let action = ucb1_action_with(&state, playout_number, 1.0, &mut rng);
+            assert!(legal_actions.contains(&action.unwrap()));
+        }
+    }
+}
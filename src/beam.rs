@@ -0,0 +1,801 @@
+#![allow(unused)]
+
+use alloc::{
+    collections::{BTreeSet, BinaryHeap},
+    vec,
+    vec::Vec,
+};
+use core::{ops::ControlFlow, time::Duration};
+
+#[cfg(feature = "std")]
+use crate::game::SystemClock;
+use crate::game::{
+    Action, ActionList, Clock, CostType, NodeCount, Outcome, ScoreType, SearchLimits,
+    SinglePlayerState,
+};
+use crate::mcts::Policy;
+
+const INF: ScoreType = 1_000_000_000;
+
+// What the beam and Chokudai searches need from a state besides the game: the evaluation the
+// beams are ordered by, and the first move of the line that reached the state. Moves are the
+// game's actions, so a joint move of several characters is searched like any other.
+pub trait BeamState: SinglePlayerState + Ord {
+    fn evaluated_score(&self) -> ScoreType;
+    fn evaluate_score(&mut self);
+    fn first_action(&self) -> Option<Action>;
+    fn set_first_action(&mut self, action: Action);
+    // Equal for the states a deduplicating search keeps only one of.
+    fn dedup_key(&self) -> u64;
+}
+
+// Tells a timed search when `time_threshold` milliseconds have passed on `clock`.
+pub(crate) struct TimeKeeper<C: Clock> {
+    clock: C,
+    start_time: Duration,
+    time_threshold: u64,
+}
+
+#[cfg(feature = "std")]
+impl TimeKeeper<SystemClock> {
+    pub fn new(time_threshold: u64) -> TimeKeeper<SystemClock> {
+        TimeKeeper::with_clock(time_threshold, SystemClock::new())
+    }
+}
+
+impl<C: Clock> TimeKeeper<C> {
+    pub fn with_clock(time_threshold: u64, clock: C) -> TimeKeeper<C> {
+        TimeKeeper {
+            start_time: clock.now(),
+            clock,
+            time_threshold,
+        }
+    }
+
+    pub fn is_time_over(&self) -> bool {
+        Duration::from_millis(self.time_threshold) <= self.clock.now() - self.start_time
+    }
+}
+
+// The move to the best evaluated neighbour, the first legal one on ties.
+pub fn greedy_action<S: BeamState>(state: &S) -> Outcome {
+    if state.is_done() {
+        return Outcome::NoAction;
+    }
+    let legal_actions = state.legal_actions();
+    let mut best_score = -INF;
+    let mut best_action = None;
+    for act in legal_actions.iter() {
+        let mut now_state = state.clone();
+        now_state.advance(*act);
+        now_state.evaluate_score();
+        if best_score < now_state.evaluated_score() {
+            best_score = now_state.evaluated_score();
+            best_action = Some(*act);
+        }
+    }
+    best_action.into()
+}
+
+pub fn beam_search_action<S: BeamState>(state: &S, beam_width: usize, beam_depth: u64) -> Outcome {
+    beam_search_with_count(state, beam_width, beam_depth, SearchLimits::default(), None).0
+}
+
+// Beam search that never expands a move the remaining `budget` cannot pay for.
+pub fn budgeted_beam_search_action<S: BeamState>(
+    state: &S,
+    beam_width: usize,
+    beam_depth: u64,
+    budget: CostType,
+) -> Outcome {
+    let limits = SearchLimits {
+        budget,
+        ..SearchLimits::default()
+    };
+    beam_search_with_count(state, beam_width, beam_depth, limits, None).0
+}
+
+// Beam search under all of `limits`; with `stop_at_score` it returns the first move of the
+// first line reaching that score.
+pub fn limited_beam_search_action<S: BeamState>(
+    state: &S,
+    beam_width: usize,
+    beam_depth: u64,
+    limits: SearchLimits,
+) -> Outcome {
+    beam_search_with_count(state, beam_width, beam_depth, limits, None).0
+}
+
+// Beam search to the end of the game that stops after expanding `max_nodes` states, in place
+// of a time limit: it plays the same on every machine.
+pub fn node_limited_beam_search_action<S: BeamState>(
+    state: &S,
+    beam_width: usize,
+    max_nodes: usize,
+) -> Outcome {
+    let limits = SearchLimits {
+        max_nodes: Some(max_nodes),
+        ..SearchLimits::default()
+    };
+    beam_search_with_count(state, beam_width, u64::MAX, limits, None).0
+}
+
+// `beam_search_action` that also counts the nodes; `pruned` are the states the width cut off.
+pub fn beam_search_with_nodes<S: BeamState>(
+    state: &S,
+    beam_width: usize,
+    beam_depth: u64,
+) -> (Outcome, NodeCount) {
+    beam_search_with_count(state, beam_width, beam_depth, SearchLimits::default(), None)
+}
+
+// How a beam keeps its states: `pop` and `peek` give the best evaluated state first.
+pub trait BeamQueue<S>: Default {
+    fn push(&mut self, state: S);
+    fn pop(&mut self) -> Option<S>;
+    fn peek(&self) -> Option<&S>;
+    fn len(&self) -> usize;
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    // A new, empty queue configured like this one.
+    fn empty(&self) -> Self {
+        Self::default()
+    }
+}
+
+impl<S: Ord> BeamQueue<S> for BinaryHeap<S> {
+    fn push(&mut self, state: S) {
+        BinaryHeap::push(self, state)
+    }
+
+    fn pop(&mut self) -> Option<S> {
+        BinaryHeap::pop(self)
+    }
+
+    fn peek(&self) -> Option<&S> {
+        BinaryHeap::peek(self)
+    }
+
+    fn len(&self) -> usize {
+        BinaryHeap::len(self)
+    }
+}
+
+pub(crate) fn beam_search_with_count<S: BeamState>(
+    state: &S,
+    beam_width: usize,
+    beam_depth: u64,
+    limits: SearchLimits,
+    on_generate: Option<&mut OnGenerate<S>>,
+) -> (Outcome, NodeCount) {
+    let queue = BinaryHeap::new();
+    let expansion = Expansion::default();
+    beam_search_in(
+        state,
+        beam_width,
+        beam_depth,
+        limits,
+        expansion,
+        on_generate,
+        queue,
+    )
+}
+
+// Beam search that keeps only the first of the states sharing a `state_hash` at each depth,
+// so the width is not spent on transpositions.
+pub fn dedup_beam_search_with_nodes<S: BeamState>(
+    state: &S,
+    beam_width: usize,
+    beam_depth: u64,
+) -> (Outcome, NodeCount) {
+    let limits = SearchLimits::default();
+    let queue = BinaryHeap::new();
+    let expansion = Expansion {
+        dedup: true,
+        ..Expansion::default()
+    };
+    beam_search_in(
+        state, beam_width, beam_depth, limits, expansion, None, queue,
+    )
+}
+
+// Which children of a state `beam_search_in` generates.
+pub(crate) struct Expansion<'p, S> {
+    // Keeps only the first of the states sharing a `dedup_key` at each depth.
+    pub(crate) dedup: bool,
+    // Orders the actions highest prior first; without it they keep the game's order.
+    pub(crate) policy: Option<&'p dyn Policy<S>>,
+    pub(crate) cap: Option<usize>,
+}
+
+impl<S> Default for Expansion<'_, S> {
+    fn default() -> Self {
+        Expansion {
+            dedup: false,
+            policy: None,
+            cap: None,
+        }
+    }
+}
+
+impl<S: SinglePlayerState> Expansion<'_, S> {
+    fn actions(&self, state: &S, budget: CostType) -> ActionList {
+        let mut actions = state.affordable_actions(budget);
+        if let Some(policy) = self.policy {
+            let priors = policy.priors(state, &actions);
+            let mut ranked: Vec<(f64, Action)> = priors.into_iter().zip(actions).collect();
+            ranked.sort_by(|a, b| b.0.total_cmp(&a.0));
+            actions = ranked.into_iter().map(|(_, action)| action).collect();
+        }
+        if let Some(cap) = self.cap {
+            actions.truncate(cap.max(1));
+        }
+        actions
+    }
+}
+
+// Sees every child a search generates, with its depth, its parent and the move between them,
+// before the child joins a beam: `DepthRecorder` and the traced searches hook in here.
+pub(crate) type OnGenerate<'h, S> = dyn FnMut(usize, &S, Action, &mut S) + 'h;
+
+// The first move of the line to `best`, or the greedy move when the search found no line.
+pub(crate) fn line_outcome<S: BeamState>(state: &S, best: Option<&S>, budget: CostType) -> Outcome {
+    if state.is_done() {
+        return Outcome::NoAction;
+    }
+    match best.and_then(BeamState::first_action) {
+        Some(action) => Outcome::Action(action),
+        None => budgeted_greedy_action(state, budget),
+    }
+}
+
+pub(crate) fn beam_search_in<S: BeamState, Q: BeamQueue<S>>(
+    state: &S,
+    beam_width: usize,
+    beam_depth: u64,
+    limits: SearchLimits,
+    expansion: Expansion<S>,
+    on_generate: Option<&mut OnGenerate<S>>,
+    queue: Q,
+) -> (Outcome, NodeCount) {
+    let run = BeamRun::new(state, beam_width, queue);
+    let (best_state, count) = run.run(beam_depth, limits, &expansion, on_generate, None);
+    (line_outcome(state, Some(&best_state), limits.budget), count)
+}
+
+// A beam search between two depths: `run` goes on to the end and `BeamStepper` one depth at
+// a time.
+pub(crate) struct BeamRun<S, Q> {
+    beam_width: usize,
+    pub(crate) depth: usize,
+    pub(crate) beam: Q,
+    // The best state of the last finished depth.
+    best: S,
+    count: NodeCount,
+}
+
+impl<S: BeamState, Q: BeamQueue<S>> BeamRun<S, Q> {
+    pub(crate) fn new(state: &S, beam_width: usize, mut queue: Q) -> BeamRun<S, Q> {
+        queue.push(state.clone());
+        BeamRun {
+            beam_width,
+            depth: 0,
+            beam: queue,
+            best: state.clone(),
+            count: NodeCount::default(),
+        }
+    }
+
+    // Steps `beam_depth` more depths or until a limit stops the search. Returns the state it
+    // ends on: the best of the deepest depth it finished, or the start when it expanded nothing.
+    pub(crate) fn run(
+        mut self,
+        beam_depth: u64,
+        limits: SearchLimits,
+        expansion: &Expansion<S>,
+        mut on_generate: Option<&mut OnGenerate<S>>,
+        time_keeper: Option<&TimeKeeper<&dyn Clock>>,
+    ) -> (S, NodeCount) {
+        let budget = limits.budget;
+        search_span!(
+            "beam_search",
+            beam_width = self.beam_width,
+            beam_depth,
+            budget
+        );
+        if self.best.is_done() || self.best.affordable_actions(budget).is_empty() {
+            return (self.best, self.count);
+        }
+        for _ in 0..beam_depth {
+            let on_generate = on_generate.as_deref_mut();
+            if !self.step(&limits, expansion, on_generate, time_keeper) {
+                break;
+            }
+            if self.best.is_done() || limits.reached(self.best.score()) {
+                break;
+            }
+        }
+        (self.best, self.count)
+    }
+
+    // Expands the `beam_width` best states into the next depth. Returns false when nothing was
+    // generated or the nodes or the time ran out first; the unfinished depth is lost and `best`
+    // stands.
+    pub(crate) fn step(
+        &mut self,
+        limits: &SearchLimits,
+        expansion: &Expansion<S>,
+        mut on_generate: Option<&mut OnGenerate<S>>,
+        time_keeper: Option<&TimeKeeper<&dyn Clock>>,
+    ) -> bool {
+        let mut next_beam = self.beam.empty();
+        let mut seen = BTreeSet::new();
+        for _ in 0..self.beam_width {
+            let out_of_time = time_keeper.is_some_and(TimeKeeper::is_time_over);
+            if limits.out_of_nodes(self.count.expanded) || out_of_time {
+                return false;
+            }
+            let Some(now_state) = self.beam.pop() else {
+                break;
+            };
+            if now_state.is_done() {
+                continue;
+            }
+            self.count.expanded += 1;
+            for act in expansion.actions(&now_state, limits.budget) {
+                let mut next_state = now_state.clone();
+                next_state.advance(act);
+                next_state.evaluate_score();
+                if self.depth == 0 {
+                    next_state.set_first_action(act);
+                }
+                if expansion.dedup && !seen.insert(next_state.dedup_key()) {
+                    self.count.duplicates += 1;
+                    continue;
+                }
+                if let Some(on_generate) = on_generate.as_deref_mut() {
+                    on_generate(self.depth + 1, &now_state, act, &mut next_state);
+                }
+                next_beam.push(next_state);
+                self.count.generated += 1;
+            }
+        }
+        self.count.pruned += self.beam.len();
+        let bytes = (self.beam.len() + next_beam.len()) * core::mem::size_of::<S>();
+        self.count.peak_bytes = self.count.peak_bytes.max(bytes);
+        search_event!(
+            depth = self.depth,
+            beam_size = next_beam.len(),
+            pruned = self.beam.len(),
+            best_score = next_beam.peek().map(|s| s.evaluated_score()),
+        );
+        let Some(top) = next_beam.peek() else {
+            return false;
+        };
+        self.best = top.clone();
+        self.beam = next_beam;
+        self.depth += 1;
+        self.count.max_depth = self.depth;
+        true
+    }
+}
+
+// Time-limited beam search that reads the time from `clock`.
+pub fn beam_search_with_clock<S: BeamState>(
+    state: &S,
+    beam_width: usize,
+    time_threshold: u64,
+    clock: &impl Clock,
+) -> Outcome {
+    let time_keeper = TimeKeeper::with_clock(time_threshold, clock as &dyn Clock);
+    let run = BeamRun::new(state, beam_width, BinaryHeap::new());
+    let limits = SearchLimits::default();
+    let expansion = Expansion::default();
+    let (best_state, _) = run.run(u64::MAX, limits, &expansion, None, Some(&time_keeper));
+    line_outcome(state, Some(&best_state), CostType::MAX)
+}
+
+pub fn chokudai_search_action<S: BeamState>(
+    state: &S,
+    beam_width: usize,
+    beam_depth: usize,
+    beam_number: usize,
+) -> Outcome {
+    budgeted_chokudai_search_action(state, beam_width, beam_depth, beam_number, CostType::MAX)
+}
+
+// Chokudai search that drops every state over `budget`.
+pub fn budgeted_chokudai_search_action<S: BeamState>(
+    state: &S,
+    beam_width: usize,
+    beam_depth: usize,
+    beam_number: usize,
+    budget: CostType,
+) -> Outcome {
+    let limits = SearchLimits {
+        budget,
+        ..SearchLimits::default()
+    };
+    chokudai_search_with(
+        state,
+        beam_width,
+        beam_depth,
+        beam_number,
+        limits,
+        false,
+        None,
+    )
+    .0
+}
+
+// Chokudai search under all of `limits`; with `stop_at_score` it stops as soon as it generates
+// a line reaching that score, in the middle of an iteration, and plays its first move.
+pub fn limited_chokudai_search_action<S: BeamState>(
+    state: &S,
+    beam_width: usize,
+    beam_depth: usize,
+    beam_number: usize,
+    limits: SearchLimits,
+) -> Outcome {
+    chokudai_search_with(
+        state,
+        beam_width,
+        beam_depth,
+        beam_number,
+        limits,
+        false,
+        None,
+    )
+    .0
+}
+
+// Chokudai search that iterates until it has expanded `max_nodes` states, the node-count
+// version of `chokudai_search_with_clock`.
+pub fn node_limited_chokudai_search_action<S: BeamState>(
+    state: &S,
+    beam_width: usize,
+    beam_depth: usize,
+    max_nodes: usize,
+) -> Outcome {
+    let limits = SearchLimits {
+        max_nodes: Some(max_nodes),
+        ..SearchLimits::default()
+    };
+    limited_chokudai_search_action(state, beam_width, beam_depth, usize::MAX, limits)
+}
+
+// `chokudai_search_action` that also counts the nodes; `pruned` are the states left in the
+// beams when the search stops.
+pub fn chokudai_search_with_nodes<S: BeamState>(
+    state: &S,
+    beam_width: usize,
+    beam_depth: usize,
+    beam_number: usize,
+) -> (Outcome, NodeCount) {
+    chokudai_search_with(
+        state,
+        beam_width,
+        beam_depth,
+        beam_number,
+        SearchLimits::default(),
+        false,
+        None,
+    )
+}
+
+// Chokudai search that never pushes a state twice to the same depth, across all iterations:
+// later passes spend their width on new states instead of re-expanding old ones.
+// `duplicates` counts the re-expansions avoided.
+pub fn dedup_chokudai_search_with_nodes<S: BeamState>(
+    state: &S,
+    beam_width: usize,
+    beam_depth: usize,
+    beam_number: usize,
+) -> (Outcome, NodeCount) {
+    chokudai_search_with(
+        state,
+        beam_width,
+        beam_depth,
+        beam_number,
+        SearchLimits::default(),
+        true,
+        None,
+    )
+}
+
+pub(crate) fn chokudai_search_with<S: BeamState>(
+    state: &S,
+    beam_width: usize,
+    beam_depth: usize,
+    beam_number: usize,
+    limits: SearchLimits,
+    dedup: bool,
+    on_generate: Option<&mut OnGenerate<S>>,
+) -> (Outcome, NodeCount) {
+    let run = ChokudaiRun::new(state, beam_width, beam_depth);
+    let (leaf, count) = run.run(beam_number, limits, dedup, on_generate, None);
+    (line_outcome(state, leaf.as_ref(), limits.budget), count)
+}
+
+// Chokudai search between iterations: a beam per depth, kept from one iteration to the next.
+// `run` goes on to the end and `ChokudaiSearch` up to every new incumbent.
+pub(crate) struct ChokudaiRun<S> {
+    beam_width: usize,
+    beam: Vec<BinaryHeap<S>>,
+    // The `dedup_key`s pushed to every depth so far, when deduplicating.
+    seen: Vec<BTreeSet<u64>>,
+    pub(crate) iteration: usize,
+    count: NodeCount,
+}
+
+impl<S: BeamState> ChokudaiRun<S> {
+    pub(crate) fn new(state: &S, beam_width: usize, beam_depth: usize) -> ChokudaiRun<S> {
+        let mut beam = vec![BinaryHeap::new(); beam_depth + 1];
+        beam[0].push(state.clone());
+        ChokudaiRun {
+            beam_width,
+            beam,
+            seen: vec![BTreeSet::new(); beam_depth + 1],
+            iteration: 0,
+            count: NodeCount::default(),
+        }
+    }
+
+    // Iterates `beam_number` more times, or until a limit stops the search or, with a
+    // `time_keeper`, the first iteration that ends out of time. Returns the leaf of the line it
+    // picks: the first to reach `stop_at_score`, or else the best state of the deepest beam with
+    // a first move.
+    pub(crate) fn run(
+        mut self,
+        beam_number: usize,
+        limits: SearchLimits,
+        dedup: bool,
+        mut on_generate: Option<&mut OnGenerate<S>>,
+        time_keeper: Option<&TimeKeeper<&dyn Clock>>,
+    ) -> (Option<S>, NodeCount) {
+        let budget = limits.budget;
+        let beam_width = self.beam_width;
+        let beam_depth = self.beam.len() - 1;
+        search_span!(
+            "chokudai_search",
+            beam_width,
+            beam_depth,
+            beam_number,
+            budget
+        );
+        let state = self.beam[0].peek();
+        if state.is_none_or(|state| state.is_done() || state.affordable_actions(budget).is_empty())
+        {
+            return (None, self.count);
+        }
+        let mut reached = None;
+        for _ in 0..beam_number {
+            let on_generate = on_generate.as_deref_mut();
+            if let ControlFlow::Break(leaf) = self.iterate(&limits, dedup, on_generate) {
+                reached = leaf;
+                break;
+            }
+            if time_keeper.is_some_and(TimeKeeper::is_time_over) {
+                break;
+            }
+        }
+        self.count.pruned = self.open();
+        let leaf = reached.or_else(|| self.leaf().map(|(_, leaf)| leaf.clone()));
+        (leaf, self.count)
+    }
+
+    // One pass down the depths. Breaks with the leaf of a line reaching `stop_at_score`, or
+    // with None when the nodes ran out or every line is finished.
+    pub(crate) fn iterate(
+        &mut self,
+        limits: &SearchLimits,
+        dedup: bool,
+        mut on_generate: Option<&mut OnGenerate<S>>,
+    ) -> ControlFlow<Option<S>> {
+        self.iteration += 1;
+        let expanded = self.count.expanded;
+        for t in 0..self.beam.len() - 1 {
+            for _ in 0..self.beam_width {
+                if limits.out_of_nodes(self.count.expanded) {
+                    return ControlFlow::Break(None);
+                }
+                let Some(now_state) = self.beam[t].peek().cloned() else {
+                    break;
+                };
+                if now_state.is_done() {
+                    break;
+                }
+                self.beam[t].pop();
+                self.count.expanded += 1;
+                for act in now_state.affordable_actions(limits.budget) {
+                    let mut next_state = now_state.clone();
+                    next_state.advance(act);
+                    next_state.evaluate_score();
+                    if t == 0 {
+                        next_state.set_first_action(act);
+                    }
+                    if dedup && !self.seen[t + 1].insert(next_state.dedup_key()) {
+                        self.count.duplicates += 1;
+                        continue;
+                    }
+                    if let Some(on_generate) = on_generate.as_deref_mut() {
+                        on_generate(t + 1, &now_state, act, &mut next_state);
+                    }
+                    self.count.generated += 1;
+                    self.count.max_depth = self.count.max_depth.max(t + 1);
+                    if limits.reached(next_state.score()) && next_state.first_action().is_some() {
+                        return ControlFlow::Break(Some(next_state));
+                    }
+                    self.beam[t + 1].push(next_state);
+                }
+            }
+        }
+        let open = self.open();
+        self.count.peak_bytes = self.count.peak_bytes.max(open * core::mem::size_of::<S>());
+        search_event!(
+            iteration = self.iteration,
+            frontier = open,
+            best_score = self.leaf().map(|(_, s)| s.evaluated_score()),
+        );
+        // Every line is finished, so more iterations would only spin.
+        if self.count.expanded == expanded {
+            return ControlFlow::Break(None);
+        }
+        ControlFlow::Continue(())
+    }
+
+    fn open(&self) -> usize {
+        self.beam.iter().map(BinaryHeap::len).sum()
+    }
+
+    // The best state of the deepest beam with a first move, and its depth.
+    pub(crate) fn leaf(&self) -> Option<(usize, &S)> {
+        (0..self.beam.len()).rev().find_map(|t| {
+            let top = self.beam[t].peek()?;
+            top.first_action().is_some().then_some((t, top))
+        })
+    }
+}
+
+pub(crate) fn budgeted_greedy_action<S: SinglePlayerState>(state: &S, budget: CostType) -> Outcome {
+    let mut best_score = -INF;
+    let mut best_action = None;
+    for act in state.affordable_actions(budget) {
+        let mut next_state = state.clone();
+        next_state.advance(act);
+        if best_score < next_state.score() {
+            best_score = next_state.score();
+            best_action = Some(act);
+        }
+    }
+    best_action.into()
+}
+
+// Time-limited chokudai search that reads the time from `clock`.
+pub fn chokudai_search_with_clock<S: BeamState>(
+    state: &S,
+    beam_width: usize,
+    beam_depth: usize,
+    time_threshold: u64,
+    clock: &impl Clock,
+) -> Outcome {
+    chokudai_search_timed(state, beam_width, beam_depth, time_threshold, clock, false)
+}
+
+// Chokudai search that iterates until an iteration ends out of time.
+pub(crate) fn chokudai_search_timed<S: BeamState>(
+    state: &S,
+    beam_width: usize,
+    beam_depth: usize,
+    time_threshold: u64,
+    clock: &impl Clock,
+    dedup: bool,
+) -> Outcome {
+    let time_keeper = TimeKeeper::with_clock(time_threshold, clock as &dyn Clock);
+    let run = ChokudaiRun::new(state, beam_width, beam_depth);
+    let limits = SearchLimits::default();
+    let (leaf, _) = run.run(usize::MAX, limits, dedup, None, Some(&time_keeper));
+    line_outcome(state, leaf.as_ref(), CostType::MAX)
+}
+
+#[cfg(test)]
+mod tests {
+    use smallvec::smallvec;
+
+    use super::*;
+    use crate::game::MockClock;
+
+    // `testing::Ladder` without `std`: action 1 pays 1, or 5 once action 0 has been played.
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    struct Ladder {
+        rest: usize,
+        bonus: bool,
+        score: ScoreType,
+        first_action: Option<Action>,
+    }
+
+    impl Ladder {
+        fn new(rest: usize) -> Ladder {
+            Ladder {
+                rest,
+                bonus: false,
+                score: 0,
+                first_action: None,
+            }
+        }
+    }
+
+    impl SinglePlayerState for Ladder {
+        fn is_done(&self) -> bool {
+            self.rest == 0
+        }
+
+        fn advance(&mut self, action: Action) {
+            self.rest -= 1;
+            if action == 0 {
+                self.bonus = true;
+            } else {
+                self.score += if self.bonus { 5 } else { 1 };
+            }
+        }
+
+        fn legal_actions(&self) -> ActionList {
+            smallvec![0, 1]
+        }
+
+        fn score(&self) -> ScoreType {
+            self.score
+        }
+    }
+
+    impl PartialOrd for Ladder {
+        fn partial_cmp(&self, other: &Self) -> Option<core::cmp::Ordering> {
+            Some(self.cmp(other))
+        }
+    }
+
+    impl Ord for Ladder {
+        fn cmp(&self, other: &Self) -> core::cmp::Ordering {
+            self.score.cmp(&other.score)
+        }
+    }
+
+    impl BeamState for Ladder {
+        fn evaluated_score(&self) -> ScoreType {
+            self.score
+        }
+
+        fn evaluate_score(&mut self) {}
+
+        fn first_action(&self) -> Option<Action> {
+            self.first_action
+        }
+
+        fn set_first_action(&mut self, action: Action) {
+            self.first_action = Some(action);
+        }
+
+        fn dedup_key(&self) -> u64 {
+            (self.rest as u64) << 32 | self.score as u64
+        }
+    }
+
+    #[test]
+    fn test_without_std() {
+        // Greedy takes the point now; the searches give it up for the bonus.
+        let state = Ladder::new(3);
+        assert_eq!(greedy_action(&state), Outcome::Action(1));
+        assert_eq!(beam_search_action(&state, 2, 3), Outcome::Action(0));
+        assert_eq!(chokudai_search_action(&state, 1, 3, 4), Outcome::Action(0));
+        let (_, count) = dedup_beam_search_with_nodes(&state, 4, 3);
+        assert!(0 < count.duplicates);
+        let clock = MockClock::ticking(Duration::from_millis(1));
+        assert_eq!(
+            chokudai_search_with_clock(&state, 1, 3, 100, &clock),
+            Outcome::Action(0)
+        );
+    }
+}
@@ -0,0 +1,109 @@
+#![allow(unused)]
+
+use alloc::{vec, vec::Vec};
+use core::cmp::Reverse;
+
+use crate::game::{Action, NodeCount, ScoreType, SinglePlayerState};
+
+// Depth-first branch and bound: the best score within `depth` turns and the first action of
+// its line, like an exhaustive search but skipping every subtree whose `upper_bound` cannot
+// beat the best line so far. `upper_bound(state, depth)` must never be below the best score
+// `depth` more turns can reach from `state`; then the result is optimal.
+pub fn branch_and_bound<S, B>(
+    state: &S,
+    depth: usize,
+    upper_bound: &B,
+) -> (ScoreType, Option<Action>)
+where
+    S: SinglePlayerState,
+    B: Fn(&S, usize) -> ScoreType,
+{
+    branch_and_bound_with_nodes(state, depth, upper_bound).0
+}
+
+// `branch_and_bound` that also counts the nodes; `pruned` are the subtrees the bound cut off.
+pub fn branch_and_bound_with_nodes<S, B>(
+    state: &S,
+    depth: usize,
+    upper_bound: &B,
+) -> ((ScoreType, Option<Action>), NodeCount)
+where
+    S: SinglePlayerState,
+    B: Fn(&S, usize) -> ScoreType,
+{
+    let mut count = NodeCount::default();
+    let mut best = None;
+    search(state, depth, 0, upper_bound, &mut best, &mut count);
+    (best.unwrap_or((state.score(), None)), count)
+}
+
+// Raises `best` with every better line below `state`.
+fn search<S, B>(
+    state: &S,
+    depth: usize,
+    ply: usize,
+    upper_bound: &B,
+    best: &mut Option<(ScoreType, Option<Action>)>,
+    count: &mut NodeCount,
+) where
+    S: SinglePlayerState,
+    B: Fn(&S, usize) -> ScoreType,
+{
+    let legal_actions = state.legal_actions();
+    // A blocked state ends the game with its current score.
+    if state.is_done() || depth == 0 || legal_actions.is_empty() {
+        if best.is_none_or(|(best_score, _)| best_score < state.score()) {
+            *best = Some((state.score(), None));
+        }
+        return;
+    }
+    count.expanded += 1;
+    let mut children: Vec<(ScoreType, Action, S)> = legal_actions
+        .into_iter()
+        .map(|action| {
+            let mut next_state = state.clone();
+            next_state.advance(action);
+            (upper_bound(&next_state, depth - 1), action, next_state)
+        })
+        .collect();
+    count.generated += children.len();
+    count.max_depth = count.max_depth.max(ply + 1);
+    // The most promising child first, so that the first lines already prune well.
+    children.sort_by_key(|&(bound, _, _)| Reverse(bound));
+    for (bound, action, next_state) in children {
+        if best.is_some_and(|(best_score, _)| bound <= best_score) {
+            count.pruned += 1;
+            continue;
+        }
+        let before = best.map(|(score, _)| score);
+        search(&next_state, depth - 1, ply + 1, upper_bound, best, count);
+        // Only the root records which move starts the best line.
+        if ply == 0 && best.map(|(score, _)| score) != before {
+            if let Some((_, first)) = best {
+                *first = Some(action);
+            }
+        }
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use crate::endgame::exhaustive_search;
+    use crate::testing::Ladder;
+
+    #[test]
+    fn test_branch_and_bound() {
+        let state = Ladder::new(8);
+        let bound =
+            |state: &Ladder, depth: usize| state.score + 5 * depth.min(state.rest) as ScoreType;
+        let ((score, action), count) = branch_and_bound_with_nodes(&state, 8, &bound);
+        assert_eq!(Some((score, action)), exhaustive_search(&state, 8));
+        assert_eq!((score, action), (35, Some(0)));
+        assert!(0 < count.pruned);
+        let loose = |_: &Ladder, _: usize| ScoreType::MAX;
+        let ((score, _), count) = branch_and_bound_with_nodes(&state, 8, &loose);
+        assert_eq!(score, 35);
+        assert_eq!(count.pruned, 0);
+    }
+}
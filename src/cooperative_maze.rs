@@ -0,0 +1,319 @@
+#![allow(unused)]
+
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use crate::game::{add_score, splitmix64, ActionList, Outcome, SinglePlayerState};
+use crate::section3::BeamState;
+pub use crate::section3::{beam_search_action, chokudai_search_action};
+
+pub type ScoreType = crate::game::ScoreType;
+pub type Action = usize;
+
+pub const HEIGHT: usize = 5;
+pub const WIDTH: usize = 5;
+pub const END_TURN: usize = 10;
+pub const CHARACTER_N: usize = 3;
+const INF: ScoreType = 1_000_000_000;
+
+const DX: [isize; 4] = [1, -1, 0, 0];
+const DY: [isize; 4] = [0, 0, 1, -1];
+
+// A joint action packs one move per character as base-4 digits, character 0 first.
+pub fn encode(moves: &[Action; CHARACTER_N]) -> Action {
+    moves.iter().rev().fold(0, |code, &m| code * 4 + m)
+}
+
+pub fn decode(mut action: Action) -> [Action; CHARACTER_N] {
+    let mut moves = [0; CHARACTER_N];
+    for m in moves.iter_mut() {
+        *m = action % 4;
+        action /= 4;
+    }
+    moves
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Coord {
+    pub y: usize,
+    pub x: usize,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct CooperativeMazeState {
+    pub game_score: ScoreType,
+    pub evaluated_score: ScoreType,
+    pub first_action: Option<Action>,
+    points: [[ScoreType; WIDTH]; HEIGHT],
+    turn: usize,
+    characters: [Coord; CHARACTER_N],
+}
+
+impl CooperativeMazeState {
+    pub fn new() -> CooperativeMazeState {
+        Self::generate(&mut rand::thread_rng())
+    }
+
+    pub fn with_seed(seed: u64) -> CooperativeMazeState {
+        Self::generate(&mut StdRng::seed_from_u64(seed))
+    }
+
+    fn generate(rng: &mut impl Rng) -> CooperativeMazeState {
+        let mut characters = [Coord::default(); CHARACTER_N];
+        for character in characters.iter_mut() {
+            character.y = rng.gen_range(0..HEIGHT);
+            character.x = rng.gen_range(0..WIDTH);
+        }
+        let mut points = [[0; WIDTH]; HEIGHT];
+        for (y, row) in points.iter_mut().enumerate() {
+            for (x, point) in row.iter_mut().enumerate() {
+                if characters.iter().any(|c| c.y == y && c.x == x) {
+                    continue;
+                }
+                *point = rng.gen_range(0..10);
+            }
+        }
+        CooperativeMazeState {
+            game_score: 0,
+            evaluated_score: 0,
+            first_action: None,
+            points,
+            turn: 0,
+            characters,
+        }
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.turn == END_TURN
+    }
+
+    // Moves every character, then collects; a cell shared by several characters pays once.
+    pub fn advance(&mut self, action: Action) {
+        for (character, m) in self.characters.iter_mut().zip(decode(action)) {
+            character.y = character.y.checked_add_signed(DY[m]).unwrap_or(0);
+            character.x = character.x.checked_add_signed(DX[m]).unwrap_or(0);
+        }
+        for character in self.characters.iter() {
+            let point = &mut self.points[character.y][character.x];
+            self.game_score = add_score(self.game_score, *point);
+            *point = 0;
+        }
+        self.turn += 1;
+    }
+
+    pub fn character_actions(&self, character_id: usize) -> ActionList {
+        let character = self.characters[character_id];
+        (0..4)
+            .filter(|&m| {
+                let ty = character.y.checked_add_signed(DY[m]).unwrap_or(HEIGHT);
+                let tx = character.x.checked_add_signed(DX[m]).unwrap_or(WIDTH);
+                ty < HEIGHT && tx < WIDTH
+            })
+            .collect()
+    }
+
+    // The cartesian product of every character's moves.
+    pub fn legal_actions(&self) -> ActionList {
+        let mut joint = vec![[0; CHARACTER_N]];
+        for id in 0..CHARACTER_N {
+            let moves = self.character_actions(id);
+            joint = joint
+                .iter()
+                .flat_map(|prefix| {
+                    moves.iter().map(move |&m| {
+                        let mut next = *prefix;
+                        next[id] = m;
+                        next
+                    })
+                })
+                .collect();
+        }
+        joint.iter().map(encode).collect()
+    }
+
+    pub fn evaluate_score(&mut self) {
+        self.evaluated_score = self.game_score;
+    }
+}
+
+impl std::fmt::Display for CooperativeMazeState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "turn:\t{}", self.turn)?;
+        writeln!(f, "score:\t{}", self.game_score)?;
+        for h in 0..HEIGHT {
+            for w in 0..WIDTH {
+                let ch = if let Some(id) = self.characters.iter().position(|c| c.y == h && c.x == w)
+                {
+                    (b'A' + id as u8) as char
+                } else if self.points[h][w] > 0 {
+                    (self.points[h][w] as u8 + b'0') as char
+                } else {
+                    '.'
+                };
+                write!(f, "{}", ch)?;
+            }
+            writeln!(f)?
+        }
+        Ok(())
+    }
+}
+
+impl Default for CooperativeMazeState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SinglePlayerState for CooperativeMazeState {
+    fn is_done(&self) -> bool {
+        CooperativeMazeState::is_done(self)
+    }
+
+    fn advance(&mut self, action: Action) {
+        CooperativeMazeState::advance(self, action)
+    }
+
+    fn legal_actions(&self) -> ActionList {
+        CooperativeMazeState::legal_actions(self)
+    }
+
+    fn score(&self) -> ScoreType {
+        self.game_score
+    }
+}
+
+// Joint moves are single actions, so the section3 searches run on this maze as they are.
+impl BeamState for CooperativeMazeState {
+    fn evaluated_score(&self) -> ScoreType {
+        self.evaluated_score
+    }
+
+    fn evaluate_score(&mut self) {
+        CooperativeMazeState::evaluate_score(self)
+    }
+
+    fn first_action(&self) -> Option<Action> {
+        self.first_action
+    }
+
+    fn set_first_action(&mut self, action: Action) {
+        self.first_action = Some(action);
+    }
+
+    fn dedup_key(&self) -> u64 {
+        let cells = self.characters.iter().map(|c| (c.y * WIDTH + c.x) as u64);
+        let points = self.points.iter().flatten().map(|&point| point as u64);
+        cells
+            .chain(points)
+            .fold(self.turn as u64, |key, value| splitmix64(key ^ value))
+    }
+}
+
+impl PartialEq for CooperativeMazeState {
+    fn eq(&self, other: &Self) -> bool {
+        self.evaluated_score == other.evaluated_score
+    }
+}
+
+impl PartialOrd for CooperativeMazeState {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Eq for CooperativeMazeState {}
+
+impl Ord for CooperativeMazeState {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.evaluated_score.cmp(&other.evaluated_score)
+    }
+}
+
+// Every character takes its best neighbour on its own, like the auto-move characters of
+// section4; they happily chase the same point.
+pub fn independent_greedy_action(state: &CooperativeMazeState) -> Outcome {
+    if state.is_done() {
+        return Outcome::NoAction;
+    }
+    let mut moves = [0; CHARACTER_N];
+    for (id, m) in moves.iter_mut().enumerate() {
+        let character = state.characters[id];
+        let mut best_point = -INF;
+        for action in state.character_actions(id) {
+            let y = character.y.checked_add_signed(DY[action]).unwrap();
+            let x = character.x.checked_add_signed(DX[action]).unwrap();
+            if best_point < state.points[y][x] {
+                best_point = state.points[y][x];
+                *m = action;
+            }
+        }
+    }
+    Outcome::Action(encode(&moves))
+}
+
+pub fn greedy_action(state: &CooperativeMazeState) -> Outcome {
+    if state.is_done() {
+        return Outcome::NoAction;
+    }
+    let mut best_score = -INF;
+    let mut best_action = None;
+    for act in state.legal_actions() {
+        let mut next_state = *state;
+        next_state.advance(act);
+        next_state.evaluate_score();
+        if best_score < next_state.evaluated_score {
+            best_score = next_state.evaluated_score;
+            best_action = Some(act);
+        }
+    }
+    best_action.into()
+}
+
+pub fn play_game() {
+    let mut state = CooperativeMazeState::new();
+    println!("{}", state);
+    while !state.is_done() {
+        let Outcome::Action(action) = beam_search_action(&state, 5, 4) else {
+            break;
+        };
+        state.advance(action);
+        println!("{}", state);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::{GameRunner, Summary};
+
+    const GAME_NUMBER: usize = 50;
+
+    fn summary<A: Fn(&CooperativeMazeState) -> Outcome>(agent: A) -> Summary {
+        let mut runner = GameRunner::new(CooperativeMazeState::with_seed, agent);
+        Summary::from_results(&runner.run_episodes(GAME_NUMBER))
+    }
+
+    #[test]
+    fn test_joint_action_encoding() {
+        let state = CooperativeMazeState::with_seed(0);
+        let legal_actions = state.legal_actions();
+        let expected: usize = (0..CHARACTER_N)
+            .map(|id| state.character_actions(id).len())
+            .product();
+        assert_eq!(legal_actions.len(), expected);
+        for action in legal_actions {
+            assert_eq!(encode(&decode(action)), action);
+        }
+    }
+
+    #[test]
+    fn test_joint_search_vs_independent() {
+        let independent = summary(independent_greedy_action);
+        let beam = summary(|s| beam_search_action(s, 5, 4));
+        let chokudai = summary(|s| chokudai_search_action(s, 1, 4, 5));
+        println!("Independent Greedy Score:\t{}", independent.mean);
+        println!("Joint Beam Search Score:\t{}", beam.mean);
+        println!("Joint Chokudai Search Score:\t{}", chokudai.mean);
+        assert!(independent.mean <= beam.mean);
+        assert!(independent.mean <= chokudai.mean);
+    }
+}
@@ -0,0 +1,97 @@
+#![allow(unused)]
+
+use alloc::{vec, vec::Vec};
+use core::{cmp::Reverse, time::Duration};
+
+use crate::game::{Action, Clock, Outcome, ScoreType, SinglePlayerState};
+
+// Limited discrepancy search: trusts the greedy ordering, highest next score first, and only
+// looks at the lines within `depth` turns that leave it at most `discrepancies` times. Returns
+// the best score found and the first action of its line.
+pub fn limited_discrepancy_search<S: SinglePlayerState>(
+    state: &S,
+    depth: usize,
+    discrepancies: usize,
+) -> (ScoreType, Option<Action>) {
+    if state.is_done() || depth == 0 {
+        return (state.score(), None);
+    }
+    let mut children: Vec<(Action, S)> = state
+        .legal_actions()
+        .into_iter()
+        .map(|action| {
+            let mut next_state = state.clone();
+            next_state.advance(action);
+            (action, next_state)
+        })
+        .collect();
+    children.sort_by_key(|(_, next_state)| Reverse(next_state.score()));
+    let mut best: Option<(ScoreType, Option<Action>)> = None;
+    for (i, (action, next_state)) in children.iter().enumerate() {
+        // Every child but the first one is a discrepancy.
+        let left = match (i, discrepancies) {
+            (0, _) => discrepancies,
+            (_, 0) => break,
+            _ => discrepancies - 1,
+        };
+        let (score, _) = limited_discrepancy_search(next_state, depth - 1, left);
+        if best.is_none_or(|(best_score, _)| best_score < score) {
+            best = Some((score, Some(*action)));
+        }
+    }
+    // A blocked state ends the game with its current score.
+    best.unwrap_or((state.score(), None))
+}
+
+pub fn lds_action<S: SinglePlayerState>(state: &S, depth: usize, discrepancies: usize) -> Outcome {
+    limited_discrepancy_search(state, depth, discrepancies)
+        .1
+        .into()
+}
+
+// Iterative LDS: allows 0, 1, 2, ... discrepancies until `time_limit` runs out on `clock` and
+// plays the best line of the last finished run. The first run is the greedy line, so there is
+// always an answer.
+pub fn iterative_lds_with_clock<S: SinglePlayerState>(
+    state: &S,
+    depth: usize,
+    time_limit: Duration,
+    clock: &impl Clock,
+) -> Outcome {
+    let start = clock.now();
+    let (_, mut best_action) = limited_discrepancy_search(state, depth, 0);
+    // More than one discrepancy per turn changes nothing.
+    for discrepancies in 1..=depth {
+        if time_limit <= clock.now() - start {
+            break;
+        }
+        (_, best_action) = limited_discrepancy_search(state, depth, discrepancies);
+    }
+    best_action.into()
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use crate::game::MockClock;
+    use crate::testing::Ladder;
+
+    #[test]
+    fn test_limited_discrepancy_search() {
+        let state = Ladder::new(3);
+        assert_eq!(limited_discrepancy_search(&state, 3, 0), (3, Some(1)));
+        assert_eq!(limited_discrepancy_search(&state, 3, 1), (10, Some(0)));
+        assert_eq!(lds_action(&state, 3, 3), Outcome::Action(0));
+    }
+
+    #[test]
+    fn test_iterative_lds_with_clock() {
+        let state = Ladder::new(4);
+        let clock = MockClock::new();
+        let action = iterative_lds_with_clock(&state, 4, Duration::from_millis(1), &clock);
+        assert_eq!(action, Outcome::Action(0));
+        let clock = MockClock::ticking(Duration::from_millis(1));
+        let action = iterative_lds_with_clock(&state, 4, Duration::from_millis(1), &clock);
+        assert_eq!(action, Outcome::Action(1));
+    }
+}
@@ -0,0 +1,175 @@
+#![allow(unused)]
+
+use alloc::{collections::BTreeMap, vec, vec::Vec};
+
+use crate::game::{Action, ActionList, Outcome, ScoreType, SinglePlayerState};
+
+// Best final score and first action from `state`. Gives up with `None` as soon as a line
+// does not end within `depth` turns, so calling it outside of the endgame is cheap.
+pub fn exhaustive_search<S: SinglePlayerState>(
+    state: &S,
+    depth: usize,
+) -> Option<(ScoreType, Option<Action>)> {
+    if state.is_done() {
+        return Some((state.score(), None));
+    }
+    if depth == 0 {
+        return None;
+    }
+    let mut best: Option<(ScoreType, Option<Action>)> = None;
+    for action in state.legal_actions() {
+        let mut next_state = state.clone();
+        next_state.advance(action);
+        let (score, _) = exhaustive_search(&next_state, depth - 1)?;
+        if best.is_none_or(|(best_score, _)| best_score < score) {
+            best = Some((score, Some(action)));
+        }
+    }
+    // A blocked state ends the game with its current score.
+    Some(best.unwrap_or((state.score(), None)))
+}
+
+// Best score within `depth` turns and the first action of its line, solving every
+// `(key(state), depth)` only once. `key` must tell apart any two states whose futures differ;
+// the gain on top of the current score is what gets shared, so lines that meet again with
+// different scores still share it. Exact, and fast on small boards with short horizons.
+pub fn memoized_search<S, K, F>(state: &S, depth: usize, key: &F) -> (ScoreType, Option<Action>)
+where
+    S: SinglePlayerState,
+    K: Ord,
+    F: Fn(&S) -> K,
+{
+    let mut memo = BTreeMap::new();
+    let (gain, action) = best_gain(state, depth, key, &mut memo);
+    (state.score() + gain, action)
+}
+
+fn best_gain<S, K, F>(
+    state: &S,
+    depth: usize,
+    key: &F,
+    memo: &mut BTreeMap<(K, usize), ScoreType>,
+) -> (ScoreType, Option<Action>)
+where
+    S: SinglePlayerState,
+    K: Ord,
+    F: Fn(&S) -> K,
+{
+    if state.is_done() || depth == 0 {
+        return (0, None);
+    }
+    let mut best: Option<(ScoreType, Option<Action>)> = None;
+    for action in state.legal_actions() {
+        let mut next_state = state.clone();
+        next_state.advance(action);
+        let entry = (key(&next_state), depth - 1);
+        let future = match memo.get(&entry) {
+            Some(&future) => future,
+            None => {
+                let (future, _) = best_gain(&next_state, depth - 1, key, memo);
+                memo.insert(entry, future);
+                future
+            }
+        };
+        let gain = next_state.score() - state.score() + future;
+        if best.is_none_or(|(best_gain, _)| best_gain < gain) {
+            best = Some((gain, Some(action)));
+        }
+    }
+    // A blocked state ends the game with its current score.
+    best.unwrap_or((0, None))
+}
+
+// Plays optimally once at most `k` turns are left and defers to `agent` before that.
+pub fn with_endgame_solver<S, A>(agent: A, k: usize) -> impl Fn(&S) -> Outcome
+where
+    S: SinglePlayerState,
+    A: Fn(&S) -> Outcome,
+{
+    move |state: &S| match exhaustive_search(state, k) {
+        Some((_, action)) => action.into(),
+        None => agent(state),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use smallvec::smallvec;
+
+    use super::*;
+
+    // Action 1 pays 1 now, action 0 pays nothing now but 3 on the last turn.
+    #[derive(Clone)]
+    struct Trap {
+        rest: usize,
+        saved: bool,
+        score: ScoreType,
+    }
+
+    impl SinglePlayerState for Trap {
+        fn is_done(&self) -> bool {
+            self.rest == 0
+        }
+
+        fn advance(&mut self, action: Action) {
+            self.rest -= 1;
+            if action == 0 {
+                self.saved = true;
+            } else {
+                self.score += 1;
+            }
+            if self.rest == 0 && self.saved {
+                self.score += 3;
+            }
+        }
+
+        fn legal_actions(&self) -> ActionList {
+            smallvec![0, 1]
+        }
+
+        fn score(&self) -> ScoreType {
+            self.score
+        }
+    }
+
+    #[test]
+    fn test_exhaustive_search() {
+        let state = Trap {
+            rest: 3,
+            saved: false,
+            score: 0,
+        };
+        assert_eq!(exhaustive_search(&state, 3), Some((5, Some(0))));
+        assert_eq!(exhaustive_search(&state, 2), None);
+    }
+
+    #[test]
+    fn test_memoized_search() {
+        let state = Trap {
+            rest: 6,
+            saved: false,
+            score: 0,
+        };
+        let key = |state: &Trap| (state.rest, state.saved);
+        assert_eq!(memoized_search(&state, 6, &key), (8, Some(0)));
+        assert_eq!(
+            Some(memoized_search(&state, 6, &key)),
+            exhaustive_search(&state, 6)
+        );
+        assert_eq!(memoized_search(&state, 2, &key), (2, Some(1)));
+    }
+
+    #[test]
+    fn test_with_endgame_solver() {
+        let agent = with_endgame_solver(|_: &Trap| Outcome::Action(1), 3);
+        let mut state = Trap {
+            rest: 5,
+            saved: false,
+            score: 0,
+        };
+        while !state.is_done() {
+            state.advance(agent(&state).unwrap());
+        }
+        assert_eq!(state.score, 7);
+    }
+}
@@ -0,0 +1,275 @@
+#![allow(unused)]
+
+use alloc::{vec, vec::Vec};
+
+use crate::game::{Action, ActionList, NodeCount, Outcome};
+
+pub trait StochasticState: Clone {
+    fn is_done(&self) -> bool;
+    fn legal_actions(&self) -> ActionList;
+    // Every possible result of `action` with its probability.
+    fn outcomes(&self, action: Action) -> Vec<(f64, Self)>;
+    // Must stay within `value_bounds` for the Star pruning to be exact.
+    fn evaluate(&self) -> f64;
+    fn value_bounds(&self) -> (f64, f64);
+
+    // Pure transition: `chance` in [0, 1) picks the outcome by cumulative probability, so
+    // callers bring their own randomness. Illegal actions return the state unchanged.
+    fn next_state(&self, action: Action, chance: f64) -> Self {
+        if self.is_done() || !self.legal_actions().contains(&action) {
+            return self.clone();
+        }
+        let outcomes = self.outcomes(action);
+        let mut rest = chance;
+        for (p, state) in outcomes.iter() {
+            if rest < *p {
+                return state.clone();
+            }
+            rest -= p;
+        }
+        outcomes
+            .last()
+            .map_or_else(|| self.clone(), |(_, state)| state.clone())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Pruning {
+    None,
+    Star1,
+    // Star1 plus probing one action of every successor to get lower bounds first.
+    Star2,
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ExpectimaxResult {
+    pub action: Option<Action>,
+    pub value: f64,
+    pub count: NodeCount,
+}
+
+struct Searcher {
+    pruning: Pruning,
+    count: NodeCount,
+    root_depth: usize,
+}
+
+impl Searcher {
+    fn max_value<S: StochasticState>(
+        &mut self,
+        state: &S,
+        depth: usize,
+        alpha: f64,
+        beta: f64,
+    ) -> (f64, Option<Action>) {
+        self.visit(depth);
+        if state.is_done() || depth == 0 {
+            return (state.evaluate(), None);
+        }
+        self.count.expanded += 1;
+        let mut best = (f64::NEG_INFINITY, None);
+        for action in state.legal_actions() {
+            let value = self.chance_value(state, action, depth, alpha.max(best.0), beta);
+            if best.0 < value {
+                best = (value, Some(action));
+            }
+            if self.pruning != Pruning::None && beta <= best.0 {
+                break;
+            }
+        }
+        if best.1.is_none() {
+            return (state.evaluate(), None);
+        }
+        best
+    }
+
+    fn chance_value<S: StochasticState>(
+        &mut self,
+        state: &S,
+        action: Action,
+        depth: usize,
+        alpha: f64,
+        beta: f64,
+    ) -> f64 {
+        let outcomes = state.outcomes(action);
+        let (lower, upper) = state.value_bounds();
+        if self.pruning == Pruning::None {
+            return outcomes
+                .iter()
+                .map(|(p, child)| p * self.max_value(child, depth - 1, lower, upper).0)
+                .sum();
+        }
+        // Lower bounds of the successors: the bound of the value range, or a probed value.
+        let mut lower_bounds = vec![lower; outcomes.len()];
+        if self.pruning == Pruning::Star2 {
+            for (i, (_, child)) in outcomes.iter().enumerate() {
+                lower_bounds[i] = self.probe(child, depth - 1);
+            }
+            let bound: f64 = outcomes
+                .iter()
+                .zip(&lower_bounds)
+                .map(|((p, _), w)| p * w)
+                .sum();
+            if beta <= bound {
+                return bound;
+            }
+        }
+        let mut sum = 0.0;
+        let mut rest_probability = 1.0;
+        let mut rest_lower: f64 = outcomes
+            .iter()
+            .zip(&lower_bounds)
+            .map(|((p, _), w)| p * w)
+            .sum();
+        for (i, (p, child)) in outcomes.iter().enumerate() {
+            rest_probability -= p;
+            rest_lower -= p * lower_bounds[i];
+            let child_alpha = (alpha - sum - rest_probability * upper) / p;
+            let child_beta = (beta - sum - rest_lower) / p;
+            let (value, _) = self.max_value(
+                child,
+                depth - 1,
+                child_alpha.max(lower),
+                child_beta.min(upper),
+            );
+            sum += p * value;
+            if sum + rest_probability * upper <= alpha {
+                return sum + rest_probability * upper;
+            }
+            if beta <= sum + rest_lower {
+                return sum + rest_lower;
+            }
+        }
+        sum
+    }
+
+    // Counts a max node `depth` plies above the horizon.
+    fn visit(&mut self, depth: usize) {
+        self.count.generated += 1;
+        self.count.max_depth = self.count.max_depth.max(self.root_depth - depth);
+    }
+
+    // Value of playing only the first action, a lower bound of the max node.
+    fn probe<S: StochasticState>(&mut self, state: &S, depth: usize) -> f64 {
+        self.visit(depth);
+        if state.is_done() || depth == 0 {
+            return state.evaluate();
+        }
+        let Some(&action) = state.legal_actions().first() else {
+            return state.evaluate();
+        };
+        state
+            .outcomes(action)
+            .iter()
+            .map(|(p, child)| p * self.probe(child, depth - 1))
+            .sum()
+    }
+}
+
+pub fn expectimax<S: StochasticState>(
+    state: &S,
+    depth: usize,
+    pruning: Pruning,
+) -> ExpectimaxResult {
+    let mut searcher = Searcher {
+        pruning,
+        count: NodeCount::default(),
+        root_depth: depth,
+    };
+    let (lower, upper) = state.value_bounds();
+    let (value, action) = searcher.max_value(state, depth, lower, upper);
+    // The root is not a generated node.
+    searcher.count.generated -= 1;
+    ExpectimaxResult {
+        action,
+        value,
+        count: searcher.count,
+    }
+}
+
+pub fn expectimax_action<S: StochasticState>(state: &S, depth: usize, pruning: Pruning) -> Outcome {
+    if state.is_done() {
+        return Outcome::NoAction;
+    }
+    expectimax(state, depth, pruning).action.into()
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use smallvec::smallvec;
+
+    use super::*;
+
+    const END_TURN: usize = 6;
+
+    // Action 0 always gains 1; action 1 gains 3 with probability 0.4 and loses 1 otherwise;
+    // action 2 gains 2 or 0 evenly.
+    #[derive(Clone)]
+    struct DiceGame {
+        turn: usize,
+        total: i64,
+    }
+
+    impl StochasticState for DiceGame {
+        fn is_done(&self) -> bool {
+            self.turn == END_TURN
+        }
+
+        fn legal_actions(&self) -> ActionList {
+            smallvec![0, 1, 2]
+        }
+
+        fn outcomes(&self, action: Action) -> Vec<(f64, Self)> {
+            let next = |gain: i64| DiceGame {
+                turn: self.turn + 1,
+                total: self.total + gain,
+            };
+            match action {
+                0 => vec![(1.0, next(1))],
+                1 => vec![(0.4, next(3)), (0.6, next(-1))],
+                _ => vec![(0.5, next(2)), (0.5, next(0))],
+            }
+        }
+
+        fn evaluate(&self) -> f64 {
+            self.total as f64
+        }
+
+        fn value_bounds(&self) -> (f64, f64) {
+            (-(END_TURN as f64), 3.0 * END_TURN as f64)
+        }
+    }
+
+    #[test]
+    fn test_pruning_keeps_value() {
+        let state = DiceGame { turn: 0, total: 0 };
+        let full = expectimax(&state, END_TURN, Pruning::None);
+        let star1 = expectimax(&state, END_TURN, Pruning::Star1);
+        let star2 = expectimax(&state, END_TURN, Pruning::Star2);
+        assert!((full.value - 6.0).abs() < 1e-9);
+        assert!((star1.value - full.value).abs() < 1e-9);
+        assert!((star2.value - full.value).abs() < 1e-9);
+        assert!(star1.count.generated < full.count.generated);
+        assert!(star1.count.effective_branching_factor() < full.count.effective_branching_factor());
+        println!("Expectimax Nodes (none):\t{}", full.count);
+        println!("Expectimax Nodes (star1):\t{}", star1.count);
+        println!("Expectimax Nodes (star2):\t{}", star2.count);
+    }
+
+    #[test]
+    fn test_expectimax_action() {
+        let state = DiceGame { turn: 0, total: 0 };
+        assert_eq!(
+            expectimax_action(&state, 2, Pruning::Star1),
+            Outcome::Action(0)
+        );
+        let done = DiceGame {
+            turn: END_TURN,
+            total: 0,
+        };
+        assert_eq!(
+            expectimax_action(&done, 2, Pruning::Star1),
+            Outcome::NoAction
+        );
+    }
+}
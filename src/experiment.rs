@@ -0,0 +1,329 @@
+#![allow(unused)]
+
+use std::{collections::BTreeMap, fs, io, path::Path, str::FromStr};
+
+use toml::{Table, Value};
+
+use crate::game::Outcome;
+use crate::instances::{instance, suite, Difficulty};
+use crate::leaderboard::{Leaderboard, Run};
+use crate::section3::{
+    beam_search_action, chokudai_search_action, greedy_action, MazeState, END_TURN,
+};
+
+// An experiment read from a TOML file, e.g.
+//
+//     name = "beam widths"
+//     output = "leaderboard.jsonl"
+//
+//     [environment]
+//     instances = ["easy", "hard-2"]
+//     seeds = [0, 1, 2]
+//
+//     [[agents]]
+//     algorithm = "beam"
+//     grid = { width = [2, 5], depth = [10, 100] }
+//
+// `instances` takes instance ids and difficulties, which stand for their whole suite, and
+// `seeds` adds `MazeState::with_seed` mazes. Every agent is run once per point of its grid, so
+// the file above gives four runs, each on six mazes.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Experiment {
+    pub name: String,
+    // The leaderboard the runs are recorded in.
+    pub output: Option<String>,
+    pub environments: Vec<(u64, MazeState)>,
+    pub agents: Vec<AgentConfig>,
+}
+
+// One set of parameters, sorted by name.
+pub type Params = BTreeMap<String, i64>;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct AgentConfig {
+    pub algorithm: String,
+    pub grid: BTreeMap<String, Vec<i64>>,
+}
+
+impl AgentConfig {
+    // Every combination of the grid values; a single empty set for an empty grid.
+    pub fn points(&self) -> Vec<Params> {
+        let mut points = vec![Params::new()];
+        for (name, values) in self.grid.iter() {
+            points = points
+                .iter()
+                .flat_map(|point| {
+                    values.iter().map(move |&value| {
+                        let mut point = point.clone();
+                        point.insert(name.clone(), value);
+                        point
+                    })
+                })
+                .collect();
+        }
+        points
+    }
+}
+
+// Unknown algorithms are caught while parsing, so that a typo does not surface halfway through
+// a long experiment.
+const ALGORITHMS: [&str; 3] = ["greedy", "beam", "chokudai"];
+
+pub type Agent = Box<dyn Fn(&MazeState) -> Outcome>;
+
+// The agent of `algorithm` with `params`; missing parameters take the defaults of the book.
+pub fn agent(algorithm: &str, params: &Params) -> Option<Agent> {
+    let param = |name: &str, default: i64| params.get(name).copied().unwrap_or(default).max(1);
+    let width = param("width", 2) as usize;
+    let depth = param("depth", END_TURN as i64);
+    Some(match algorithm {
+        "greedy" => Box::new(greedy_action),
+        "beam" => Box::new(move |s: &MazeState| beam_search_action(s, width, depth as u64)),
+        "chokudai" => {
+            let number = param("number", 2) as usize;
+            Box::new(move |s: &MazeState| chokudai_search_action(s, width, depth as usize, number))
+        }
+        _ => return None,
+    })
+}
+
+fn format_params(params: &Params) -> String {
+    params
+        .iter()
+        .map(|(name, value)| format!("{}={}", name, value))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+impl Experiment {
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Experiment> {
+        fs::read_to_string(path)?
+            .parse()
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    // One run per agent and grid point, in file order, recorded in `output` if it is set.
+    pub fn run(&self) -> io::Result<Vec<Run>> {
+        let mut leaderboard = self.output.as_ref().map(Leaderboard::open).transpose()?;
+        let mut runs = vec![];
+        for config in self.agents.iter() {
+            for params in config.points() {
+                let agent = agent(&config.algorithm, &params).unwrap();
+                let (seeds, scores) = self
+                    .environments
+                    .iter()
+                    .map(|&(seed, state)| {
+                        let mut state = state;
+                        while !state.is_done() {
+                            let Outcome::Action(action) = agent(&state) else {
+                                break;
+                            };
+                            state.advance(action);
+                        }
+                        (seed, state.game_score)
+                    })
+                    .unzip();
+                let run = Run::new(&config.algorithm, &format_params(&params), seeds, scores);
+                if let Some(leaderboard) = leaderboard.as_mut() {
+                    leaderboard.record(run.clone())?;
+                }
+                runs.push(run);
+            }
+        }
+        Ok(runs)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseExperimentError(pub String);
+
+impl std::fmt::Display for ParseExperimentError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "invalid experiment: {}", self.0)
+    }
+}
+
+impl std::error::Error for ParseExperimentError {}
+
+fn error<T>(message: impl Into<String>) -> Result<T, ParseExperimentError> {
+    Err(ParseExperimentError(message.into()))
+}
+
+fn array<'a>(table: &'a Table, key: &str) -> Result<&'a [Value], ParseExperimentError> {
+    match table.get(key) {
+        None => Ok(&[]),
+        Some(Value::Array(values)) => Ok(values),
+        Some(_) => error(format!("'{}' is not an array", key)),
+    }
+}
+
+fn integers(table: &Table, key: &str) -> Result<Vec<i64>, ParseExperimentError> {
+    array(table, key)?
+        .iter()
+        .map(|value| match value {
+            Value::Integer(n) => Ok(*n),
+            _ => error(format!("'{}' has a value that is not an integer", key)),
+        })
+        .collect()
+}
+
+fn string(table: &Table, key: &str) -> Result<Option<String>, ParseExperimentError> {
+    match table.get(key) {
+        None => Ok(None),
+        Some(Value::String(s)) => Ok(Some(s.clone())),
+        Some(_) => error(format!("'{}' is not a string", key)),
+    }
+}
+
+fn environments(table: &Table) -> Result<Vec<(u64, MazeState)>, ParseExperimentError> {
+    let mut environments = vec![];
+    for value in array(table, "instances")? {
+        let Value::String(id) = value else {
+            return error("'instances' has a value that is not a string");
+        };
+        let difficulty = match id.as_str() {
+            "easy" => Some(Difficulty::Easy),
+            "medium" => Some(Difficulty::Medium),
+            "hard" => Some(Difficulty::Hard),
+            _ => None,
+        };
+        match (difficulty, instance(id)) {
+            (Some(difficulty), _) => environments
+                .extend(suite(difficulty).map(|instance| (instance.seed, instance.state()))),
+            (None, Some(instance)) => environments.push((instance.seed, instance.state())),
+            (None, None) => return error(format!("unknown instance '{}'", id)),
+        }
+    }
+    for seed in integers(table, "seeds")? {
+        let Ok(seed) = u64::try_from(seed) else {
+            return error(format!("negative seed {}", seed));
+        };
+        environments.push((seed, MazeState::with_seed(seed)));
+    }
+    Ok(environments)
+}
+
+fn agent_config(value: &Value) -> Result<AgentConfig, ParseExperimentError> {
+    let Value::Table(table) = value else {
+        return error("an agent is not a table");
+    };
+    let Some(algorithm) = string(table, "algorithm")? else {
+        return error("an agent has no algorithm");
+    };
+    if !ALGORITHMS.contains(&algorithm.as_str()) {
+        return error(format!("unknown algorithm '{}'", algorithm));
+    }
+    let mut grid = BTreeMap::new();
+    match table.get("grid") {
+        None => {}
+        Some(Value::Table(parameters)) => {
+            for (name, value) in parameters.iter() {
+                let values = match value {
+                    Value::Integer(n) => vec![*n],
+                    Value::Array(_) => integers(parameters, name)?,
+                    _ => return error(format!("parameter '{}' is not an integer", name)),
+                };
+                grid.insert(name.clone(), values);
+            }
+        }
+        Some(_) => return error("'grid' is not a table"),
+    }
+    Ok(AgentConfig { algorithm, grid })
+}
+
+impl FromStr for Experiment {
+    type Err = ParseExperimentError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let table: Table = s
+            .parse()
+            .map_err(|e: toml::de::Error| ParseExperimentError(e.message().to_string()))?;
+        let environments = match table.get("environment") {
+            None => vec![],
+            Some(Value::Table(environment)) => environments(environment)?,
+            Some(_) => return error("'environment' is not a table"),
+        };
+        if environments.is_empty() {
+            return error("no environment");
+        }
+        let agents = array(&table, "agents")?
+            .iter()
+            .map(agent_config)
+            .collect::<Result<Vec<_>, _>>()?;
+        if agents.is_empty() {
+            return error("no agent");
+        }
+        Ok(Experiment {
+            name: string(&table, "name")?.unwrap_or_default(),
+            output: string(&table, "output")?,
+            environments,
+            agents,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXPERIMENT: &str = r#"
+        name = "beam widths"
+
+        [environment]
+        instances = ["easy", "hard-2"]
+        seeds = [0]
+
+        [[agents]]
+        algorithm = "greedy"
+
+        [[agents]]
+        algorithm = "beam"
+        grid = { width = [1, 2], depth = 3 }
+    "#;
+
+    #[test]
+    fn test_parse_experiment() {
+        let experiment: Experiment = EXPERIMENT.parse().unwrap();
+        assert_eq!(experiment.name, "beam widths");
+        assert_eq!(experiment.output, None);
+        let seeds: Vec<u64> = experiment.environments.iter().map(|e| e.0).collect();
+        assert_eq!(seeds, vec![4, 11, 1, 8, 0]);
+        assert_eq!(experiment.agents[0].points(), vec![Params::new()]);
+        let points = experiment.agents[1].points();
+        assert_eq!(points.len(), 2);
+        assert_eq!(format_params(&points[1]), "depth=3 width=2");
+
+        for (text, message) in [
+            ("[[agents]]\nalgorithm = \"greedy\"", "no environment"),
+            ("[environment]\nseeds = [1]", "no agent"),
+            (
+                "[environment]\ninstances = [\"easy-9\"]",
+                "unknown instance",
+            ),
+            (
+                "[environment]\nseeds = [1]\n[[agents]]\nalgorithm = \"dfs\"",
+                "unknown algorithm",
+            ),
+            ("[environment]\nseeds = [-1]", "negative seed"),
+            ("name = ", ""),
+        ] {
+            let e = text.parse::<Experiment>().unwrap_err();
+            assert!(e.0.contains(message), "{}", e);
+        }
+    }
+
+    #[test]
+    fn test_run_experiment() {
+        let path = std::env::temp_dir().join(format!("experiment-{}.jsonl", std::process::id()));
+        let _ = fs::remove_file(&path);
+        let mut experiment: Experiment = EXPERIMENT.parse().unwrap();
+        experiment.output = Some(path.to_string_lossy().into_owned());
+        let runs = experiment.run().unwrap();
+        assert_eq!(runs.len(), 3);
+        // The easy suite greedy scores, see `instances::INSTANCES`.
+        assert_eq!(runs[0].scores[..3], [301, 313, 455]);
+        assert_eq!(runs[2].params, "depth=3 width=2");
+        assert_eq!(Leaderboard::open(&path).unwrap().runs(), &runs[..]);
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,107 @@
+#![allow(unused)]
+
+use std::collections::BinaryHeap;
+
+use crate::game::Outcome;
+use crate::learning::{Features, WeightedEvaluator};
+use crate::section3::{greedy_action, MazeState, ScoreType};
+
+// Evaluates many states in one call so that a model (e.g. candle or tch) can run them as a
+// single batch. Higher values are better.
+pub trait ExternalEvaluator<S> {
+    fn evaluate_batch(&self, states: &[S]) -> Vec<f64>;
+}
+
+impl<S: Features> ExternalEvaluator<S> for WeightedEvaluator {
+    fn evaluate_batch(&self, states: &[S]) -> Vec<f64> {
+        states
+            .iter()
+            .map(|state| self.evaluate(&state.features()))
+            .collect()
+    }
+}
+
+// Same as `beam_search_action`, but every depth is evaluated with one batched call.
+pub fn external_beam_search_action(
+    state: &MazeState,
+    beam_width: usize,
+    beam_depth: u64,
+    evaluator: &impl ExternalEvaluator<MazeState>,
+) -> Outcome {
+    if state.is_done() {
+        return Outcome::NoAction;
+    }
+    let mut now_beam = BinaryHeap::new();
+    let mut best_state = *state;
+    now_beam.push(*state);
+    for d in 0..beam_depth {
+        let mut children = vec![];
+        for _ in 0..beam_width {
+            let Some(now_state) = now_beam.pop() else {
+                break;
+            };
+            for act in now_state.legal_actions() {
+                let mut next_state = now_state;
+                next_state.advance(act);
+                if d == 0 {
+                    next_state.first_action = Some(act);
+                }
+                children.push(next_state);
+            }
+        }
+        let values = evaluator.evaluate_batch(&children);
+        let mut next_beam = BinaryHeap::new();
+        for (mut child, value) in children.into_iter().zip(values) {
+            child.evaluated_score = (value * 1000.0).round() as ScoreType;
+            next_beam.push(child);
+        }
+        now_beam = next_beam;
+        let Some(top) = now_beam.peek() else {
+            break;
+        };
+        best_state = *top;
+        if best_state.is_done() {
+            break;
+        }
+    }
+    match best_state.first_action {
+        Some(action) => Outcome::Action(action),
+        None => greedy_action(state),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::section3::END_TURN;
+    use std::cell::Cell;
+
+    struct ScoreEvaluator {
+        calls: Cell<usize>,
+    }
+
+    impl ExternalEvaluator<MazeState> for ScoreEvaluator {
+        fn evaluate_batch(&self, states: &[MazeState]) -> Vec<f64> {
+            self.calls.set(self.calls.get() + 1);
+            states.iter().map(|s| s.game_score as f64).collect()
+        }
+    }
+
+    #[test]
+    fn test_external_beam_search_batches_per_depth() {
+        let state = MazeState::with_seed(0);
+        let evaluator = ScoreEvaluator {
+            calls: Cell::new(0),
+        };
+        let outcome = external_beam_search_action(&state, 3, 5, &evaluator);
+        assert_ne!(outcome, Outcome::NoAction);
+        assert_eq!(evaluator.calls.get(), 5);
+    }
+
+    #[test]
+    fn test_weighted_evaluator_as_external() {
+        let states = [MazeState::with_seed(1), MazeState::with_seed(2)];
+        let evaluator = WeightedEvaluator::new(vec![0.0, 0.0, 1.0]);
+        assert_eq!(evaluator.evaluate_batch(&states), vec![END_TURN as f64; 2]);
+    }
+}
@@ -0,0 +1,1110 @@
+#![allow(unused)]
+
+use core::{cell::Cell, ops::ControlFlow, time::Duration};
+#[cfg(feature = "std")]
+use std::{
+    io::Write,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex,
+    },
+    thread,
+    time::Instant,
+};
+
+#[cfg(feature = "std")]
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use smallvec::SmallVec;
+
+// i128 keeps very long experiments exact at some cost in speed.
+#[cfg(not(feature = "score-i128"))]
+pub type ScoreType = i64;
+#[cfg(feature = "score-i128")]
+pub type ScoreType = i128;
+pub type Action = usize;
+// Legal moves live on the stack: a maze never has more than four, and games with more spill
+// to the heap.
+pub type ActionList = SmallVec<[Action; 4]>;
+
+pub type CostType = u64;
+
+// `score + point` for every game score. With `saturating-score` the sum sticks at the
+// bounds; otherwise an overflow panics in debug builds and wraps in release builds.
+#[inline]
+pub fn add_score(score: ScoreType, point: ScoreType) -> ScoreType {
+    if cfg!(feature = "saturating-score") {
+        return score.saturating_add(point);
+    }
+    if cfg!(debug_assertions) {
+        return score
+            .checked_add(point)
+            .unwrap_or_else(|| panic!("score overflow: {} + {}", score, point));
+    }
+    score.wrapping_add(point)
+}
+
+// Called with every new best state, its score and the iteration it was found at. Returning
+// `ControlFlow::Break` stops the search, which then returns its best so far.
+pub type ImprovementHook<'a, S> = dyn FnMut(&S, ScoreType, usize) -> ControlFlow<()> + 'a;
+
+// Returns `Break` once a state reaches `target`, for any search that takes an
+// `ImprovementHook`: the hooked Chokudai search and anytime A* of section3, and the local
+// searches of section4.
+pub fn stop_at_score<S>(target: ScoreType) -> impl FnMut(&S, ScoreType, usize) -> ControlFlow<()> {
+    move |_, score, _| {
+        if target <= score {
+            ControlFlow::Break(())
+        } else {
+            ControlFlow::Continue(())
+        }
+    }
+}
+
+// Makes a child from two parents of a population method. Every problem picks its own, since
+// what a part of a solution is depends on how it is encoded.
+pub trait Crossover<S> {
+    fn crossover(&self, a: &S, b: &S, rng: &mut impl Rng) -> S;
+}
+
+// A steady genetic algorithm maximizing `fitness`: `random` makes the first `population`
+// members, and each generation keeps the best one and fills the rest with children of two
+// tournament winners, each changed by `mutate`. Returns the best member of the last generation.
+#[cfg(feature = "std")]
+pub fn genetic_algorithm<S: Clone, R: Rng>(
+    population: usize,
+    generation: usize,
+    crossover: &impl Crossover<S>,
+    random: impl Fn(&mut R) -> S,
+    mutate: impl Fn(&mut S, &mut R),
+    fitness: impl Fn(&S) -> ScoreType,
+    rng: &mut R,
+) -> S {
+    let size = population.max(2);
+    let mut members: Vec<(ScoreType, S)> = (0..size)
+        .map(|_| {
+            let member = random(rng);
+            (fitness(&member), member)
+        })
+        .collect();
+    for _ in 0..generation {
+        members.sort_by_key(|(score, _)| core::cmp::Reverse(*score));
+        let mut next = vec![members[0].clone()];
+        while next.len() < size {
+            let mut tournament = || {
+                let (i, j) = (rng.gen_range(0..size), rng.gen_range(0..size));
+                i.min(j)
+            };
+            let (a, b) = (tournament(), tournament());
+            let mut child = crossover.crossover(&members[a].1, &members[b].1, rng);
+            mutate(&mut child, rng);
+            next.push((fitness(&child), child));
+        }
+        members = next;
+    }
+    members
+        .into_iter()
+        .max_by_key(|(score, _)| *score)
+        .unwrap()
+        .1
+}
+
+// Limits a search checks while it runs. `budget` caps the total action cost of a line,
+// `stop_at_score` returns as soon as a line reaches that game score and `max_nodes` stops
+// after that many expansions, a time limit that is the same on every machine. The beam and
+// Chokudai searches take all three. `LocalSearch`, and the section4 optimizers built on it,
+// count a neighbour as a node and have no costs, and MCTS through `MctsConfig::limits` only
+// counts expansions. The other optimizers stop early through an `ImprovementHook` such as
+// `stop_at_score` instead.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SearchLimits {
+    pub budget: CostType,
+    pub stop_at_score: Option<ScoreType>,
+    pub max_nodes: Option<usize>,
+}
+
+impl SearchLimits {
+    pub fn reached(&self, score: ScoreType) -> bool {
+        self.stop_at_score.is_some_and(|target| target <= score)
+    }
+
+    pub fn out_of_nodes(&self, expanded: usize) -> bool {
+        self.max_nodes
+            .is_some_and(|max_nodes| max_nodes <= expanded)
+    }
+}
+
+impl Default for SearchLimits {
+    fn default() -> Self {
+        SearchLimits {
+            budget: CostType::MAX,
+            stop_at_score: None,
+            max_nodes: None,
+        }
+    }
+}
+
+// Where time-limited searches read the time: any monotonic time since an arbitrary origin.
+pub trait Clock {
+    fn now(&self) -> Duration;
+}
+
+impl<C: Clock + ?Sized> Clock for &C {
+    fn now(&self) -> Duration {
+        (**self).now()
+    }
+}
+
+// Wall-clock time since the clock was created.
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, Copy)]
+pub struct SystemClock {
+    origin: Instant,
+}
+
+#[cfg(feature = "std")]
+impl SystemClock {
+    pub fn new() -> SystemClock {
+        SystemClock {
+            origin: Instant::now(),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl Default for SystemClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "std")]
+impl Clock for SystemClock {
+    fn now(&self) -> Duration {
+        self.origin.elapsed()
+    }
+}
+
+// Time that only moves when told to: by `advance`, and by `tick` after every reading. Makes
+// time-limited searches deterministic in tests.
+#[derive(Debug, Clone, Default)]
+pub struct MockClock {
+    now: Cell<Duration>,
+    tick: Duration,
+}
+
+impl MockClock {
+    pub fn new() -> MockClock {
+        MockClock::default()
+    }
+
+    pub fn ticking(tick: Duration) -> MockClock {
+        MockClock {
+            now: Cell::new(Duration::ZERO),
+            tick,
+        }
+    }
+
+    pub fn advance(&self, duration: Duration) {
+        self.now.set(self.now.get() + duration);
+    }
+}
+
+impl Clock for MockClock {
+    fn now(&self) -> Duration {
+        let now = self.now.get();
+        self.now.set(now + self.tick);
+        now
+    }
+}
+
+// One step of SplitMix64: a well-mixed 64-bit value for every input, so neighbouring seeds
+// give unrelated results.
+pub fn splitmix64(x: u64) -> u64 {
+    let mut z = x.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+// A tree of seeds: `stream(i)` derives the i-th substream, which derives its own, so an
+// experiment seeds every game, thread and restart from one number and reproduces exactly
+// however the work is scheduled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct RngStreams {
+    seed: u64,
+}
+
+impl RngStreams {
+    pub const fn new(seed: u64) -> RngStreams {
+        RngStreams { seed }
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    pub fn stream(&self, index: u64) -> RngStreams {
+        RngStreams::new(splitmix64(splitmix64(self.seed) ^ index))
+    }
+
+    // A ChaCha8 generator for this stream. Named rather than `StdRng`, whose algorithm rand
+    // may change between versions, so a stream draws the same numbers on every build.
+    pub fn rng(&self) -> ChaCha8Rng {
+        ChaCha8Rng::seed_from_u64(self.seed)
+    }
+}
+
+// A chess clock with increment: `remaining` to spend, and `increment` more after every move.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FischerClock {
+    pub remaining: Duration,
+    pub increment: Duration,
+}
+
+impl FischerClock {
+    pub fn new(base: Duration, increment: Duration) -> FischerClock {
+        FischerClock {
+            remaining: base,
+            increment,
+        }
+    }
+
+    // What the next move may take: an even share of the time left over `moves_left` moves,
+    // plus the increment it gets back, but never all of the time left.
+    pub fn move_budget(&self, moves_left: usize) -> Duration {
+        let share = self.remaining / moves_left.max(1) as u32 + self.increment;
+        share.min(self.remaining * 9 / 10)
+    }
+
+    // Charges a move that took `elapsed`. Returns false, with no time left, when the move
+    // took longer than the time there was.
+    pub fn punch(&mut self, elapsed: Duration) -> bool {
+        if self.remaining < elapsed {
+            self.remaining = Duration::ZERO;
+            return false;
+        }
+        self.remaining = self.remaining - elapsed + self.increment;
+        true
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    Action(Action),
+    NoAction,
+}
+
+impl Outcome {
+    pub fn unwrap(self) -> Action {
+        match self {
+            Outcome::Action(action) => action,
+            Outcome::NoAction => panic!("called `Outcome::unwrap()` on a `NoAction` value"),
+        }
+    }
+}
+
+impl From<Option<Action>> for Outcome {
+    fn from(action: Option<Action>) -> Self {
+        action.map_or(Outcome::NoAction, Outcome::Action)
+    }
+}
+
+pub trait SinglePlayerState: Clone {
+    fn is_done(&self) -> bool;
+    fn advance(&mut self, action: Action);
+    fn legal_actions(&self) -> ActionList;
+    fn score(&self) -> ScoreType;
+
+    // Every action costs one unless the game says otherwise.
+    fn action_cost(&self, action: Action) -> CostType {
+        1
+    }
+
+    // Total cost of the actions taken so far; games that do not track it never run out.
+    fn spent(&self) -> CostType {
+        0
+    }
+
+    // Legal actions that keep the total cost within `budget`.
+    fn affordable_actions(&self, budget: CostType) -> ActionList {
+        self.legal_actions()
+            .into_iter()
+            .filter(|&action| self.spent().saturating_add(self.action_cost(action)) <= budget)
+            .collect()
+    }
+
+    // Pure transition for fuzzing and model checking: an illegal action, or any action once
+    // the game is done, returns the state unchanged instead of panicking.
+    fn next_state(&self, action: Action) -> Self {
+        let mut next_state = self.clone();
+        if !self.is_done() && self.legal_actions().contains(&action) {
+            next_state.advance(action);
+        }
+        next_state
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WinningStatus {
+    Win,
+    Lose,
+    Draw,
+    None,
+}
+
+// Everything is seen from the side of the player to move.
+pub trait TwoPlayerState: Clone {
+    fn is_done(&self) -> bool;
+    fn advance(&mut self, action: Action);
+    fn legal_actions(&self) -> ActionList;
+    fn winning_status(&self) -> WinningStatus;
+
+    // False for states whose evaluation is about to swing, say right after a capture that can
+    // be taken back. Searches that reach their horizon in such a state look a little further
+    // instead of trusting the evaluation there.
+    fn is_quiet(&self) -> bool {
+        true
+    }
+
+    // The value of a finished game for the side to move, from 0 for a loss to 1 for a win, and
+    // None while it goes on. Games that score more finely than the winning status override it.
+    fn terminal_value(&self) -> Option<f64> {
+        match self.winning_status() {
+            WinningStatus::Win => Some(1.0),
+            WinningStatus::Lose => Some(0.0),
+            WinningStatus::Draw => Some(0.5),
+            WinningStatus::None => None,
+        }
+    }
+
+    // Same as `SinglePlayerState::next_state`.
+    fn next_state(&self, action: Action) -> Self {
+        let mut next_state = self.clone();
+        if !self.is_done() && self.legal_actions().contains(&action) {
+            next_state.advance(action);
+        }
+        next_state
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NodeCount {
+    pub expanded: usize,
+    pub generated: usize,
+    // Generated states that the search threw away without expanding them: cut off by a beam's
+    // width or left open when it stopped.
+    pub pruned: usize,
+    // Deepest level the search generated a node at.
+    pub max_depth: usize,
+    // Largest total size of the open beams or heaps, from their lengths.
+    pub peak_bytes: usize,
+    // Children dropped as they were generated because a state with the same hash was already
+    // seen at that depth. They count as neither generated nor pruned.
+    pub duplicates: usize,
+}
+
+impl NodeCount {
+    // Children per expanded node.
+    pub fn branching_factor(&self) -> f64 {
+        self.generated as f64 / self.expanded.max(1) as f64
+    }
+
+    // The b* of a uniform tree of depth `max_depth` with as many nodes as were generated:
+    // generated = b* + b*^2 + ... + b*^max_depth. Lower means better pruning.
+    pub fn effective_branching_factor(&self) -> f64 {
+        if self.max_depth == 0 || self.generated == 0 {
+            return 0.0;
+        }
+        let nodes = |b: f64| {
+            (0..self.max_depth)
+                .scan(1.0, |power, _| {
+                    *power *= b;
+                    Some(*power)
+                })
+                .sum::<f64>()
+        };
+        let (mut low, mut high) = (0.0, self.generated as f64);
+        for _ in 0..100 {
+            let middle = (low + high) / 2.0;
+            if nodes(middle) < self.generated as f64 {
+                low = middle;
+            } else {
+                high = middle;
+            }
+        }
+        (low + high) / 2.0
+    }
+}
+
+impl core::fmt::Display for NodeCount {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(
+            f,
+            "expanded: {}\tgenerated: {}\tpruned: {}\tdepth: {}\tebf: {:.3}",
+            self.expanded,
+            self.generated,
+            self.pruned,
+            self.max_depth,
+            self.effective_branching_factor()
+        )
+    }
+}
+
+// Plays a line searched ahead of time one move per call, and searches again with `planner`
+// from the state it is given once the line goes stale: its next move is no longer legal, or
+// would no longer change the score by what it did when the line was planned, for instance
+// because the points it was heading for are gone. Also searches again when the line runs out.
+#[cfg(feature = "std")]
+pub struct PlanExecutor<P> {
+    planner: P,
+    // The moves left to play with the score change each was planned to bring, next one last.
+    steps: Vec<(Action, ScoreType)>,
+    replans: usize,
+}
+
+#[cfg(feature = "std")]
+impl<P> PlanExecutor<P> {
+    pub fn new(planner: P) -> PlanExecutor<P> {
+        PlanExecutor {
+            planner,
+            steps: vec![],
+            replans: 0,
+        }
+    }
+
+    // How many lines were dropped before they ran out.
+    pub fn replans(&self) -> usize {
+        self.replans
+    }
+
+    // The moves left in the current line.
+    pub fn plan(&self) -> Vec<Action> {
+        self.steps.iter().rev().map(|&(action, _)| action).collect()
+    }
+
+    pub fn action<S: SinglePlayerState>(&mut self, state: &S) -> Outcome
+    where
+        P: FnMut(&S) -> Vec<Action>,
+    {
+        if state.is_done() {
+            return Outcome::NoAction;
+        }
+        if let Some((action, delta)) = self.steps.pop() {
+            if state.legal_actions().contains(&action) {
+                let mut next_state = state.clone();
+                next_state.advance(action);
+                if next_state.score() - state.score() == delta {
+                    return Outcome::Action(action);
+                }
+            }
+            self.steps.clear();
+            self.replans += 1;
+        }
+        // Only the prefix of the new line that can be played is kept.
+        let mut now_state = state.clone();
+        for action in (self.planner)(state) {
+            if now_state.is_done() || !now_state.legal_actions().contains(&action) {
+                break;
+            }
+            let score = now_state.score();
+            now_state.advance(action);
+            self.steps.push((action, now_state.score() - score));
+        }
+        self.steps.reverse();
+        self.steps.pop().map(|(action, _)| action).into()
+    }
+}
+
+// A bar on stderr with the done/total count, the rate, the ETA and the best score so far,
+// with the `progress` feature; without it every call does nothing. Hidden when stderr is not a
+// terminal. Shared between threads by reference.
+#[cfg(feature = "std")]
+#[derive(Debug)]
+pub struct Progress {
+    #[cfg(feature = "progress")]
+    bar: indicatif::ProgressBar,
+}
+
+#[cfg(feature = "std")]
+impl Progress {
+    #[allow(unused_variables)]
+    pub fn new(label: &str, total: usize) -> Progress {
+        #[cfg(feature = "progress")]
+        {
+            let bar = indicatif::ProgressBar::new(total as u64);
+            let style = indicatif::ProgressStyle::with_template(
+                "{prefix} {bar:30} {pos}/{len} {per_sec} ETA {eta} {msg}",
+            )
+            .unwrap();
+            bar.set_style(style);
+            bar.set_prefix(label.to_string());
+            Progress { bar }
+        }
+        #[cfg(not(feature = "progress"))]
+        Progress {}
+    }
+
+    #[allow(unused_variables)]
+    pub fn inc(&self, n: usize) {
+        #[cfg(feature = "progress")]
+        self.bar.inc(n as u64);
+    }
+
+    #[allow(unused_variables)]
+    pub fn set_best(&self, score: ScoreType) {
+        #[cfg(feature = "progress")]
+        self.bar.set_message(format!("best: {}", score));
+    }
+
+    // Leaves the final counts on screen.
+    pub fn finish(&self) {
+        #[cfg(feature = "progress")]
+        self.bar.finish();
+    }
+}
+
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, Copy)]
+pub struct GameResult {
+    pub seed: u64,
+    pub score: ScoreType,
+    pub turns: usize,
+    pub duration: Duration,
+}
+
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, Copy)]
+pub struct Summary {
+    pub games: usize,
+    pub mean: f64,
+    pub min: ScoreType,
+    pub max: ScoreType,
+    pub mean_duration: Duration,
+}
+
+#[cfg(feature = "std")]
+impl Summary {
+    pub fn from_results(results: &[GameResult]) -> Summary {
+        let games = results.len();
+        let mean = results.iter().map(|r| r.score as f64).sum::<f64>() / games.max(1) as f64;
+        let min = results.iter().map(|r| r.score).min().unwrap_or(0);
+        let max = results.iter().map(|r| r.score).max().unwrap_or(0);
+        let total: Duration = results.iter().map(|r| r.duration).sum();
+        Summary {
+            games,
+            mean,
+            min,
+            max,
+            mean_duration: total / games.max(1) as u32,
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::fmt::Display for Summary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "games: {}\tmean: {}\tmin: {}\tmax: {}\ttime: {:?}",
+            self.games, self.mean, self.min, self.max, self.mean_duration
+        )
+    }
+}
+
+// What a `GameRunner` agent returns: the move, and the nodes its search took when it counts
+// them, as the `_with_nodes` searches do.
+#[cfg(feature = "std")]
+pub trait Decision {
+    fn outcome(&self) -> Outcome;
+
+    fn nodes(&self) -> Option<NodeCount> {
+        None
+    }
+}
+
+#[cfg(feature = "std")]
+impl Decision for Outcome {
+    fn outcome(&self) -> Outcome {
+        *self
+    }
+}
+
+#[cfg(feature = "std")]
+impl Decision for (Outcome, NodeCount) {
+    fn outcome(&self) -> Outcome {
+        self.0
+    }
+
+    fn nodes(&self) -> Option<NodeCount> {
+        Some(self.1)
+    }
+}
+
+// What a runner knows about one played turn; `state` is the state after `action`.
+#[cfg(feature = "std")]
+pub struct TurnEvent<'s, S> {
+    pub seed: u64,
+    pub turn: usize,
+    pub action: Action,
+    pub state: &'s S,
+    // Time the agent took to choose `action`.
+    pub think_time: Duration,
+    // What the agent's search counted while choosing `action`, if it counts.
+    pub nodes: Option<NodeCount>,
+}
+
+#[cfg(feature = "std")]
+type TurnCallback<'a, S> = Box<dyn FnMut(&TurnEvent<S>) + 'a>;
+
+#[cfg(feature = "std")]
+pub struct GameRunner<'a, S, E, A> {
+    environment: E,
+    agent: A,
+    callbacks: Vec<TurnCallback<'a, S>>,
+}
+
+#[cfg(feature = "std")]
+impl<'a, S, E, A, D> GameRunner<'a, S, E, A>
+where
+    S: SinglePlayerState,
+    E: Fn(u64) -> S,
+    A: Fn(&S) -> D,
+    D: Decision,
+{
+    pub fn new(environment: E, agent: A) -> Self {
+        GameRunner {
+            environment,
+            agent,
+            callbacks: vec![],
+        }
+    }
+
+    pub fn on_turn(self, mut callback: impl FnMut(&S, Action) + 'a) -> Self {
+        self.on_event(move |event| callback(event.state, event.action))
+    }
+
+    pub fn on_event(mut self, callback: impl FnMut(&TurnEvent<S>) + 'a) -> Self {
+        self.callbacks.push(Box::new(callback));
+        self
+    }
+
+    // Writes one JSON object per turn to `writer` so that other tools can replay the games.
+    // The state is its `Display` text, and `nodes` holds the expanded, generated and pruned
+    // counts of agents that return them, or null. Turns whose line cannot be written are
+    // skipped.
+    pub fn json_lines(self, mut writer: impl Write + 'a) -> Self
+    where
+        S: std::fmt::Display,
+    {
+        self.on_event(move |event| {
+            let nodes = match event.nodes {
+                Some(count) => format!(
+                    "{{\"expanded\":{},\"generated\":{},\"pruned\":{}}}",
+                    count.expanded, count.generated, count.pruned
+                ),
+                None => "null".to_string(),
+            };
+            let _ = writeln!(
+                writer,
+                "{{\"seed\":{},\"turn\":{},\"action\":{},\"score\":{},\"think_us\":{},\"nodes\":{},\"state\":\"{}\"}}",
+                event.seed,
+                event.turn,
+                event.action,
+                event.state.score(),
+                event.think_time.as_micros(),
+                nodes,
+                json_escape(&event.state.to_string())
+            );
+        })
+    }
+
+    pub fn run(&mut self, seed: u64) -> GameResult {
+        play(&self.environment, &self.agent, seed, &mut self.callbacks)
+    }
+
+    pub fn run_episodes(&mut self, game_number: usize) -> Vec<GameResult> {
+        (0..game_number as u64).map(|seed| self.run(seed)).collect()
+    }
+}
+
+#[cfg(feature = "std")]
+impl<'a, S, E, A, D> GameRunner<'a, S, E, A>
+where
+    S: SinglePlayerState,
+    E: Fn(u64) -> S + Sync,
+    A: Fn(&S) -> D + Sync,
+    D: Decision,
+{
+    // Per-turn callbacks are not called here since they cannot be shared between threads.
+    pub fn run_batch(
+        &self,
+        game_number: usize,
+        thread_number: usize,
+        seeds: impl IntoIterator<Item = u64>,
+    ) -> Vec<GameResult> {
+        let seeds: Vec<u64> = seeds.into_iter().take(game_number).collect();
+        let (environment, agent) = (&self.environment, &self.agent);
+        play_in_parallel(&seeds, thread_number, |seed| {
+            play(environment, agent, seed, &mut [])
+        })
+    }
+}
+
+// `run_batch` over the seeds 0..`game_number` for an agent that draws its randomness from the
+// generator it is given: game `seed` gets `streams.stream(seed)`. The results only depend on
+// `streams`, never on the thread count or on which thread played which game.
+#[cfg(feature = "std")]
+pub fn run_seeded_batch<S, E, A>(
+    environment: E,
+    agent: A,
+    game_number: usize,
+    thread_number: usize,
+    streams: RngStreams,
+) -> Vec<GameResult>
+where
+    S: SinglePlayerState,
+    E: Fn(u64) -> S + Sync,
+    A: Fn(&S, &mut ChaCha8Rng) -> Outcome + Sync,
+{
+    let seeds: Vec<u64> = (0..game_number as u64).collect();
+    play_in_parallel(&seeds, thread_number, |seed| {
+        let rng = std::cell::RefCell::new(streams.stream(seed).rng());
+        let agent = |state: &S| agent(state, &mut rng.borrow_mut());
+        play(&environment, &agent, seed, &mut [])
+    })
+}
+
+// `f(i)` for every i in 0..n, on `thread_number` threads that each take the next i as they
+// free up. The results come back in index order, whichever thread finished first.
+#[cfg(feature = "std")]
+pub fn parallel_map<T: Send>(
+    n: usize,
+    thread_number: usize,
+    f: impl Fn(usize) -> T + Sync,
+) -> Vec<T> {
+    let results = Mutex::new((0..n).map(|_| None).collect::<Vec<_>>());
+    let next = AtomicUsize::new(0);
+    thread::scope(|scope| {
+        for _ in 0..thread_number.max(1) {
+            scope.spawn(|| loop {
+                let i = next.fetch_add(1, Ordering::Relaxed);
+                if n <= i {
+                    break;
+                }
+                let result = f(i);
+                results.lock().unwrap()[i] = Some(result);
+            });
+        }
+    });
+    results
+        .into_inner()
+        .unwrap()
+        .into_iter()
+        .flatten()
+        .collect()
+}
+
+// Plays one game per seed on `thread_number` threads and returns the results in seed order.
+#[cfg(feature = "std")]
+fn play_in_parallel(
+    seeds: &[u64],
+    thread_number: usize,
+    play: impl Fn(u64) -> GameResult + Sync,
+) -> Vec<GameResult> {
+    let best = Mutex::new(None);
+    let progress = Progress::new("games", seeds.len());
+    let results = parallel_map(seeds.len(), thread_number, |i| {
+        let result = play(seeds[i]);
+        let mut best = best.lock().unwrap();
+        if *best < Some(result.score) {
+            *best = Some(result.score);
+            progress.set_best(result.score);
+        }
+        progress.inc(1);
+        result
+    });
+    progress.finish();
+    results
+}
+
+#[cfg(feature = "std")]
+fn play<S, E, A, D>(
+    environment: &E,
+    agent: &A,
+    seed: u64,
+    callbacks: &mut [TurnCallback<'_, S>],
+) -> GameResult
+where
+    S: SinglePlayerState,
+    E: Fn(u64) -> S,
+    A: Fn(&S) -> D,
+    D: Decision,
+{
+    let start_time = Instant::now();
+    let mut state = environment(seed);
+    let mut turns = 0;
+    while !state.is_done() {
+        let think_start = Instant::now();
+        let decision = agent(&state);
+        let Outcome::Action(action) = decision.outcome() else {
+            break;
+        };
+        let think_time = Instant::now().duration_since(think_start);
+        state.advance(action);
+        turns += 1;
+        let event = TurnEvent {
+            seed,
+            turn: turns,
+            action,
+            state: &state,
+            think_time,
+            nodes: decision.nodes(),
+        };
+        for callback in callbacks.iter_mut() {
+            callback(&event);
+        }
+    }
+    GameResult {
+        seed,
+        score: state.score(),
+        turns,
+        duration: Instant::now().duration_since(start_time),
+    }
+}
+
+#[cfg(feature = "std")]
+fn json_escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for ch in text.chars() {
+        match ch {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\t' => escaped.push_str("\\t"),
+            ch if ch.is_control() => escaped.push_str(&format!("\\u{:04x}", ch as u32)),
+            ch => escaped.push(ch),
+        }
+    }
+    escaped
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use smallvec::smallvec;
+
+    use super::*;
+
+    #[derive(Clone)]
+    struct CountDown {
+        rest: usize,
+        score: ScoreType,
+    }
+
+    impl SinglePlayerState for CountDown {
+        fn is_done(&self) -> bool {
+            self.rest == 0
+        }
+
+        fn advance(&mut self, action: Action) {
+            self.rest -= 1;
+            self.score += action as ScoreType;
+        }
+
+        fn legal_actions(&self) -> ActionList {
+            smallvec![0, 1]
+        }
+
+        fn score(&self) -> ScoreType {
+            self.score
+        }
+    }
+
+    #[test]
+    fn test_rng_streams_are_fixed() {
+        let draw = |streams: RngStreams| streams.rng().gen::<u64>();
+        // ChaCha8 seeded with 0, whatever `StdRng` happens to be.
+        assert_eq!(draw(RngStreams::new(0)), 13_080_132_717_333_068_652);
+        assert_ne!(
+            draw(RngStreams::new(0).stream(0)),
+            draw(RngStreams::new(0).stream(1))
+        );
+    }
+
+    #[test]
+    fn test_add_score() {
+        assert_eq!(add_score(2, 3), 5);
+    }
+
+    #[test]
+    #[cfg(all(debug_assertions, not(feature = "saturating-score")))]
+    #[should_panic(expected = "score overflow")]
+    fn test_add_score_overflow_panics() {
+        add_score(ScoreType::MAX, 1);
+    }
+
+    #[test]
+    #[cfg(feature = "saturating-score")]
+    fn test_add_score_saturates() {
+        assert_eq!(add_score(ScoreType::MAX, 1), ScoreType::MAX);
+    }
+
+    // One point per set bit; the GA knows nothing of mazes.
+    struct OnePoint;
+
+    impl Crossover<[bool; 32]> for OnePoint {
+        fn crossover(&self, a: &[bool; 32], b: &[bool; 32], rng: &mut impl Rng) -> [bool; 32] {
+            let cut = rng.gen_range(0..32);
+            core::array::from_fn(|i| if i < cut { a[i] } else { b[i] })
+        }
+    }
+
+    #[test]
+    fn test_genetic_algorithm() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let fitness = |bits: &[bool; 32]| bits.iter().filter(|&&b| b).count() as ScoreType;
+        let best = genetic_algorithm(
+            20,
+            100,
+            &OnePoint,
+            |rng: &mut StdRng| core::array::from_fn(|_| rng.gen_bool(0.5)),
+            |bits, rng| {
+                let i = rng.gen_range(0..32);
+                bits[i] = !bits[i];
+            },
+            fitness,
+            &mut rng,
+        );
+        assert!(28 <= fitness(&best));
+    }
+
+    #[test]
+    fn test_game_runner() {
+        let mut actions = vec![];
+        let results = GameRunner::new(
+            |seed| CountDown {
+                rest: seed as usize,
+                score: 0,
+            },
+            |_: &CountDown| Outcome::Action(1),
+        )
+        .on_turn(|_, action| actions.push(action))
+        .run_episodes(4);
+        assert_eq!(
+            results.iter().map(|r| r.score).collect::<Vec<_>>(),
+            vec![0, 1, 2, 3]
+        );
+        assert_eq!(actions.len(), 6);
+        let summary = Summary::from_results(&results);
+        assert_eq!(summary.mean, 1.5);
+        assert_eq!((summary.min, summary.max), (0, 3));
+    }
+
+    impl std::fmt::Display for CountDown {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "rest:\t{}\n\"{}\"", self.rest, self.score)
+        }
+    }
+
+    #[test]
+    fn test_json_lines() {
+        let mut out = vec![];
+        GameRunner::new(
+            |seed| CountDown {
+                rest: seed as usize,
+                score: 0,
+            },
+            |_: &CountDown| Outcome::Action(1),
+        )
+        .json_lines(&mut out)
+        .run_episodes(3);
+        let out = String::from_utf8(out).unwrap();
+        let lines: Vec<&str> = out.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].starts_with(r#"{"seed":1,"turn":1,"action":1,"score":1,"think_us":"#));
+        assert!(lines[0].contains(r#""nodes":null,"#));
+        assert!(lines[2].ends_with(r#""state":"rest:\t0\n\"2\""}"#));
+        let mut out = vec![];
+        let count = NodeCount {
+            expanded: 3,
+            generated: 7,
+            pruned: 2,
+            ..NodeCount::default()
+        };
+        GameRunner::new(
+            |seed| CountDown {
+                rest: seed as usize,
+                score: 0,
+            },
+            |_: &CountDown| (Outcome::Action(1), count),
+        )
+        .json_lines(&mut out)
+        .run(1);
+        let out = String::from_utf8(out).unwrap();
+        assert!(out.contains(r#""nodes":{"expanded":3,"generated":7,"pruned":2},"#));
+    }
+
+    #[test]
+    fn test_fischer_clock() {
+        let ms = Duration::from_millis;
+        let mut clock = FischerClock::new(ms(100), ms(10));
+        assert_eq!(clock.move_budget(10), ms(20));
+        assert_eq!(clock.move_budget(1), ms(90));
+        assert!(clock.punch(ms(30)));
+        assert_eq!(clock.remaining, ms(80));
+        assert!(!clock.punch(ms(81)));
+        assert_eq!(clock.remaining, Duration::ZERO);
+    }
+
+    #[test]
+    fn test_effective_branching_factor() {
+        let count = NodeCount {
+            expanded: 5,
+            generated: 2 + 4 + 8,
+            pruned: 0,
+            max_depth: 3,
+            peak_bytes: 0,
+            duplicates: 0,
+        };
+        assert!((count.effective_branching_factor() - 2.0).abs() < 1e-9);
+        assert!((count.branching_factor() - 2.8).abs() < 1e-9);
+        assert_eq!(NodeCount::default().effective_branching_factor(), 0.0);
+    }
+
+    #[test]
+    fn test_parallel_map() {
+        for thread_number in [0, 1, 3, 16] {
+            let squares = parallel_map(10, thread_number, |i| i * i);
+            assert_eq!(squares, (0..10).map(|i| i * i).collect::<Vec<_>>());
+        }
+        assert!(parallel_map(0, 4, |i| i).is_empty());
+    }
+
+    #[test]
+    fn test_run_batch() {
+        let runner = GameRunner::new(
+            |seed| CountDown {
+                rest: seed as usize % 7,
+                score: 0,
+            },
+            |_: &CountDown| Outcome::Action(1),
+        );
+        let results = runner.run_batch(20, 4, 100..);
+        assert_eq!(results.len(), 20);
+        for (i, result) in results.iter().enumerate() {
+            assert_eq!(result.seed, 100 + i as u64);
+            assert_eq!(result.score, result.seed as ScoreType % 7);
+        }
+    }
+
+    #[test]
+    fn test_game_runner_stops_on_no_action() {
+        let result = GameRunner::new(
+            |_| CountDown { rest: 5, score: 0 },
+            |_: &CountDown| Outcome::NoAction,
+        )
+        .run(0);
+        assert_eq!(result.turns, 0);
+    }
+}
@@ -0,0 +1,830 @@
+#![allow(unused)]
+
+use std::sync::Arc;
+
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use crate::game::{add_score, splitmix64, Action, ActionList, ScoreType, SinglePlayerState};
+use crate::learning::Features;
+use crate::maze_generator::sample_point;
+use crate::section3::{BeamState, Coord};
+
+// Where the points of a maze live: a value per cell, 0 for none. The section3 maze keeps a
+// fixed array; boards that are too big for that pick the representation that suits how full
+// they are.
+pub trait PointGrid: Clone {
+    fn empty(height: usize, width: usize) -> Self;
+    fn height(&self) -> usize;
+    fn width(&self) -> usize;
+    fn get(&self, y: usize, x: usize) -> ScoreType;
+    fn set(&mut self, y: usize, x: usize, value: ScoreType);
+
+    // Removes the point at (y, x) and returns it.
+    fn take(&mut self, y: usize, x: usize) -> ScoreType {
+        let point = self.get(y, x);
+        if point != 0 {
+            self.set(y, x, 0);
+        }
+        point
+    }
+
+    // The cells with a point, row by row.
+    fn occupied(&self) -> Vec<(usize, usize, ScoreType)> {
+        let mut cells = vec![];
+        for y in 0..self.height() {
+            for x in 0..self.width() {
+                let point = self.get(y, x);
+                if point != 0 {
+                    cells.push((y, x, point));
+                }
+            }
+        }
+        cells
+    }
+
+    // Bytes on the heap, which a clone copies unless the grid shares them.
+    fn heap_bytes(&self) -> usize;
+}
+
+// One value per cell: the cost of a copy grows with the area.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DenseGrid {
+    height: usize,
+    width: usize,
+    cells: Vec<ScoreType>,
+}
+
+impl PointGrid for DenseGrid {
+    fn empty(height: usize, width: usize) -> Self {
+        DenseGrid {
+            height,
+            width,
+            cells: vec![0; height * width],
+        }
+    }
+
+    fn height(&self) -> usize {
+        self.height
+    }
+
+    fn width(&self) -> usize {
+        self.width
+    }
+
+    fn get(&self, y: usize, x: usize) -> ScoreType {
+        self.cells[y * self.width + x]
+    }
+
+    fn set(&mut self, y: usize, x: usize, value: ScoreType) {
+        self.cells[y * self.width + x] = value;
+    }
+
+    fn heap_bytes(&self) -> usize {
+        self.cells.capacity() * std::mem::size_of::<ScoreType>()
+    }
+}
+
+// Only the cells with a point, sorted by row-major index: the cost of a copy grows with the
+// number of points, and collecting them makes it shrink. Lookups are a binary search.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SparseGrid {
+    height: usize,
+    width: usize,
+    cells: Vec<(u32, ScoreType)>,
+}
+
+impl SparseGrid {
+    fn index(&self, y: usize, x: usize) -> u32 {
+        debug_assert!(y < self.height && x < self.width);
+        (y * self.width + x) as u32
+    }
+
+    pub fn len(&self) -> usize {
+        self.cells.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cells.is_empty()
+    }
+}
+
+impl PointGrid for SparseGrid {
+    fn empty(height: usize, width: usize) -> Self {
+        assert!(height * width <= u32::MAX as usize);
+        SparseGrid {
+            height,
+            width,
+            cells: vec![],
+        }
+    }
+
+    fn height(&self) -> usize {
+        self.height
+    }
+
+    fn width(&self) -> usize {
+        self.width
+    }
+
+    fn get(&self, y: usize, x: usize) -> ScoreType {
+        let index = self.index(y, x);
+        self.cells
+            .binary_search_by_key(&index, |cell| cell.0)
+            .map_or(0, |i| self.cells[i].1)
+    }
+
+    fn set(&mut self, y: usize, x: usize, value: ScoreType) {
+        let index = self.index(y, x);
+        match (
+            self.cells.binary_search_by_key(&index, |cell| cell.0),
+            value,
+        ) {
+            (Ok(i), 0) => {
+                self.cells.remove(i);
+            }
+            (Ok(i), value) => self.cells[i].1 = value,
+            (Err(_), 0) => {}
+            (Err(i), value) => self.cells.insert(i, (index, value)),
+        }
+    }
+
+    fn occupied(&self) -> Vec<(usize, usize, ScoreType)> {
+        self.cells
+            .iter()
+            .map(|&(index, point)| {
+                let index = index as usize;
+                (index / self.width, index % self.width, point)
+            })
+            .collect()
+    }
+
+    fn heap_bytes(&self) -> usize {
+        self.cells.capacity() * std::mem::size_of::<(u32, ScoreType)>()
+    }
+}
+
+// Points of 0 to `NIBBLE_MAX` packed two cells to a byte, the even cell in the low half: a
+// sixteenth of the room of a `ScoreType` per cell.
+pub const NIBBLE_MAX: ScoreType = 15;
+
+// A point that a nibble cannot hold, from a generator or a cell change.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PointOverflow(pub ScoreType);
+
+impl std::fmt::Display for PointOverflow {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "point {} is above {}", self.0, NIBBLE_MAX)
+    }
+}
+
+impl std::error::Error for PointOverflow {}
+
+pub fn check_nibble(value: ScoreType) -> Result<(), PointOverflow> {
+    match value {
+        0..=NIBBLE_MAX => Ok(()),
+        _ => Err(PointOverflow(value)),
+    }
+}
+
+pub fn get_nibble(bytes: &[u8], index: usize) -> ScoreType {
+    ((bytes[index / 2] >> (index % 2 * 4)) & 0xf) as ScoreType
+}
+
+pub fn set_nibble(bytes: &mut [u8], index: usize, value: ScoreType) {
+    assert!(
+        (0..=NIBBLE_MAX).contains(&value),
+        "point {} does not fit in 4 bits",
+        value
+    );
+    let shift = index % 2 * 4;
+    let byte = &mut bytes[index / 2];
+    *byte = (*byte & !(0xf << shift)) | ((value as u8) << shift);
+}
+
+// `DenseGrid` in nibbles: still a copy per area, but a sixteenth of it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PackedGrid {
+    height: usize,
+    width: usize,
+    cells: Vec<u8>,
+}
+
+impl PointGrid for PackedGrid {
+    fn empty(height: usize, width: usize) -> Self {
+        PackedGrid {
+            height,
+            width,
+            cells: vec![0; (height * width).div_ceil(2)],
+        }
+    }
+
+    fn height(&self) -> usize {
+        self.height
+    }
+
+    fn width(&self) -> usize {
+        self.width
+    }
+
+    fn get(&self, y: usize, x: usize) -> ScoreType {
+        get_nibble(&self.cells, y * self.width + x)
+    }
+
+    fn set(&mut self, y: usize, x: usize, value: ScoreType) {
+        set_nibble(&mut self.cells, y * self.width + x, value);
+    }
+
+    fn heap_bytes(&self) -> usize {
+        self.cells.capacity()
+    }
+}
+
+pub const CHUNK: usize = 64;
+
+// `DenseGrid` cut into chunks of `CHUNK` cells behind reference counts. A clone shares every
+// chunk, and a write copies the chunk table and the one chunk it lands in, so a child that
+// collects a point costs O(area / CHUNK + CHUNK) instead of O(area), and siblings keep sharing
+// everything they did not touch. The counts are atomic, so states can move between threads.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CowGrid {
+    height: usize,
+    width: usize,
+    chunks: Arc<Vec<Arc<[ScoreType; CHUNK]>>>,
+}
+
+impl CowGrid {
+    // How many chunks `self` and `other` hold in common.
+    pub fn shared_chunks(&self, other: &CowGrid) -> usize {
+        self.chunks
+            .iter()
+            .zip(other.chunks.iter())
+            .filter(|(a, b)| Arc::ptr_eq(a, b))
+            .count()
+    }
+}
+
+impl PointGrid for CowGrid {
+    fn empty(height: usize, width: usize) -> Self {
+        let chunk = Arc::new([0; CHUNK]);
+        CowGrid {
+            height,
+            width,
+            chunks: Arc::new(vec![chunk; (height * width).div_ceil(CHUNK)]),
+        }
+    }
+
+    fn height(&self) -> usize {
+        self.height
+    }
+
+    fn width(&self) -> usize {
+        self.width
+    }
+
+    fn get(&self, y: usize, x: usize) -> ScoreType {
+        let index = y * self.width + x;
+        self.chunks[index / CHUNK][index % CHUNK]
+    }
+
+    fn set(&mut self, y: usize, x: usize, value: ScoreType) {
+        if self.get(y, x) == value {
+            return;
+        }
+        let index = y * self.width + x;
+        let chunks = Arc::make_mut(&mut self.chunks);
+        Arc::make_mut(&mut chunks[index / CHUNK])[index % CHUNK] = value;
+    }
+
+    // Shared chunks counted in full.
+    fn heap_bytes(&self) -> usize {
+        self.chunks.len()
+            * (std::mem::size_of::<Arc<[ScoreType; CHUNK]>>()
+                + CHUNK * std::mem::size_of::<ScoreType>())
+    }
+}
+
+const BITS: usize = 4;
+const FANOUT: usize = 1 << BITS;
+const MASK: usize = FANOUT - 1;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum TrieNode {
+    Leaf([ScoreType; FANOUT]),
+    Branch([Option<Arc<TrieNode>>; FANOUT]),
+}
+
+impl TrieNode {
+    fn is_empty(&self) -> bool {
+        match self {
+            TrieNode::Leaf(cells) => cells.iter().all(|&point| point == 0),
+            TrieNode::Branch(children) => children.iter().all(Option::is_none),
+        }
+    }
+}
+
+// A persistent grid: a trie over the row-major cell index with `FANOUT` children per node, as
+// in a HAMT, where empty subtries are left out and a write copies only the nodes on the path
+// to its cell. A clone is a reference count, and states that differ in a few cells share all
+// the rest, however many of them are kept: a search tree of hundreds of thousands of states
+// holds about a path per state instead of a board per state. Reads and writes take
+// log_FANOUT(area) steps. `Send` and `Sync`, so trees can be searched on several threads.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PersistentGrid {
+    height: usize,
+    width: usize,
+    // Bits of the index consumed above the leaves.
+    shift: usize,
+    root: Option<Arc<TrieNode>>,
+}
+
+fn set_in(slot: &mut Option<Arc<TrieNode>>, shift: usize, index: usize, value: ScoreType) {
+    if slot.is_none() && value == 0 {
+        return;
+    }
+    let node = slot.get_or_insert_with(|| {
+        Arc::new(if shift == 0 {
+            TrieNode::Leaf([0; FANOUT])
+        } else {
+            TrieNode::Branch(Default::default())
+        })
+    });
+    match Arc::make_mut(node) {
+        TrieNode::Leaf(cells) => cells[index & MASK] = value,
+        TrieNode::Branch(children) => set_in(
+            &mut children[(index >> shift) & MASK],
+            shift - BITS,
+            index,
+            value,
+        ),
+    }
+    if node.is_empty() {
+        *slot = None;
+    }
+}
+
+fn trie_bytes(node: &TrieNode) -> usize {
+    std::mem::size_of::<TrieNode>()
+        + match node {
+            TrieNode::Leaf(_) => 0,
+            TrieNode::Branch(children) => children.iter().flatten().map(|c| trie_bytes(c)).sum(),
+        }
+}
+
+impl PointGrid for PersistentGrid {
+    fn empty(height: usize, width: usize) -> Self {
+        let mut shift = 0;
+        while FANOUT << shift < height * width {
+            shift += BITS;
+        }
+        PersistentGrid {
+            height,
+            width,
+            shift,
+            root: None,
+        }
+    }
+
+    fn height(&self) -> usize {
+        self.height
+    }
+
+    fn width(&self) -> usize {
+        self.width
+    }
+
+    fn get(&self, y: usize, x: usize) -> ScoreType {
+        let index = y * self.width + x;
+        let mut shift = self.shift;
+        let mut node = self.root.as_deref();
+        while let Some(now) = node {
+            match now {
+                TrieNode::Leaf(cells) => return cells[index & MASK],
+                TrieNode::Branch(children) => {
+                    node = children[(index >> shift) & MASK].as_deref();
+                    shift = shift.saturating_sub(BITS);
+                }
+            }
+        }
+        0
+    }
+
+    fn set(&mut self, y: usize, x: usize, value: ScoreType) {
+        if self.get(y, x) != value {
+            set_in(&mut self.root, self.shift, y * self.width + x, value);
+        }
+    }
+
+    // Shared nodes counted in full.
+    fn heap_bytes(&self) -> usize {
+        self.root.as_deref().map_or(0, trie_bytes)
+    }
+}
+
+const DX: [isize; 4] = [1, -1, 0, 0];
+const DY: [isize; 4] = [0, 0, 1, -1];
+
+// The section3 maze on a board of any size, with its points in `G`, so the section3 searches
+// run on whichever grid suits the board; `MazeState::to_grid` moves a section3 maze over. The
+// walls never change, so clones share them, and a maze without walls keeps none. Ordered by
+// the evaluation, like `MazeState`.
+#[derive(Debug, Clone)]
+pub struct GridMazeState<G> {
+    pub character: Coord,
+    pub game_score: ScoreType,
+    pub evaluated_score: ScoreType,
+    pub first_action: Option<Action>,
+    pub turn: usize,
+    pub end_turn: usize,
+    pub points: G,
+    // Row by row.
+    walls: Option<Arc<[bool]>>,
+}
+
+impl<G: PointGrid> GridMazeState<G> {
+    // A fresh game on `points` with `walls` given row by row, or none when `walls` is empty.
+    pub fn new(character: Coord, points: G, walls: Vec<bool>, end_turn: usize) -> GridMazeState<G> {
+        let walls = walls.contains(&true).then(|| walls.into());
+        GridMazeState {
+            character,
+            game_score: 0,
+            evaluated_score: 0,
+            first_action: None,
+            turn: 0,
+            end_turn,
+            points,
+            walls,
+        }
+    }
+
+    // `point_number` points of 1 to 9 on distinct cells other than the start, without walls,
+    // so generating takes time in the number of points rather than in the area. Boards with
+    // fewer free cells than that are filled up; a board needs a cell for the character.
+    pub fn with_seed(
+        seed: u64,
+        height: usize,
+        width: usize,
+        point_number: usize,
+        end_turn: usize,
+    ) -> GridMazeState<G> {
+        assert!(
+            0 < height && 0 < width,
+            "an empty board has no cell to start on"
+        );
+        let mut rng = StdRng::seed_from_u64(seed);
+        let character = Coord::from_point(rng.gen_range(0..width), rng.gen_range(0..height));
+        let mut points = G::empty(height, width);
+        let mut placed = 0;
+        while placed < point_number.min(height * width - 1) {
+            let (y, x) = (rng.gen_range(0..height), rng.gen_range(0..width));
+            if (y, x) != (character.y, character.x) && points.get(y, x) == 0 {
+                points.set(y, x, 1 + sample_point(&mut rng, 8));
+                placed += 1;
+            }
+        }
+        GridMazeState::new(character, points, vec![], end_turn)
+    }
+
+    // The same maze with its points in another representation.
+    pub fn convert<H: PointGrid>(&self) -> GridMazeState<H> {
+        let mut points = H::empty(self.points.height(), self.points.width());
+        for (y, x, point) in self.points.occupied() {
+            points.set(y, x, point);
+        }
+        GridMazeState {
+            character: self.character,
+            game_score: self.game_score,
+            evaluated_score: self.evaluated_score,
+            first_action: self.first_action,
+            turn: self.turn,
+            end_turn: self.end_turn,
+            points,
+            walls: self.walls.clone(),
+        }
+    }
+
+    pub fn is_wall(&self, y: usize, x: usize) -> bool {
+        self.walls
+            .as_ref()
+            .is_some_and(|walls| walls[y * self.points.width() + x])
+    }
+
+    fn target(&self, action: Action) -> Option<(usize, usize)> {
+        let y = self.character.y.checked_add_signed(DY[action])?;
+        let x = self.character.x.checked_add_signed(DX[action])?;
+        let inside = y < self.points.height() && x < self.points.width();
+        (inside && !self.is_wall(y, x)).then_some((y, x))
+    }
+}
+
+impl<G> PartialEq for GridMazeState<G> {
+    fn eq(&self, other: &Self) -> bool {
+        self.evaluated_score == other.evaluated_score
+    }
+}
+
+impl<G> PartialOrd for GridMazeState<G> {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<G> Eq for GridMazeState<G> {}
+
+impl<G> Ord for GridMazeState<G> {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.evaluated_score.cmp(&other.evaluated_score)
+    }
+}
+
+impl<G: PointGrid> SinglePlayerState for GridMazeState<G> {
+    fn is_done(&self) -> bool {
+        self.turn == self.end_turn
+    }
+
+    fn advance(&mut self, action: Action) {
+        let (y, x) = self.target(action).unwrap();
+        self.character = Coord::from_point(x, y);
+        self.game_score = add_score(self.game_score, self.points.take(y, x));
+        self.turn += 1;
+    }
+
+    fn legal_actions(&self) -> ActionList {
+        (0..4)
+            .filter(|&action| self.target(action).is_some())
+            .collect()
+    }
+
+    fn score(&self) -> ScoreType {
+        self.game_score
+    }
+}
+
+impl<G: PointGrid> Features for GridMazeState<G> {
+    fn grid_size(&self) -> (usize, usize) {
+        (self.points.height(), self.points.width())
+    }
+
+    fn point(&self, y: usize, x: usize) -> f64 {
+        self.points.get(y, x) as f64
+    }
+
+    fn position(&self) -> (usize, usize) {
+        (self.character.y, self.character.x)
+    }
+
+    fn remaining_turns(&self) -> usize {
+        self.end_turn - self.turn
+    }
+}
+
+impl<G: PointGrid> BeamState for GridMazeState<G> {
+    fn evaluated_score(&self) -> ScoreType {
+        self.evaluated_score
+    }
+
+    fn evaluate_score(&mut self) {
+        self.evaluated_score = self.game_score;
+    }
+
+    fn first_action(&self) -> Option<Action> {
+        self.first_action
+    }
+
+    fn set_first_action(&mut self, action: Action) {
+        self.first_action = Some(action);
+    }
+
+    // The character's cell and the points left; `occupied` visits every cell of a dense grid.
+    fn dedup_key(&self) -> u64 {
+        let width = self.points.width();
+        let cell = (self.character.y * width + self.character.x) as u64;
+        self.points
+            .occupied()
+            .into_iter()
+            .fold(splitmix64(cell), |key, (y, x, point)| {
+                let index = (y * width + x) as u64;
+                splitmix64(splitmix64(key ^ index) ^ point as u64)
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+    use crate::game::Outcome;
+    use crate::maze_generator::MazeGenerator;
+    use crate::section3::{beam_search_action, greedy_action, MazeState};
+
+    // Everything but the evaluation, which is all `==` compares.
+    type Cells = (Coord, ScoreType, usize, Vec<(usize, usize, ScoreType)>);
+
+    fn cells<G: PointGrid>(state: &GridMazeState<G>) -> Cells {
+        let occupied = state.points.occupied();
+        (state.character, state.game_score, state.turn, occupied)
+    }
+
+    #[test]
+    fn test_grids_agree() {
+        let mut dense = DenseGrid::empty(3, 5);
+        let mut sparse = SparseGrid::empty(3, 5);
+        let mut packed = PackedGrid::empty(3, 5);
+        let mut cow = CowGrid::empty(3, 5);
+        let mut persistent = PersistentGrid::empty(3, 5);
+        for (y, x, value) in [
+            (2, 4, 5),
+            (0, 0, 1),
+            (1, 2, 7),
+            (2, 4, 0),
+            (1, 2, 4),
+            (1, 3, 15),
+        ] {
+            dense.set(y, x, value);
+            sparse.set(y, x, value);
+            packed.set(y, x, value);
+            cow.set(y, x, value);
+            persistent.set(y, x, value);
+        }
+        assert_eq!(cow.occupied(), dense.occupied());
+        assert_eq!(persistent.occupied(), dense.occupied());
+        // Emptied subtries are dropped.
+        for (y, x, _) in dense.occupied() {
+            persistent.set(y, x, 0);
+        }
+        assert_eq!(persistent.heap_bytes(), 0);
+        assert_eq!(dense.occupied(), vec![(0, 0, 1), (1, 2, 4), (1, 3, 15)]);
+        assert_eq!(sparse.occupied(), dense.occupied());
+        assert_eq!(packed.occupied(), dense.occupied());
+        assert_eq!(packed.heap_bytes(), 8);
+        assert_eq!(sparse.len(), 3);
+        assert_eq!(sparse.take(1, 2), 4);
+        assert_eq!(sparse.take(1, 2), 0);
+        assert_eq!(sparse.get(1, 2), 0);
+        assert_eq!(sparse.len(), 2);
+    }
+
+    fn unique_bytes(node: &Option<Arc<TrieNode>>, seen: &mut HashSet<*const TrieNode>) -> usize {
+        let Some(node) = node else {
+            return 0;
+        };
+        if !seen.insert(Arc::as_ptr(node)) {
+            return 0;
+        }
+        std::mem::size_of::<TrieNode>()
+            + match node.as_ref() {
+                TrieNode::Leaf(_) => 0,
+                TrieNode::Branch(children) => children.iter().map(|c| unique_bytes(c, seen)).sum(),
+            }
+    }
+
+    #[test]
+    fn test_persistent_grid_sharing() {
+        // A random tree of states, as an MCTS keeps them, next to the same states in a sparse
+        // grid for the first few hundred.
+        let root: GridMazeState<PersistentGrid> = GridMazeState::with_seed(0, 100, 100, 2000, 100);
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut states = vec![root.clone()];
+        let mut checked = vec![root.convert::<SparseGrid>()];
+        while states.len() < 20_000 {
+            let i = rng.gen_range(0..states.len());
+            if states[i].is_done() {
+                continue;
+            }
+            let actions = states[i].legal_actions();
+            let action = actions[rng.gen_range(0..actions.len())];
+            let mut child = states[i].clone();
+            child.advance(action);
+            states.push(child);
+            if i < checked.len() && checked.len() < 500 {
+                let mut child = checked[i].clone();
+                child.advance(action);
+                checked.push(child);
+            }
+        }
+        for (state, expected) in states.iter().zip(checked.iter()) {
+            assert_eq!(state.points.occupied(), expected.points.occupied());
+            assert_eq!(state.game_score, expected.game_score);
+        }
+        let mut seen = HashSet::new();
+        let shared: usize = states
+            .iter()
+            .map(|state| unique_bytes(&state.points.root, &mut seen))
+            .sum();
+        let dense = states.len() * 100 * 100 * std::mem::size_of::<ScoreType>();
+        println!(
+            "Persistent Grid Bytes:\t{}\tDense Grid Bytes:\t{}",
+            shared, dense
+        );
+        assert!(shared * 100 < dense);
+    }
+
+    #[test]
+    #[should_panic(expected = "does not fit in 4 bits")]
+    fn test_packed_grid_overflow() {
+        PackedGrid::empty(1, 1).set(0, 0, NIBBLE_MAX + 1);
+    }
+
+    #[test]
+    fn test_sparse_maze() {
+        let dense: GridMazeState<DenseGrid> = GridMazeState::with_seed(0, 100, 100, 500, 200);
+        let sparse: GridMazeState<SparseGrid> = dense.convert();
+        let packed: GridMazeState<PackedGrid> = dense.convert();
+        assert_eq!(cells(&packed.convert::<DenseGrid>()), cells(&dense));
+        let cow: GridMazeState<CowGrid> = dense.convert();
+        assert_eq!(cells(&cow.convert::<DenseGrid>()), cells(&dense));
+        let persistent: GridMazeState<PersistentGrid> = dense.convert();
+        assert_eq!(cells(&persistent.convert::<DenseGrid>()), cells(&dense));
+        assert_eq!(sparse.points.len(), 500);
+        assert_eq!(cells(&sparse.convert::<DenseGrid>()), cells(&dense));
+        // Both representations play the same game.
+        let (mut dense, mut sparse) = (dense, sparse);
+        while !dense.is_done() {
+            let action = beam_search_action(&dense, 3, 5).unwrap();
+            assert_eq!(beam_search_action(&sparse, 3, 5), Outcome::Action(action));
+            dense.advance(action);
+            sparse.advance(action);
+        }
+        assert_eq!(sparse.game_score, dense.game_score);
+        assert!(0 < dense.game_score);
+
+        // Children share every chunk with their parent but the one they collected a point in.
+        let mut parent = cow;
+        let mut collected = 0;
+        while !parent.is_done() {
+            let action = beam_search_action(&parent, 3, 5).unwrap();
+            let mut child = parent.clone();
+            child.advance(action);
+            let chunks = parent.points.chunks.len();
+            if child.game_score == parent.game_score {
+                assert_eq!(child.points.shared_chunks(&parent.points), chunks);
+            } else {
+                assert_eq!(child.points.shared_chunks(&parent.points), chunks - 1);
+                collected += 1;
+            }
+            parent = child;
+        }
+        assert_eq!(parent.game_score, dense.game_score);
+        assert!(0 < collected);
+
+        // A board of a million cells with 5% of them full takes a fraction of the dense bytes.
+        let huge: GridMazeState<SparseGrid> = GridMazeState::with_seed(0, 1000, 1000, 50_000, 100);
+        let dense_bytes = 1000 * 1000 * std::mem::size_of::<ScoreType>();
+        assert!(huge.points.heap_bytes() * 4 <= dense_bytes);
+        let total = |state: &GridMazeState<SparseGrid>| {
+            state
+                .points
+                .occupied()
+                .iter()
+                .map(|cell| cell.2)
+                .sum::<ScoreType>()
+        };
+        let mut state = huge.clone();
+        while let Outcome::Action(action) = greedy_action(&state) {
+            state.advance(action);
+        }
+        println!("Sparse Greedy Score:\t{}", state.game_score);
+        assert!(0 < state.game_score);
+        assert_eq!(total(&state) + state.game_score, total(&huge));
+    }
+
+    #[test]
+    fn test_section3_maze_on_a_grid() {
+        let generator = MazeGenerator {
+            wall_density: 0.2,
+            ..Default::default()
+        };
+        let mut state = MazeState::with_generator(0, &generator).unwrap();
+        let mut grid: GridMazeState<SparseGrid> = state.to_grid();
+        // The beams share the chunks of the board instead of copying it.
+        let mut cow: GridMazeState<CowGrid> = state.to_grid();
+        assert!((0..30).any(|y| (0..30).any(|x| grid.is_wall(y, x))));
+        while !state.is_done() {
+            assert_eq!(grid.legal_actions(), state.legal_actions());
+            let action = beam_search_action(&state, 3, 5).unwrap();
+            assert_eq!(beam_search_action(&grid, 3, 5), Outcome::Action(action));
+            assert_eq!(beam_search_action(&cow, 3, 5), Outcome::Action(action));
+            state.advance(action);
+            grid.advance(action);
+            cow.advance(action);
+        }
+        assert!(grid.is_done());
+        assert_eq!(grid.game_score, state.game_score);
+        assert_eq!(cow.game_score, state.game_score);
+    }
+
+    #[test]
+    fn test_tiny_boards() {
+        let state: GridMazeState<SparseGrid> = GridMazeState::with_seed(0, 1, 1, 10, 5);
+        assert!(state.points.is_empty());
+        assert_eq!(greedy_action(&state), Outcome::NoAction);
+        let state: GridMazeState<SparseGrid> = GridMazeState::with_seed(0, 1, 2, 10, 5);
+        assert_eq!(state.points.len(), 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "an empty board has no cell to start on")]
+    fn test_empty_board() {
+        GridMazeState::<SparseGrid>::with_seed(0, 0, 5, 10, 5);
+    }
+}
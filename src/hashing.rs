@@ -0,0 +1,72 @@
+#![allow(unused)]
+
+use crate::learning::Features;
+
+// FNV-1a over explicit 64-bit words, little end first. Unlike `DefaultHasher` it is the same
+// on every Rust version and platform, so hashes can be saved, as opening books are.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Fnv(u64);
+
+impl Fnv {
+    pub fn new() -> Fnv {
+        Fnv(0xcbf2_9ce4_8422_2325)
+    }
+
+    pub fn write(mut self, word: u64) -> Fnv {
+        for byte in word.to_le_bytes() {
+            self.0 = (self.0 ^ byte as u64).wrapping_mul(0x0100_0000_01b3);
+        }
+        self
+    }
+
+    pub fn finish(self) -> u64 {
+        self.0
+    }
+}
+
+impl Default for Fnv {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// A fingerprint for transposition detection: equal states must hash equally, and different
+// states should rarely collide. The default covers any grid game through `Features`: the
+// character's cell and the points left on the board, through `Fnv` so the hash is stable.
+// Games with a cheaper incremental hash override it.
+pub trait StateHash: Features {
+    fn state_hash(&self) -> u64 {
+        let (y, x) = self.position();
+        let mut hasher = Fnv::new().write(y as u64).write(x as u64);
+        let (height, width) = self.grid_size();
+        for y in 0..height {
+            for x in 0..width {
+                hasher = hasher.write(self.point(y, x).to_bits());
+            }
+        }
+        hasher.finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::section3::MazeState;
+
+    #[test]
+    fn test_fnv_is_fixed() {
+        assert_eq!(Fnv::new().finish(), 0xcbf2_9ce4_8422_2325);
+        assert_eq!(Fnv::new().write(1).finish(), 0x89cd_3129_1d2a_efa4);
+    }
+
+    #[test]
+    fn test_state_hash() {
+        let state = MazeState::with_seed(0);
+        assert_eq!(state.state_hash(), MazeState::with_seed(0).state_hash());
+        assert_ne!(state.state_hash(), MazeState::with_seed(1).state_hash());
+        let actions = state.legal_actions();
+        let mut next_state = state;
+        next_state.advance(actions[0]);
+        assert_ne!(state.state_hash(), next_state.state_hash());
+    }
+}
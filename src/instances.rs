@@ -0,0 +1,291 @@
+#![allow(unused)]
+
+use crate::maze_generator::{MazeGenerator, PointDistribution};
+use crate::section3::{beam_search_action, greedy_action, MazeState, ScoreType, END_TURN};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Difficulty {
+    Easy,
+    Medium,
+    Hard,
+}
+
+// A fixed section3 maze to quote results on. `random_score` is the mean of 1000 random playouts
+// seeded with `seed`, `greedy_score` what `greedy_action` scores on it and `best_known` the best
+// of the beam searches of width 2 and 20 over the whole game, so a reasonable agent lands in
+// between.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Instance {
+    pub id: &'static str,
+    pub difficulty: Difficulty,
+    pub seed: u64,
+    pub height: usize,
+    pub width: usize,
+    pub generator: MazeGenerator,
+    pub random_score: f64,
+    pub greedy_score: ScoreType,
+    pub best_known: ScoreType,
+}
+
+impl Instance {
+    pub fn state(&self) -> MazeState {
+        MazeState::small_with_generator(self.seed, self.height, self.width, &self.generator)
+            .expect("instance generators fit in a nibble")
+    }
+
+    pub fn expected_range(&self) -> std::ops::RangeInclusive<ScoreType> {
+        self.greedy_score..=self.best_known
+    }
+
+    // Where `score` falls between random play (0%) and the best known score (100%), so that
+    // results on instances with very different point totals can be averaged. Falls outside that
+    // range for agents worse than random or better than the best known.
+    pub fn normalize(&self, score: ScoreType) -> f64 {
+        let span = (self.best_known as f64 - self.random_score).max(1.0);
+        100.0 * (score as f64 - self.random_score) / span
+    }
+}
+
+const UNIFORM: MazeGenerator = MazeGenerator {
+    distribution: PointDistribution::Uniform { max: 9 },
+    wall_density: 0.0,
+    sparsity: 0.0,
+};
+
+const CLUSTERED: MazeGenerator = MazeGenerator {
+    distribution: PointDistribution::Clustered {
+        max: 9,
+        centers: 12,
+        radius: 4.0,
+    },
+    wall_density: 0.1,
+    sparsity: 0.3,
+};
+
+const WALLED: MazeGenerator = MazeGenerator {
+    distribution: PointDistribution::Uniform { max: 9 },
+    wall_density: 0.2,
+    sparsity: 0.5,
+};
+
+// Easy boards are small and open, medium ones clustered with a few walls, hard ones large,
+// sparse and walled. Ids never change meaning; new instances get new ids.
+pub const INSTANCES: [Instance; 9] = [
+    Instance {
+        id: "easy-1",
+        difficulty: Difficulty::Easy,
+        seed: 4,
+        height: 10,
+        width: 10,
+        generator: UNIFORM,
+        random_score: 154.1,
+        greedy_score: 301,
+        best_known: 394,
+    },
+    Instance {
+        id: "easy-2",
+        difficulty: Difficulty::Easy,
+        seed: 11,
+        height: 10,
+        width: 10,
+        generator: UNIFORM,
+        random_score: 160.9,
+        greedy_score: 313,
+        best_known: 438,
+    },
+    Instance {
+        id: "easy-3",
+        difficulty: Difficulty::Easy,
+        seed: 1,
+        height: 15,
+        width: 15,
+        generator: UNIFORM,
+        random_score: 184.4,
+        greedy_score: 455,
+        best_known: 645,
+    },
+    Instance {
+        id: "medium-1",
+        difficulty: Difficulty::Medium,
+        seed: 6,
+        height: 20,
+        width: 20,
+        generator: CLUSTERED,
+        random_score: 78.2,
+        greedy_score: 111,
+        best_known: 309,
+    },
+    Instance {
+        id: "medium-2",
+        difficulty: Difficulty::Medium,
+        seed: 14,
+        height: 20,
+        width: 20,
+        generator: CLUSTERED,
+        random_score: 69.4,
+        greedy_score: 101,
+        best_known: 361,
+    },
+    Instance {
+        id: "medium-3",
+        difficulty: Difficulty::Medium,
+        seed: 7,
+        height: 25,
+        width: 25,
+        generator: CLUSTERED,
+        random_score: 57.0,
+        greedy_score: 158,
+        best_known: 422,
+    },
+    Instance {
+        id: "hard-1",
+        difficulty: Difficulty::Hard,
+        seed: 7,
+        height: 30,
+        width: 30,
+        generator: WALLED,
+        random_score: 63.3,
+        greedy_score: 52,
+        best_known: 332,
+    },
+    Instance {
+        id: "hard-2",
+        difficulty: Difficulty::Hard,
+        seed: 8,
+        height: 30,
+        width: 30,
+        generator: WALLED,
+        random_score: 87.9,
+        greedy_score: 67,
+        best_known: 173,
+    },
+    Instance {
+        id: "hard-3",
+        difficulty: Difficulty::Hard,
+        seed: 17,
+        height: 30,
+        width: 30,
+        generator: WALLED,
+        random_score: 80.9,
+        greedy_score: 28,
+        best_known: 467,
+    },
+];
+
+pub fn instance(id: &str) -> Option<&'static Instance> {
+    INSTANCES.iter().find(|instance| instance.id == id)
+}
+
+pub fn suite(difficulty: Difficulty) -> impl Iterator<Item = &'static Instance> {
+    INSTANCES
+        .iter()
+        .filter(move |instance| instance.difficulty == difficulty)
+}
+
+// Final score of `agent` on one instance.
+pub fn play(instance: &Instance, agent: impl Fn(&MazeState) -> crate::game::Outcome) -> ScoreType {
+    let mut state = instance.state();
+    while !state.is_done() {
+        let crate::game::Outcome::Action(action) = agent(&state) else {
+            break;
+        };
+        state.advance(action);
+    }
+    state.game_score
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReportRow {
+    pub id: &'static str,
+    pub score: ScoreType,
+    pub normalized: f64,
+}
+
+// The score of one agent on a set of instances, raw and normalized.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct NormalizedReport {
+    pub rows: Vec<ReportRow>,
+}
+
+impl NormalizedReport {
+    pub fn new<'a>(
+        instances: impl IntoIterator<Item = &'a Instance>,
+        agent: impl Fn(&MazeState) -> crate::game::Outcome,
+    ) -> NormalizedReport {
+        let rows = instances
+            .into_iter()
+            .map(|instance| {
+                let score = play(instance, &agent);
+                ReportRow {
+                    id: instance.id,
+                    score,
+                    normalized: instance.normalize(score),
+                }
+            })
+            .collect();
+        NormalizedReport { rows }
+    }
+
+    pub fn mean(&self) -> f64 {
+        self.rows.iter().map(|row| row.normalized).sum::<f64>() / self.rows.len().max(1) as f64
+    }
+}
+
+impl std::fmt::Display for NormalizedReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for row in self.rows.iter() {
+            writeln!(f, "{}\t{}\t{:.1}%", row.id, row.score, row.normalized)?;
+        }
+        write!(f, "mean\t\t{:.1}%", self.mean())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use super::*;
+
+    #[test]
+    fn test_instances() {
+        assert_eq!(instance("medium-2").unwrap().seed, 14);
+        assert!(instance("medium-4").is_none());
+        for difficulty in [Difficulty::Easy, Difficulty::Medium, Difficulty::Hard] {
+            assert_eq!(suite(difficulty).count(), 3);
+        }
+        let mut ids: Vec<_> = INSTANCES.iter().map(|instance| instance.id).collect();
+        ids.sort();
+        ids.dedup();
+        assert_eq!(ids.len(), INSTANCES.len());
+    }
+
+    #[test]
+    fn test_reference_scores() {
+        for instance in INSTANCES.iter() {
+            assert_eq!(play(instance, greedy_action), instance.greedy_score);
+            let beam = play(instance, |s| beam_search_action(s, 2, END_TURN));
+            println!("{} Beam Search Score:\t{}", instance.id, beam);
+            assert!(beam <= instance.best_known);
+        }
+    }
+
+    #[test]
+    fn test_normalized_report() {
+        let easy = instance("easy-1").unwrap();
+        assert_eq!(easy.normalize(easy.best_known), 100.0);
+        assert!(easy.normalize(0) < 0.0);
+        for instance in INSTANCES.iter() {
+            let random = crate::stats::mc_evaluate_with(
+                &instance.state(),
+                1000,
+                &mut StdRng::seed_from_u64(instance.seed),
+            );
+            assert!((random.mean - instance.random_score).abs() < 0.1);
+            assert!(instance.normalize(random.mean.round() as ScoreType).abs() < 1.0);
+        }
+        let report = NormalizedReport::new(suite(Difficulty::Easy), greedy_action);
+        println!("{}", report);
+        assert_eq!(report.rows.len(), 3);
+        assert!(0.0 < report.mean() && report.mean() < 100.0);
+    }
+}
@@ -0,0 +1,371 @@
+#![allow(unused)]
+
+use std::{
+    fs::{self, OpenOptions},
+    io::{self, Write},
+    path::{Path, PathBuf},
+    process::Command,
+    str::FromStr,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::game::{Outcome, ScoreType};
+use crate::instances::{play, Instance};
+use crate::section3::MazeState;
+
+// One benchmark run: which code, which agent with which parameters, on which seeds, and what
+// it scored on each of them.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Run {
+    pub rev: String,
+    pub timestamp: u64,
+    pub algorithm: String,
+    pub params: String,
+    pub seeds: Vec<u64>,
+    pub scores: Vec<ScoreType>,
+}
+
+impl Run {
+    // A run of the code checked out now.
+    pub fn new(algorithm: &str, params: &str, seeds: Vec<u64>, scores: Vec<ScoreType>) -> Run {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_secs());
+        Run {
+            rev: git_rev(),
+            timestamp,
+            algorithm: algorithm.to_string(),
+            params: params.to_string(),
+            seeds,
+            scores,
+        }
+    }
+
+    // Plays `agent` once on each instance; the seeds are the instance seeds.
+    pub fn on_instances<'a>(
+        algorithm: &str,
+        params: &str,
+        instances: impl IntoIterator<Item = &'a Instance>,
+        agent: impl Fn(&MazeState) -> Outcome,
+    ) -> Run {
+        let (seeds, scores) = instances
+            .into_iter()
+            .map(|instance| (instance.seed, play(instance, &agent)))
+            .unzip();
+        Run::new(algorithm, params, seeds, scores)
+    }
+
+    pub fn mean(&self) -> f64 {
+        self.scores.iter().map(|&score| score as f64).sum::<f64>() / self.scores.len().max(1) as f64
+    }
+}
+
+// The short hash of HEAD, with a `-dirty` suffix for uncommitted changes, or "unknown" outside
+// of a git checkout.
+pub fn git_rev() -> String {
+    let git = |args: &[&str]| {
+        Command::new("git")
+            .args(args)
+            .output()
+            .ok()
+            .filter(|output| output.status.success())
+            .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_string())
+    };
+    let Some(rev) = git(&["rev-parse", "--short", "HEAD"]) else {
+        return "unknown".to_string();
+    };
+    match git(&["status", "--porcelain", "--untracked-files=no"]) {
+        Some(status) if !status.is_empty() => format!("{}-dirty", rev),
+        _ => rev,
+    }
+}
+
+// Every run recorded in a JSON Lines file, one object per run in the order they were recorded.
+// The file is only ever appended to, so runs from older revisions stay comparable.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Leaderboard {
+    path: PathBuf,
+    runs: Vec<Run>,
+}
+
+impl Leaderboard {
+    // A missing file is an empty leaderboard, created by the first `record`.
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Leaderboard> {
+        let path = path.as_ref().to_path_buf();
+        let runs = match fs::read_to_string(&path) {
+            Ok(text) => text
+                .lines()
+                .filter(|line| !line.trim().is_empty())
+                .map(|line| line.parse())
+                .collect::<Result<_, _>>()
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => vec![],
+            Err(e) => return Err(e),
+        };
+        Ok(Leaderboard { path, runs })
+    }
+
+    pub fn record(&mut self, run: Run) -> io::Result<()> {
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        writeln!(file, "{}", run)?;
+        self.runs.push(run);
+        Ok(())
+    }
+
+    pub fn runs(&self) -> &[Run] {
+        &self.runs
+    }
+
+    // The runs of `algorithm`, or all runs, best mean first; equal means keep their order.
+    pub fn query(&self, algorithm: Option<&str>) -> Vec<&Run> {
+        let mut runs: Vec<&Run> = self
+            .runs
+            .iter()
+            .filter(|run| algorithm.is_none_or(|algorithm| run.algorithm == algorithm))
+            .collect();
+        runs.sort_by(|a, b| b.mean().total_cmp(&a.mean()));
+        runs
+    }
+
+    pub fn print(&self, algorithm: Option<&str>) {
+        println!("rank\tmean\talgorithm\tparams\trev\tseeds");
+        for (rank, run) in self.query(algorithm).into_iter().enumerate() {
+            println!(
+                "{}\t{:.2}\t{}\t{}\t{}\t{}",
+                rank + 1,
+                run.mean(),
+                run.algorithm,
+                run.params,
+                run.rev,
+                run.seeds.len()
+            );
+        }
+    }
+}
+
+fn write_string(f: &mut std::fmt::Formatter<'_>, s: &str) -> std::fmt::Result {
+    write!(f, "\"")?;
+    for c in s.chars() {
+        match c {
+            '"' => write!(f, "\\\"")?,
+            '\\' => write!(f, "\\\\")?,
+            '\n' => write!(f, "\\n")?,
+            '\t' => write!(f, "\\t")?,
+            c if c.is_control() => write!(f, "\\u{:04x}", c as u32)?,
+            c => write!(f, "{}", c)?,
+        }
+    }
+    write!(f, "\"")
+}
+
+fn write_list<T: std::fmt::Display>(
+    f: &mut std::fmt::Formatter<'_>,
+    values: &[T],
+) -> std::fmt::Result {
+    write!(f, "[")?;
+    for (i, value) in values.iter().enumerate() {
+        if 0 < i {
+            write!(f, ",")?;
+        }
+        write!(f, "{}", value)?;
+    }
+    write!(f, "]")
+}
+
+// A single-line JSON object.
+impl std::fmt::Display for Run {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{{\"rev\":")?;
+        write_string(f, &self.rev)?;
+        write!(f, ",\"timestamp\":{},\"algorithm\":", self.timestamp)?;
+        write_string(f, &self.algorithm)?;
+        write!(f, ",\"params\":")?;
+        write_string(f, &self.params)?;
+        write!(f, ",\"seeds\":")?;
+        write_list(f, &self.seeds)?;
+        write!(f, ",\"scores\":")?;
+        write_list(f, &self.scores)?;
+        write!(f, "}}")
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseRunError(pub String);
+
+impl std::fmt::Display for ParseRunError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "invalid run '{}'", self.0)
+    }
+}
+
+impl std::error::Error for ParseRunError {}
+
+// Just enough JSON for the objects `Run` writes: strings, integers and lists of integers.
+struct Parser<'a> {
+    rest: &'a str,
+}
+
+impl Parser<'_> {
+    fn eat(&mut self, c: char) -> Option<()> {
+        self.rest = self.rest.trim_start().strip_prefix(c)?;
+        Some(())
+    }
+
+    fn string(&mut self) -> Option<String> {
+        self.eat('"')?;
+        let mut s = String::new();
+        let mut chars = self.rest.char_indices();
+        while let Some((i, c)) = chars.next() {
+            match c {
+                '"' => {
+                    self.rest = &self.rest[i + 1..];
+                    return Some(s);
+                }
+                '\\' => s.push(match chars.next()?.1 {
+                    'n' => '\n',
+                    't' => '\t',
+                    'u' => {
+                        let hex: String =
+                            (0..4).filter_map(|_| chars.next()).map(|p| p.1).collect();
+                        char::from_u32(u32::from_str_radix(&hex, 16).ok()?)?
+                    }
+                    c => c,
+                }),
+                c => s.push(c),
+            }
+        }
+        None
+    }
+
+    fn number<T: FromStr>(&mut self) -> Option<T> {
+        self.rest = self.rest.trim_start();
+        let end = self
+            .rest
+            .find(|c: char| !(c == '-' || c.is_ascii_digit()))
+            .unwrap_or(self.rest.len());
+        let value = self.rest[..end].parse().ok()?;
+        self.rest = &self.rest[end..];
+        Some(value)
+    }
+
+    fn list<T: FromStr>(&mut self) -> Option<Vec<T>> {
+        self.eat('[')?;
+        let mut values = vec![];
+        if self.eat(']').is_some() {
+            return Some(values);
+        }
+        loop {
+            values.push(self.number()?);
+            if self.eat(']').is_some() {
+                return Some(values);
+            }
+            self.eat(',')?;
+        }
+    }
+
+    fn run(&mut self) -> Option<Run> {
+        let mut run = Run {
+            rev: String::new(),
+            timestamp: 0,
+            algorithm: String::new(),
+            params: String::new(),
+            seeds: vec![],
+            scores: vec![],
+        };
+        self.eat('{')?;
+        loop {
+            let key = self.string()?;
+            self.eat(':')?;
+            match key.as_str() {
+                "rev" => run.rev = self.string()?,
+                "timestamp" => run.timestamp = self.number()?,
+                "algorithm" => run.algorithm = self.string()?,
+                "params" => run.params = self.string()?,
+                "seeds" => run.seeds = self.list()?,
+                "scores" => run.scores = self.list()?,
+                _ => return None,
+            }
+            if self.eat('}').is_some() {
+                break;
+            }
+            self.eat(',')?;
+        }
+        self.rest.trim().is_empty().then_some(run)
+    }
+}
+
+impl FromStr for Run {
+    type Err = ParseRunError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Parser { rest: s }
+            .run()
+            .filter(|run| run.seeds.len() == run.scores.len())
+            .ok_or_else(|| ParseRunError(s.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::instances::{suite, Difficulty};
+    use crate::section3::greedy_action;
+
+    #[test]
+    fn test_run_round_trip() {
+        let run = Run {
+            rev: "abc1234-dirty".to_string(),
+            timestamp: 1700000000,
+            algorithm: "beam \"wide\"".to_string(),
+            params: "width=2\tdepth=\\3".to_string(),
+            seeds: vec![1, 2, 3],
+            scores: vec![10, -4, 0],
+        };
+        let line = run.to_string();
+        assert!(!line.contains('\n'));
+        assert_eq!(line.parse::<Run>().unwrap(), run);
+        let empty = Run {
+            seeds: vec![],
+            scores: vec![],
+            ..run.clone()
+        };
+        assert_eq!(empty.to_string().parse::<Run>().unwrap(), empty);
+        assert!("{\"rev\":\"a\"".parse::<Run>().is_err());
+        assert!("{\"seeds\":[1],\"scores\":[]}".parse::<Run>().is_err());
+        assert!("{\"color\":\"red\"}".parse::<Run>().is_err());
+    }
+
+    #[test]
+    fn test_leaderboard() {
+        let path = std::env::temp_dir().join(format!("leaderboard-{}.jsonl", std::process::id()));
+        let _ = fs::remove_file(&path);
+        let mut leaderboard = Leaderboard::open(&path).unwrap();
+        assert!(leaderboard.runs().is_empty());
+        let greedy = Run::on_instances("greedy", "", suite(Difficulty::Easy), greedy_action);
+        assert_eq!(greedy.seeds, vec![4, 11, 1]);
+        assert_eq!(greedy.scores, vec![301, 313, 455]);
+        leaderboard.record(greedy.clone()).unwrap();
+        leaderboard
+            .record(Run::new("perfect", "", vec![4], vec![1000]))
+            .unwrap();
+        leaderboard
+            .record(Run::new("greedy", "copy", vec![4], vec![0]))
+            .unwrap();
+
+        let reopened = Leaderboard::open(&path).unwrap();
+        assert_eq!(reopened, leaderboard);
+        let all: Vec<&str> = reopened
+            .query(None)
+            .iter()
+            .map(|run| run.algorithm.as_str())
+            .collect();
+        assert_eq!(all, vec!["perfect", "greedy", "greedy"]);
+        let greedy_runs = reopened.query(Some("greedy"));
+        assert_eq!(greedy_runs.len(), 2);
+        assert_eq!(greedy_runs[0], &greedy);
+        fs::remove_file(&path).unwrap();
+    }
+}
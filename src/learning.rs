@@ -0,0 +1,436 @@
+#![allow(unused)]
+
+use rand::Rng;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct WeightedEvaluator {
+    pub weights: Vec<f64>,
+}
+
+impl WeightedEvaluator {
+    pub fn new(weights: Vec<f64>) -> WeightedEvaluator {
+        WeightedEvaluator { weights }
+    }
+
+    pub fn evaluate(&self, features: &[f64]) -> f64 {
+        self.weights.iter().zip(features).map(|(w, f)| w * f).sum()
+    }
+}
+
+pub trait Features {
+    fn grid_size(&self) -> (usize, usize);
+    fn point(&self, y: usize, x: usize) -> f64;
+    fn position(&self) -> (usize, usize);
+    fn remaining_turns(&self) -> usize;
+
+    // Only used for drawing; grids without walls keep the default.
+    fn is_wall(&self, y: usize, x: usize) -> bool {
+        false
+    }
+
+    // [points within distance 2, distance to the best cell, remaining turns,
+    //  point density of the four quadrants around the character, distance to the nearest point]
+    fn features(&self) -> Vec<f64> {
+        let (height, width) = self.grid_size();
+        let (cy, cx) = self.position();
+        let mut nearby = 0.0;
+        let mut best = (0.0, 0);
+        let mut sums = [0.0; 4];
+        let mut cells = [0; 4];
+        let mut nearest = height + width;
+        for y in 0..height {
+            for x in 0..width {
+                if (y, x) == (cy, cx) {
+                    continue;
+                }
+                let point = self.point(y, x);
+                let distance = cy.abs_diff(y) + cx.abs_diff(x);
+                if distance <= 2 {
+                    nearby += point;
+                }
+                if best.0 < point || (best.0 == point && distance < best.1) {
+                    best = (point, distance);
+                }
+                let quadrant = if y < cy && x <= cx {
+                    0
+                } else if y <= cy && x > cx {
+                    1
+                } else if y > cy && x >= cx {
+                    2
+                } else {
+                    3
+                };
+                sums[quadrant] += point;
+                cells[quadrant] += 1;
+                if 0.0 < point {
+                    nearest = nearest.min(distance);
+                }
+            }
+        }
+        let mut features = vec![nearby, best.1 as f64, self.remaining_turns() as f64];
+        features.extend(
+            sums.iter()
+                .zip(cells)
+                .map(|(&sum, n)| if n == 0 { 0.0 } else { sum / n as f64 }),
+        );
+        features.push(nearest as f64);
+        features
+    }
+}
+
+// Simulated annealing over the weights, maximizing `objective`. Every step perturbs one
+// weight by a uniform value in `-step..step`. Returns the best weights and their objective.
+pub fn anneal_weights(
+    rng: &mut impl Rng,
+    initial: &[f64],
+    number: usize,
+    start_temp: f64,
+    end_temp: f64,
+    step: f64,
+    objective: impl Fn(&[f64]) -> f64,
+) -> (Vec<f64>, f64) {
+    let mut now_weights = initial.to_vec();
+    let mut now_score = objective(&now_weights);
+    let mut best_weights = now_weights.clone();
+    let mut best_score = now_score;
+    for i in 0..number {
+        let mut next_weights = now_weights.clone();
+        let index = rng.gen_range(0..next_weights.len());
+        next_weights[index] += rng.gen_range(-step..step);
+        let next_score = objective(&next_weights);
+        let temp = start_temp + (end_temp - start_temp) * (i as f64 / number as f64);
+        let probability = ((next_score - now_score) / temp).exp();
+        if now_score < next_score || probability > rng.gen_range(0.0..1.0) {
+            now_score = next_score;
+            now_weights = next_weights;
+        }
+        if best_score < now_score {
+            best_score = now_score;
+            best_weights = now_weights.clone();
+        }
+    }
+    (best_weights, best_score)
+}
+
+// Cross-entropy method with an independent normal distribution per weight.
+pub fn cross_entropy_weights(
+    rng: &mut impl Rng,
+    mean: &[f64],
+    std_dev: f64,
+    population: usize,
+    elite: usize,
+    generation: usize,
+    objective: impl Fn(&[f64]) -> f64,
+) -> (Vec<f64>, f64) {
+    let mut mean = mean.to_vec();
+    let mut std_dev = vec![std_dev; mean.len()];
+    let mut best = (mean.clone(), objective(&mean));
+    for _ in 0..generation {
+        let mut samples: Vec<(Vec<f64>, f64)> = (0..population)
+            .map(|_| {
+                let weights: Vec<f64> = mean
+                    .iter()
+                    .zip(std_dev.iter())
+                    .map(|(m, s)| m + s * standard_normal(rng))
+                    .collect();
+                let score = objective(&weights);
+                (weights, score)
+            })
+            .collect();
+        samples.sort_by(|a, b| b.1.total_cmp(&a.1));
+        let elites = &samples[..elite.clamp(1, samples.len())];
+        if best.1 < elites[0].1 {
+            best = elites[0].clone();
+        }
+        let n = elites.len() as f64;
+        for i in 0..mean.len() {
+            mean[i] = elites.iter().map(|(w, _)| w[i]).sum::<f64>() / n;
+            let var = elites
+                .iter()
+                .map(|(w, _)| (w[i] - mean[i]).powi(2))
+                .sum::<f64>()
+                / n;
+            std_dev[i] = var.sqrt().max(1e-3);
+        }
+    }
+    best
+}
+
+// (1+1) evolution strategy: every step adds normal noise of scale `sigma` to all weights and
+// keeps the result unless it scores worse. `sigma` follows the 1/5 success rule, growing by
+// exp(1/3) after an improvement and shrinking by exp(-1/12) otherwise, so it settles where one
+// step in five improves. Returns the best weights and their objective.
+pub fn one_plus_one_es(
+    rng: &mut impl Rng,
+    initial: &[f64],
+    sigma: f64,
+    number: usize,
+    objective: impl Fn(&[f64]) -> f64,
+) -> (Vec<f64>, f64) {
+    let mut best = (initial.to_vec(), objective(initial));
+    let mut sigma = sigma;
+    for _ in 0..number {
+        let next_weights: Vec<f64> = best
+            .0
+            .iter()
+            .map(|w| w + sigma * standard_normal(rng))
+            .collect();
+        let next_score = objective(&next_weights);
+        if best.1 < next_score {
+            sigma *= (1.0f64 / 3.0).exp();
+        } else {
+            sigma *= (-1.0f64 / 12.0).exp();
+        }
+        if best.1 <= next_score {
+            best = (next_weights, next_score);
+        }
+    }
+    best
+}
+
+// A problem over real vectors whose `objective` is maximized, like the weight searches above.
+// Searches start uniformly inside `bounds`, one (low, high) pair per coordinate, and stay
+// inside them.
+pub trait ContinuousProblem {
+    fn bounds(&self) -> Vec<(f64, f64)>;
+    fn objective(&self, x: &[f64]) -> f64;
+}
+
+// Any objective within fixed bounds, e.g. the game score of a set of evaluation weights.
+pub struct FnProblem<F: Fn(&[f64]) -> f64> {
+    pub bounds: Vec<(f64, f64)>,
+    pub objective: F,
+}
+
+impl<F: Fn(&[f64]) -> f64> ContinuousProblem for FnProblem<F> {
+    fn bounds(&self) -> Vec<(f64, f64)> {
+        self.bounds.clone()
+    }
+
+    fn objective(&self, x: &[f64]) -> f64 {
+        (self.objective)(x)
+    }
+}
+
+// Minus the Rastrigin function: a bowl covered in local optima, with the best value 0 at the
+// origin.
+pub struct Rastrigin {
+    pub dimension: usize,
+}
+
+impl ContinuousProblem for Rastrigin {
+    fn bounds(&self) -> Vec<(f64, f64)> {
+        vec![(-5.12, 5.12); self.dimension]
+    }
+
+    fn objective(&self, x: &[f64]) -> f64 {
+        let tau = 2.0 * std::f64::consts::PI;
+        -x.iter()
+            .map(|xi| 10.0 + xi * xi - 10.0 * (tau * xi).cos())
+            .sum::<f64>()
+    }
+}
+
+// Minus the Rosenbrock function: a narrow curved valley, with the best value 0 at (1, .., 1).
+pub struct Rosenbrock {
+    pub dimension: usize,
+}
+
+impl ContinuousProblem for Rosenbrock {
+    fn bounds(&self) -> Vec<(f64, f64)> {
+        vec![(-2.0, 2.0); self.dimension]
+    }
+
+    fn objective(&self, x: &[f64]) -> f64 {
+        -x.windows(2)
+            .map(|w| 100.0 * (w[1] - w[0] * w[0]).powi(2) + (1.0 - w[0]).powi(2))
+            .sum::<f64>()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PsoConfig {
+    pub particles: usize,
+    pub iterations: usize,
+    // How much of its velocity a particle keeps, and how hard it is pulled towards its own
+    // best and the swarm's best.
+    pub inertia: f64,
+    pub cognitive: f64,
+    pub social: f64,
+}
+
+impl Default for PsoConfig {
+    // The constriction coefficients of Clerc and Kennedy.
+    fn default() -> Self {
+        PsoConfig {
+            particles: 30,
+            iterations: 200,
+            inertia: 0.7298,
+            cognitive: 1.49618,
+            social: 1.49618,
+        }
+    }
+}
+
+// Particle swarm optimization. Velocities are capped at the width of the bounds and positions
+// clamped to them. Returns the best point and its objective.
+pub fn particle_swarm(
+    rng: &mut impl Rng,
+    problem: &impl ContinuousProblem,
+    config: &PsoConfig,
+) -> (Vec<f64>, f64) {
+    let bounds = problem.bounds();
+    let mut positions: Vec<Vec<f64>> = (0..config.particles.max(1))
+        .map(|_| {
+            bounds
+                .iter()
+                .map(|&(low, high)| rng.gen_range(low..=high))
+                .collect()
+        })
+        .collect();
+    let mut velocities: Vec<Vec<f64>> = positions
+        .iter()
+        .map(|_| {
+            bounds
+                .iter()
+                .map(|&(low, high)| rng.gen_range(-(high - low)..=high - low) * 0.1)
+                .collect()
+        })
+        .collect();
+    let mut personal: Vec<(Vec<f64>, f64)> = positions
+        .iter()
+        .map(|x| (x.clone(), problem.objective(x)))
+        .collect();
+    let mut best = personal
+        .iter()
+        .max_by(|a, b| a.1.total_cmp(&b.1))
+        .unwrap()
+        .clone();
+    for _ in 0..config.iterations {
+        for ((x, v), own) in positions
+            .iter_mut()
+            .zip(velocities.iter_mut())
+            .zip(personal.iter_mut())
+        {
+            for (i, &(low, high)) in bounds.iter().enumerate() {
+                let (r1, r2): (f64, f64) = (rng.gen(), rng.gen());
+                v[i] = config.inertia * v[i]
+                    + config.cognitive * r1 * (own.0[i] - x[i])
+                    + config.social * r2 * (best.0[i] - x[i]);
+                v[i] = v[i].clamp(low - high, high - low);
+                x[i] = (x[i] + v[i]).clamp(low, high);
+            }
+            let score = problem.objective(x);
+            if own.1 < score {
+                *own = (x.clone(), score);
+                if best.1 < score {
+                    best = own.clone();
+                }
+            }
+        }
+    }
+    best
+}
+
+// Box-Muller transform.
+pub fn standard_normal(rng: &mut impl Rng) -> f64 {
+    let u1: f64 = rng.gen_range(f64::EPSILON..1.0);
+    let u2: f64 = rng.gen_range(0.0..1.0);
+    (-2.0 * u1.ln()).sqrt() * (2.0 * std::f64::consts::PI * u2).cos()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    fn objective(weights: &[f64]) -> f64 {
+        -(weights[0] - 2.0).powi(2) - (weights[1] + 1.0).powi(2)
+    }
+
+    #[test]
+    fn test_weighted_evaluator() {
+        let evaluator = WeightedEvaluator::new(vec![1.0, -2.0]);
+        assert_eq!(evaluator.evaluate(&[3.0, 1.0]), 1.0);
+    }
+
+    struct Board {
+        points: [[f64; 3]; 3],
+        position: (usize, usize),
+    }
+
+    impl Features for Board {
+        fn grid_size(&self) -> (usize, usize) {
+            (3, 3)
+        }
+
+        fn point(&self, y: usize, x: usize) -> f64 {
+            self.points[y][x]
+        }
+
+        fn position(&self) -> (usize, usize) {
+            self.position
+        }
+
+        fn remaining_turns(&self) -> usize {
+            7
+        }
+    }
+
+    #[test]
+    fn test_default_features() {
+        let board = Board {
+            points: [[4.0, 0.0, 0.0], [0.0, 9.0, 0.0], [0.0, 0.0, 2.0]],
+            position: (1, 1),
+        };
+        let features = board.features();
+        assert_eq!(features.len(), 8);
+        assert_eq!(&features[..3], &[6.0, 2.0, 7.0]);
+        assert_eq!(features[3], 2.0);
+        assert_eq!(features[5], 1.0);
+        assert_eq!(features[7], 2.0);
+    }
+
+    #[test]
+    fn test_anneal_weights() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let (weights, score) =
+            anneal_weights(&mut rng, &[0.0, 0.0], 5000, 1.0, 0.001, 0.2, objective);
+        assert!(score > -0.01, "{:?}", weights);
+    }
+
+    #[test]
+    fn test_one_plus_one_es() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let (weights, score) = one_plus_one_es(&mut rng, &[0.0, 0.0], 1.0, 300, objective);
+        assert!(score > -0.01, "{:?}", weights);
+    }
+
+    #[test]
+    fn test_particle_swarm() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let config = PsoConfig::default();
+        let (x, score) = particle_swarm(&mut rng, &Rosenbrock { dimension: 2 }, &config);
+        assert!(score > -1e-3, "{:?}", x);
+        assert!((x[0] - 1.0).abs() < 0.1 && (x[1] - 1.0).abs() < 0.1);
+        // Many runs end in one of the local optima at whole numbers; the best of a few does not.
+        let best = (0..5)
+            .map(|_| particle_swarm(&mut rng, &Rastrigin { dimension: 2 }, &config).1)
+            .fold(f64::NEG_INFINITY, f64::max);
+        assert!(best > -1e-3);
+        let weights = FnProblem {
+            bounds: vec![(-5.0, 5.0); 2],
+            objective,
+        };
+        let (weights, score) = particle_swarm(&mut rng, &weights, &config);
+        assert!(score > -0.01, "{:?}", weights);
+    }
+
+    #[test]
+    fn test_cross_entropy_weights() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let (weights, score) =
+            cross_entropy_weights(&mut rng, &[0.0, 0.0], 3.0, 50, 10, 30, objective);
+        assert!(score > -0.01, "{:?}", weights);
+    }
+}
@@ -0,0 +1,92 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+// Telemetry hooks: with the `tracing` feature they forward to `tracing` at debug level,
+// otherwise they expand to nothing. Defined before the modules so every module can use them.
+#[cfg(feature = "tracing")]
+macro_rules! search_span {
+    ($($arg:tt)*) => {
+        let _span = tracing::debug_span!($($arg)*).entered();
+    };
+}
+#[cfg(not(feature = "tracing"))]
+#[allow(unused_macros)]
+macro_rules! search_span {
+    ($($arg:tt)*) => {};
+}
+
+#[cfg(feature = "tracing")]
+macro_rules! search_event {
+    ($($arg:tt)*) => {
+        tracing::debug!($($arg)*)
+    };
+}
+#[cfg(not(feature = "tracing"))]
+#[allow(unused_macros)]
+macro_rules! search_event {
+    ($($arg:tt)*) => {};
+}
+
+#[cfg(feature = "std")]
+pub mod aco;
+pub mod alpha_beta;
+#[cfg(feature = "gif")]
+pub mod animation;
+pub mod arena;
+#[cfg(feature = "std")]
+pub mod bandit;
+pub mod beam;
+pub mod branch_and_bound;
+#[cfg(feature = "std")]
+pub mod cooperative_maze;
+pub mod discrepancy;
+pub mod endgame;
+pub mod expectimax;
+#[cfg(feature = "experiment")]
+pub mod experiment;
+#[cfg(feature = "external-eval")]
+pub mod external;
+pub mod game;
+#[cfg(feature = "std")]
+pub mod grid;
+#[cfg(feature = "std")]
+pub mod hashing;
+#[cfg(feature = "std")]
+pub mod instances;
+#[cfg(feature = "std")]
+pub mod leaderboard;
+#[cfg(feature = "std")]
+pub mod learning;
+#[cfg(feature = "std")]
+pub mod maze_generator;
+pub mod mcts;
+#[cfg(feature = "std")]
+pub mod memory;
+#[cfg(feature = "std")]
+pub mod opening_book;
+#[cfg(feature = "std")]
+pub mod partial_maze;
+pub mod pn_search;
+#[cfg(feature = "std")]
+pub mod search_tree;
+#[cfg(feature = "std")]
+pub mod section3;
+#[cfg(feature = "std")]
+pub mod section4;
+#[cfg(feature = "std")]
+pub mod section5;
+#[cfg(feature = "std")]
+pub mod simultaneous;
+#[cfg(feature = "std")]
+pub mod stats;
+#[cfg(feature = "std")]
+pub mod stochastic_maze;
+#[cfg(all(feature = "std", any(test, feature = "testing")))]
+pub mod testing;
+#[cfg(feature = "std")]
+pub mod tournament;
+#[cfg(feature = "std")]
+pub mod tsp;
+#[cfg(feature = "std")]
+pub mod tuning;
@@ -1,8 +1,43 @@
-pub mod section3;
-pub mod section4;
-
-use section4::play_game;
+use lean_search::leaderboard::Leaderboard;
+use lean_search::section4::play_game;
 
 fn main() {
-    play_game();
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match args.first().map(String::as_str) {
+        // lean-search leaderboard PATH [ALGORITHM]
+        Some("leaderboard") => {
+            let Some(path) = args.get(1) else {
+                eprintln!("usage: lean-search leaderboard PATH [ALGORITHM]");
+                std::process::exit(2);
+            };
+            match Leaderboard::open(path) {
+                Ok(leaderboard) => leaderboard.print(args.get(2).map(String::as_str)),
+                Err(e) => {
+                    eprintln!("{}: {}", path, e);
+                    std::process::exit(1);
+                }
+            }
+        }
+        // lean-search experiment FILE
+        #[cfg(feature = "experiment")]
+        Some("experiment") => {
+            let Some(path) = args.get(1) else {
+                eprintln!("usage: lean-search experiment FILE");
+                std::process::exit(2);
+            };
+            let runs = lean_search::experiment::Experiment::load(path).and_then(|e| e.run());
+            match runs {
+                Ok(runs) => {
+                    for run in runs {
+                        println!("{}\t{}\t{:.2}", run.algorithm, run.params, run.mean());
+                    }
+                }
+                Err(e) => {
+                    eprintln!("{}: {}", path, e);
+                    std::process::exit(1);
+                }
+            }
+        }
+        _ => play_game(),
+    }
 }
@@ -0,0 +1,277 @@
+#![allow(unused)]
+
+use std::collections::VecDeque;
+
+use rand::Rng;
+
+use crate::learning::standard_normal;
+
+pub type ScoreType = crate::game::ScoreType;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PointDistribution {
+    // Every value in 0..=max is equally likely.
+    Uniform {
+        max: ScoreType,
+    },
+    // Values fall off with the distance to the nearest of `centers` random cells.
+    Clustered {
+        max: ScoreType,
+        centers: usize,
+        radius: f64,
+    },
+    Gaussian {
+        max: ScoreType,
+        mean: f64,
+        std_dev: f64,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MazeGenerator {
+    pub distribution: PointDistribution,
+    // Probability that a cell is a wall.
+    pub wall_density: f64,
+    // Probability that an open cell has no point.
+    pub sparsity: f64,
+}
+
+impl Default for MazeGenerator {
+    // The classic fill: no walls and uniform points in 0..10.
+    fn default() -> Self {
+        MazeGenerator {
+            distribution: PointDistribution::Uniform { max: 9 },
+            wall_density: 0.0,
+            sparsity: 0.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Maze<const H: usize, const W: usize> {
+    pub points: [[ScoreType; W]; H],
+    pub walls: [[bool; W]; H],
+}
+
+impl MazeGenerator {
+    // The largest point `generate` can place.
+    pub fn max_point(&self) -> ScoreType {
+        match self.distribution {
+            PointDistribution::Uniform { max }
+            | PointDistribution::Clustered { max, .. }
+            | PointDistribution::Gaussian { max, .. } => max,
+        }
+    }
+
+    // `start` is kept open and empty. Open cells that cannot be reached from it (or from the
+    // largest open area without a start) become walls, so every point left can be collected.
+    pub fn generate<const H: usize, const W: usize>(
+        &self,
+        rng: &mut impl Rng,
+        start: Option<(usize, usize)>,
+    ) -> Maze<H, W> {
+        let centers: Vec<(usize, usize)> = match self.distribution {
+            PointDistribution::Clustered { centers, .. } => (0..centers)
+                .map(|_| (rng.gen_range(0..H), rng.gen_range(0..W)))
+                .collect(),
+            _ => vec![],
+        };
+        let mut maze = Maze {
+            points: [[0; W]; H],
+            walls: [[false; W]; H],
+        };
+        for y in 0..H {
+            for x in 0..W {
+                if start == Some((y, x)) {
+                    continue;
+                }
+                // Extra draws only when enabled, so the default keeps the old random stream.
+                if 0.0 < self.wall_density && rng.gen_bool(self.wall_density) {
+                    maze.walls[y][x] = true;
+                    continue;
+                }
+                if 0.0 < self.sparsity && rng.gen_bool(self.sparsity) {
+                    continue;
+                }
+                maze.points[y][x] = self.point(rng, y, x, &centers);
+            }
+        }
+        let origin = start.or_else(|| largest_area(&maze.walls));
+        let reachable = match origin {
+            Some(origin) => reachable_cells(&maze.walls, origin),
+            None => [[false; W]; H],
+        };
+        for (y, row) in reachable.iter().enumerate() {
+            for (x, &is_reachable) in row.iter().enumerate() {
+                if !is_reachable {
+                    maze.walls[y][x] = true;
+                    maze.points[y][x] = 0;
+                }
+            }
+        }
+        maze
+    }
+
+    fn point(
+        &self,
+        rng: &mut impl Rng,
+        y: usize,
+        x: usize,
+        centers: &[(usize, usize)],
+    ) -> ScoreType {
+        match self.distribution {
+            PointDistribution::Uniform { max } => sample_point(rng, max),
+            PointDistribution::Clustered { max, radius, .. } => {
+                let weight = centers
+                    .iter()
+                    .map(|&(cy, cx)| {
+                        let d2 = (cy.abs_diff(y).pow(2) + cx.abs_diff(x).pow(2)) as f64;
+                        (-d2 / (2.0 * radius * radius)).exp()
+                    })
+                    .fold(0.0, f64::max);
+                ((weight * (max + 1) as f64 * rng.gen::<f64>()) as ScoreType).min(max)
+            }
+            PointDistribution::Gaussian { max, mean, std_dev } => {
+                let value = mean + std_dev * standard_normal(rng);
+                (value.round() as ScoreType).clamp(0, max)
+            }
+        }
+    }
+}
+
+// A point in 0..=max drawn through u64, so the random stream and the maze do not change with
+// the width of `ScoreType`.
+pub fn sample_point(rng: &mut impl Rng, max: ScoreType) -> ScoreType {
+    rng.gen_range(0..=max.max(0) as u64) as ScoreType
+}
+
+const DX: [isize; 4] = [1, -1, 0, 0];
+const DY: [isize; 4] = [0, 0, 1, -1];
+
+// Open cells connected to `start` by moves through open cells.
+pub fn reachable_cells<const H: usize, const W: usize>(
+    walls: &[[bool; W]; H],
+    start: (usize, usize),
+) -> [[bool; W]; H] {
+    let mut seen = [[false; W]; H];
+    if walls[start.0][start.1] {
+        return seen;
+    }
+    let mut queue = VecDeque::from([start]);
+    seen[start.0][start.1] = true;
+    while let Some((y, x)) = queue.pop_front() {
+        for d in 0..4 {
+            let ty = y.checked_add_signed(DY[d]).unwrap_or(H);
+            let tx = x.checked_add_signed(DX[d]).unwrap_or(W);
+            if ty < H && tx < W && !walls[ty][tx] && !seen[ty][tx] {
+                seen[ty][tx] = true;
+                queue.push_back((ty, tx));
+            }
+        }
+    }
+    seen
+}
+
+// True when every open cell can be reached from `start`.
+pub fn is_reachable<const H: usize, const W: usize>(
+    walls: &[[bool; W]; H],
+    start: (usize, usize),
+) -> bool {
+    let seen = reachable_cells(walls, start);
+    (0..H).all(|y| (0..W).all(|x| walls[y][x] || seen[y][x]))
+}
+
+fn largest_area<const H: usize, const W: usize>(walls: &[[bool; W]; H]) -> Option<(usize, usize)> {
+    let mut done = [[false; W]; H];
+    let mut best: Option<((usize, usize), usize)> = None;
+    for y in 0..H {
+        for x in 0..W {
+            if walls[y][x] || done[y][x] {
+                continue;
+            }
+            let seen = reachable_cells(walls, (y, x));
+            let mut size = 0;
+            for (done_row, seen_row) in done.iter_mut().zip(seen.iter()) {
+                for (d, &s) in done_row.iter_mut().zip(seen_row.iter()) {
+                    if s {
+                        *d = true;
+                        size += 1;
+                    }
+                }
+            }
+            if best.is_none_or(|(_, best_size)| best_size < size) {
+                best = Some(((y, x), size));
+            }
+        }
+    }
+    best.map(|(cell, _)| cell)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    #[test]
+    fn test_default_matches_uniform_fill() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let maze: Maze<5, 5> = MazeGenerator::default().generate(&mut rng, Some((1, 2)));
+        let mut rng = StdRng::seed_from_u64(0);
+        for y in 0..5 {
+            for x in 0..5 {
+                let expected = if (y, x) == (1, 2) {
+                    0
+                } else {
+                    rng.gen_range(0..10i64) as ScoreType
+                };
+                assert_eq!(maze.points[y][x], expected);
+                assert!(!maze.walls[y][x]);
+            }
+        }
+    }
+
+    #[test]
+    fn test_walls_keep_reachability() {
+        let generator = MazeGenerator {
+            wall_density: 0.4,
+            ..Default::default()
+        };
+        for seed in 0..20 {
+            let mut rng = StdRng::seed_from_u64(seed);
+            let maze: Maze<10, 10> = generator.generate(&mut rng, Some((0, 0)));
+            assert!(!maze.walls[0][0]);
+            assert!(is_reachable(&maze.walls, (0, 0)));
+            let maze: Maze<10, 10> = generator.generate(&mut rng, None);
+            let open = (0..10)
+                .flat_map(|y| (0..10).map(move |x| (y, x)))
+                .find(|&(y, x)| !maze.walls[y][x]);
+            assert!(open.is_none_or(|start| is_reachable(&maze.walls, start)));
+        }
+    }
+
+    #[test]
+    fn test_distributions_stay_in_range() {
+        let distributions = [
+            PointDistribution::Clustered {
+                max: 9,
+                centers: 3,
+                radius: 2.0,
+            },
+            PointDistribution::Gaussian {
+                max: 9,
+                mean: 4.5,
+                std_dev: 3.0,
+            },
+        ];
+        let mut rng = StdRng::seed_from_u64(0);
+        for distribution in distributions {
+            let generator = MazeGenerator {
+                distribution,
+                sparsity: 0.3,
+                ..Default::default()
+            };
+            let maze: Maze<20, 20> = generator.generate(&mut rng, None);
+            assert!(maze.points.iter().flatten().all(|p| (0..=9).contains(p)));
+        }
+    }
+}
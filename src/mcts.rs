@@ -0,0 +1,1172 @@
+#![allow(unused)]
+
+use alloc::{
+    collections::{BTreeMap, BTreeSet},
+    vec,
+    vec::Vec,
+};
+use core::{ops::Index, time::Duration};
+#[cfg(feature = "std")]
+use std::{
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    thread::{self, JoinHandle},
+};
+
+#[cfg(feature = "std")]
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+use crate::arena::{Arena, ArenaNode, NodeId};
+#[cfg(feature = "std")]
+use crate::game::{parallel_map, SystemClock};
+use crate::game::{
+    Action, Clock, Outcome, RngStreams, SearchLimits, TwoPlayerState, WinningStatus,
+};
+#[cfg(feature = "std")]
+use crate::hashing::StateHash;
+
+pub trait Policy<S> {
+    // Prior probabilities for `actions`, in the same order.
+    fn priors(&self, state: &S, actions: &[Action]) -> Vec<f64>;
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct UniformPolicy;
+
+impl<S> Policy<S> for UniformPolicy {
+    fn priors(&self, _state: &S, actions: &[Action]) -> Vec<f64> {
+        vec![1.0 / actions.len().max(1) as f64; actions.len()]
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Selection {
+    Ucb1,
+    // Q + c * P * sqrt(N) / (1 + n)
+    Puct,
+    // Sequential halving at the root (SHOT) and UCB1 below it: the playouts are split evenly
+    // over the root moves in rounds that each drop the worse half. Needs a playout budget, so
+    // a search against the clock uses UCB1 at the root as well.
+    SequentialHalving,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Parallel {
+    None,
+    // Independent trees whose root visit counts are summed at the end.
+    Root(usize),
+    // Every leaf evaluation runs one playout per thread and averages them.
+    Leaf(usize),
+    // Root parallel over a fixed number of trees, tree `i` playing out with stream `i` of
+    // `seed`. Threads only decide which tree runs where, so the chosen action is the same for
+    // every thread count.
+    Deterministic {
+        trees: usize,
+        threads: usize,
+        seed: u64,
+    },
+}
+
+// Progressive widening: a node visited n times only selects among its first ceil(k * n^alpha)
+// children, highest prior first, so that wide nodes spend their playouts on few moves before
+// trying more of them.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Widening {
+    pub k: f64,
+    pub alpha: f64,
+}
+
+#[cfg(feature = "std")]
+impl Widening {
+    fn children(&self, n: usize, len: usize) -> usize {
+        ((self.k * (n as f64).powf(self.alpha)).ceil() as usize).clamp(1, len.max(1))
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct MctsConfig {
+    pub playout_number: usize,
+    pub c: f64,
+    pub expand_threshold: usize,
+    pub selection: Selection,
+    pub parallel: Parallel,
+    pub widening: Option<Widening>,
+    // Seeds the playouts of the main thread and of root-parallel thread `i` with streams of
+    // `RngStreams::new(seed)`, so a search repeats exactly; None draws fresh entropy.
+    // Leaf-parallel playouts always do.
+    pub seed: Option<u64>,
+    // Only `max_nodes` applies: every tree stops its playouts once it has expanded that many
+    // nodes. Two-player states have no game score to stop at and no action costs, and
+    // `PonderingMcts` keeps one tree for the whole game, so it ignores the limits.
+    pub limits: SearchLimits,
+}
+
+impl Default for MctsConfig {
+    fn default() -> Self {
+        MctsConfig {
+            playout_number: 1000,
+            c: 1.0,
+            expand_threshold: 10,
+            selection: Selection::Ucb1,
+            parallel: Parallel::None,
+            widening: None,
+            seed: None,
+            limits: SearchLimits::default(),
+        }
+    }
+}
+
+// Plays random moves to the end and returns the value for the player to move in `state`.
+#[cfg(feature = "std")]
+pub fn playout<S: TwoPlayerState>(state: S) -> f64 {
+    playout_with(state, &mut rand::thread_rng())
+}
+
+pub fn playout_with<S: TwoPlayerState>(mut state: S, rng: &mut impl Rng) -> f64 {
+    let mut flip = false;
+    let value = loop {
+        if let Some(value) = state.terminal_value() {
+            break value;
+        }
+        let legal_actions = state.legal_actions();
+        if legal_actions.is_empty() {
+            break 0.0;
+        }
+        state.advance(legal_actions[rng.gen_range(0..legal_actions.len())]);
+        flip = !flip;
+    };
+    if flip {
+        1.0 - value
+    } else {
+        value
+    }
+}
+
+#[cfg(feature = "std")]
+fn leaf_playout<S: TwoPlayerState + Send>(state: &S, thread_number: usize) -> f64 {
+    thread::scope(|scope| {
+        let handles: Vec<_> = (0..thread_number.max(1))
+            .map(|_| {
+                let state = state.clone();
+                scope.spawn(move || playout(state))
+            })
+            .collect();
+        let values: Vec<f64> = handles.into_iter().map(|h| h.join().unwrap()).collect();
+        values.iter().sum::<f64>() / values.len() as f64
+    })
+}
+
+// One position of an MCTS tree. Its parents are kept by the `Arena` the tree lives in.
+#[derive(Debug, Clone)]
+pub struct Node<S> {
+    pub state: S,
+    pub prior: f64,
+    // Accumulated value for the player to move in `state`.
+    pub w: f64,
+    pub n: usize,
+    // Each move and the node it leads to. With a transposition table two moves, from this
+    // node or from another one, may lead to the same node.
+    pub children: Vec<(Action, NodeId)>,
+}
+
+impl<S> Node<S> {
+    pub fn new(state: S) -> Node<S> {
+        Node {
+            state,
+            prior: 1.0,
+            w: 0.0,
+            n: 0,
+            children: vec![],
+        }
+    }
+}
+
+// An MCTS tree stored in an `Arena`: children are `NodeId`s and every node links back to its
+// first parent, so the tree grows without an allocation per node and moving the root down
+// after a move only changes `root`. The siblings it leaves behind stay in the arena until the
+// tree is dropped.
+//
+// A tree made by `with_transpositions` also keeps a table from state hashes to nodes, and an
+// expansion that reaches a state already in it links to that node instead of allocating a new
+// one, so the lines that transpose share their statistics. The game must not repeat a state
+// along a line of play, as `AlternateMazeState` cannot since it counts turns, or the tree would
+// gain cycles.
+#[derive(Debug, Clone)]
+pub struct Tree<S> {
+    arena: Arena<Node<S>>,
+    root: NodeId,
+    // Nodes whose children were generated.
+    expanded: usize,
+    hash: Option<fn(&S) -> u64>,
+    table: BTreeMap<u64, NodeId>,
+}
+
+impl<S: TwoPlayerState + Send> Tree<S> {
+    pub fn new(state: S) -> Tree<S> {
+        let mut arena = Arena::new();
+        let root = arena.root(Node::new(state));
+        Tree {
+            arena,
+            root,
+            expanded: 0,
+            hash: None,
+            table: BTreeMap::new(),
+        }
+    }
+
+    #[cfg(feature = "std")]
+    pub fn with_transpositions(state: S) -> Tree<S>
+    where
+        S: StateHash,
+    {
+        let mut tree = Tree::new(state);
+        tree.hash = Some(|state: &S| state.state_hash());
+        tree.remember(tree.root);
+        tree
+    }
+
+    pub fn root(&self) -> NodeId {
+        self.root
+    }
+
+    pub fn expanded(&self) -> usize {
+        self.expanded
+    }
+
+    // The node already holding `state`, if the tree keeps a transposition table.
+    fn lookup(&self, state: &S) -> Option<NodeId> {
+        self.table.get(&(self.hash?)(state)).copied()
+    }
+
+    fn remember(&mut self, id: NodeId) {
+        if let Some(hash) = self.hash {
+            self.table.insert(hash(&self.arena[id].state), id);
+        }
+    }
+
+    // Children come highest prior first, in the game's order among equal priors.
+    pub fn expand(&mut self, id: NodeId, policy: &dyn Policy<S>) {
+        let state = &self.arena[id].state;
+        let legal_actions = state.legal_actions();
+        let priors = policy.priors(state, &legal_actions);
+        let mut ranked: Vec<(f64, Action, S)> = priors
+            .into_iter()
+            .zip(legal_actions)
+            .map(|(prior, action)| {
+                let mut next_state = state.clone();
+                next_state.advance(action);
+                (prior, action, next_state)
+            })
+            .collect();
+        ranked.sort_by(|a, b| b.0.total_cmp(&a.0));
+        let children = ranked
+            .into_iter()
+            .map(|(prior, action, state)| {
+                if let Some(shared) = self.lookup(&state) {
+                    return (action, shared);
+                }
+                let node = Node {
+                    prior,
+                    ..Node::new(state)
+                };
+                let child = self.arena.alloc(id, action, node);
+                self.remember(child);
+                (action, child)
+            })
+            .collect();
+        self.arena[id].children = children;
+        self.expanded += 1;
+    }
+
+    // The actions at the root and their visit counts, in child order.
+    pub fn root_visits(&self) -> Vec<(Option<Action>, usize)> {
+        self.arena[self.root]
+            .children
+            .iter()
+            .map(|&(action, c)| (Some(action), self.arena[c].n))
+            .collect()
+    }
+
+    pub fn best_action(&self) -> Option<Action> {
+        let children = &self.arena[self.root].children;
+        let best = children.iter().max_by_key(|&&(_, c)| self.arena[c].n)?;
+        Some(best.0)
+    }
+
+    // Distinct nodes under the root, the root included.
+    pub fn tree_size(&self) -> usize {
+        self.reachable().len()
+    }
+
+    fn reachable(&self) -> BTreeSet<NodeId> {
+        let mut seen = BTreeSet::new();
+        let mut stack = vec![self.root];
+        while let Some(id) = stack.pop() {
+            if seen.insert(id) {
+                stack.extend(self.arena[id].children.iter().map(|&(_, c)| c));
+            }
+        }
+        seen
+    }
+
+    // Bytes held by the arena, the child lists and the transposition table, assuming `S` owns
+    // no heap memory itself.
+    pub fn tree_bytes(&self) -> usize {
+        let children: usize = self.arena.values().map(|n| n.children.capacity()).sum();
+        self.arena.capacity() * core::mem::size_of::<ArenaNode<Node<S>>>()
+            + children * core::mem::size_of::<(Action, NodeId)>()
+            + self.table.len() * core::mem::size_of::<(u64, NodeId)>()
+    }
+
+    // Makes the child reached by `action` the new root, or a fresh node if it was never
+    // expanded.
+    pub fn reroot(&mut self, action: Action) {
+        let children = &self.arena[self.root].children;
+        self.root = match children.iter().find(|&&(a, _)| a == action) {
+            Some(&(_, child)) => child,
+            None => {
+                let state = self.arena[self.root].state.next_state(action);
+                match self.lookup(&state) {
+                    Some(shared) => shared,
+                    None => {
+                        let root = self.arena.root(Node::new(state));
+                        self.remember(root);
+                        root
+                    }
+                }
+            }
+        };
+    }
+}
+
+// Playouts and UCB/PUCT selection need `f64::ln` and `sqrt`, which only `std` has.
+#[cfg(feature = "std")]
+impl<S: TwoPlayerState + Send> Tree<S> {
+    pub fn evaluate(&mut self, config: &MctsConfig, policy: &dyn Policy<S>) -> f64 {
+        self.evaluate_with(self.root, config, policy, &mut rand::thread_rng())
+    }
+
+    // One playout through `id`; returns its value for the player to move at `id`.
+    pub fn evaluate_with(
+        &mut self,
+        id: NodeId,
+        config: &MctsConfig,
+        policy: &dyn Policy<S>,
+        rng: &mut impl Rng,
+    ) -> f64 {
+        let node = &mut self.arena[id];
+        if let Some(value) = node.state.terminal_value() {
+            node.w += value;
+            node.n += 1;
+            return value;
+        }
+        if node.children.is_empty() {
+            let value = match config.parallel {
+                Parallel::Leaf(thread_number) => leaf_playout(&node.state, thread_number),
+                _ => playout_with(node.state.clone(), rng),
+            };
+            node.w += value;
+            node.n += 1;
+            if node.n == config.expand_threshold {
+                self.expand(id, policy);
+            }
+            return value;
+        }
+        let child = self.next_child(id, config);
+        let value = 1.0 - self.evaluate_with(child, config, policy, rng);
+        let node = &mut self.arena[id];
+        node.w += value;
+        node.n += 1;
+        value
+    }
+
+    // Value of the child from the side of its parent's player.
+    fn child_value(&self, child: NodeId) -> f64 {
+        let child = &self.arena[child];
+        if child.n == 0 {
+            0.0
+        } else {
+            1.0 - child.w / child.n as f64
+        }
+    }
+
+    fn next_child(&self, id: NodeId, config: &MctsConfig) -> NodeId {
+        let node = &self.arena[id];
+        let total = node
+            .children
+            .iter()
+            .map(|&(_, c)| self.arena[c].n)
+            .sum::<usize>() as f64;
+        let score = |id: NodeId| {
+            let child = &self.arena[id];
+            match config.selection {
+                Selection::Ucb1 | Selection::SequentialHalving => {
+                    if child.n == 0 {
+                        return f64::INFINITY;
+                    }
+                    self.child_value(id) + config.c * (2.0 * total.ln() / child.n as f64).sqrt()
+                }
+                Selection::Puct => {
+                    self.child_value(id)
+                        + config.c * child.prior * total.sqrt() / (1.0 + child.n as f64)
+                }
+            }
+        };
+        let open = match config.widening {
+            Some(widening) => widening.children(node.n, node.children.len()),
+            None => node.children.len(),
+        };
+        let mut best = node.children[0].1;
+        for &(_, child) in &node.children[1..open] {
+            if score(best) < score(child) {
+                best = child;
+            }
+        }
+        best
+    }
+}
+
+impl<S> Index<NodeId> for Tree<S> {
+    type Output = Node<S>;
+
+    fn index(&self, id: NodeId) -> &Node<S> {
+        &self.arena[id]
+    }
+}
+
+#[cfg(feature = "std")]
+impl<S: TwoPlayerState + StateHash + Send> Tree<S> {
+    // Nodes holding a state already held by another node under the root: work the
+    // transposition table of `with_transpositions` shares, so always 0 in such a tree.
+    pub fn transpositions(&self) -> usize {
+        let mut seen = BTreeSet::new();
+        self.reachable()
+            .into_iter()
+            .filter(|&id| !seen.insert(self.arena[id].state.state_hash()))
+            .count()
+    }
+}
+
+#[cfg(feature = "std")]
+fn search_root<S: TwoPlayerState + Send>(
+    state: &S,
+    config: &MctsConfig,
+    policy: &dyn Policy<S>,
+    playout_number: usize,
+    rng: &mut impl Rng,
+) -> Tree<S> {
+    search_tree(
+        Tree::new(state.clone()),
+        config,
+        policy,
+        playout_number,
+        rng,
+    )
+}
+
+#[cfg(feature = "std")]
+fn search_tree<S: TwoPlayerState + Send>(
+    mut tree: Tree<S>,
+    config: &MctsConfig,
+    policy: &dyn Policy<S>,
+    playout_number: usize,
+    rng: &mut impl Rng,
+) -> Tree<S> {
+    let root = tree.root();
+    tree.expand(root, policy);
+    if tree[root].children.is_empty() {
+        return tree;
+    }
+    if config.selection == Selection::SequentialHalving {
+        sequential_halving(&mut tree, config, policy, playout_number, rng);
+        return tree;
+    }
+    for _ in 0..playout_number {
+        if config.limits.out_of_nodes(tree.expanded()) {
+            break;
+        }
+        tree.evaluate_with(root, config, policy, rng);
+    }
+    tree
+}
+
+// Spends `playout_number` playouts on the root's children in ceil(log2(k)) rounds of equal
+// budget, each round split evenly over the children left and keeping the better half of them
+// by value. What the rounds leave over goes to the last child, which so ends up the most
+// visited one and is what `best_action` picks.
+#[cfg(feature = "std")]
+fn sequential_halving<S: TwoPlayerState + Send>(
+    tree: &mut Tree<S>,
+    config: &MctsConfig,
+    policy: &dyn Policy<S>,
+    playout_number: usize,
+    rng: &mut impl Rng,
+) {
+    let root = tree.root();
+    let mut candidates: Vec<NodeId> = tree[root].children.iter().map(|&(_, c)| c).collect();
+    let rounds = candidates.len().next_power_of_two().trailing_zeros().max(1) as usize;
+    let mut used = 0;
+    let mut visit = |tree: &mut Tree<S>, child: NodeId, rng: &mut _| {
+        if config.limits.out_of_nodes(tree.expanded()) {
+            return;
+        }
+        let value = 1.0 - tree.evaluate_with(child, config, policy, rng);
+        let root = &mut tree.arena[root];
+        root.w += value;
+        root.n += 1;
+    };
+    while 1 < candidates.len() {
+        let per_child = (playout_number / rounds / candidates.len()).max(1);
+        for &child in candidates.iter() {
+            for _ in 0..per_child {
+                visit(tree, child, rng);
+            }
+        }
+        used += per_child * candidates.len();
+        candidates.sort_by(|&a, &b| tree.child_value(b).total_cmp(&tree.child_value(a)));
+        candidates.truncate(candidates.len().div_ceil(2));
+    }
+    for _ in 0..playout_number.saturating_sub(used).max(1) {
+        visit(tree, candidates[0], rng);
+    }
+}
+
+// Sums the root visit counts of every tree, in tree order, and picks the most visited action.
+#[cfg(feature = "std")]
+fn merge_visits(visits: Vec<Vec<(Option<Action>, usize)>>) -> Outcome {
+    let mut trees = visits.into_iter();
+    let Some(mut total) = trees.next() else {
+        return Outcome::NoAction;
+    };
+    for tree in trees {
+        for (sum, (_, n)) in total.iter_mut().zip(tree) {
+            sum.1 += n;
+        }
+    }
+    total
+        .into_iter()
+        .max_by_key(|&(_, n)| n)
+        .and_then(|(action, _)| action)
+        .into()
+}
+
+#[cfg(feature = "std")]
+fn config_rng(config: &MctsConfig, stream: u64) -> ChaCha8Rng {
+    match config.seed {
+        Some(seed) => RngStreams::new(seed).stream(stream).rng(),
+        None => ChaCha8Rng::from_entropy(),
+    }
+}
+
+#[cfg(feature = "std")]
+fn deterministic_root_parallel<S: TwoPlayerState + Send + Sync>(
+    state: &S,
+    config: &MctsConfig,
+    policy: &(dyn Policy<S> + Sync),
+    tree_number: usize,
+    thread_number: usize,
+    seed: u64,
+) -> Outcome {
+    let tree_number = tree_number.max(1);
+    let playout_number = config.playout_number.div_ceil(tree_number);
+    let visits = parallel_map(tree_number, thread_number, |i| {
+        let mut rng = RngStreams::new(seed).stream(i as u64).rng();
+        search_root(state, config, policy, playout_number, &mut rng).root_visits()
+    });
+    merge_visits(visits)
+}
+
+#[cfg(feature = "std")]
+pub fn mcts_action_with_policy<S: TwoPlayerState + Send + Sync>(
+    state: &S,
+    config: &MctsConfig,
+    policy: &(dyn Policy<S> + Sync),
+) -> Outcome {
+    if state.is_done() {
+        return Outcome::NoAction;
+    }
+    let thread_number = match config.parallel {
+        Parallel::Root(thread_number) => thread_number,
+        Parallel::Deterministic {
+            trees,
+            threads,
+            seed,
+        } => return deterministic_root_parallel(state, config, policy, trees, threads, seed),
+        _ => {
+            let mut rng = config_rng(config, 0);
+            return search_root(state, config, policy, config.playout_number, &mut rng)
+                .best_action()
+                .into();
+        }
+    };
+    let thread_number = thread_number.max(1);
+    let playout_number = config.playout_number.div_ceil(thread_number);
+    let visits: Vec<Vec<(Option<Action>, usize)>> = thread::scope(|scope| {
+        let handles: Vec<_> = (0..thread_number)
+            .map(|i| {
+                scope.spawn(move || {
+                    let mut rng = config_rng(config, i as u64);
+                    search_root(state, config, policy, playout_number, &mut rng).root_visits()
+                })
+            })
+            .collect();
+        handles.into_iter().map(|h| h.join().unwrap()).collect()
+    });
+    merge_visits(visits)
+}
+
+// One tree, as `Parallel::None`, whose transpositions share a node.
+#[cfg(feature = "std")]
+pub fn mcts_action_with_transpositions<S: TwoPlayerState + StateHash + Send>(
+    state: &S,
+    config: &MctsConfig,
+) -> Outcome {
+    if state.is_done() {
+        return Outcome::NoAction;
+    }
+    let tree = Tree::with_transpositions(state.clone());
+    let mut rng = config_rng(config, 0);
+    search_tree(
+        tree,
+        config,
+        &UniformPolicy,
+        config.playout_number,
+        &mut rng,
+    )
+    .best_action()
+    .into()
+}
+
+// Determinized MCTS for games with hidden information: one tree for each of `determinizations`,
+// fully observable states drawn from what the player knows, with the playouts split evenly
+// between them, and a vote by visit counts. The legal actions at the root must not depend on
+// the hidden part, so that every tree lists them in the same order.
+#[cfg(feature = "std")]
+pub fn determinized_mcts_action_with<S: TwoPlayerState + Send>(
+    determinizations: &[S],
+    config: &MctsConfig,
+    rng: &mut impl Rng,
+) -> Outcome {
+    if determinizations.is_empty() || determinizations[0].is_done() {
+        return Outcome::NoAction;
+    }
+    let playout_number = config.playout_number.div_ceil(determinizations.len());
+    let visits = determinizations
+        .iter()
+        .map(|state| search_root(state, config, &UniformPolicy, playout_number, rng).root_visits())
+        .collect();
+    merge_visits(visits)
+}
+
+// One tree that runs playouts until `time_limit` has passed on `clock` instead of for
+// `playout_number` of them.
+#[cfg(feature = "std")]
+pub fn mcts_action_with_clock<S: TwoPlayerState + Send>(
+    state: &S,
+    config: &MctsConfig,
+    time_limit: Duration,
+    clock: &impl Clock,
+) -> Outcome {
+    if state.is_done() {
+        return Outcome::NoAction;
+    }
+    let start = clock.now();
+    let mut rng = config_rng(config, 0);
+    let mut tree = Tree::new(state.clone());
+    let root = tree.root();
+    tree.expand(root, &UniformPolicy);
+    if tree[root].children.is_empty() {
+        return Outcome::NoAction;
+    }
+    while clock.now() - start < time_limit && !config.limits.out_of_nodes(tree.expanded()) {
+        tree.evaluate_with(root, config, &UniformPolicy, &mut rng);
+    }
+    tree.best_action().into()
+}
+
+#[cfg(feature = "std")]
+pub fn mcts_action_within<S: TwoPlayerState + Send>(
+    state: &S,
+    config: &MctsConfig,
+    time_limit: Duration,
+) -> Outcome {
+    mcts_action_with_clock(state, config, time_limit, &SystemClock::new())
+}
+
+#[cfg(feature = "std")]
+pub fn mcts_action<S: TwoPlayerState + Send + Sync>(state: &S, config: &MctsConfig) -> Outcome {
+    mcts_action_with_policy(state, config, &UniformPolicy)
+}
+
+// An MCTS agent that keeps its tree from move to move and goes on searching it in a background
+// thread while the opponent thinks. The playouts spent on the opponent's move are not lost:
+// `notify_opponent_move` re-roots the tree on the subtree of the move actually played. With
+// `MctsConfig::seed`, ply p searches with stream 2p and ponders with stream 2p + 1, so the
+// moves repeat exactly whenever the pondering does.
+#[cfg(feature = "std")]
+pub struct PonderingMcts<S> {
+    config: MctsConfig,
+    // Moves played since the start, by both sides.
+    ply: u64,
+    tree: Arc<Mutex<Tree<S>>>,
+    stop: Arc<AtomicBool>,
+    pondered: Arc<AtomicUsize>,
+    worker: Option<JoinHandle<()>>,
+}
+
+#[cfg(feature = "std")]
+impl<S: TwoPlayerState + Send + 'static> PonderingMcts<S> {
+    pub fn new(state: &S, config: MctsConfig) -> PonderingMcts<S> {
+        PonderingMcts {
+            config,
+            ply: 0,
+            tree: Arc::new(Mutex::new(Tree::new(state.clone()))),
+            stop: Arc::new(AtomicBool::new(false)),
+            pondered: Arc::new(AtomicUsize::new(0)),
+            worker: None,
+        }
+    }
+
+    // The position at the root of the tree.
+    pub fn state(&self) -> S {
+        let tree = self.tree.lock().unwrap();
+        tree[tree.root()].state.clone()
+    }
+
+    // Playouts run while pondering, over the whole game.
+    pub fn pondered(&self) -> usize {
+        self.pondered.load(Ordering::Relaxed)
+    }
+
+    // Adds `playout_number` playouts to the tree, plays the most visited action and re-roots
+    // the tree on it. Stops pondering first.
+    pub fn choose_action(&mut self) -> Outcome {
+        self.stop_pondering();
+        let mut tree = self.tree.lock().unwrap();
+        let root = tree.root();
+        if tree[root].state.is_done() {
+            return Outcome::NoAction;
+        }
+        if tree[root].children.is_empty() {
+            tree.expand(root, &UniformPolicy);
+        }
+        if tree[root].children.is_empty() {
+            return Outcome::NoAction;
+        }
+        let mut rng = config_rng(&self.config, 2 * self.ply);
+        for _ in 0..self.config.playout_number {
+            tree.evaluate_with(root, &self.config, &UniformPolicy, &mut rng);
+        }
+        let Some(action) = tree.best_action() else {
+            return Outcome::NoAction;
+        };
+        tree.reroot(action);
+        self.ply += 1;
+        Outcome::Action(action)
+    }
+
+    // Searches the opponent's position in the background until the next call.
+    pub fn start_pondering(&mut self) {
+        self.stop_pondering();
+        self.stop.store(false, Ordering::Relaxed);
+        let (tree, stop, pondered) = (self.tree.clone(), self.stop.clone(), self.pondered.clone());
+        let config = self.config;
+        let mut rng = config_rng(&config, 2 * self.ply + 1);
+        self.worker = Some(thread::spawn(move || {
+            while !stop.load(Ordering::Relaxed) {
+                let mut tree = tree.lock().unwrap();
+                let root = tree.root();
+                if tree[root].state.is_done() {
+                    break;
+                }
+                tree.evaluate_with(root, &config, &UniformPolicy, &mut rng);
+                pondered.fetch_add(1, Ordering::Relaxed);
+            }
+        }));
+    }
+
+    // Stops pondering and re-roots the tree on the opponent's `action`.
+    pub fn notify_opponent_move(&mut self, action: Action) {
+        self.stop_pondering();
+        self.tree.lock().unwrap().reroot(action);
+        self.ply += 1;
+    }
+
+    fn stop_pondering(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(worker) = self.worker.take() {
+            worker.join().unwrap();
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<S> Drop for PonderingMcts<S> {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(worker) = self.worker.take() {
+            let _ = worker.join();
+        }
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use crate::section5::{random_action, AlternateMazeState};
+    use crate::tournament::play_match;
+
+    const GAME_NUMBER: usize = 100;
+
+    struct OnlyPolicy(Action);
+
+    impl<S> Policy<S> for OnlyPolicy {
+        fn priors(&self, _state: &S, actions: &[Action]) -> Vec<f64> {
+            actions
+                .iter()
+                .map(|&a| if a == self.0 { 1.0 } else { 0.0 })
+                .collect()
+        }
+    }
+
+    fn win_rate(config: MctsConfig) -> f64 {
+        let mut points = 0.0;
+        for seed in 0..GAME_NUMBER as u64 {
+            let state = AlternateMazeState::with_seed(seed);
+            let mcts = |s: &AlternateMazeState| mcts_action(s, &config);
+            points += play_match(state, &mcts, &random_action);
+            points += 1.0 - play_match(state, &random_action, &mcts);
+        }
+        points / (2 * GAME_NUMBER) as f64
+    }
+
+    #[test]
+    fn test_mcts_vs_random() {
+        let rate = win_rate(MctsConfig {
+            playout_number: 300,
+            ..Default::default()
+        });
+        println!("MCTS(UCB1) vs Random:\t{}", rate);
+        assert!(0.5 < rate);
+    }
+
+    #[test]
+    fn test_puct_vs_random() {
+        let rate = win_rate(MctsConfig {
+            playout_number: 300,
+            selection: Selection::Puct,
+            ..Default::default()
+        });
+        println!("MCTS(PUCT) vs Random:\t{}", rate);
+        assert!(0.5 < rate);
+    }
+
+    #[test]
+    fn test_sequential_halving_vs_random() {
+        let rate = win_rate(MctsConfig {
+            playout_number: 300,
+            selection: Selection::SequentialHalving,
+            ..Default::default()
+        });
+        println!("MCTS(SHOT) vs Random:\t{}", rate);
+        assert!(0.5 < rate);
+    }
+
+    #[test]
+    fn test_sequential_halving_budget() {
+        let state = AlternateMazeState::with_seed(0);
+        let config = MctsConfig {
+            selection: Selection::SequentialHalving,
+            ..Default::default()
+        };
+        for playout_number in [1, 10, 100] {
+            let tree = search_root(
+                &state,
+                &config,
+                &UniformPolicy,
+                playout_number,
+                &mut rand::thread_rng(),
+            );
+            let visits = tree.root_visits();
+            // Every move gets at least one playout, however small the budget.
+            assert!(visits.iter().all(|&(_, n)| 0 < n));
+            assert!(playout_number <= tree[tree.root()].n);
+            let most = visits.iter().map(|&(_, n)| n).max().unwrap();
+            assert_eq!(visits.iter().filter(|&&(_, n)| n == most).count(), 1);
+        }
+    }
+
+    #[test]
+    fn test_progressive_widening() {
+        let widening = Widening { k: 1.0, alpha: 0.5 };
+        assert_eq!(widening.children(0, 4), 1);
+        assert_eq!(widening.children(4, 4), 2);
+        assert_eq!(widening.children(100, 4), 4);
+        let rate = win_rate(MctsConfig {
+            playout_number: 300,
+            widening: Some(widening),
+            ..Default::default()
+        });
+        println!("MCTS(Progressive Widening) vs Random:\t{}", rate);
+        assert!(0.5 < rate);
+
+        // The most likely move first, and the root opens the others one at a time.
+        let state = AlternateMazeState::with_seed(0);
+        let action = state.legal_actions()[1];
+        let config = MctsConfig {
+            widening: Some(Widening { k: 1.0, alpha: 0.3 }),
+            ..Default::default()
+        };
+        let visits = search_root(
+            &state,
+            &config,
+            &OnlyPolicy(action),
+            10,
+            &mut rand::thread_rng(),
+        )
+        .root_visits();
+        assert_eq!(visits[0].0, Some(action));
+        assert_eq!(visits.iter().filter(|&&(_, n)| 0 < n).count(), 2);
+    }
+
+    #[test]
+    fn test_root_parallel_vs_random() {
+        let rate = win_rate(MctsConfig {
+            playout_number: 300,
+            parallel: Parallel::Root(4),
+            ..Default::default()
+        });
+        println!("MCTS(Root Parallel) vs Random:\t{}", rate);
+        assert!(0.5 < rate);
+    }
+
+    #[test]
+    fn test_leaf_parallel_vs_random() {
+        let rate = win_rate(MctsConfig {
+            playout_number: 100,
+            parallel: Parallel::Leaf(2),
+            ..Default::default()
+        });
+        println!("MCTS(Leaf Parallel) vs Random:\t{}", rate);
+        assert!(0.5 < rate);
+    }
+
+    #[test]
+    fn test_deterministic_parallel() {
+        for seed in 0..5 {
+            let state = AlternateMazeState::with_seed(seed);
+            let outcomes: Vec<Outcome> = [1, 2, 4]
+                .into_iter()
+                .map(|threads| {
+                    let config = MctsConfig {
+                        playout_number: 200,
+                        parallel: Parallel::Deterministic {
+                            trees: 4,
+                            threads,
+                            seed,
+                        },
+                        ..Default::default()
+                    };
+                    mcts_action(&state, &config)
+                })
+                .collect();
+            assert!(outcomes.iter().all(|&outcome| outcome == outcomes[0]));
+        }
+    }
+
+    #[test]
+    fn test_seeded_search() {
+        let state = AlternateMazeState::with_seed(0);
+        for parallel in [Parallel::None, Parallel::Root(3)] {
+            let config = MctsConfig {
+                playout_number: 60,
+                expand_threshold: 2,
+                parallel,
+                seed: Some(7),
+                ..Default::default()
+            };
+            let roots: Vec<Vec<usize>> = (0..2)
+                .map(|_| {
+                    let mut rng = config_rng(&config, 0);
+                    let tree = search_root(&state, &config, &UniformPolicy, 60, &mut rng);
+                    tree.root_visits().into_iter().map(|(_, n)| n).collect()
+                })
+                .collect();
+            assert_eq!(roots[0], roots[1]);
+            assert_eq!(mcts_action(&state, &config), mcts_action(&state, &config));
+        }
+    }
+
+    #[test]
+    fn test_transpositions() {
+        let state = AlternateMazeState::with_seed(0);
+        let config = MctsConfig {
+            expand_threshold: 1,
+            ..Default::default()
+        };
+        let root = search_root(
+            &state,
+            &config,
+            &UniformPolicy,
+            500,
+            &mut rand::thread_rng(),
+        );
+        let transpositions = root.transpositions();
+        assert!(transpositions < root.tree_size());
+        println!(
+            "MCTS Transpositions:\t{} / {}",
+            transpositions,
+            root.tree_size()
+        );
+    }
+
+    #[test]
+    fn test_transposition_table() {
+        // Cells without points let two lines of seed 9 meet.
+        let state = AlternateMazeState::with_seed(9);
+        let config = MctsConfig {
+            expand_threshold: 1,
+            ..Default::default()
+        };
+        let mut rng = RngStreams::new(0).rng();
+        let plain = search_root(&state, &config, &UniformPolicy, 3000, &mut rng);
+        let tree = Tree::with_transpositions(state);
+        let shared = search_tree(tree, &config, &UniformPolicy, 3000, &mut rng);
+        assert!(0 < plain.transpositions());
+        assert_eq!(shared.transpositions(), 0);
+        assert!(shared.tree_size() < plain.tree_size());
+
+        let mut points = 0.0;
+        for seed in 0..GAME_NUMBER as u64 {
+            let state = AlternateMazeState::with_seed(seed);
+            let mcts = |s: &AlternateMazeState| mcts_action_with_transpositions(s, &config);
+            points += play_match(state, &mcts, &random_action);
+            points += 1.0 - play_match(state, &random_action, &mcts);
+        }
+        let rate = points / (2 * GAME_NUMBER) as f64;
+        println!("MCTS(Transposition Table) vs Random:\t{}", rate);
+        assert!(0.5 < rate);
+    }
+
+    #[test]
+    fn test_node_limit() {
+        let state = AlternateMazeState::with_seed(0);
+        let config = MctsConfig {
+            expand_threshold: 1,
+            limits: SearchLimits {
+                max_nodes: Some(5),
+                ..SearchLimits::default()
+            },
+            ..Default::default()
+        };
+        let mut rng = RngStreams::new(0).rng();
+        let tree = search_root(&state, &config, &UniformPolicy, 1000, &mut rng);
+        assert_eq!(tree.expanded(), 5);
+        assert!(tree[tree.root()].n < 1000);
+        assert!(state
+            .legal_actions()
+            .contains(&mcts_action(&state, &config).unwrap()));
+    }
+
+    #[test]
+    fn test_reroot_keeps_subtree() {
+        let mut tree = Tree::new(AlternateMazeState::with_seed(0));
+        let config = MctsConfig::default();
+        for _ in 0..300 {
+            tree.evaluate(&config, &UniformPolicy);
+        }
+        let action = tree.best_action().unwrap();
+        let (size, visits) = (tree.tree_size(), tree.root_visits());
+        let (_, child) = tree[tree.root()]
+            .children
+            .iter()
+            .copied()
+            .find(|&(a, _)| a == action)
+            .unwrap();
+        tree.reroot(action);
+        assert_eq!(tree.root(), child);
+        assert_eq!(
+            Some((Some(action), tree[child].n)),
+            visits.into_iter().find(|v| v.0 == Some(action))
+        );
+        assert!(tree.tree_size() < size);
+    }
+
+    #[test]
+    fn test_pondering() {
+        let (mut points, mut pondered) = (0.0, 0);
+        for seed in 0..20 {
+            let mut state = AlternateMazeState::with_seed(seed);
+            let config = MctsConfig {
+                playout_number: 100,
+                ..Default::default()
+            };
+            let mut agent = PonderingMcts::new(&state, config);
+            while !state.is_done() {
+                state.advance(agent.choose_action().unwrap());
+                assert_eq!(agent.state().to_string(), state.to_string());
+                if state.is_done() {
+                    break;
+                }
+                agent.start_pondering();
+                let action = random_action(&state).unwrap();
+                thread::sleep(std::time::Duration::from_millis(1));
+                agent.notify_opponent_move(action);
+                state.advance(action);
+                assert_eq!(agent.state().to_string(), state.to_string());
+            }
+            pondered += agent.pondered();
+            points += match state.winning_status() {
+                WinningStatus::Win => 1.0,
+                WinningStatus::Draw => 0.5,
+                _ => 0.0,
+            };
+        }
+        assert!(0 < pondered);
+        // The game ends with the agent to move again, so the status is its own.
+        println!("Pondering MCTS vs Random:\t{}", points / 20.0);
+    }
+
+    #[test]
+    fn test_seeded_pondering_mcts() {
+        let config = MctsConfig {
+            playout_number: 50,
+            seed: Some(3),
+            ..Default::default()
+        };
+        let play = || {
+            let mut state = AlternateMazeState::with_seed(0);
+            let mut agent = PonderingMcts::new(&state, config);
+            let mut actions = vec![];
+            while let Outcome::Action(action) = agent.choose_action() {
+                state.advance(action);
+                actions.push(action);
+                let Some(&reply) = state.legal_actions().first() else {
+                    break;
+                };
+                agent.notify_opponent_move(reply);
+                state.advance(reply);
+            }
+            actions
+        };
+        assert_eq!(play(), play());
+    }
+
+    #[test]
+    fn test_puct_follows_prior() {
+        let state = AlternateMazeState::with_seed(0);
+        let action = state.legal_actions()[1];
+        let config = MctsConfig {
+            playout_number: 100,
+            expand_threshold: 2,
+            selection: Selection::Puct,
+            ..Default::default()
+        };
+        let outcome = mcts_action_with_policy(&state, &config, &OnlyPolicy(action));
+        assert_eq!(outcome, Outcome::Action(action));
+    }
+}
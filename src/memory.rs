@@ -0,0 +1,61 @@
+#![allow(unused)]
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+static CURRENT: AtomicUsize = AtomicUsize::new(0);
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+
+// The system allocator plus a count of the live and peak bytes. Only active once a binary
+// installs it:
+//
+//     #[global_allocator]
+//     static ALLOCATOR: CountingAllocator = CountingAllocator;
+pub struct CountingAllocator;
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let pointer = System.alloc(layout);
+        if !pointer.is_null() {
+            add(layout.size());
+        }
+        pointer
+    }
+
+    unsafe fn dealloc(&self, pointer: *mut u8, layout: Layout) {
+        System.dealloc(pointer, layout);
+        CURRENT.fetch_sub(layout.size(), Ordering::Relaxed);
+    }
+
+    unsafe fn realloc(&self, pointer: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let new_pointer = System.realloc(pointer, layout, new_size);
+        if !new_pointer.is_null() {
+            CURRENT.fetch_sub(layout.size(), Ordering::Relaxed);
+            add(new_size);
+        }
+        new_pointer
+    }
+}
+
+fn add(size: usize) {
+    let current = CURRENT.fetch_add(size, Ordering::Relaxed) + size;
+    PEAK.fetch_max(current, Ordering::Relaxed);
+}
+
+// Live heap bytes; zero unless `CountingAllocator` is installed.
+pub fn current_bytes() -> usize {
+    CURRENT.load(Ordering::Relaxed)
+}
+
+pub fn peak_bytes() -> usize {
+    PEAK.load(Ordering::Relaxed)
+}
+
+// Runs `f` and returns how far the heap grew above its level at the start. The counters are
+// process wide, so other threads allocating at the same time are counted too.
+pub fn measure_peak<R>(f: impl FnOnce() -> R) -> (R, usize) {
+    let start = current_bytes();
+    PEAK.store(start, Ordering::Relaxed);
+    let result = f();
+    (result, peak_bytes().saturating_sub(start))
+}
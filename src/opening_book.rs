@@ -0,0 +1,153 @@
+#![allow(unused)]
+
+use std::{collections::HashMap, fs, io, path::Path, str::FromStr};
+
+use crate::game::{Action, Outcome, TwoPlayerState};
+use crate::hashing::StateHash;
+
+// Best moves for positions near the start, keyed by `state_hash`. The hashes go through `Fnv`,
+// so a book saved by one build still matches positions in another.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct OpeningBook {
+    moves: HashMap<u64, Action>,
+}
+
+impl OpeningBook {
+    pub fn new() -> OpeningBook {
+        OpeningBook::default()
+    }
+
+    // Asks `search` for the move in every position within `plies` moves of `state`. The search
+    // runs once per position, so it can afford to be much deeper than during a game.
+    pub fn build<S>(state: &S, plies: usize, search: impl Fn(&S) -> Outcome) -> OpeningBook
+    where
+        S: TwoPlayerState + StateHash,
+    {
+        let mut book = OpeningBook::new();
+        let mut frontier = vec![state.clone()];
+        for _ in 0..plies {
+            let mut next_frontier = vec![];
+            for state in frontier {
+                if state.is_done() || book.moves.contains_key(&state.state_hash()) {
+                    continue;
+                }
+                let Outcome::Action(action) = search(&state) else {
+                    continue;
+                };
+                book.moves.insert(state.state_hash(), action);
+                for action in state.legal_actions() {
+                    next_frontier.push(state.next_state(action));
+                }
+            }
+            frontier = next_frontier;
+        }
+        book
+    }
+
+    pub fn insert(&mut self, state: &impl StateHash, action: Action) {
+        self.moves.insert(state.state_hash(), action);
+    }
+
+    pub fn lookup(&self, state: &impl StateHash) -> Option<Action> {
+        self.moves.get(&state.state_hash()).copied()
+    }
+
+    pub fn len(&self) -> usize {
+        self.moves.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.moves.is_empty()
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> io::Result<OpeningBook> {
+        fs::read_to_string(path)?
+            .parse()
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        fs::write(path, self.to_string())
+    }
+}
+
+// One `hash action` line per position, sorted by hash so that equal books save equally.
+impl std::fmt::Display for OpeningBook {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut moves: Vec<_> = self.moves.iter().collect();
+        moves.sort();
+        for (hash, action) in moves {
+            writeln!(f, "{:016x} {}", hash, action)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseBookError(pub String);
+
+impl std::fmt::Display for ParseBookError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "invalid book line '{}'", self.0)
+    }
+}
+
+impl std::error::Error for ParseBookError {}
+
+impl FromStr for OpeningBook {
+    type Err = ParseBookError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut book = OpeningBook::new();
+        for line in s.lines().map(str::trim).filter(|line| !line.is_empty()) {
+            let error = || ParseBookError(line.to_string());
+            let (hash, action) = line.split_once(' ').ok_or_else(error)?;
+            let hash = u64::from_str_radix(hash, 16).map_err(|_| error())?;
+            let action = action.trim().parse().map_err(|_| error())?;
+            book.moves.insert(hash, action);
+        }
+        Ok(book)
+    }
+}
+
+// Plays the book move while there is one and defers to `agent` after that. A book move that
+// is not legal, from a hash collision, is ignored.
+pub fn with_opening_book<S, A>(book: OpeningBook, agent: A) -> impl Fn(&S) -> Outcome
+where
+    S: TwoPlayerState + StateHash,
+    A: Fn(&S) -> Outcome,
+{
+    move |state: &S| match book.lookup(state) {
+        Some(action) if state.legal_actions().contains(&action) => Outcome::Action(action),
+        _ => agent(state),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mcts::{mcts_action, MctsConfig};
+    use crate::section5::{greedy_action, AlternateMazeState};
+
+    #[test]
+    fn test_opening_book() {
+        let state = AlternateMazeState::with_seed(0);
+        let config = MctsConfig {
+            playout_number: 2000,
+            ..Default::default()
+        };
+        let book = OpeningBook::build(&state, 2, |s| mcts_action(s, &config));
+        assert_eq!(book.len(), 1 + state.legal_actions().len());
+        let parsed: OpeningBook = book.to_string().parse().unwrap();
+        assert_eq!(parsed, book);
+        assert!("12 x".parse::<OpeningBook>().is_err());
+
+        let agent = with_opening_book(book.clone(), |_: &AlternateMazeState| Outcome::NoAction);
+        let action = agent(&state).unwrap();
+        assert_eq!(book.lookup(&state), Some(action));
+        let mut later = state.next_state(action);
+        later.advance(greedy_action(&later).unwrap());
+        assert!(book.lookup(&later).is_none());
+        assert_eq!(agent(&later), Outcome::NoAction);
+    }
+}
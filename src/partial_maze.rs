@@ -0,0 +1,369 @@
+#![allow(unused)]
+
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use crate::game::{add_score, ActionList, Outcome, TwoPlayerState, WinningStatus};
+use crate::maze_generator::sample_point;
+use crate::mcts::{determinized_mcts_action_with, MctsConfig};
+
+pub type ScoreType = crate::game::ScoreType;
+pub type Action = usize;
+
+pub const HEIGHT: usize = 10;
+pub const WIDTH: usize = 10;
+pub const END_TURN: usize = 20;
+// Manhattan distance up to which the character sees the points.
+pub const RADIUS: usize = 2;
+const MAX_POINT: ScoreType = 9;
+
+const DX: [isize; 4] = [1, -1, 0, 0];
+const DY: [isize; 4] = [0, 0, 1, -1];
+
+fn legal_actions(y: usize, x: usize) -> ActionList {
+    (0..4)
+        .filter(|&action| {
+            let ty = y.checked_add_signed(DY[action]).unwrap_or(HEIGHT);
+            let tx = x.checked_add_signed(DX[action]).unwrap_or(WIDTH);
+            ty < HEIGHT && tx < WIDTH
+        })
+        .collect()
+}
+
+// The true state, which the agent only sees through a `Belief`.
+#[derive(Debug, Clone, Copy)]
+pub struct PartialMazeState {
+    pub y: usize,
+    pub x: usize,
+    pub game_score: ScoreType,
+    points: [[ScoreType; WIDTH]; HEIGHT],
+    turn: usize,
+}
+
+impl PartialMazeState {
+    pub fn new() -> PartialMazeState {
+        Self::generate(&mut rand::thread_rng())
+    }
+
+    pub fn with_seed(seed: u64) -> PartialMazeState {
+        Self::generate(&mut StdRng::seed_from_u64(seed))
+    }
+
+    fn generate(rng: &mut impl Rng) -> PartialMazeState {
+        let y = rng.gen_range(0..HEIGHT);
+        let x = rng.gen_range(0..WIDTH);
+        let mut points = [[0; WIDTH]; HEIGHT];
+        for (py, row) in points.iter_mut().enumerate() {
+            for (px, point) in row.iter_mut().enumerate() {
+                if py == y && px == x {
+                    continue;
+                }
+                *point = sample_point(rng, MAX_POINT);
+            }
+        }
+        PartialMazeState {
+            y,
+            x,
+            game_score: 0,
+            points,
+            turn: 0,
+        }
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.turn == END_TURN
+    }
+
+    pub fn advance(&mut self, action: Action) {
+        self.y = self.y.checked_add_signed(DY[action]).unwrap_or(0);
+        self.x = self.x.checked_add_signed(DX[action]).unwrap_or(0);
+        self.game_score = add_score(self.game_score, self.points[self.y][self.x]);
+        self.points[self.y][self.x] = 0;
+        self.turn += 1;
+    }
+
+    pub fn legal_actions(&self) -> ActionList {
+        legal_actions(self.y, self.x)
+    }
+
+    // Pure transition; illegal actions leave the state unchanged.
+    pub fn next_state(&self, action: Action) -> PartialMazeState {
+        let mut next_state = *self;
+        if !self.is_done() && self.legal_actions().contains(&action) {
+            next_state.advance(action);
+        }
+        next_state
+    }
+
+    pub fn is_visible(&self, y: usize, x: usize) -> bool {
+        self.y.abs_diff(y) + self.x.abs_diff(x) <= RADIUS
+    }
+}
+
+impl Default for PartialMazeState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// What the agent knows: its own position and every point it has seen so far.
+#[derive(Debug, Clone, Copy)]
+pub struct Belief {
+    pub y: usize,
+    pub x: usize,
+    pub game_score: ScoreType,
+    known: [[Option<ScoreType>; WIDTH]; HEIGHT],
+    turn: usize,
+}
+
+impl Belief {
+    pub fn new(state: &PartialMazeState) -> Belief {
+        let mut belief = Belief {
+            y: state.y,
+            x: state.x,
+            game_score: state.game_score,
+            known: [[None; WIDTH]; HEIGHT],
+            turn: state.turn,
+        };
+        belief.observe(state);
+        belief
+    }
+
+    // Takes in the cells currently within RADIUS.
+    pub fn observe(&mut self, state: &PartialMazeState) {
+        self.y = state.y;
+        self.x = state.x;
+        self.game_score = state.game_score;
+        self.turn = state.turn;
+        for y in 0..HEIGHT {
+            for x in 0..WIDTH {
+                if state.is_visible(y, x) {
+                    self.known[y][x] = Some(state.points[y][x]);
+                }
+            }
+        }
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.turn == END_TURN
+    }
+
+    pub fn legal_actions(&self) -> ActionList {
+        legal_actions(self.y, self.x)
+    }
+
+    pub fn known_point(&self, y: usize, x: usize) -> Option<ScoreType> {
+        self.known[y][x]
+    }
+
+    // One determinization: unseen cells are drawn from the generation prior.
+    pub fn sample(&self, rng: &mut impl Rng) -> PartialMazeState {
+        let mut points = [[0; WIDTH]; HEIGHT];
+        for (y, row) in points.iter_mut().enumerate() {
+            for (x, point) in row.iter_mut().enumerate() {
+                *point = self.known[y][x].unwrap_or_else(|| sample_point(rng, MAX_POINT));
+            }
+        }
+        PartialMazeState {
+            y: self.y,
+            x: self.x,
+            game_score: self.game_score,
+            points,
+            turn: self.turn,
+        }
+    }
+}
+
+// Best score reachable within `depth` turns in a fully known state.
+fn best_score(state: &PartialMazeState, depth: usize) -> ScoreType {
+    if depth == 0 || state.is_done() {
+        return state.game_score;
+    }
+    state
+        .legal_actions()
+        .into_iter()
+        .map(|action| {
+            let mut next_state = *state;
+            next_state.advance(action);
+            best_score(&next_state, depth - 1)
+        })
+        .max()
+        .unwrap_or(state.game_score)
+}
+
+// Plans `depth` turns ahead in each of `sample_number` determinizations and picks the first
+// action with the best total over all samples.
+pub fn determinized_action(
+    belief: &Belief,
+    sample_number: usize,
+    depth: usize,
+    rng: &mut impl Rng,
+) -> Outcome {
+    let legal_actions = belief.legal_actions();
+    if belief.is_done() || legal_actions.is_empty() {
+        return Outcome::NoAction;
+    }
+    let mut totals = vec![0; legal_actions.len()];
+    for _ in 0..sample_number {
+        let state = belief.sample(rng);
+        for (total, &action) in totals.iter_mut().zip(&legal_actions) {
+            let mut next_state = state;
+            next_state.advance(action);
+            *total += best_score(&next_state, depth.saturating_sub(1));
+        }
+    }
+    let best = (0..legal_actions.len()).max_by_key(|&i| totals[i]).unwrap();
+    Outcome::Action(legal_actions[best])
+}
+
+// A determinization as a two-player game for the MCTS of `mcts`: an opponent that can only
+// pass moves between the character's turns, and a finished game is worth its score over the
+// most any game can score.
+#[derive(Debug, Clone, Copy)]
+pub struct SoloMaze {
+    state: PartialMazeState,
+    character_to_move: bool,
+}
+
+impl SoloMaze {
+    pub fn new(state: PartialMazeState) -> SoloMaze {
+        SoloMaze {
+            state,
+            character_to_move: true,
+        }
+    }
+}
+
+impl TwoPlayerState for SoloMaze {
+    fn is_done(&self) -> bool {
+        self.state.is_done()
+    }
+
+    fn advance(&mut self, action: Action) {
+        if self.character_to_move {
+            self.state.advance(action);
+        }
+        self.character_to_move = !self.character_to_move;
+    }
+
+    fn legal_actions(&self) -> ActionList {
+        if self.character_to_move {
+            self.state.legal_actions()
+        } else {
+            ActionList::from_slice(&[0])
+        }
+    }
+
+    // Nobody wins a game against a passing opponent; `terminal_value` tells how well it went.
+    fn winning_status(&self) -> WinningStatus {
+        WinningStatus::None
+    }
+
+    fn terminal_value(&self) -> Option<f64> {
+        if !self.is_done() {
+            return None;
+        }
+        let value = self.state.game_score as f64 / (END_TURN as ScoreType * MAX_POINT) as f64;
+        Some(if self.character_to_move {
+            value
+        } else {
+            1.0 - value
+        })
+    }
+}
+
+// Runs the MCTS of `mcts` on `sample_number` determinizations and plays the move their trees
+// visited most in total.
+pub fn determinized_mcts_action(
+    belief: &Belief,
+    sample_number: usize,
+    config: &MctsConfig,
+    rng: &mut impl Rng,
+) -> Outcome {
+    if belief.is_done() {
+        return Outcome::NoAction;
+    }
+    let determinizations: Vec<SoloMaze> = (0..sample_number.max(1))
+        .map(|_| SoloMaze::new(belief.sample(rng)))
+        .collect();
+    determinized_mcts_action_with(&determinizations, config, rng)
+}
+
+// Takes the best visible neighbour, treating unseen cells as empty.
+pub fn greedy_action(belief: &Belief) -> Outcome {
+    belief
+        .legal_actions()
+        .into_iter()
+        .max_by_key(|&action| {
+            let y = belief.y.checked_add_signed(DY[action]).unwrap_or(0);
+            let x = belief.x.checked_add_signed(DX[action]).unwrap_or(0);
+            belief.known[y][x].unwrap_or(0)
+        })
+        .into()
+}
+
+// Final score of one game played on the observations only.
+pub fn play<A: FnMut(&Belief) -> Outcome>(seed: u64, mut agent: A) -> ScoreType {
+    let mut state = PartialMazeState::with_seed(seed);
+    let mut belief = Belief::new(&state);
+    while !state.is_done() {
+        let Outcome::Action(action) = agent(&belief) else {
+            break;
+        };
+        state.advance(action);
+        belief.observe(&state);
+    }
+    state.game_score
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GAME_NUMBER: u64 = 30;
+
+    fn mean_score<A: FnMut(&Belief) -> Outcome>(mut agent: A) -> f64 {
+        (0..GAME_NUMBER)
+            .map(|seed| play(seed, &mut agent))
+            .sum::<ScoreType>() as f64
+            / GAME_NUMBER as f64
+    }
+
+    #[test]
+    fn test_belief_keeps_observations() {
+        let mut state = PartialMazeState::with_seed(0);
+        let mut belief = Belief::new(&state);
+        assert_eq!(belief.known_point(state.y, state.x), Some(0));
+        let (y, x) = (state.y, state.x);
+        let action = state.legal_actions()[0];
+        state.advance(action);
+        belief.observe(&state);
+        assert_eq!(belief.known_point(y, x), Some(0));
+        let sample = belief.sample(&mut StdRng::seed_from_u64(0));
+        assert_eq!(sample.points[state.y][state.x], 0);
+        assert_eq!(sample.game_score, state.game_score);
+    }
+
+    #[test]
+    fn test_determinized_vs_greedy() {
+        let greedy = mean_score(greedy_action);
+        let mut rng = StdRng::seed_from_u64(0);
+        let determinized = mean_score(|b: &Belief| determinized_action(b, 8, 4, &mut rng));
+        println!("Partial Greedy Score:\t{}", greedy);
+        println!("Partial Determinized Score:\t{}", determinized);
+        assert!(greedy <= determinized);
+    }
+
+    #[test]
+    fn test_determinized_mcts_vs_greedy() {
+        let greedy = mean_score(greedy_action);
+        let mut rng = StdRng::seed_from_u64(0);
+        let config = MctsConfig {
+            playout_number: 800,
+            c: 0.3,
+            ..Default::default()
+        };
+        let mcts = mean_score(|b: &Belief| determinized_mcts_action(b, 8, &config, &mut rng));
+        println!("Partial Determinized MCTS Score:\t{}", mcts);
+        assert!(greedy <= mcts);
+    }
+}
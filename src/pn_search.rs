@@ -0,0 +1,222 @@
+#![allow(unused)]
+
+use alloc::{vec, vec::Vec};
+
+use crate::game::{Action, TwoPlayerState, WinningStatus};
+
+const INF: u64 = u64::MAX / 4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Target {
+    Win,
+    // Win or draw.
+    NotLose,
+}
+
+struct Node<S> {
+    state: S,
+    // The player to move at the root moves here.
+    is_or: bool,
+    pn: u64,
+    dn: u64,
+    children: Vec<(Action, Node<S>)>,
+}
+
+impl<S: TwoPlayerState> Node<S> {
+    fn new(state: S, is_or: bool, target: Target) -> Node<S> {
+        let mut node = Node {
+            state,
+            is_or,
+            pn: 1,
+            dn: 1,
+            children: vec![],
+        };
+        node.evaluate_terminal(target);
+        node
+    }
+
+    fn set_proven(&mut self, proven: bool) {
+        (self.pn, self.dn) = if proven { (0, INF) } else { (INF, 0) };
+    }
+
+    fn evaluate_terminal(&mut self, target: Target) {
+        let status = self.state.winning_status();
+        let status = if status == WinningStatus::None && self.state.legal_actions().is_empty() {
+            WinningStatus::Lose
+        } else {
+            status
+        };
+        // Convert to the side of the root player.
+        let status = match (status, self.is_or) {
+            (WinningStatus::Win, false) => WinningStatus::Lose,
+            (WinningStatus::Lose, false) => WinningStatus::Win,
+            (status, _) => status,
+        };
+        match status {
+            WinningStatus::None => {}
+            WinningStatus::Win => self.set_proven(true),
+            WinningStatus::Lose => self.set_proven(false),
+            WinningStatus::Draw => self.set_proven(target == Target::NotLose),
+        }
+    }
+
+    fn update(&mut self) {
+        let pns = self.children.iter().map(|(_, c)| c.pn);
+        let dns = self.children.iter().map(|(_, c)| c.dn);
+        if self.is_or {
+            self.pn = pns.min().unwrap_or(INF);
+            self.dn = dns.fold(0u64, |a, b| a.saturating_add(b)).min(INF);
+        } else {
+            self.pn = pns.fold(0u64, |a, b| a.saturating_add(b)).min(INF);
+            self.dn = dns.min().unwrap_or(INF);
+        }
+    }
+
+    // Expands the most proving node below this one. Returns the number of created nodes.
+    fn iterate(&mut self, target: Target) -> usize {
+        if self.children.is_empty() {
+            self.children = self
+                .state
+                .legal_actions()
+                .into_iter()
+                .map(|action| {
+                    let mut state = self.state.clone();
+                    state.advance(action);
+                    (action, Node::new(state, !self.is_or, target))
+                })
+                .collect();
+            self.update();
+            return self.children.len();
+        }
+        let is_or = self.is_or;
+        let (_, child) = self
+            .children
+            .iter_mut()
+            .min_by_key(|(_, c)| if is_or { c.pn } else { c.dn })
+            .unwrap();
+        let created = child.iterate(target);
+        self.update();
+        created
+    }
+}
+
+// Proves or disproves `target` for the player to move. Returns `None` when more than
+// `max_nodes` nodes would be needed.
+pub fn pn_search<S: TwoPlayerState>(state: &S, target: Target, max_nodes: usize) -> Option<bool> {
+    let mut root = Node::new(state.clone(), true, target);
+    let mut nodes = 1;
+    while root.pn != 0 && root.dn != 0 {
+        if max_nodes <= nodes {
+            return None;
+        }
+        nodes += root.iterate(target);
+    }
+    Some(root.pn == 0)
+}
+
+// Game theoretic value for the player to move.
+pub fn solve<S: TwoPlayerState>(state: &S, max_nodes: usize) -> Option<WinningStatus> {
+    if pn_search(state, Target::Win, max_nodes)? {
+        return Some(WinningStatus::Win);
+    }
+    if pn_search(state, Target::NotLose, max_nodes)? {
+        Some(WinningStatus::Draw)
+    } else {
+        Some(WinningStatus::Lose)
+    }
+}
+
+// Actions that keep the best game theoretic value, usable as ground truth for other agents.
+pub fn optimal_actions<S: TwoPlayerState>(state: &S, max_nodes: usize) -> Option<Vec<Action>> {
+    let best = solve(state, max_nodes)?;
+    let mut actions = vec![];
+    for action in state.legal_actions() {
+        let mut next_state = state.clone();
+        next_state.advance(action);
+        let value = match solve(&next_state, max_nodes)? {
+            WinningStatus::Win => WinningStatus::Lose,
+            WinningStatus::Lose => WinningStatus::Win,
+            status => status,
+        };
+        if value == best {
+            actions.push(action);
+        }
+    }
+    Some(actions)
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use crate::mcts::{mcts_action, MctsConfig};
+    use crate::section5::AlternateMazeState;
+
+    fn negamax(state: &AlternateMazeState) -> i32 {
+        match state.winning_status() {
+            WinningStatus::Win => return 1,
+            WinningStatus::Lose => return -1,
+            WinningStatus::Draw => return 0,
+            WinningStatus::None => {}
+        }
+        state
+            .legal_actions()
+            .into_iter()
+            .map(|action| {
+                let mut next_state = *state;
+                next_state.advance(action);
+                -negamax(&next_state)
+            })
+            .max()
+            .unwrap_or(-1)
+    }
+
+    #[test]
+    fn test_solve_matches_negamax() {
+        for seed in 0..50 {
+            let state = AlternateMazeState::with_seed(seed);
+            let expected = match negamax(&state) {
+                1 => WinningStatus::Win,
+                -1 => WinningStatus::Lose,
+                _ => WinningStatus::Draw,
+            };
+            assert_eq!(solve(&state, 100_000), Some(expected), "seed {}", seed);
+        }
+    }
+
+    #[test]
+    fn test_optimal_actions() {
+        for seed in 0..20 {
+            let state = AlternateMazeState::with_seed(seed);
+            let best = negamax(&state);
+            for action in optimal_actions(&state, 100_000).unwrap() {
+                let mut next_state = state;
+                next_state.advance(action);
+                assert_eq!(-negamax(&next_state), best);
+            }
+        }
+    }
+
+    #[test]
+    fn test_mcts_against_solver() {
+        let config = MctsConfig {
+            playout_number: 2000,
+            ..Default::default()
+        };
+        let mut agreement = 0;
+        for seed in 0..20 {
+            let state = AlternateMazeState::with_seed(seed);
+            let actions = optimal_actions(&state, 100_000).unwrap();
+            if actions.contains(&mcts_action(&state, &config).unwrap()) {
+                agreement += 1;
+            }
+        }
+        println!("MCTS Optimal Move Rate:\t{}", agreement as f64 / 20.0);
+        assert!(10 <= agreement);
+    }
+
+    #[test]
+    fn test_node_limit() {
+        let state = AlternateMazeState::with_seed(0);
+        assert_eq!(pn_search(&state, Target::Win, 1), None);
+    }
+}
@@ -0,0 +1,125 @@
+#![allow(unused)]
+
+use std::{fmt::Write, fs, io, path::Path};
+
+use crate::game::{Action, ScoreType};
+
+#[derive(Debug, Clone, Copy)]
+pub struct TreeNode {
+    pub parent: Option<usize>,
+    pub action: Option<Action>,
+    pub depth: usize,
+    pub score: ScoreType,
+    // On the path to the state the search finally preferred.
+    pub chosen: bool,
+}
+
+// Every node a search generated, in generation order; node 0 is the root.
+#[derive(Debug, Clone)]
+pub struct SearchTree {
+    pub nodes: Vec<TreeNode>,
+}
+
+impl SearchTree {
+    pub fn new(root_score: ScoreType) -> SearchTree {
+        SearchTree {
+            nodes: vec![TreeNode {
+                parent: None,
+                action: None,
+                depth: 0,
+                score: root_score,
+                chosen: false,
+            }],
+        }
+    }
+
+    pub fn push(&mut self, parent: usize, action: Action, score: ScoreType) -> usize {
+        self.nodes.push(TreeNode {
+            parent: Some(parent),
+            action: Some(action),
+            depth: self.nodes[parent].depth + 1,
+            score,
+            chosen: false,
+        });
+        self.nodes.len() - 1
+    }
+
+    // Marks `leaf` and all of its ancestors.
+    pub fn mark_path(&mut self, leaf: usize) {
+        let mut node = Some(leaf);
+        while let Some(id) = node {
+            self.nodes[id].chosen = true;
+            node = self.nodes[id].parent;
+        }
+    }
+
+    // Actions from the root to the chosen leaf.
+    pub fn chosen_path(&self) -> Vec<Action> {
+        self.nodes
+            .iter()
+            .filter(|node| node.chosen)
+            .filter_map(|node| node.action)
+            .collect()
+    }
+
+    // Nodes are ranked by depth; the chosen path is filled and drawn in bold red.
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph search {\n    node [shape=box];\n");
+        for (id, node) in self.nodes.iter().enumerate() {
+            let style = if node.chosen {
+                ", style=filled, fillcolor=lightblue"
+            } else {
+                ""
+            };
+            writeln!(
+                dot,
+                "    n{} [label=\"#{} d{}\\nscore {}\"{}];",
+                id, id, node.depth, node.score, style
+            )
+            .unwrap();
+        }
+        for (id, node) in self.nodes.iter().enumerate() {
+            let (Some(parent), Some(action)) = (node.parent, node.action) else {
+                continue;
+            };
+            let style = if node.chosen {
+                ", color=red, penwidth=2"
+            } else {
+                ""
+            };
+            writeln!(
+                dot,
+                "    n{} -> n{} [label=\"{}\"{}];",
+                parent, id, action, style
+            )
+            .unwrap();
+        }
+        dot.push_str("}\n");
+        dot
+    }
+
+    pub fn write_dot(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        fs::write(path, self.to_dot())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_dot() {
+        let mut tree = SearchTree::new(0);
+        let a = tree.push(0, 0, 3);
+        let b = tree.push(0, 1, 5);
+        let c = tree.push(b, 2, 9);
+        tree.mark_path(c);
+        assert_eq!(tree.chosen_path(), vec![1, 2]);
+        assert!(!tree.nodes[a].chosen);
+        let dot = tree.to_dot();
+        assert!(dot.starts_with("digraph search {"));
+        assert!(dot.contains("n3 [label=\"#3 d2\\nscore 9\", style=filled"));
+        assert!(dot.contains("n0 -> n1 [label=\"0\"];"));
+        assert!(dot.contains("n2 -> n3 [label=\"2\", color=red, penwidth=2];"));
+    }
+}
@@ -2,22 +2,53 @@
 
 use std::{
     char,
-    collections::BinaryHeap,
+    collections::{BinaryHeap, HashMap, HashSet},
+    fs,
+    io::{self, BufRead, Write},
+    ops::ControlFlow,
+    path::Path,
+    str::FromStr,
     time::{Duration, Instant},
 };
 
-use rand::Rng;
+use rand::{rngs::StdRng, Rng, SeedableRng};
 
-type ScoreType = i64;
-type Action = usize;
+use crate::arena::Arena;
+pub use crate::beam::{
+    beam_search_action, beam_search_with_clock, beam_search_with_nodes,
+    budgeted_beam_search_action, budgeted_chokudai_search_action, chokudai_search_action,
+    chokudai_search_with_clock, chokudai_search_with_nodes, dedup_beam_search_with_nodes,
+    dedup_chokudai_search_with_nodes, greedy_action, limited_beam_search_action,
+    limited_chokudai_search_action, node_limited_beam_search_action,
+    node_limited_chokudai_search_action, BeamQueue, BeamState,
+};
+use crate::beam::{
+    beam_search_in, beam_search_with_count, chokudai_search_timed, chokudai_search_with,
+    line_outcome, BeamRun, ChokudaiRun, Expansion, OnGenerate, TimeKeeper,
+};
+pub use crate::game::NodeCount;
+use crate::game::{
+    add_score, splitmix64, ActionList, Clock, CostType, GameRunner, ImprovementHook, Outcome,
+    SearchLimits, SinglePlayerState, Summary, SystemClock,
+};
+use crate::grid::{check_nibble, get_nibble, set_nibble, GridMazeState, PointGrid, PointOverflow};
+use crate::hashing::StateHash;
+use crate::learning::{one_plus_one_es, Features, WeightedEvaluator};
+use crate::maze_generator::{reachable_cells, MazeGenerator};
+use crate::mcts::Policy;
+use crate::search_tree::SearchTree;
+use crate::tuning::{anneal_params, Budget, Param, Trial};
+
+pub type ScoreType = crate::game::ScoreType;
+pub type Action = usize;
 
 const HEIGHT: usize = 30;
 const WIDTH: usize = 30;
-const END_TURN: u64 = 100;
+pub const END_TURN: u64 = 100;
 const INF: ScoreType = 1_000_000_000;
 
-#[derive(Debug, Clone, Copy)]
-struct Coord {
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Coord {
     pub x: usize,
     pub y: usize,
 }
@@ -33,41 +64,136 @@ impl Coord {
     }
 }
 
+// The points of the board in nibbles, so that a state copies in a few hundred bytes instead of
+// 7KB; beams of thousands of states then stay in cache. Points above `grid::NIBBLE_MAX` are
+// turned away with `PointOverflow` where they come in: generators and cell changes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct PackedPoints([u8; (HEIGHT * WIDTH).div_ceil(2)]);
+
+impl PackedPoints {
+    fn from_rows(rows: &[[ScoreType; WIDTH]; HEIGHT]) -> PackedPoints {
+        let mut points = PackedPoints([0; (HEIGHT * WIDTH).div_ceil(2)]);
+        for (y, row) in rows.iter().enumerate() {
+            for (x, &point) in row.iter().enumerate() {
+                points.set(y, x, point);
+            }
+        }
+        points
+    }
+
+    fn get(&self, y: usize, x: usize) -> ScoreType {
+        get_nibble(&self.0, y * WIDTH + x)
+    }
+
+    fn set(&mut self, y: usize, x: usize, value: ScoreType) {
+        set_nibble(&mut self.0, y * WIDTH + x, value);
+    }
+
+    // Every cell with its point, row by row.
+    fn cells(&self) -> impl Iterator<Item = (usize, usize, ScoreType)> + '_ {
+        (0..HEIGHT * WIDTH).map(|i| (i / WIDTH, i % WIDTH, get_nibble(&self.0, i)))
+    }
+}
+
 #[derive(Debug, Clone, Copy)]
-struct MazeState {
+pub struct MazeState {
     pub character: Coord,
     pub game_score: ScoreType,
     pub evaluated_score: ScoreType,
     pub first_action: Option<Action>,
-    points: [[ScoreType; WIDTH]; HEIGHT],
+    points: PackedPoints,
+    walls: [[bool; WIDTH]; HEIGHT],
+    // Cost of a move in each direction and the total paid so far.
+    move_costs: [CostType; 4],
+    spent: CostType,
     turn: u64,
 }
 
 impl MazeState {
     pub fn new() -> MazeState {
-        let mut rng = rand::thread_rng();
+        Self::generate(&mut rand::thread_rng())
+    }
+
+    pub fn with_seed(seed: u64) -> MazeState {
+        Self::generate(&mut StdRng::seed_from_u64(seed))
+    }
+
+    pub fn with_generator(
+        seed: u64,
+        generator: &MazeGenerator,
+    ) -> Result<MazeState, PointOverflow> {
+        check_nibble(generator.max_point())?;
+        Ok(Self::generate_with(
+            &mut StdRng::seed_from_u64(seed),
+            generator,
+        ))
+    }
+
+    fn generate(rng: &mut impl Rng) -> MazeState {
+        Self::generate_with(rng, &MazeGenerator::default())
+    }
+
+    fn generate_with(rng: &mut impl Rng, generator: &MazeGenerator) -> MazeState {
         let mut character = Coord::new();
         character.y = rng.gen_range(0..HEIGHT);
         character.x = rng.gen_range(0..WIDTH);
-        let mut points = [[0; WIDTH]; HEIGHT];
-        for (y, points) in points.iter_mut().enumerate() {
-            for (x, point) in points.iter_mut().enumerate() {
-                if y == character.y && x == character.x {
-                    continue;
-                }
-                *point = rng.gen_range(0..10);
-            }
-        }
+        let maze = generator.generate(rng, Some((character.y, character.x)));
         MazeState {
             character,
             game_score: 0,
             evaluated_score: 0,
             first_action: None,
-            points,
+            points: PackedPoints::from_rows(&maze.points),
+            walls: maze.walls,
+            move_costs: [1; 4],
+            spent: 0,
             turn: 0,
         }
     }
 
+    // A `height` x `width` board in the top left corner, walled off from the rest: small
+    // enough for exact searches.
+    pub fn small_with_seed(seed: u64, height: usize, width: usize) -> MazeState {
+        Self::small_with_generator(seed, height, width, &MazeGenerator::default())
+            .expect("the default generator fits in a nibble")
+    }
+
+    // `small_with_seed` filled by `generator`. Cells the character cannot reach on the small
+    // board become walls, as in the full one.
+    pub fn small_with_generator(
+        seed: u64,
+        height: usize,
+        width: usize,
+        generator: &MazeGenerator,
+    ) -> Result<MazeState, PointOverflow> {
+        check_nibble(generator.max_point())?;
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut state = Self::generate_with(&mut rng, generator);
+        state.character.y = rng.gen_range(0..height);
+        state.character.x = rng.gen_range(0..width);
+        for y in 0..HEIGHT {
+            for x in 0..WIDTH {
+                if height <= y || width <= x {
+                    state.walls[y][x] = true;
+                    state.points.set(y, x, 0);
+                }
+            }
+        }
+        let Coord { y: cy, x: cx } = state.character;
+        state.walls[cy][cx] = false;
+        state.points.set(cy, cx, 0);
+        let reachable = reachable_cells(&state.walls, (cy, cx));
+        for (y, row) in reachable.iter().enumerate() {
+            for (x, &is_reachable) in row.iter().enumerate() {
+                if !is_reachable {
+                    state.walls[y][x] = true;
+                    state.points.set(y, x, 0);
+                }
+            }
+        }
+        Ok(state)
+    }
+
     pub fn is_done(&self) -> bool {
         self.turn == END_TURN
     }
@@ -77,18 +203,20 @@ impl MazeState {
         let dy = [0, 0, 1, -1];
         self.character.x = self.character.x.checked_add_signed(dx[action]).unwrap_or(0);
         self.character.y = self.character.y.checked_add_signed(dy[action]).unwrap_or(0);
-        let point = &mut self.points[self.character.y][self.character.x];
-        if 0 < *point {
-            self.game_score += *point;
-            *point = 0;
+        self.spent += self.move_costs[action];
+        let Coord { y, x } = self.character;
+        let point = self.points.get(y, x);
+        if 0 < point {
+            self.game_score = add_score(self.game_score, point);
+            self.points.set(y, x, 0);
         }
         self.turn += 1;
     }
 
-    pub fn legal_actions(&self) -> Vec<Action> {
+    pub fn legal_actions(&self) -> ActionList {
         let dx = [1, -1, 0, 0];
         let dy = [0, 0, 1, -1];
-        let mut actions = vec![];
+        let mut actions = ActionList::new();
         for act in 0..4 {
             let ty = self
                 .character
@@ -100,16 +228,170 @@ impl MazeState {
                 .x
                 .checked_add_signed(dx[act])
                 .unwrap_or(WIDTH);
-            if ty < HEIGHT && tx < WIDTH {
+            if ty < HEIGHT && tx < WIDTH && !self.walls[ty][tx] {
                 actions.push(act);
             }
         }
         actions
     }
 
+    pub fn set_move_costs(&mut self, move_costs: [CostType; 4]) {
+        self.move_costs = move_costs;
+    }
+
     pub fn evaluate_score(&mut self) {
         self.evaluated_score = self.game_score;
     }
+
+    // The game score followed by the default grid features.
+    pub fn evaluate_weighted_score(&mut self, evaluator: &WeightedEvaluator) {
+        let mut features = vec![self.game_score as f64];
+        features.extend(self.features());
+        let value = evaluator.evaluate(&features);
+        self.evaluated_score = (value * 1000.0).round() as ScoreType;
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RenderStyle {
+    // Same as `Display`.
+    Plain,
+    // Red character, heat colored points and a dimmed path, for terminals.
+    Ansi,
+    // One emoji per cell; cells are two columns wide.
+    Emoji,
+}
+
+// 256-color palette from cold to hot, indexed by point; bigger points share the hottest color.
+const HEAT: [u8; 10] = [244, 27, 33, 39, 45, 226, 220, 214, 208, 196];
+
+impl MazeState {
+    // `path` holds the cells the character has already walked over; `Plain` ignores it.
+    pub fn render(&self, style: RenderStyle, path: &[Coord]) -> String {
+        if style == RenderStyle::Plain {
+            return self.to_string();
+        }
+        let mut text = format!("turn:\t{}\nscore:\t{}\n", self.turn, self.game_score);
+        for h in 0..HEIGHT {
+            for w in 0..WIDTH {
+                let on_path = path.iter().any(|c| c.y == h && c.x == w);
+                let point = self.points.get(h, w);
+                let cell = match style {
+                    RenderStyle::Ansi => {
+                        if self.character.y == h && self.character.x == w {
+                            "\x1b[1;31m@\x1b[0m".to_string()
+                        } else if self.walls[h][w] {
+                            "\x1b[90m#\x1b[0m".to_string()
+                        } else if 0 < point {
+                            format!(
+                                "\x1b[38;5;{}m{}\x1b[0m",
+                                HEAT[(point as usize).min(HEAT.len() - 1)],
+                                point
+                            )
+                        } else if on_path {
+                            "\x1b[2m*\x1b[0m".to_string()
+                        } else {
+                            ".".to_string()
+                        }
+                    }
+                    _ => {
+                        let emoji = if self.character.y == h && self.character.x == w {
+                            "🤖"
+                        } else if self.walls[h][w] {
+                            "🧱"
+                        } else if 7 <= point {
+                            "🟥"
+                        } else if 4 <= point {
+                            "🟧"
+                        } else if 0 < point {
+                            "🟨"
+                        } else if on_path {
+                            "👣"
+                        } else {
+                            "⬛"
+                        };
+                        emoji.to_string()
+                    }
+                };
+                text.push_str(&cell);
+            }
+            text.push('\n');
+        }
+        text
+    }
+}
+
+// How often the character stood on each cell, summed over any number of games.
+#[derive(Debug, Clone)]
+pub struct VisitHeatmap {
+    counts: [[usize; WIDTH]; HEIGHT],
+}
+
+impl VisitHeatmap {
+    pub fn new() -> VisitHeatmap {
+        VisitHeatmap {
+            counts: [[0; WIDTH]; HEIGHT],
+        }
+    }
+
+    pub fn record(&mut self, state: &MazeState) {
+        self.counts[state.character.y][state.character.x] += 1;
+    }
+
+    pub fn count(&self, y: usize, x: usize) -> usize {
+        self.counts[y][x]
+    }
+
+    // Fraction of the cells visited at least once.
+    pub fn coverage(&self) -> f64 {
+        let visited = self.counts.iter().flatten().filter(|&&c| 0 < c).count();
+        visited as f64 / (HEIGHT * WIDTH) as f64
+    }
+
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::new();
+        for row in self.counts.iter() {
+            let line: Vec<String> = row.iter().map(usize::to_string).collect();
+            csv.push_str(&line.join(","));
+            csv.push('\n');
+        }
+        csv
+    }
+
+    pub fn save_csv(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        fs::write(path, self.to_csv())
+    }
+
+    // Two columns per cell colored with `HEAT` relative to the busiest cell.
+    pub fn render(&self) -> String {
+        let max = self
+            .counts
+            .iter()
+            .flatten()
+            .copied()
+            .max()
+            .unwrap_or(0)
+            .max(1);
+        let mut text = String::new();
+        for row in self.counts.iter() {
+            for &count in row.iter() {
+                if count == 0 {
+                    text.push_str("  ");
+                } else {
+                    let level = (count * (HEAT.len() - 1)).div_ceil(max);
+                    text.push_str(&format!("\x1b[48;5;{}m  \x1b[0m", HEAT[level]));
+                }
+            }
+            text.push('\n');
+        }
+        text
+    }
+}
+
+impl Default for VisitHeatmap {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl std::fmt::Display for MazeState {
@@ -120,8 +402,10 @@ impl std::fmt::Display for MazeState {
             for w in 0..WIDTH {
                 let ch = if self.character.y == h && self.character.x == w {
                     '@'
-                } else if self.points[h][w] > 0 {
-                    (self.points[h][w] as u8 + b'0') as char
+                } else if self.walls[h][w] {
+                    '#'
+                } else if self.points.get(h, w) > 0 {
+                    std::char::from_digit(self.points.get(h, w) as u32, 16).unwrap()
                 } else {
                     '.'
                 };
@@ -133,6 +417,190 @@ impl std::fmt::Display for MazeState {
     }
 }
 
+impl Default for MazeState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseMazeError {
+    // Not HEIGHT rows of WIDTH cells.
+    Size,
+    UnknownCell(char),
+    // No '@' or more than one.
+    Character,
+    Header(String),
+}
+
+impl std::fmt::Display for ParseMazeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParseMazeError::Size => write!(f, "expected {} rows of {} cells", HEIGHT, WIDTH),
+            ParseMazeError::UnknownCell(ch) => write!(f, "unknown cell '{}'", ch),
+            ParseMazeError::Character => write!(f, "expected exactly one '@'"),
+            ParseMazeError::Header(line) => write!(f, "invalid header '{}'", line),
+        }
+    }
+}
+
+impl std::error::Error for ParseMazeError {}
+
+// Reads the `Display` format: optional `turn:` and `score:` lines, then the grid of hex digits
+// (points up to `NIBBLE_MAX`), '.' (empty), '#' (wall) and '@' (character).
+impl FromStr for MazeState {
+    type Err = ParseMazeError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut turn = 0;
+        let mut game_score = 0;
+        let mut rows = vec![];
+        for line in s.lines().map(str::trim_end).filter(|line| !line.is_empty()) {
+            let Some((key, value)) = line.split_once(':') else {
+                rows.push(line);
+                continue;
+            };
+            let header = || ParseMazeError::Header(line.to_string());
+            match key {
+                "turn" => turn = value.trim().parse().map_err(|_| header())?,
+                "score" => game_score = value.trim().parse().map_err(|_| header())?,
+                _ => return Err(header()),
+            }
+        }
+        if rows.len() != HEIGHT || rows.iter().any(|row| row.chars().count() != WIDTH) {
+            return Err(ParseMazeError::Size);
+        }
+        let mut character = None;
+        let mut points = [[0; WIDTH]; HEIGHT];
+        let mut walls = [[false; WIDTH]; HEIGHT];
+        for (y, row) in rows.iter().enumerate() {
+            for (x, ch) in row.chars().enumerate() {
+                match ch {
+                    '@' if character.is_none() => character = Some(Coord::from_point(x, y)),
+                    '@' => return Err(ParseMazeError::Character),
+                    '.' => {}
+                    '#' => walls[y][x] = true,
+                    '0'..='9' | 'a'..='f' => points[y][x] = ch.to_digit(16).unwrap() as ScoreType,
+                    _ => return Err(ParseMazeError::UnknownCell(ch)),
+                }
+            }
+        }
+        Ok(MazeState {
+            character: character.ok_or(ParseMazeError::Character)?,
+            game_score,
+            evaluated_score: 0,
+            first_action: None,
+            points: PackedPoints::from_rows(&points),
+            walls,
+            move_costs: [1; 4],
+            spent: 0,
+            turn,
+        })
+    }
+}
+
+impl MazeState {
+    pub fn load(path: impl AsRef<Path>) -> io::Result<MazeState> {
+        fs::read_to_string(path)?
+            .parse()
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        fs::write(path, self.to_string())
+    }
+}
+
+impl SinglePlayerState for MazeState {
+    fn is_done(&self) -> bool {
+        MazeState::is_done(self)
+    }
+
+    fn advance(&mut self, action: Action) {
+        MazeState::advance(self, action)
+    }
+
+    fn legal_actions(&self) -> ActionList {
+        MazeState::legal_actions(self)
+    }
+
+    fn score(&self) -> ScoreType {
+        self.game_score
+    }
+
+    fn action_cost(&self, action: Action) -> CostType {
+        self.move_costs[action]
+    }
+
+    fn spent(&self) -> CostType {
+        self.spent
+    }
+}
+
+impl Features for MazeState {
+    fn grid_size(&self) -> (usize, usize) {
+        (HEIGHT, WIDTH)
+    }
+
+    fn point(&self, y: usize, x: usize) -> f64 {
+        self.points.get(y, x) as f64
+    }
+
+    fn position(&self) -> (usize, usize) {
+        (self.character.y, self.character.x)
+    }
+
+    fn remaining_turns(&self) -> usize {
+        (END_TURN - self.turn) as usize
+    }
+
+    fn is_wall(&self, y: usize, x: usize) -> bool {
+        self.walls[y][x]
+    }
+}
+
+impl MazeState {
+    // The same maze on a `GridMazeState`, so the searches can run on another point grid. Move
+    // costs are left behind: every move on the grid costs one.
+    pub fn to_grid<G: PointGrid>(&self) -> GridMazeState<G> {
+        let mut points = G::empty(HEIGHT, WIDTH);
+        for (y, x, point) in self.points.cells() {
+            points.set(y, x, point);
+        }
+        let walls = self.walls.iter().flatten().copied().collect();
+        let mut state = GridMazeState::new(self.character, points, walls, END_TURN as usize);
+        state.game_score = self.game_score;
+        state.evaluated_score = self.evaluated_score;
+        state.first_action = self.first_action;
+        state.turn = self.turn as usize;
+        state
+    }
+}
+
+impl StateHash for MazeState {}
+
+impl BeamState for MazeState {
+    fn evaluated_score(&self) -> ScoreType {
+        self.evaluated_score
+    }
+
+    fn evaluate_score(&mut self) {
+        MazeState::evaluate_score(self)
+    }
+
+    fn first_action(&self) -> Option<Action> {
+        self.first_action
+    }
+
+    fn set_first_action(&mut self, action: Action) {
+        self.first_action = Some(action);
+    }
+
+    fn dedup_key(&self) -> u64 {
+        self.state_hash()
+    }
+}
+
 impl PartialEq for MazeState {
     fn eq(&self, other: &Self) -> bool {
         self.evaluated_score == other.evaluated_score
@@ -141,7 +609,7 @@ impl PartialEq for MazeState {
 
 impl PartialOrd for MazeState {
     fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-        self.evaluated_score.partial_cmp(&other.evaluated_score)
+        Some(self.cmp(other))
     }
 }
 
@@ -153,323 +621,2537 @@ impl Ord for MazeState {
     }
 }
 
-struct TimeKeeper {
-    start_time: Instant,
-    time_threshold: u64,
+struct TimeManager<C: Clock = SystemClock> {
+    clock: C,
+    start_time: Duration,
+    total_budget: u64,
+    end_turn: u64,
+    turn: u64,
+    last_score: ScoreType,
+    gains: Vec<ScoreType>,
 }
 
-impl TimeKeeper {
-    pub fn new(time_threshold: u64) -> TimeKeeper {
-        TimeKeeper {
-            start_time: Instant::now(),
-            time_threshold,
+impl TimeManager {
+    pub fn new(total_budget: u64, end_turn: u64) -> TimeManager {
+        TimeManager::with_clock(total_budget, end_turn, SystemClock::new())
+    }
+}
+
+impl<C: Clock> TimeManager<C> {
+    const EARLY_BONUS: f64 = 0.5;
+    const VOLATILITY_WINDOW: usize = 10;
+
+    pub fn with_clock(total_budget: u64, end_turn: u64, clock: C) -> TimeManager<C> {
+        TimeManager {
+            start_time: clock.now(),
+            clock,
+            total_budget,
+            end_turn,
+            turn: 0,
+            last_score: 0,
+            gains: vec![],
+        }
+    }
+
+    fn remaining_time(&self) -> u64 {
+        let elapsed = (self.clock.now() - self.start_time).as_millis() as u64;
+        self.total_budget.saturating_sub(elapsed)
+    }
+
+    fn volatility(&self) -> f64 {
+        let window = &self.gains[self.gains.len().saturating_sub(Self::VOLATILITY_WINDOW)..];
+        if window.len() < 2 {
+            return 0.0;
         }
+        let n = window.len() as f64;
+        let mean = window.iter().sum::<ScoreType>() as f64 / n;
+        let var = window
+            .iter()
+            .map(|&g| (g as f64 - mean).powi(2))
+            .sum::<f64>()
+            / n;
+        var.sqrt() / (mean.abs() + 1.0)
+    }
+
+    pub fn turn_budget(&self) -> u64 {
+        let remaining_time = self.remaining_time();
+        let remaining_turns = self.end_turn.saturating_sub(self.turn).max(1);
+        let base = remaining_time as f64 / remaining_turns as f64;
+        let early = 1.0 + Self::EARLY_BONUS * remaining_turns as f64 / self.end_turn.max(1) as f64;
+        let volatile = 1.0 + self.volatility().min(1.0);
+        ((base * early * volatile) as u64).min(remaining_time)
     }
 
-    pub fn is_time_over(&self) -> bool {
-        Duration::from_millis(self.time_threshold) <= Instant::now().duration_since(self.start_time)
+    pub fn record(&mut self, state: &MazeState) {
+        self.gains.push(state.game_score - self.last_score);
+        self.last_score = state.game_score;
+        self.turn += 1;
     }
 }
 
-fn random_action(state: &MazeState) -> Action {
-    let mut rng = rand::thread_rng();
+fn random_action(state: &MazeState) -> Outcome {
+    random_action_with(state, &mut rand::thread_rng())
+}
+
+fn random_action_with(state: &MazeState, rng: &mut impl Rng) -> Outcome {
     let legal_action = state.legal_actions();
-    legal_action[rng.gen_range(0..legal_action.len())]
+    if state.is_done() || legal_action.is_empty() {
+        return Outcome::NoAction;
+    }
+    Outcome::Action(legal_action[rng.gen_range(0..legal_action.len())])
 }
 
-fn greedy_action(state: &MazeState) -> Action {
-    let legal_actions = state.legal_actions();
+pub fn weighted_greedy_action(state: &MazeState, evaluator: &WeightedEvaluator) -> Outcome {
+    if state.is_done() {
+        return Outcome::NoAction;
+    }
     let mut best_score = -INF;
     let mut best_action = None;
-    for act in legal_actions.iter() {
-        let mut now_state = *state;
-        now_state.advance(*act);
-        now_state.evaluate_score();
-        if best_score < now_state.evaluated_score {
-            best_score = now_state.evaluated_score;
-            best_action = Some(*act);
-        }
-    }
-    assert_ne!(best_action, None);
-    best_action.unwrap()
-}
-
-fn beam_search_action(state: &MazeState, beam_width: usize, beam_depth: u64) -> Action {
-    let mut now_beam = BinaryHeap::new();
-    let mut best_state = MazeState::new();
-    now_beam.push(*state);
-    for d in 0..beam_depth {
-        let mut next_beam = BinaryHeap::new();
-        for _ in 0..beam_width {
-            let Some(now_state) = now_beam.pop() else {
-                break;
-            };
-            let legal_actions = now_state.legal_actions();
-            for act in legal_actions.iter() {
-                let mut next_state = now_state;
-                next_state.advance(*act);
-                next_state.evaluate_score();
-                if d == 0 {
-                    next_state.first_action = Some(*act);
-                }
-                next_beam.push(next_state);
-            }
-        }
-        now_beam = next_beam;
-        best_state = *now_beam.peek().unwrap();
-        if best_state.is_done() {
-            break;
+    for act in state.legal_actions() {
+        let mut next_state = *state;
+        next_state.advance(act);
+        next_state.evaluate_weighted_score(evaluator);
+        if best_score < next_state.evaluated_score {
+            best_score = next_state.evaluated_score;
+            best_action = Some(act);
         }
     }
-    assert_ne!(best_state.first_action, None);
-    best_state.first_action.unwrap()
+    best_action.into()
 }
 
-fn beam_search_with_time_threshold_action(
-    state: &MazeState,
-    beam_width: usize,
-    time_threshold: u64,
-) -> Action {
-    let time_keeper = TimeKeeper::new(time_threshold);
-    let mut now_beam = BinaryHeap::new();
-    let mut best_state = MazeState::new();
-    now_beam.push(*state);
-    for d in 0.. {
-        let mut next_beam = BinaryHeap::new();
-        for _ in 0..beam_width {
-            if time_keeper.is_time_over() {
-                return best_state.first_action.unwrap();
-            }
-            let Some(now_state) = now_beam.pop() else {
-                break;
-            };
-            let legal_actions = now_state.legal_actions();
-            for act in legal_actions.iter() {
-                let mut next_state = now_state;
-                next_state.advance(*act);
-                next_state.evaluate_score();
-                if d == 0 {
-                    next_state.first_action = Some(*act);
-                }
-                next_beam.push(next_state);
+// Tunes the weights of `weighted_greedy_action` with `one_plus_one_es` from `initial`, scoring
+// every candidate by its mean game score over the seeds 0..`game_number`, played on
+// `thread_number` threads. The seeds never change, so candidates are compared on equal terms.
+pub fn tune_greedy_weights(
+    rng: &mut impl Rng,
+    initial: &[f64],
+    sigma: f64,
+    number: usize,
+    game_number: usize,
+    thread_number: usize,
+) -> (WeightedEvaluator, f64) {
+    let objective = |weights: &[f64]| {
+        let evaluator = WeightedEvaluator::new(weights.to_vec());
+        let results = GameRunner::new(MazeState::with_seed, |state: &MazeState| {
+            weighted_greedy_action(state, &evaluator)
+        })
+        .run_batch(game_number, thread_number, 0..);
+        Summary::from_results(&results).mean
+    };
+    let (weights, score) = one_plus_one_es(rng, initial, sigma, number, objective);
+    (WeightedEvaluator::new(weights), score)
+}
+
+// The knobs `tune_beam_search` turns: beam width, time split, deduplication and diversity
+// penalty. Time is counted in expanded nodes, `TUNED_GAME_NODES` a game, so that a point
+// scores the same on every machine; the split runs from an even share of them a turn at 0 to
+// twice the share on the first turn and none on the last at 100.
+pub const BEAM_SEARCH_SPACE: [Param; 4] = [
+    Param::Int { low: 1, high: 8 },
+    Param::Int { low: 0, high: 100 },
+    Param::Switch,
+    Param::Int { low: 0, high: 10 },
+];
+
+pub const TUNED_GAME_NODES: usize = 5_000;
+
+// The nodes turn `turn` gets under the time split `front`, see `BEAM_SEARCH_SPACE`.
+fn turn_nodes(turn: u64, front: i64) -> usize {
+    let even = TUNED_GAME_NODES as f64 / END_TURN as f64;
+    let remaining = END_TURN.saturating_sub(turn) as f64 / END_TURN as f64;
+    let tilt = front.clamp(0, 100) as f64 / 100.0 * (2.0 * remaining - 1.0);
+    ((even * (1.0 + tilt)) as usize).max(1)
+}
+
+// The agent for a point of `BEAM_SEARCH_SPACE`. The diversity penalty lowers the evaluation of
+// a child by that much for every earlier child at the same depth on the same cell, so that a
+// narrow beam is not filled by one crowd.
+pub fn tuned_beam_search_action(state: &MazeState, params: &[i64]) -> Outcome {
+    let width = params[0].max(1) as usize;
+    let limits = SearchLimits {
+        max_nodes: Some(turn_nodes(state.turn, params[1])),
+        ..SearchLimits::default()
+    };
+    let expansion = Expansion {
+        dedup: params[2] == 1,
+        ..Expansion::default()
+    };
+    let penalty = params[3].max(0) as ScoreType;
+    let mut crowds: HashMap<(usize, usize, usize), ScoreType> = HashMap::new();
+    let mut diversify = |depth: usize, _: &MazeState, _: Action, child: &mut MazeState| {
+        let crowd = crowds
+            .entry((depth, child.character.y, child.character.x))
+            .or_default();
+        child.evaluated_score -= penalty * *crowd;
+        *crowd += 1;
+    };
+    let on_generate: Option<&mut OnGenerate<MazeState>> = if penalty == 0 {
+        None
+    } else {
+        Some(&mut diversify)
+    };
+    let queue = BinaryHeap::new();
+    beam_search_in(
+        state,
+        width,
+        u64::MAX,
+        limits,
+        expansion,
+        on_generate,
+        queue,
+    )
+    .0
+}
+
+// Looks for the beam search settings with the best mean game score, see `anneal_params`.
+pub fn tune_beam_search(rng: &mut impl Rng, budget: Budget) -> Vec<Trial<Vec<i64>>> {
+    anneal_params(
+        rng,
+        &BEAM_SEARCH_SPACE,
+        budget,
+        20.0,
+        1.0,
+        |params, seed| {
+            let mut state = MazeState::with_seed(seed);
+            while let Outcome::Action(action) = tuned_beam_search_action(&state, params) {
+                state.advance(action);
             }
+            state.game_score as f64
+        },
+    )
+}
+
+// One bucket per evaluated score, so push and pop are O(1) amortized when scores are small
+// integers like the maze's. Bucket 0 holds the lowest score in the queue, so negative scores
+// work too; states in a bucket come out last in, first out. Scores spread over more than
+// `MAX_BUCKETS` values fall back to a binary heap until the queue is emptied.
+#[derive(Debug, Clone, Default)]
+pub struct BucketQueue {
+    buckets: Vec<Vec<MazeState>>,
+    // Evaluated score of bucket 0.
+    base: ScoreType,
+    // Highest non-empty bucket, or 0 when the buckets are empty.
+    top: usize,
+    len: usize,
+    // Every state in the queue once the scores have spread too far for buckets.
+    heap: BinaryHeap<MazeState>,
+}
+
+impl BucketQueue {
+    pub const MAX_BUCKETS: usize = 1 << 16;
+
+    // Bucket count needed to hold scores from `low` to `high`, if it is within the limit.
+    fn span(low: ScoreType, high: ScoreType) -> Option<usize> {
+        let span = usize::try_from(high.checked_sub(low)?).ok()?;
+        (span < Self::MAX_BUCKETS).then_some(span + 1)
+    }
+
+    fn spill(&mut self) {
+        for bucket in &mut self.buckets {
+            self.heap.extend(bucket.drain(..));
         }
-        now_beam = next_beam;
-        best_state = *now_beam.peek().unwrap();
-        if best_state.is_done() {
-            break;
-        }
+        self.top = 0;
+        self.len = 0;
     }
-    assert_ne!(best_state.first_action, None);
-    best_state.first_action.unwrap()
 }
 
-fn chokudai_search_action(
-    state: &MazeState,
-    beam_width: usize,
-    beam_depth: usize,
-    beam_number: usize,
-) -> Option<Action> {
-    let mut beam = vec![BinaryHeap::new(); beam_depth + 1];
-    beam[0].push(*state);
-    for _ in 0..beam_number {
-        for t in 0..beam_depth {
-            for _ in 0..beam_width {
-                if beam[t].is_empty() {
-                    break;
-                }
-                let Some(now_state) = beam[t].peek().cloned() else {
-                    break;
-                };
-                if now_state.is_done() {
-                    break;
-                }
-                beam[t].pop();
-                let legal_actions = now_state.legal_actions();
-                for act in legal_actions.iter() {
-                    let mut next_state = now_state;
-                    next_state.advance(*act);
-                    next_state.evaluate_score();
-                    if t == 0 {
-                        next_state.first_action = Some(*act);
-                    }
-                    beam[t + 1].push(next_state);
-                }
-            }
+impl BeamQueue<MazeState> for BucketQueue {
+    fn push(&mut self, state: MazeState) {
+        let score = state.evaluated_score;
+        if !self.heap.is_empty() {
+            self.heap.push(state);
+            return;
+        }
+        if self.len == 0 {
+            self.base = score;
+        }
+        let high = (self.base + self.top as ScoreType).max(score);
+        let Some(span) = Self::span(self.base.min(score), high) else {
+            self.spill();
+            self.heap.push(state);
+            return;
+        };
+        if score < self.base {
+            let shift = (self.base - score) as usize;
+            self.buckets.splice(0..0, (0..shift).map(|_| Vec::new()));
+            self.top += shift;
+            self.base = score;
+        }
+        if self.buckets.len() < span {
+            self.buckets.resize_with(span, Vec::new);
+        }
+        let key = (score - self.base) as usize;
+        self.buckets[key].push(state);
+        self.top = self.top.max(key);
+        self.len += 1;
+    }
+
+    fn pop(&mut self) -> Option<MazeState> {
+        if !self.heap.is_empty() {
+            return self.heap.pop();
         }
+        let state = self.buckets.get_mut(self.top)?.pop()?;
+        self.len -= 1;
+        while 0 < self.top && self.buckets[self.top].is_empty() {
+            self.top -= 1;
+        }
+        Some(state)
     }
-    for t in (0..=beam_depth).rev() {
-        if !beam[t].is_empty() {
-            return beam[t].peek()?.first_action;
+
+    fn peek(&self) -> Option<&MazeState> {
+        if !self.heap.is_empty() {
+            return self.heap.peek();
         }
+        self.buckets.get(self.top)?.last()
+    }
+
+    fn len(&self) -> usize {
+        self.len + self.heap.len()
     }
-    None
 }
 
-fn chokudai_search_with_time_threshold_action(
+// Beam search with the beams kept in a `BucketQueue` instead of a binary heap.
+pub fn bucket_beam_search_action(state: &MazeState, beam_width: usize, beam_depth: u64) -> Outcome {
+    bucket_beam_search_with_nodes(state, beam_width, beam_depth).0
+}
+
+// `bucket_beam_search_action` that also counts the nodes, like `beam_search_with_nodes`.
+pub fn bucket_beam_search_with_nodes(
     state: &MazeState,
     beam_width: usize,
-    beam_depth: usize,
-    time_threshold: u64,
-) -> Option<Action> {
-    let time_keeper = TimeKeeper::new(time_threshold);
-    let mut beam = vec![BinaryHeap::new(); beam_depth + 1];
-    beam[0].push(*state);
-    loop {
-        for t in 0..beam_depth {
-            for _ in 0..beam_width {
-                if beam[t].is_empty() {
-                    break;
-                }
-                let Some(now_state) = beam[t].peek().cloned() else {
-                    break;
-                };
-                if now_state.is_done() {
-                    break;
-                }
-                beam[t].pop();
-                let legal_actions = now_state.legal_actions();
-                for act in legal_actions.iter() {
-                    let mut next_state = now_state;
-                    next_state.advance(*act);
-                    next_state.evaluate_score();
-                    if t == 0 {
-                        next_state.first_action = Some(*act);
+    beam_depth: u64,
+) -> (Outcome, NodeCount) {
+    let limits = SearchLimits::default();
+    let queue = BucketQueue::default();
+    let expansion = Expansion::default();
+    beam_search_in(
+        state, beam_width, beam_depth, limits, expansion, None, queue,
+    )
+}
+
+// Which of the states with equal evaluations a beam keeps first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TieBreak {
+    // Whatever order the heap happens to give.
+    Arbitrary,
+    // More points already collected; only differs from the evaluation when the evaluation is
+    // not the game score.
+    Banked,
+    // A pseudo-random order fixed by the seed and the state.
+    Random(u64),
+    // Closer to the nearest remaining point.
+    NearestPoint,
+}
+
+impl TieBreak {
+    fn key(&self, state: &MazeState) -> ScoreType {
+        match *self {
+            TieBreak::Arbitrary => 0,
+            TieBreak::Banked => state.game_score,
+            TieBreak::Random(seed) => splitmix64(seed ^ state.state_hash()) as ScoreType,
+            TieBreak::NearestPoint => {
+                let Coord { y: cy, x: cx } = state.character;
+                let mut nearest = HEIGHT + WIDTH;
+                for (y, x, point) in state.points.cells() {
+                    if 0 < point {
+                        nearest = nearest.min(cy.abs_diff(y) + cx.abs_diff(x));
                     }
-                    beam[t + 1].push(next_state);
                 }
+                -(nearest as ScoreType)
             }
         }
-        if time_keeper.is_time_over() {
-            break;
-        }
     }
-    for t in (0..=beam_depth).rev() {
-        if !beam[t].is_empty() {
-            return beam[t].peek()?.first_action;
-        }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct TieKeyed {
+    key: (ScoreType, ScoreType),
+    state: MazeState,
+}
+
+impl PartialEq for TieKeyed {
+    fn eq(&self, other: &Self) -> bool {
+        self.key == other.key
     }
-    None
 }
 
-pub fn play_game() {
+impl Eq for TieKeyed {}
+
+impl PartialOrd for TieKeyed {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for TieKeyed {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.key.cmp(&other.key)
+    }
+}
+
+// A binary heap ordered by the evaluation, then by the tie-break key.
+#[derive(Debug, Clone)]
+pub struct TieBreakQueue {
+    heap: BinaryHeap<TieKeyed>,
+    tie_break: TieBreak,
+}
+
+impl TieBreakQueue {
+    pub fn new(tie_break: TieBreak) -> TieBreakQueue {
+        TieBreakQueue {
+            heap: BinaryHeap::new(),
+            tie_break,
+        }
+    }
+}
+
+impl Default for TieBreakQueue {
+    fn default() -> Self {
+        Self::new(TieBreak::Arbitrary)
+    }
+}
+
+impl BeamQueue<MazeState> for TieBreakQueue {
+    fn push(&mut self, state: MazeState) {
+        let key = (state.evaluated_score, self.tie_break.key(&state));
+        self.heap.push(TieKeyed { key, state });
+    }
+
+    fn pop(&mut self) -> Option<MazeState> {
+        self.heap.pop().map(|keyed| keyed.state)
+    }
+
+    fn peek(&self) -> Option<&MazeState> {
+        self.heap.peek().map(|keyed| &keyed.state)
+    }
+
+    fn len(&self) -> usize {
+        self.heap.len()
+    }
+
+    fn empty(&self) -> Self {
+        Self::new(self.tie_break)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BeamConfig {
+    pub width: usize,
+    pub depth: u64,
+    pub tie_break: TieBreak,
+    // Expands at most this many actions of every state, the first ones in the game's order or
+    // the policy's.
+    pub expansion_cap: Option<usize>,
+}
+
+impl Default for BeamConfig {
+    fn default() -> Self {
+        BeamConfig {
+            width: 2,
+            depth: END_TURN,
+            tie_break: TieBreak::Arbitrary,
+            expansion_cap: None,
+        }
+    }
+}
+
+pub fn beam_search_with_config(state: &MazeState, config: &BeamConfig) -> Outcome {
+    policy_beam_search_with_nodes(state, config, None).0
+}
+
+// Beam search that expands the actions of every state highest prior first, so that with
+// `expansion_cap` only the ones `policy` likes best are generated at all.
+pub fn policy_beam_search_action(
+    state: &MazeState,
+    config: &BeamConfig,
+    policy: &dyn Policy<MazeState>,
+) -> Outcome {
+    policy_beam_search_with_nodes(state, config, Some(policy)).0
+}
+
+pub fn policy_beam_search_with_nodes(
+    state: &MazeState,
+    config: &BeamConfig,
+    policy: Option<&dyn Policy<MazeState>>,
+) -> (Outcome, NodeCount) {
+    let limits = SearchLimits::default();
+    let queue = TieBreakQueue::new(config.tie_break);
+    let expansion = Expansion {
+        policy,
+        cap: config.expansion_cap,
+        ..Expansion::default()
+    };
+    beam_search_in(
+        state,
+        config.width,
+        config.depth,
+        limits,
+        expansion,
+        None,
+        queue,
+    )
+}
+
+// Prefers moves onto bigger points; every move keeps some weight, so nothing is ruled out
+// without an expansion cap.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PointPolicy;
+
+impl Policy<MazeState> for PointPolicy {
+    fn priors(&self, state: &MazeState, actions: &[Action]) -> Vec<f64> {
+        let weights: Vec<f64> = actions
+            .iter()
+            .map(|&action| {
+                let next_state = state.next_state(action);
+                1.0 + (next_state.game_score - state.game_score) as f64
+            })
+            .collect();
+        let total = weights.iter().sum::<f64>();
+        weights.iter().map(|weight| weight / total).collect()
+    }
+}
+
+// Summary of the states a search generated at one depth.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DepthStats {
+    pub depth: usize,
+    pub beam_size: usize,
+    pub best: ScoreType,
+    pub worst: ScoreType,
+    pub mean: f64,
+    // States with the `dedup_key` of an earlier state at the same depth; what a deduplicating
+    // search would prune.
+    pub duplicates: usize,
+}
+
+#[derive(Default)]
+struct DepthRecorder {
+    // The `dedup_key`s seen at every depth.
+    depths: Vec<(DepthStats, HashSet<u64>)>,
+}
+
+impl DepthRecorder {
+    fn record<S: BeamState>(&mut self, depth: usize, state: &S) {
+        while self.depths.len() < depth {
+            let stats = DepthStats {
+                depth: self.depths.len() + 1,
+                beam_size: 0,
+                best: -INF,
+                worst: INF,
+                mean: 0.0,
+                duplicates: 0,
+            };
+            self.depths.push((stats, HashSet::new()));
+        }
+        let (stats, seen) = &mut self.depths[depth - 1];
+        let score = state.evaluated_score();
+        stats.beam_size += 1;
+        stats.best = stats.best.max(score);
+        stats.worst = stats.worst.min(score);
+        stats.mean += (score as f64 - stats.mean) / stats.beam_size as f64;
+        if !seen.insert(state.dedup_key()) {
+            stats.duplicates += 1;
+        }
+    }
+
+    fn finish(self) -> Vec<DepthStats> {
+        self.depths.into_iter().map(|(stats, _)| stats).collect()
+    }
+}
+
+pub fn depth_stats_csv(stats: &[DepthStats]) -> String {
+    let mut csv = String::from("depth,beam_size,best,worst,mean,duplicates\n");
+    for s in stats {
+        csv.push_str(&format!(
+            "{},{},{},{},{},{}\n",
+            s.depth, s.beam_size, s.best, s.worst, s.mean, s.duplicates
+        ));
+    }
+    csv
+}
+
+// `beam_search_action` that also reports every depth.
+pub fn beam_search_with_stats<S: BeamState>(
+    state: &S,
+    beam_width: usize,
+    beam_depth: u64,
+) -> (Outcome, Vec<DepthStats>) {
+    let mut recorder = DepthRecorder::default();
+    let (outcome, _) = beam_search_with_count(
+        state,
+        beam_width,
+        beam_depth,
+        SearchLimits::default(),
+        Some(&mut |depth, _, _, child| recorder.record(depth, child)),
+    );
+    (outcome, recorder.finish())
+}
+
+// Beam search one depth at a time, for inspection. Every step expands the `beam_width` best
+// candidates exactly like `beam_search_action`.
+pub struct BeamStepper {
+    run: BeamRun<MazeState, BinaryHeap<MazeState>>,
+}
+
+impl BeamStepper {
+    pub fn new(state: &MazeState, beam_width: usize) -> BeamStepper {
+        BeamStepper {
+            run: BeamRun::new(state, beam_width, BinaryHeap::new()),
+        }
+    }
+
+    pub fn depth(&self) -> usize {
+        self.run.depth
+    }
+
+    // Returns false when no candidate could be expanded.
+    pub fn step(&mut self) -> bool {
+        let limits = SearchLimits::default();
+        self.run.step(&limits, &Expansion::default(), None, None)
+    }
+
+    // Current candidates, best first.
+    pub fn candidates(&self) -> Vec<MazeState> {
+        let mut candidates = self.run.beam.clone().into_sorted_vec();
+        candidates.reverse();
+        candidates
+    }
+}
+
+// Debug REPL over a `BeamStepper`. Commands: `step [n]`, `beam` (lists the candidates),
+// `show <i>` (dumps candidate i), `load <path>` (restarts from a saved state) and `quit`.
+pub fn search_repl(
+    state: &MazeState,
+    beam_width: usize,
+    input: impl BufRead,
+    mut output: impl Write,
+) -> io::Result<BeamStepper> {
+    let mut stepper = BeamStepper::new(state, beam_width);
+    for line in input.lines() {
+        let line = line?;
+        let mut words = line.split_whitespace();
+        let number = |word: Option<&str>| word.and_then(|w| w.parse::<usize>().ok());
+        match words.next() {
+            Some("step") => {
+                for _ in 0..number(words.next()).unwrap_or(1) {
+                    if !stepper.step() {
+                        writeln!(output, "nothing to expand")?;
+                        break;
+                    }
+                }
+                writeln!(output, "depth:\t{}", stepper.depth())?;
+            }
+            Some("beam") => {
+                for (i, candidate) in stepper.candidates().iter().enumerate() {
+                    writeln!(
+                        output,
+                        "{}\tscore: {}\tposition: ({}, {})\tfirst action: {:?}",
+                        i,
+                        candidate.evaluated_score,
+                        candidate.character.y,
+                        candidate.character.x,
+                        candidate.first_action
+                    )?;
+                }
+            }
+            Some("show") => {
+                match number(words.next()).and_then(|i| stepper.candidates().get(i).copied()) {
+                    Some(candidate) => writeln!(output, "{}", candidate)?,
+                    None => writeln!(output, "no such candidate")?,
+                }
+            }
+            Some("load") => match words.next().map(MazeState::load) {
+                Some(Ok(state)) => stepper = BeamStepper::new(&state, beam_width),
+                Some(Err(error)) => writeln!(output, "{}", error)?,
+                None => writeln!(output, "usage: load <path>")?,
+            },
+            Some("quit") => break,
+            Some(command) => writeln!(output, "unknown command: {}", command)?,
+            None => {}
+        }
+    }
+    Ok(stepper)
+}
+
+// Picks the beam width from the measured node throughput so that one search fits into
+// `time_threshold` ms. The throughput and branching factor are updated after every search.
+pub struct AutoBeamSearcher {
+    time_threshold: u64,
+    max_depth: u64,
+    beam_width: usize,
+    nodes_per_ms: Option<f64>,
+    branching: f64,
+}
+
+impl AutoBeamSearcher {
+    const CALIBRATION_WIDTH: usize = 2;
+    const SAFETY: f64 = 0.8;
+    const SMOOTHING: f64 = 0.5;
+
+    pub fn new(time_threshold: u64, max_depth: u64) -> AutoBeamSearcher {
+        AutoBeamSearcher {
+            time_threshold,
+            max_depth,
+            beam_width: Self::CALIBRATION_WIDTH,
+            nodes_per_ms: None,
+            branching: 4.0,
+        }
+    }
+
+    pub fn beam_width(&self) -> usize {
+        self.beam_width
+    }
+
+    pub fn search(&mut self, state: &MazeState) -> Outcome {
+        if state.is_done() {
+            return Outcome::NoAction;
+        }
+        let beam_depth = self.max_depth.min(END_TURN - state.turn).max(1);
+        let start_time = Instant::now();
+        let (outcome, count) = beam_search_with_count(
+            state,
+            self.beam_width,
+            beam_depth,
+            SearchLimits::default(),
+            None,
+        );
+        let elapsed = Instant::now().duration_since(start_time).as_secs_f64() * 1000.0;
+        if 0 < count.expanded {
+            let nodes_per_ms = count.generated as f64 / elapsed.max(1e-3);
+            self.nodes_per_ms = Some(match self.nodes_per_ms {
+                Some(old) => old + Self::SMOOTHING * (nodes_per_ms - old),
+                None => nodes_per_ms,
+            });
+            self.branching = count.branching_factor();
+        }
+        if let Some(nodes_per_ms) = self.nodes_per_ms {
+            let nodes = nodes_per_ms * self.time_threshold as f64 * Self::SAFETY;
+            let next_depth = self.max_depth.min(END_TURN - state.turn - 1).max(1);
+            self.beam_width =
+                ((nodes / (next_depth as f64 * self.branching.max(1.0))) as usize).max(1);
+        }
+        outcome
+    }
+}
+
+// A change to one cell of a maze while a game runs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CellChange {
+    // A point appears, changes or, with 0, disappears.
+    Point {
+        y: usize,
+        x: usize,
+        value: ScoreType,
+    },
+    // A wall appears and takes the point of the cell with it.
+    Wall {
+        y: usize,
+        x: usize,
+    },
+}
+
+impl CellChange {
+    fn check(&self) -> Result<(), PointOverflow> {
+        match *self {
+            CellChange::Point { value, .. } => check_nibble(value),
+            CellChange::Wall { .. } => Ok(()),
+        }
+    }
+}
+
+impl MazeState {
+    // Leaves the maze untouched when the new point does not fit.
+    pub fn apply_change(&mut self, change: CellChange) -> Result<(), PointOverflow> {
+        change.check()?;
+        match change {
+            CellChange::Point { y, x, value } => self.points.set(y, x, value),
+            CellChange::Wall { y, x } => {
+                self.walls[y][x] = true;
+                self.points.set(y, x, 0);
+            }
+        }
+        Ok(())
+    }
+}
+
+// Beam search that keeps its beam from turn to turn: the lines that start with the move played
+// are kept one move shorter, and every search only adds one more depth to them. Each candidate
+// keeps its line, so when cells change the beam is repaired by replaying the lines on the new
+// maze, which drops the lines a new wall blocks and rescores the rest, instead of searching
+// again from scratch.
+pub struct ReplanningBeamSearcher {
+    beam_width: usize,
+    beam_depth: usize,
+    root: MazeState,
+    // Leaves and their lines from `root`, all of the same length, best first.
+    beam: Vec<(MazeState, Vec<Action>)>,
+}
+
+impl ReplanningBeamSearcher {
+    pub fn new(state: &MazeState, beam_width: usize, beam_depth: usize) -> ReplanningBeamSearcher {
+        ReplanningBeamSearcher {
+            beam_width: beam_width.max(1),
+            beam_depth: beam_depth.max(1),
+            root: *state,
+            beam: vec![(*state, vec![])],
+        }
+    }
+
+    // The best line found so far.
+    pub fn plan(&self) -> &[Action] {
+        self.beam.first().map_or(&[], |(_, line)| line)
+    }
+
+    pub fn search(&mut self) -> Outcome {
+        while self.plan().len() < self.beam_depth {
+            let mut next_beam = vec![];
+            for (now_state, line) in self.beam.iter() {
+                if now_state.is_done() {
+                    continue;
+                }
+                for act in now_state.legal_actions() {
+                    let mut next_state = *now_state;
+                    next_state.advance(act);
+                    next_state.evaluate_score();
+                    let mut next_line = line.clone();
+                    next_line.push(act);
+                    next_beam.push((next_state, next_line));
+                }
+            }
+            if next_beam.is_empty() {
+                break;
+            }
+            // Stable, so equal candidates keep the order of their parents.
+            next_beam.sort_by_key(|(state, _)| std::cmp::Reverse(state.evaluated_score));
+            next_beam.truncate(self.beam_width);
+            self.beam = next_beam;
+        }
+        match self.plan().first() {
+            Some(&action) => Outcome::Action(action),
+            None => greedy_action(&self.root),
+        }
+    }
+
+    // Moves the root along `action`, which need not be the one `search` returned.
+    pub fn advance(&mut self, action: Action) {
+        self.root.advance(action);
+        self.beam.retain(|(_, line)| line.first() == Some(&action));
+        for (_, line) in self.beam.iter_mut() {
+            line.remove(0);
+        }
+        if self.beam.is_empty() {
+            self.beam = vec![(self.root, vec![])];
+        }
+    }
+
+    // Applies `changes` to the root and replays every line on the new maze. Returns how many
+    // lines survived, or the first point that does not fit, with nothing applied.
+    pub fn notify(&mut self, changes: &[CellChange]) -> Result<usize, PointOverflow> {
+        for change in changes {
+            change.check()?;
+        }
+        for &change in changes {
+            self.root.apply_change(change)?;
+        }
+        let root = self.root;
+        self.beam = std::mem::take(&mut self.beam)
+            .into_iter()
+            .filter_map(|(_, line)| {
+                let mut state = root;
+                for &act in line.iter() {
+                    if !state.legal_actions().contains(&act) {
+                        return None;
+                    }
+                    state.advance(act);
+                }
+                state.evaluate_score();
+                Some((state, line))
+            })
+            .collect();
+        self.beam
+            .sort_by_key(|(state, _)| std::cmp::Reverse(state.evaluated_score));
+        let survivors = self.beam.len();
+        if self.beam.is_empty() {
+            self.beam = vec![(root, vec![])];
+        }
+        Ok(survivors)
+    }
+}
+
+fn beam_search_with_time_threshold_action(
+    state: &MazeState,
+    beam_width: usize,
+    time_threshold: u64,
+) -> Outcome {
+    beam_search_with_clock(state, beam_width, time_threshold, &SystemClock::new())
+}
+
+// `chokudai_search_action` that also reports every depth over all iterations.
+pub fn chokudai_search_with_stats<S: BeamState>(
+    state: &S,
+    beam_width: usize,
+    beam_depth: usize,
+    beam_number: usize,
+) -> (Outcome, Vec<DepthStats>) {
+    let mut recorder = DepthRecorder::default();
+    let (outcome, _) = chokudai_search_with(
+        state,
+        beam_width,
+        beam_depth,
+        beam_number,
+        SearchLimits::default(),
+        false,
+        Some(&mut |depth, _, _, child| recorder.record(depth, child)),
+    );
+    (outcome, recorder.finish())
+}
+
+// A new best line found by an anytime search: deeper lines beat shallower ones, then higher
+// evaluations win.
+#[derive(Debug, Clone, Copy)]
+pub struct Incumbent {
+    pub action: Action,
+    pub depth: usize,
+    pub score: ScoreType,
+    pub iteration: usize,
+    // The leaf at the end of the line.
+    pub state: MazeState,
+}
+
+// Chokudai search as an iterator over its incumbents; every `next` runs iterations until the
+// best line improves, for at most `beam_number` iterations in total.
+pub struct ChokudaiSearch {
+    run: ChokudaiRun<MazeState>,
+    beam_number: usize,
+    best: Option<(usize, ScoreType)>,
+}
+
+impl ChokudaiSearch {
+    pub fn new(
+        state: &MazeState,
+        beam_width: usize,
+        beam_depth: usize,
+        beam_number: usize,
+    ) -> ChokudaiSearch {
+        ChokudaiSearch {
+            run: ChokudaiRun::new(state, beam_width, beam_depth),
+            beam_number,
+            best: None,
+        }
+    }
+
+    fn incumbent(&self) -> Option<Incumbent> {
+        let (depth, top) = self.run.leaf()?;
+        Some(Incumbent {
+            action: top.first_action?,
+            depth,
+            score: top.evaluated_score,
+            iteration: self.run.iteration,
+            state: *top,
+        })
+    }
+}
+
+impl Iterator for ChokudaiSearch {
+    type Item = Incumbent;
+
+    fn next(&mut self) -> Option<Incumbent> {
+        let limits = SearchLimits::default();
+        while self.run.iteration < self.beam_number {
+            let flow = self.run.iterate(&limits, false, None);
+            if flow.is_break() {
+                self.beam_number = self.run.iteration;
+            }
+            let Some(incumbent) = self.incumbent() else {
+                continue;
+            };
+            if self.best < Some((incumbent.depth, incumbent.score)) {
+                self.best = Some((incumbent.depth, incumbent.score));
+                return Some(incumbent);
+            }
+        }
+        None
+    }
+}
+
+// Chokudai search that hands every incumbent leaf to `on_improvement`, which may stop it early.
+pub fn chokudai_search_with_hook(
+    state: &MazeState,
+    beam_width: usize,
+    beam_depth: usize,
+    beam_number: usize,
+    on_improvement: &mut ImprovementHook<MazeState>,
+) -> Outcome {
+    if state.is_done() {
+        return Outcome::NoAction;
+    }
+    let mut best_action = None;
+    for incumbent in ChokudaiSearch::new(state, beam_width, beam_depth, beam_number) {
+        best_action = Some(incumbent.action);
+        let flow = on_improvement(&incumbent.state, incumbent.score, incumbent.iteration);
+        if flow.is_break() {
+            break;
+        }
+    }
+    best_action.into()
+}
+
+fn chokudai_search_with_time_threshold_action(
+    state: &MazeState,
+    beam_width: usize,
+    beam_depth: usize,
+    time_threshold: u64,
+) -> Outcome {
+    chokudai_search_with_clock(
+        state,
+        beam_width,
+        beam_depth,
+        time_threshold,
+        &SystemClock::new(),
+    )
+}
+
+fn dedup_chokudai_search_with_time_threshold_action(
+    state: &MazeState,
+    beam_width: usize,
+    beam_depth: usize,
+    time_threshold: u64,
+) -> Outcome {
+    let clock = SystemClock::new();
+    chokudai_search_timed(state, beam_width, beam_depth, time_threshold, &clock, true)
+}
+
+impl MazeState {
+    // At most the points the next `turns` moves can collect: the k-th move reaches only cells
+    // within k steps whose distance has the parity of k, and no cell pays twice.
+    fn reachable_points(&self, turns: usize) -> ScoreType {
+        let Coord { y: cy, x: cx } = self.character;
+        let mut best_at = vec![0; turns + 1];
+        let mut values = vec![];
+        for (y, x, point) in self.points.cells() {
+            let distance = cy.abs_diff(y) + cx.abs_diff(x);
+            if 0 < point && distance <= turns {
+                best_at[distance] = best_at[distance].max(point);
+                values.push(point);
+            }
+        }
+        for k in 2..=turns {
+            best_at[k] = best_at[k].max(best_at[k - 2]);
+        }
+        values.sort_unstable_by(|a, b| b.cmp(a));
+        let per_turn: ScoreType = best_at.iter().skip(1).sum();
+        per_turn.min(values.iter().take(turns).sum())
+    }
+}
+
+// At most the score `depth` more turns can reach, as the upper bound of `branch_and_bound`.
+pub fn reachable_score_bound(state: &MazeState, depth: usize) -> ScoreType {
+    state.game_score + state.reachable_points(depth.min(state.remaining_turns()))
+}
+
+// A* sees the next turns as a shortest path: a move costs the points it misses compared with
+// the best cell on the board, so the cheapest line collects the most. `h` is the fewest points
+// the rest of the line can miss.
+#[derive(Clone, Copy)]
+struct AStarNode {
+    f: ScoreType,
+    g: ScoreType,
+    h: ScoreType,
+    state: MazeState,
+}
+
+impl PartialEq for AStarNode {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other).is_eq()
+    }
+}
+
+impl PartialOrd for AStarNode {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Eq for AStarNode {}
+
+// The lowest `f` first, then the deepest.
+impl Ord for AStarNode {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        other
+            .f
+            .cmp(&self.f)
+            .then(self.state.turn.cmp(&other.state.turn))
+    }
+}
+
+// Weighted A* (`f = g + weight * h`) over the next `beam_depth` turns. A weight over one finds
+// a line sooner, missing at most `weight` times the points an optimal line misses.
+pub fn weighted_astar_action(
+    state: &MazeState,
+    weight: f64,
+    beam_depth: u64,
+    time_threshold: u64,
+) -> Outcome {
+    if state.is_done() {
+        return Outcome::NoAction;
+    }
+    let time_keeper = TimeKeeper::new(time_threshold);
+    let mut best = None;
+    weighted_astar_pass(
+        state,
+        weight,
+        beam_depth,
+        &time_keeper,
+        &mut best,
+        &mut NodeCount::default(),
+    );
+    match best.and_then(|(_, leaf): (ScoreType, MazeState)| leaf.first_action) {
+        Some(action) => Outcome::Action(action),
+        None => greedy_action(state),
+    }
+}
+
+pub fn anytime_astar_action(
+    state: &MazeState,
+    weight: f64,
+    beam_depth: u64,
+    time_threshold: u64,
+) -> Outcome {
+    let clock = SystemClock::new();
+    let mut on_improvement = |_: &MazeState, _, _| ControlFlow::Continue(());
+    anytime_astar_with_clock(
+        state,
+        weight,
+        beam_depth,
+        time_threshold,
+        &clock,
+        &mut on_improvement,
+    )
+}
+
+// Anytime weighted A*: passes with a weight halving towards one until the time runs out or a
+// pass with weight one proves the incumbent optimal. Every pass prunes the lines that cannot
+// beat the incumbent, which goes to `on_improvement` with the pass it was found in.
+pub fn anytime_astar_with_clock(
+    state: &MazeState,
+    weight: f64,
+    beam_depth: u64,
+    time_threshold: u64,
+    clock: &impl Clock,
+    on_improvement: &mut ImprovementHook<MazeState>,
+) -> Outcome {
+    search_span!("anytime_astar", weight, beam_depth, time_threshold);
+    if state.is_done() {
+        return Outcome::NoAction;
+    }
+    let time_keeper = TimeKeeper::with_clock(time_threshold, clock);
+    let mut best: Option<(ScoreType, MazeState)> = None;
+    let mut weight = weight.max(1.0);
+    let mut count = NodeCount::default();
+    for iteration in 0.. {
+        let before = best.map(|(loss, _)| loss);
+        let finished = weighted_astar_pass(
+            state,
+            weight,
+            beam_depth,
+            &time_keeper,
+            &mut best,
+            &mut count,
+        );
+        search_event!(iteration, weight, expanded = count.expanded, finished);
+        if let Some((loss, leaf)) = best {
+            if before != Some(loss) && on_improvement(&leaf, leaf.game_score, iteration).is_break()
+            {
+                break;
+            }
+        }
+        if !finished || weight <= 1.0 {
+            break;
+        }
+        weight = if weight < 1.05 {
+            1.0
+        } else {
+            1.0 + (weight - 1.0) / 2.0
+        };
+    }
+    match best.and_then(|(_, leaf)| leaf.first_action) {
+        Some(action) => Outcome::Action(action),
+        None => greedy_action(state),
+    }
+}
+
+// One weighted A* pass; `best` holds the fewest missed points of any line so far and its
+// leaf. Returns false when the time ran out before the pass ended.
+fn weighted_astar_pass<C: Clock>(
+    state: &MazeState,
+    weight: f64,
+    beam_depth: u64,
+    time_keeper: &TimeKeeper<C>,
+    best: &mut Option<(ScoreType, MazeState)>,
+    count: &mut NodeCount,
+) -> bool {
+    let horizon = (state.turn + beam_depth).min(END_TURN);
+    let step = state.points.cells().map(|cell| cell.2).max().unwrap_or(0);
+    let node = |now_state: MazeState| {
+        let g = (now_state.turn - state.turn) as ScoreType * step
+            - (now_state.game_score - state.game_score);
+        let turns = (horizon - now_state.turn) as usize;
+        // A blocked state misses every point left.
+        let mut h = turns as ScoreType * step;
+        if !now_state.legal_actions().is_empty() {
+            h -= now_state.reachable_points(turns);
+        }
+        let f = g + (weight * h as f64) as ScoreType;
+        AStarNode {
+            f,
+            g,
+            h,
+            state: now_state,
+        }
+    };
+    let beaten = |best: &Option<(ScoreType, MazeState)>, node: &AStarNode| {
+        best.is_some_and(|(loss, _)| loss <= node.g + node.h)
+    };
+    let mut open = BinaryHeap::new();
+    let mut seen = HashSet::new();
+    open.push(node(*state));
+    while let Some(now) = open.pop() {
+        if time_keeper.is_time_over() {
+            return false;
+        }
+        if beaten(best, &now) {
+            count.pruned += 1;
+            continue;
+        }
+        let now_state = now.state;
+        let legal_actions = now_state.legal_actions();
+        if now_state.turn == horizon || legal_actions.is_empty() {
+            *best = Some((now.g + now.h, now_state));
+            return true;
+        }
+        count.expanded += 1;
+        for act in legal_actions {
+            let mut next_state = now_state;
+            next_state.advance(act);
+            if now_state.turn == state.turn {
+                next_state.first_action = Some(act);
+            }
+            if !seen.insert((next_state.state_hash(), next_state.turn)) {
+                count.duplicates += 1;
+                continue;
+            }
+            let next = node(next_state);
+            if beaten(best, &next) {
+                count.pruned += 1;
+                continue;
+            }
+            count.generated += 1;
+            count.max_depth = count.max_depth.max((next_state.turn - state.turn) as usize);
+            open.push(next);
+        }
+        count.peak_bytes = count
+            .peak_bytes
+            .max(open.len() * std::mem::size_of::<AStarNode>());
+    }
+    true
+}
+// A state that remembers its node in a `SearchTree` or an arena; it plays and orders like the
+// state alone, so the generic searches run on it exactly as on the untraced state, and an
+// `OnGenerate` hook hands every child its node.
+#[derive(Clone, Copy)]
+struct Traced<S = MazeState, I = usize> {
+    state: S,
+    node: I,
+}
+
+impl<S: Ord, I> PartialEq for Traced<S, I> {
+    fn eq(&self, other: &Self) -> bool {
+        self.state == other.state
+    }
+}
+
+impl<S: Ord, I> PartialOrd for Traced<S, I> {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<S: Ord, I> Eq for Traced<S, I> {}
+
+impl<S: Ord, I> Ord for Traced<S, I> {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.state.cmp(&other.state)
+    }
+}
+
+impl<S: SinglePlayerState, I: Clone> SinglePlayerState for Traced<S, I> {
+    fn is_done(&self) -> bool {
+        self.state.is_done()
+    }
+
+    fn advance(&mut self, action: Action) {
+        self.state.advance(action)
+    }
+
+    fn legal_actions(&self) -> ActionList {
+        self.state.legal_actions()
+    }
+
+    fn score(&self) -> ScoreType {
+        self.state.score()
+    }
+
+    fn action_cost(&self, action: Action) -> CostType {
+        self.state.action_cost(action)
+    }
+
+    fn spent(&self) -> CostType {
+        self.state.spent()
+    }
+}
+
+impl<S: BeamState, I: Clone> BeamState for Traced<S, I> {
+    fn evaluated_score(&self) -> ScoreType {
+        self.state.evaluated_score()
+    }
+
+    fn evaluate_score(&mut self) {
+        self.state.evaluate_score()
+    }
+
+    fn first_action(&self) -> Option<Action> {
+        self.state.first_action()
+    }
+
+    fn set_first_action(&mut self, action: Action) {
+        self.state.set_first_action(action)
+    }
+
+    fn dedup_key(&self) -> u64 {
+        self.state.dedup_key()
+    }
+}
+
+// Same search as `beam_search_action`, but returns the whole line to the best state. The
+// beams only carry a handle into the arena, which is the one copy of every line.
+pub fn beam_search_plan(state: &MazeState, beam_width: usize, beam_depth: u64) -> Vec<Action> {
+    let mut arena = Arena::with_capacity(beam_width * beam_depth as usize * 4 + 1);
+    let root = Traced {
+        state: *state,
+        node: arena.root(()),
+    };
+    let run = BeamRun::new(&root, beam_width, BinaryHeap::new());
+    let (best, _) = run.run(
+        beam_depth,
+        SearchLimits::default(),
+        &Expansion::default(),
+        Some(&mut |_, parent, act, child| child.node = arena.alloc(parent.node, act, ())),
+        None,
+    );
+    arena.plan(best.node)
+}
+
+// Same search as `beam_search_action`, also returning every generated node for `to_dot`.
+// Meant for small widths and depths.
+pub fn beam_search_tree(
+    state: &MazeState,
+    beam_width: usize,
+    beam_depth: u64,
+) -> (Outcome, SearchTree) {
+    let mut tree = SearchTree::new(state.game_score);
+    let root = Traced {
+        state: *state,
+        node: 0,
+    };
+    let run = BeamRun::new(&root, beam_width, BinaryHeap::new());
+    let (best, _) = run.run(
+        beam_depth,
+        SearchLimits::default(),
+        &Expansion::default(),
+        Some(&mut |_, parent, act, child| {
+            child.node = tree.push(parent.node, act, child.state.evaluated_score);
+        }),
+        None,
+    );
+    tree.mark_path(best.node);
+    (line_outcome(state, Some(&best.state), CostType::MAX), tree)
+}
+
+// Same search as `chokudai_search_action`, also returning every generated node.
+pub fn chokudai_search_tree(
+    state: &MazeState,
+    beam_width: usize,
+    beam_depth: usize,
+    beam_number: usize,
+) -> (Outcome, SearchTree) {
+    let mut tree = SearchTree::new(state.game_score);
+    let root = Traced {
+        state: *state,
+        node: 0,
+    };
+    let run = ChokudaiRun::new(&root, beam_width, beam_depth);
+    let (leaf, _) = run.run(
+        beam_number,
+        SearchLimits::default(),
+        false,
+        Some(&mut |_, parent, act, child| {
+            child.node = tree.push(parent.node, act, child.state.evaluated_score);
+        }),
+        None,
+    );
+    if let Some(leaf) = &leaf {
+        tree.mark_path(leaf.node);
+    }
+    let leaf = leaf.map(|leaf| leaf.state);
+    (line_outcome(state, leaf.as_ref(), CostType::MAX), tree)
+}
+
+pub fn play_game() {
     let mut state = MazeState::new();
+    let mut time_manager = TimeManager::new(1000, END_TURN);
     println!("{}", state);
     while !state.is_done() {
-        state.advance(
-            chokudai_search_with_time_threshold_action(&state, 5, END_TURN as usize, 10).unwrap(),
+        let time_threshold = time_manager.turn_budget();
+        match chokudai_search_with_time_threshold_action(
+            &state,
+            5,
+            END_TURN as usize,
+            time_threshold,
+        ) {
+            Outcome::Action(action) => state.advance(action),
+            Outcome::NoAction => {
+                println!("no legal action at turn {}", state.turn);
+                break;
+            }
+        }
+        time_manager.record(&state);
+    }
+    println!("{}", state)
+}
+
+#[cfg(test)]
+mod test {
+    use std::{cell::RefCell, ops::ControlFlow};
+
+    use rand_chacha::ChaCha8Rng;
+
+    use super::*;
+    use crate::branch_and_bound::{branch_and_bound, branch_and_bound_with_nodes};
+    use crate::discrepancy::lds_action;
+    use crate::endgame::{memoized_search, with_endgame_solver};
+    use crate::game::{
+        run_seeded_batch, stop_at_score, GameResult, GameRunner, MockClock, RngStreams, Summary,
+    };
+    use crate::learning::anneal_weights;
+    use crate::maze_generator::{MazeGenerator, PointDistribution};
+    use crate::stats::{compare_agents, mc_evaluate_with, Estimate};
+    use crate::tuning::grid_search;
+    const GAME_NUMBER: usize = 100;
+    const THREAD_NUMBER: usize = 4;
+
+    // Mean score over the seeds 0..GAME_NUMBER. Deterministic agents always get the same mean,
+    // so the floors the score tests assert sit a little under it and only a regression fails.
+    fn mean_score(agent: impl Fn(&MazeState) -> Outcome) -> f64 {
+        let results = GameRunner::new(MazeState::with_seed, agent).run_episodes(GAME_NUMBER);
+        Summary::from_results(&results).mean
+    }
+
+    fn seeded_random_agent(seed: u64) -> impl Fn(&MazeState) -> Outcome {
+        let rng = RefCell::new(StdRng::seed_from_u64(seed));
+        move |state| random_action_with(state, &mut *rng.borrow_mut())
+    }
+
+    #[test]
+    fn test_run_seeded_batch() {
+        let scores = |thread_number: usize, seed: u64| -> Vec<ScoreType> {
+            run_seeded_batch(
+                MazeState::with_seed,
+                |state: &MazeState, rng: &mut ChaCha8Rng| random_action_with(state, rng),
+                20,
+                thread_number,
+                RngStreams::new(seed),
+            )
+            .iter()
+            .map(|result| result.score)
+            .collect()
+        };
+        assert_eq!(scores(1, 0), scores(4, 0));
+        assert_ne!(scores(4, 0), scores(4, 1));
+    }
+
+    #[test]
+    fn test_random_score() {
+        let mean = mean_score(seeded_random_agent(0));
+        println!("Random Score:\t{}", mean);
+        assert!(150.0 <= mean);
+    }
+
+    #[test]
+    fn test_score_ordering() {
+        let random = mean_score(seeded_random_agent(0));
+        let greedy = mean_score(greedy_action);
+        let beam = mean_score(|state: &MazeState| beam_search_action(state, 2, END_TURN));
+        assert!(random < greedy, "random {} >= greedy {}", random, greedy);
+        assert!(greedy < beam, "greedy {} >= beam {}", greedy, beam);
+    }
+
+    #[test]
+    fn test_generated_maze_with_walls() {
+        let generator = MazeGenerator {
+            distribution: PointDistribution::Clustered {
+                max: 9,
+                centers: 5,
+                radius: 4.0,
+            },
+            wall_density: 0.2,
+            sparsity: 0.0,
+        };
+        let env = |seed| MazeState::with_generator(seed, &generator).unwrap();
+        let results = GameRunner::new(env, greedy_action)
+            .on_turn(|state, _| assert!(!state.walls[state.character.y][state.character.x]))
+            .run_episodes(10);
+        let mean = Summary::from_results(&results).mean;
+        println!("Greedy Score (walls):\t{}", mean);
+        assert!(70.0 <= mean);
+    }
+
+    #[test]
+    fn test_text_round_trip() {
+        let generator = MazeGenerator {
+            wall_density: 0.2,
+            ..Default::default()
+        };
+        let mut state = MazeState::with_generator(0, &generator).unwrap();
+        state.advance(state.legal_actions()[0]);
+        let loaded: MazeState = state.to_string().parse().unwrap();
+        assert_eq!(loaded.to_string(), state.to_string());
+        assert_eq!(loaded.turn, state.turn);
+        // One file per process, so concurrent test runs do not race on it.
+        let name = format!("lean_search_round_trip_{}.txt", std::process::id());
+        let path = std::env::temp_dir().join(name);
+        state.save(&path).unwrap();
+        let loaded = MazeState::load(&path);
+        fs::remove_file(&path).unwrap();
+        assert_eq!(loaded.unwrap().to_string(), state.to_string());
+        assert_eq!("@".parse::<MazeState>().unwrap_err(), ParseMazeError::Size);
+    }
+
+    #[test]
+    fn test_corridor_instance() {
+        let state: MazeState = include_str!("../instances/corridor.txt").parse().unwrap();
+        let mut greedy = GameRunner::new(|_| state, greedy_action);
+        let mut beam = GameRunner::new(
+            |_| state,
+            |s: &MazeState| beam_search_action(s, 2, END_TURN),
         );
+        // Greedy follows the row of 1s into the dead end.
+        assert_eq!(greedy.run(0).score, 29);
+        assert!(greedy.run(0).score < beam.run(0).score);
     }
-    println!("{}", state)
-}
 
-#[cfg(test)]
-mod test {
-    use super::*;
-    const GAME_NUMBER: usize = 100;
+    #[test]
+    fn test_budgeted_search() {
+        const BUDGET: CostType = 60;
+        let play = |agent: &dyn Fn(&MazeState) -> Outcome| {
+            let mut state = MazeState::with_seed(0);
+            // Vertical moves are three times as expensive.
+            state.set_move_costs([1, 1, 3, 3]);
+            while let Outcome::Action(action) = agent(&state) {
+                assert!(state.affordable_actions(BUDGET).contains(&action));
+                state.advance(action);
+            }
+            assert!(state.spent() <= BUDGET);
+            assert!(state.affordable_actions(BUDGET).is_empty() || state.is_done());
+            state.game_score
+        };
+        let beam = play(&|s| budgeted_beam_search_action(s, 2, 20, BUDGET));
+        let chokudai = play(&|s| budgeted_chokudai_search_action(s, 1, 20, 2, BUDGET));
+        println!("Budgeted Beam Search Score:\t{}", beam);
+        println!("Budgeted Chokudai Search Score:\t{}", chokudai);
+        assert!(180 <= beam && 180 <= chokudai);
+    }
 
     #[test]
-    fn test_random_score() {
-        let mut mean = 0.0;
-        for _ in 0..GAME_NUMBER {
-            let mut state = MazeState::new();
-            while !state.is_done() {
-                state.advance(random_action(&state))
+    fn test_node_count() {
+        let state = MazeState::with_seed(0);
+        let (_, count) = beam_search_with_count(&state, 2, 3, SearchLimits::default(), None);
+        assert!(count.expanded <= 1 + 2 * 2);
+        assert!(0 < count.pruned);
+        assert!(count.expanded + count.pruned <= 1 + count.generated);
+    }
+
+    #[test]
+    fn test_effective_branching_factor() {
+        let state = MazeState::with_seed(0);
+        let (_, beam) = beam_search_with_nodes(&state, 5, 10);
+        let (_, chokudai) = chokudai_search_with_nodes(&state, 1, 10, 5);
+        assert_eq!(beam.max_depth, 10);
+        assert!(beam.effective_branching_factor() < 4.0);
+        println!("Beam Search Nodes:\t{}", beam);
+        println!("Chokudai Search Nodes:\t{}", chokudai);
+    }
+
+    #[test]
+    fn test_chokudai_incumbents() {
+        let state = MazeState::with_seed(0);
+        let incumbents: Vec<Incumbent> = ChokudaiSearch::new(&state, 1, 10, 5).collect();
+        for pair in incumbents.windows(2) {
+            assert!((pair[0].depth, pair[0].score) < (pair[1].depth, pair[1].score));
+        }
+        let last = incumbents.last().unwrap();
+        assert_eq!(
+            Outcome::Action(last.action),
+            chokudai_search_action(&state, 1, 10, 5)
+        );
+        // Anytime use: stop as soon as a full-depth line is found.
+        let first_full = ChokudaiSearch::new(&state, 1, 10, 100).find(|i| i.depth == 10);
+        assert_eq!(first_full.map(|i| i.iteration), Some(1));
+    }
+
+    #[test]
+    fn test_max_nodes() {
+        let state = MazeState::with_seed(0);
+        let limits = SearchLimits {
+            max_nodes: Some(7),
+            ..SearchLimits::default()
+        };
+        let (_, count) = beam_search_with_count(&state, 2, END_TURN, limits, None);
+        assert_eq!(count.expanded, 7);
+        // Seven expansions finish depths 1 to 4 of a width-2 beam: 1 + 2 + 2 + 2.
+        assert_eq!(
+            limited_beam_search_action(&state, 2, END_TURN, limits),
+            beam_search_action(&state, 2, 4)
+        );
+        let (_, count) = chokudai_search_with(&state, 1, 10, usize::MAX, limits, false, None);
+        assert_eq!(count.expanded, 7);
+        // Without a node limit the iterations end once every line is finished.
+        let (_, count) = chokudai_search_with(
+            &state,
+            1,
+            3,
+            usize::MAX,
+            SearchLimits::default(),
+            false,
+            None,
+        );
+        assert!(0 < count.expanded);
+    }
+
+    #[test]
+    fn test_stop_at_score() {
+        let state = MazeState::with_seed(0);
+        let unlimited = SearchLimits::default();
+        assert_eq!(
+            limited_beam_search_action(&state, 2, END_TURN, unlimited),
+            beam_search_action(&state, 2, END_TURN)
+        );
+        // Any first move reaches a target of zero, so the search stops after one depth.
+        let limits = SearchLimits {
+            stop_at_score: Some(0),
+            ..unlimited
+        };
+        assert_eq!(
+            limited_beam_search_action(&state, 2, END_TURN, limits),
+            beam_search_action(&state, 2, 1)
+        );
+        let limits = SearchLimits {
+            stop_at_score: Some(1),
+            ..unlimited
+        };
+        let Outcome::Action(action) =
+            limited_chokudai_search_action(&state, 1, END_TURN as usize, 10, limits)
+        else {
+            panic!("no action");
+        };
+        assert!(state.legal_actions().contains(&action));
+        let mut stop = stop_at_score(5);
+        let mut last = 0;
+        chokudai_search_with_hook(
+            &state,
+            1,
+            END_TURN as usize,
+            10,
+            &mut |leaf, score, iteration| {
+                last = score;
+                stop(leaf, score, iteration)
+            },
+        );
+        assert!(5 <= last);
+    }
+
+    #[test]
+    fn test_dedup_chokudai_search() {
+        let state = MazeState::with_seed(0);
+        let (outcome, count) = dedup_chokudai_search_with_nodes(&state, 2, 20, 10);
+        let (_, plain) = chokudai_search_with_nodes(&state, 2, 20, 10);
+        assert_ne!(outcome, Outcome::NoAction);
+        assert!(0 < count.duplicates);
+        assert_eq!(plain.duplicates, 0);
+        println!("Chokudai Re-expansions Avoided:\t{}", count.duplicates);
+    }
+
+    #[test]
+    fn test_dedup_beam_search() {
+        let state = MazeState::with_seed(0);
+        let (outcome, count) = dedup_beam_search_with_nodes(&state, 20, 10);
+        let (_, plain) = beam_search_with_nodes(&state, 20, 10);
+        assert_ne!(outcome, Outcome::NoAction);
+        assert!(0 < count.duplicates);
+        assert_eq!(plain.duplicates, 0);
+        println!("Dedup Beam Duplicates:\t{}", count.duplicates);
+    }
+
+    #[test]
+    fn test_tie_break() {
+        let state = MazeState::with_seed(0);
+        for tie_break in [
+            TieBreak::Banked,
+            TieBreak::Random(1),
+            TieBreak::NearestPoint,
+        ] {
+            let config = BeamConfig {
+                width: 3,
+                depth: 10,
+                tie_break,
+                ..BeamConfig::default()
+            };
+            let action = beam_search_with_config(&state, &config).unwrap();
+            assert!(state.legal_actions().contains(&action));
+        }
+        let mut queue = TieBreakQueue::new(TieBreak::NearestPoint);
+        let mut near = state;
+        near.evaluated_score = 5;
+        let mut far = near;
+        far.character = Coord { y: 0, x: 0 };
+        far.points = PackedPoints::from_rows(&[[0; WIDTH]; HEIGHT]);
+        far.points.set(HEIGHT - 1, WIDTH - 1, 1);
+        near.points = far.points;
+        near.character = Coord {
+            y: HEIGHT - 1,
+            x: WIDTH - 2,
+        };
+        BeamQueue::push(&mut queue, far);
+        BeamQueue::push(&mut queue, near);
+        let popped = BeamQueue::pop(&mut queue).unwrap();
+        assert_eq!(
+            (popped.character.y, popped.character.x),
+            (HEIGHT - 1, WIDTH - 2)
+        );
+        let random = TieBreakQueue::new(TieBreak::Random(7));
+        assert_eq!(random.empty().tie_break, TieBreak::Random(7));
+    }
+
+    #[test]
+    fn test_anytime_astar() {
+        fn best_gain(state: &MazeState, depth: u64) -> ScoreType {
+            if depth == 0 || state.is_done() {
+                return state.game_score;
             }
-            mean += state.game_score as f64;
+            let mut best = state.game_score;
+            for act in state.legal_actions() {
+                let mut next_state = *state;
+                next_state.advance(act);
+                best = best.max(best_gain(&next_state, depth - 1));
+            }
+            best
         }
-        mean /= GAME_NUMBER as f64;
-        println!("Random Score:\t{}", mean)
+        for seed in 0..3 {
+            let state = MazeState::with_seed(seed);
+            let clock = MockClock::new();
+            let mut scores = vec![];
+            let action =
+                anytime_astar_with_clock(&state, 4.0, 6, 10, &clock, &mut |_, score, _| {
+                    scores.push(score);
+                    ControlFlow::Continue(())
+                })
+                .unwrap();
+            assert!(state.legal_actions().contains(&action));
+            assert!(scores.windows(2).all(|w| w[0] < w[1]));
+            assert_eq!(scores.last(), Some(&best_gain(&state, 6)));
+        }
+        let state = MazeState::with_seed(0);
+        let action = weighted_astar_action(&state, 2.0, 10, 10).unwrap();
+        assert!(state.legal_actions().contains(&action));
     }
 
     #[test]
-    fn test_greedy_score() {
-        let mut mean = 0.0;
-        for _ in 0..GAME_NUMBER {
-            let mut state = MazeState::new();
-            while !state.is_done() {
-                state.advance(greedy_action(&state))
+    fn test_branch_and_bound_bounds_beam_search() {
+        for seed in 0..3 {
+            let state = MazeState::with_seed(seed);
+            let ((optimum, action), count) =
+                branch_and_bound_with_nodes(&state, 8, &reachable_score_bound);
+            let loose = |_: &MazeState, _: usize| ScoreType::MAX;
+            assert_eq!(
+                branch_and_bound(&state, 5, &reachable_score_bound).0,
+                branch_and_bound(&state, 5, &loose).0
+            );
+            let mut leaf = state;
+            for action in beam_search_plan(&state, 5, 8) {
+                leaf.advance(action);
             }
-            mean += state.game_score as f64;
+            assert!(leaf.game_score <= optimum);
+            assert!(state.legal_actions().contains(&action.unwrap()));
+            assert!(0 < count.pruned);
         }
-        mean /= GAME_NUMBER as f64;
-        println!("Greedy Score:\t{}", mean)
     }
 
     #[test]
-    fn test_beam_search_score() {
-        let mut mean = 0.0;
-        for _ in 0..GAME_NUMBER {
-            let mut state = MazeState::new();
-            while !state.is_done() {
-                state.advance(beam_search_action(&state, 2, END_TURN))
+    fn test_beam_search_against_exact_solver() {
+        const HORIZON: u64 = 8;
+        let key = |state: &MazeState| (state.state_hash(), state.turn);
+        for seed in 0..5 {
+            let state = MazeState::small_with_seed(seed, 5, 5);
+            let (optimum, _) = memoized_search(&state, HORIZON as usize, &key);
+            assert_eq!(
+                branch_and_bound(&state, HORIZON as usize, &reachable_score_bound).0,
+                optimum
+            );
+            let mut leaf = state;
+            for action in beam_search_plan(&state, 10, HORIZON) {
+                leaf.advance(action);
             }
-            mean += state.game_score as f64;
+            assert!(leaf.game_score <= optimum);
+            assert!(
+                optimum * 9 <= leaf.game_score * 10,
+                "seed {}: beam {} of {}",
+                seed,
+                leaf.game_score,
+                optimum
+            );
         }
-        mean /= GAME_NUMBER as f64;
-        println!("Beam Search Score:\t{}", mean)
     }
 
     #[test]
-    fn test_beam_search_with_time_threshold_score() {
-        let mut mean = 0.0;
-        for _ in 0..GAME_NUMBER {
-            let mut state = MazeState::new();
-            while !state.is_done() {
-                state.advance(beam_search_with_time_threshold_action(&state, 5, 10))
+    fn test_bucket_queue() {
+        let mut bucket = BucketQueue::default();
+        let mut heap = BinaryHeap::new();
+        let mut state = MazeState::with_seed(0);
+        for score in [3, 0, 7, 3, 12, -2, 7] {
+            state.evaluated_score = score;
+            BeamQueue::push(&mut bucket, state);
+            heap.push(state);
+        }
+        assert_eq!(bucket.len(), heap.len());
+        assert_eq!(bucket.peek().unwrap().evaluated_score, 12);
+        while let Some(top) = heap.pop() {
+            let popped = BeamQueue::pop(&mut bucket).unwrap();
+            assert_eq!(popped.evaluated_score, top.evaluated_score);
+        }
+        assert!(BeamQueue::is_empty(&bucket));
+        assert!(BeamQueue::pop(&mut bucket).is_none());
+    }
+
+    #[test]
+    fn test_bucket_queue_wide_scores() {
+        let mut bucket = BucketQueue::default();
+        let mut heap = BinaryHeap::new();
+        let mut state = MazeState::with_seed(0);
+        for score in [5, -3, ScoreType::MAX, 0, ScoreType::MIN, -3, 40] {
+            state.evaluated_score = score;
+            BeamQueue::push(&mut bucket, state);
+            heap.push(state);
+            assert_eq!(bucket.len(), heap.len());
+            assert_eq!(
+                bucket.peek().unwrap().evaluated_score,
+                heap.peek().unwrap().evaluated_score
+            );
+        }
+        while let Some(top) = heap.pop() {
+            let popped = BeamQueue::pop(&mut bucket).unwrap();
+            assert_eq!(popped.evaluated_score, top.evaluated_score);
+        }
+        assert!(BeamQueue::is_empty(&bucket));
+        state.evaluated_score = -7;
+        BeamQueue::push(&mut bucket, state);
+        assert_eq!(BeamQueue::pop(&mut bucket).unwrap().evaluated_score, -7);
+    }
+
+    #[test]
+    fn test_beam_search_plan() {
+        let state = MazeState::with_seed(0);
+        let plan = beam_search_plan(&state, 3, 10);
+        assert_eq!(plan.len(), 10);
+        assert_eq!(Outcome::Action(plan[0]), beam_search_action(&state, 3, 10));
+        let mut now_state = state;
+        for &action in &plan {
+            assert!(now_state.legal_actions().contains(&action));
+            now_state.advance(action);
+        }
+    }
+
+    #[test]
+    fn test_plan_executor() {
+        let state = MazeState::with_seed(0);
+        let mut executor =
+            crate::game::PlanExecutor::new(|s: &MazeState| beam_search_plan(s, 3, 10));
+        let action = executor.action(&state).unwrap();
+        assert_eq!(executor.plan().len(), 9);
+        let mut now_state = state;
+        now_state.advance(action);
+        let next = executor.plan()[0];
+        assert_eq!(executor.action(&now_state), Outcome::Action(next));
+        assert_eq!(executor.replans(), 0);
+
+        // Points vanish while the game runs, so lines planned for them go stale.
+        let (mut blind, mut executed, mut replans) = (0, 0, 0);
+        for seed in 0..5 {
+            let mut rng = StdRng::seed_from_u64(seed);
+            let mut blind_state = MazeState::with_seed(seed);
+            let blind_plan = beam_search_plan(&blind_state, 10, END_TURN);
+            let mut state = blind_state;
+            let mut executor =
+                crate::game::PlanExecutor::new(|s: &MazeState| beam_search_plan(s, 10, END_TURN));
+            for &blind_action in blind_plan.iter() {
+                let action = executor.action(&state).unwrap();
+                assert!(state.legal_actions().contains(&action));
+                state.advance(action);
+                blind_state.advance(blind_action);
+                for _ in 0..5 {
+                    let (y, x) = (rng.gen_range(0..HEIGHT), rng.gen_range(0..WIDTH));
+                    let change = CellChange::Point { y, x, value: 0 };
+                    state.apply_change(change).unwrap();
+                    blind_state.apply_change(change).unwrap();
+                }
             }
-            mean += state.game_score as f64;
+            assert!(state.is_done());
+            blind += blind_state.game_score;
+            executed += state.game_score;
+            replans += executor.replans();
         }
-        mean /= GAME_NUMBER as f64;
-        println!("Beam Search 10ms Score:\t{}", mean)
+        println!(
+            "Blind Plan Score:\t{}\tPlan Executor Score:\t{}",
+            blind / 5,
+            executed / 5
+        );
+        assert!(0 < replans);
+        assert!(blind < executed);
+    }
+
+    #[test]
+    fn test_packed_points() {
+        // Down from 900 scores of 8 bytes for the points alone.
+        assert!(std::mem::size_of::<MazeState>() < 1500);
+        let state = MazeState::with_seed(0);
+        let text = state.to_string();
+        assert_eq!(text.parse::<MazeState>().unwrap().to_string(), text);
+        let mut changed = state;
+        changed
+            .apply_change(CellChange::Point {
+                y: 1,
+                x: 2,
+                value: 15,
+            })
+            .unwrap();
+        assert_eq!(changed.point(1, 2), 15.0);
+        let text = changed.to_string();
+        assert_eq!(text.parse::<MazeState>().unwrap().to_string(), text);
+        let too_big = CellChange::Point {
+            y: 1,
+            x: 2,
+            value: 16,
+        };
+        assert_eq!(changed.apply_change(too_big), Err(PointOverflow(16)));
+        assert_eq!(changed.point(1, 2), 15.0);
+        let generator = MazeGenerator {
+            distribution: PointDistribution::Uniform { max: 20 },
+            ..Default::default()
+        };
+        assert_eq!(
+            MazeState::with_generator(0, &generator).unwrap_err(),
+            PointOverflow(20)
+        );
+        assert_eq!(changed.point(1, 3), state.point(1, 3));
+    }
+
+    #[test]
+    fn test_legal_actions_stay_inline() {
+        let mut state = MazeState::with_seed(0);
+        while !state.is_done() {
+            let legal_actions = state.legal_actions();
+            assert!(!legal_actions.spilled());
+            state.advance(legal_actions[0]);
+        }
+    }
+
+    #[test]
+    fn test_chokudai_hook() {
+        let state = MazeState::with_seed(0);
+        let mut depths = vec![];
+        let outcome = chokudai_search_with_hook(&state, 1, 10, 5, &mut |leaf, _, _| {
+            depths.push(leaf.turn);
+            ControlFlow::Continue(())
+        });
+        assert_eq!(outcome, chokudai_search_action(&state, 1, 10, 5));
+        assert!(depths.windows(2).all(|pair| pair[0] <= pair[1]));
+        let mut calls = 0;
+        chokudai_search_with_hook(&state, 1, 10, 100, &mut |_, _, _| {
+            calls += 1;
+            ControlFlow::Break(())
+        });
+        assert_eq!(calls, 1);
+    }
+
+    #[test]
+    fn test_search_tree_matches_search() {
+        let state = MazeState::with_seed(0);
+        let (outcome, tree) = beam_search_tree(&state, 2, 3);
+        assert_eq!(outcome, beam_search_action(&state, 2, 3));
+        let path = tree.chosen_path();
+        assert_eq!(path.len(), 3);
+        assert_eq!(Outcome::Action(path[0]), outcome);
+        let (outcome, tree) = chokudai_search_tree(&state, 1, 3, 2);
+        assert_eq!(outcome, chokudai_search_action(&state, 1, 3, 2));
+        assert_eq!(Outcome::Action(tree.chosen_path()[0]), outcome);
+        assert!(tree.to_dot().contains("color=red"));
+    }
+
+    #[test]
+    fn test_render() {
+        let mut state = MazeState::with_seed(0);
+        let mut path = vec![state.character];
+        for _ in 0..5 {
+            state.advance(greedy_action(&state).unwrap());
+            path.push(state.character);
+        }
+        assert_eq!(state.render(RenderStyle::Plain, &path), state.to_string());
+        let ansi = state.render(RenderStyle::Ansi, &path);
+        assert!(ansi.contains("\x1b[1;31m@"));
+        assert_eq!(ansi.matches("\x1b[2m*").count(), 5);
+        let emoji = state.render(RenderStyle::Emoji, &path);
+        assert_eq!(emoji.matches('🤖').count(), 1);
+        assert_eq!(emoji.matches('👣').count(), 5);
+        // Points past the palette take its hottest color.
+        state
+            .apply_change(CellChange::Point {
+                y: 0,
+                x: 0,
+                value: 15,
+            })
+            .unwrap();
+        assert!(state
+            .render(RenderStyle::Ansi, &path)
+            .contains("\x1b[38;5;196m15"));
+    }
+
+    #[test]
+    fn test_visit_heatmap() {
+        let heatmap = |agent: &dyn Fn(&MazeState) -> Outcome| {
+            let mut heatmap = VisitHeatmap::new();
+            GameRunner::new(MazeState::with_seed, agent)
+                .on_turn(|state, _| heatmap.record(state))
+                .run_episodes(5);
+            heatmap
+        };
+        let greedy = heatmap(&greedy_action);
+        let beam = heatmap(&|s| beam_search_action(s, 2, 10));
+        let total: usize = (0..HEIGHT)
+            .flat_map(|y| (0..WIDTH).map(move |x| (y, x)))
+            .map(|(y, x)| greedy.count(y, x))
+            .sum();
+        assert_eq!(total, 5 * END_TURN as usize);
+        assert_eq!(greedy.to_csv().lines().count(), HEIGHT);
+        println!("Greedy Coverage:\t{}", greedy.coverage());
+        println!("Beam Search Coverage:\t{}", beam.coverage());
+    }
+
+    #[test]
+    fn test_search_repl() {
+        let state = MazeState::with_seed(0);
+        let mut output = vec![];
+        let input = "step 3\nbeam\nshow 0\nshow 999\nfly\nquit\nstep\n".as_bytes();
+        let stepper = search_repl(&state, 2, input, &mut output).unwrap();
+        assert_eq!(stepper.depth(), 3);
+        let best = stepper.candidates()[0];
+        assert_eq!(
+            Outcome::Action(best.first_action.unwrap()),
+            beam_search_action(&state, 2, 3)
+        );
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.contains("depth:\t3"));
+        assert!(output.contains(&best.to_string()));
+        assert!(output.contains("no such candidate"));
+        assert!(output.contains("unknown command: fly"));
+    }
+
+    #[test]
+    fn test_depth_stats() {
+        let state = MazeState::with_seed(0);
+        let (outcome, stats) = beam_search_with_stats(&state, 3, 5);
+        assert_eq!(outcome, beam_search_action(&state, 3, 5));
+        assert_eq!(stats.len(), 5);
+        for s in &stats {
+            assert!(s.worst as f64 <= s.mean && s.mean <= s.best as f64);
+            assert!(s.duplicates < s.beam_size);
+        }
+        let (outcome, stats) = chokudai_search_with_stats(&state, 1, 5, 3);
+        assert_eq!(outcome, chokudai_search_action(&state, 1, 5, 3));
+        let csv = depth_stats_csv(&stats);
+        assert_eq!(csv.lines().count(), 1 + stats.len());
+        print!("{}", csv);
+    }
+
+    #[test]
+    fn test_greedy_score() {
+        let mean = mean_score(greedy_action);
+        println!("Greedy Score:\t{}", mean);
+        assert!(470.0 <= mean);
+    }
+
+    #[test]
+    fn test_beam_search_score() {
+        let mean = mean_score(|state: &MazeState| beam_search_action(state, 2, END_TURN));
+        println!("Beam Search Score:\t{}", mean);
+        assert!(630.0 <= mean);
+    }
+
+    #[test]
+    fn test_bucket_beam_search_score() {
+        let mean = mean_score(|state: &MazeState| bucket_beam_search_action(state, 2, END_TURN));
+        println!("Bucket Beam Search Score:\t{}", mean);
+        assert!(625.0 <= mean);
+    }
+
+    #[test]
+    fn test_policy_beam_search() {
+        let state = MazeState::with_seed(0);
+        let config = BeamConfig::default();
+        let capped = BeamConfig {
+            expansion_cap: Some(2),
+            ..config
+        };
+        let (_, all) = policy_beam_search_with_nodes(&state, &config, Some(&PointPolicy));
+        let (_, few) = policy_beam_search_with_nodes(&state, &capped, Some(&PointPolicy));
+        assert!(few.generated < all.generated);
+        // A cap of one with the policy is the greedy agent, which takes the first best move.
+        let greedy = BeamConfig {
+            width: 1,
+            expansion_cap: Some(1),
+            ..config
+        };
+        for seed in 0..10 {
+            let state = MazeState::with_seed(seed);
+            let action = policy_beam_search_action(&state, &greedy, &PointPolicy);
+            assert_eq!(action, greedy_action(&state));
+        }
+    }
+
+    #[test]
+    fn test_policy_beam_search_score() {
+        let config = BeamConfig {
+            expansion_cap: Some(2),
+            ..BeamConfig::default()
+        };
+        let mean =
+            mean_score(|state: &MazeState| policy_beam_search_action(state, &config, &PointPolicy));
+        println!("Policy Beam Search (cap 2) Score:\t{}", mean);
+        assert!(625.0 <= mean);
+    }
+
+    #[test]
+    fn test_tie_break_score() {
+        // A few shallow games: `test_tie_break` covers the orderings themselves.
+        fn mean(agent: impl Fn(&MazeState) -> Outcome) -> f64 {
+            let results = GameRunner::new(MazeState::with_seed, agent).run_episodes(5);
+            Summary::from_results(&results).mean
+        }
+        let greedy = mean(greedy_action::<MazeState>);
+        for tie_break in [
+            TieBreak::Arbitrary,
+            TieBreak::Random(0),
+            TieBreak::NearestPoint,
+        ] {
+            let config = BeamConfig {
+                depth: 5,
+                tie_break,
+                ..BeamConfig::default()
+            };
+            let score = mean(|state: &MazeState| beam_search_with_config(state, &config));
+            println!("Beam Search ({:?}) Score:\t{}", tie_break, score);
+            assert!(greedy <= score);
+        }
+    }
+
+    // Wall-clock limited, so the score depends on the machine: run by hand. The node-limited
+    // tests below check the same searches deterministically.
+    #[test]
+    #[ignore]
+    fn test_beam_search_with_time_threshold_score() {
+        let results = GameRunner::new(MazeState::with_seed, |state: &MazeState| {
+            beam_search_with_time_threshold_action(state, 5, 10)
+        })
+        .run_batch(GAME_NUMBER, THREAD_NUMBER, 0..);
+        let mean = Summary::from_results(&results).mean;
+        println!("Beam Search 10ms Score:\t{}", mean);
+        assert!(450.0 <= mean);
     }
 
     #[test]
     fn test_chokudai_search_score() {
+        let mean =
+            mean_score(|state: &MazeState| chokudai_search_action(state, 1, END_TURN as usize, 2));
+        println!("Chokudai Search Score:\t{}", mean);
+        assert!(630.0 <= mean);
+    }
+
+    #[test]
+    fn test_node_limited_beam_search_score() {
+        let mean = mean_score(|state: &MazeState| node_limited_beam_search_action(state, 5, 300));
+        println!("Beam Search 300 Nodes Score:\t{}", mean);
+        assert!(600.0 <= mean);
+    }
+
+    #[test]
+    fn test_node_limited_chokudai_search_score() {
+        let mean = mean_score(|state: &MazeState| {
+            node_limited_chokudai_search_action(state, 1, END_TURN as usize, 300)
+        });
+        println!("Chokudai Search 300 Nodes Score:\t{}", mean);
+        assert!(600.0 <= mean);
+    }
+
+    #[test]
+    #[ignore]
+    fn test_chokudai_search_1ms_score() {
+        let results = GameRunner::new(MazeState::with_seed, |state: &MazeState| {
+            chokudai_search_with_time_threshold_action(state, 5, END_TURN as usize, 1)
+        })
+        .run_batch(GAME_NUMBER, THREAD_NUMBER, 0..);
+        let mean = Summary::from_results(&results).mean;
+        println!("Beam Search 1ms Score:\t{}", mean);
+        assert!(450.0 <= mean);
+    }
+
+    #[test]
+    #[ignore]
+    fn test_dedup_chokudai_search_1ms_score() {
+        let results = GameRunner::new(MazeState::with_seed, |state: &MazeState| {
+            dedup_chokudai_search_with_time_threshold_action(state, 5, END_TURN as usize, 1)
+        })
+        .run_batch(GAME_NUMBER, THREAD_NUMBER, 0..);
+        println!(
+            "Dedup Chokudai Search 1ms Score:\t{}",
+            Summary::from_results(&results).mean
+        )
+    }
+
+    #[test]
+    #[ignore]
+    fn test_anytime_astar_10ms_score() {
+        let results = GameRunner::new(MazeState::with_seed, |state: &MazeState| {
+            anytime_astar_action(state, 4.0, 10, 10)
+        })
+        .run_batch(GAME_NUMBER, THREAD_NUMBER, 0..);
+        println!(
+            "Anytime A* 10ms Score:\t{}",
+            Summary::from_results(&results).mean
+        )
+    }
+
+    #[test]
+    #[ignore]
+    fn test_lds_score() {
+        let results = GameRunner::new(MazeState::with_seed, |state: &MazeState| {
+            lds_action(state, 8, 2)
+        })
+        .run_batch(GAME_NUMBER, THREAD_NUMBER, 0..);
+        println!("LDS Score:\t{}", Summary::from_results(&results).mean)
+    }
+
+    #[test]
+    fn test_mc_evaluate() {
+        let state = MazeState::with_seed(0);
+        let mut rng = StdRng::seed_from_u64(0);
+        let estimate = mc_evaluate_with(&state, 50, &mut rng);
+        assert!(0.0 < estimate.mean && estimate.low() < estimate.high());
+        let greedy = GameRunner::new(MazeState::with_seed, greedy_action).run_episodes(20);
+        let random = GameRunner::new(MazeState::with_seed, seeded_random_agent(0)).run_episodes(20);
+        let scores = |results: &[GameResult]| results.iter().map(|r| r.score).collect::<Vec<_>>();
+        let greedy = Estimate::from_scores(&scores(&greedy));
+        let random = Estimate::from_scores(&scores(&random));
+        println!("Greedy:\t{}\nRandom:\t{}", greedy, random);
+        assert!(greedy.clearly_above(&random));
+    }
+
+    #[test]
+    fn test_compare_greedy_random() {
+        let comparison = compare_agents(
+            MazeState::with_seed,
+            greedy_action,
+            random_action,
+            0..GAME_NUMBER as u64,
+        );
+        println!("Greedy vs Random:\t{}", comparison);
+        assert!(comparison.mean_difference > 0.0);
+    }
+
+    #[test]
+    fn test_tune_beam_width() {
+        let trials = grid_search(&[1, 2, 3], 5, |&beam_width, seed| {
+            GameRunner::new(MazeState::with_seed, |state: &MazeState| {
+                beam_search_action(state, beam_width, 10)
+            })
+            .run(seed)
+            .score as f64
+        });
+        println!("Best Beam Width:\t{}", trials[0]);
+    }
+
+    #[test]
+    fn test_auto_beam_search_score() {
         let mut mean = 0.0;
-        for _ in 0..GAME_NUMBER {
-            let mut state = MazeState::new();
+        for seed in 0..10 {
+            let mut state = MazeState::with_seed(seed);
+            let mut searcher = AutoBeamSearcher::new(1, END_TURN);
             while !state.is_done() {
-                state.advance(chokudai_search_action(&state, 1, END_TURN as usize, 2).unwrap())
+                state.advance(searcher.search(&state).unwrap());
+                assert!(1 <= searcher.beam_width());
             }
+            assert_eq!(searcher.search(&state), Outcome::NoAction);
             mean += state.game_score as f64;
         }
-        mean /= GAME_NUMBER as f64;
-        println!("Chokudai Search Score:\t{}", mean)
+        println!("Auto Beam Search 1ms Score:\t{}", mean / 10.0)
     }
 
     #[test]
-    fn test_chokudai_search_1ms_score() {
-        let mut mean = 0.0;
-        for _ in 0..GAME_NUMBER {
-            let mut state = MazeState::new();
-            while !state.is_done() {
-                state.advance(
-                    chokudai_search_with_time_threshold_action(&state, 5, END_TURN as usize, 1)
-                        .unwrap(),
-                )
+    fn test_learn_greedy_weights() {
+        let objective = |weights: &[f64]| {
+            let evaluator = WeightedEvaluator::new(weights.to_vec());
+            let results = GameRunner::new(MazeState::with_seed, |state: &MazeState| {
+                weighted_greedy_action(state, &evaluator)
+            })
+            .run_episodes(3);
+            Summary::from_results(&results).mean
+        };
+        let initial = [1.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0];
+        let baseline = objective(&initial);
+        let mut rng = StdRng::seed_from_u64(0);
+        let (weights, score) = anneal_weights(&mut rng, &initial, 30, 10.0, 1.0, 0.5, objective);
+        println!("Learned Weights:\t{:?}\t{} -> {}", weights, baseline, score);
+        assert!(baseline <= score);
+    }
+
+    #[test]
+    fn test_tune_greedy_weights() {
+        let initial = [1.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0];
+        let mut rng = StdRng::seed_from_u64(0);
+        let (evaluator, _) = tune_greedy_weights(&mut rng, &initial, 0.5, 20, 10, 4);
+        // Tuned on the seeds 0..10, judged on seeds it never saw.
+        let held_out = |evaluator: &WeightedEvaluator| {
+            let results = GameRunner::new(MazeState::with_seed, |state: &MazeState| {
+                weighted_greedy_action(state, evaluator)
+            })
+            .run_batch(30, 4, 100..);
+            Summary::from_results(&results).mean
+        };
+        let baseline = held_out(&WeightedEvaluator::new(initial.to_vec()));
+        let score = held_out(&evaluator);
+        println!(
+            "Tuned Weights:\t{:?}\t{} -> {}",
+            evaluator.weights, baseline, score
+        );
+        assert!(baseline < score);
+    }
+
+    #[test]
+    fn test_turn_nodes() {
+        for front in [0, 50, 100] {
+            let total: usize = (0..END_TURN).map(|turn| turn_nodes(turn, front)).sum();
+            assert!(
+                total.abs_diff(TUNED_GAME_NODES) <= END_TURN as usize,
+                "{}",
+                total
+            );
+        }
+        assert_eq!(turn_nodes(0, 0), turn_nodes(END_TURN - 1, 0));
+        assert!(turn_nodes(END_TURN - 1, 100) < turn_nodes(0, 100));
+    }
+
+    #[test]
+    fn test_tune_beam_search() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let budget = Budget {
+            points: 8,
+            game_number: 2,
+            thread_number: 2,
+        };
+        let trials = tune_beam_search(&mut rng, budget);
+        assert_eq!(trials.len(), 8);
+        let best = &trials[0];
+        println!("Tuned Beam Search:\t{}", best);
+        let greedy = GameRunner::new(MazeState::with_seed, greedy_action).run_episodes(2);
+        assert!(Summary::from_results(&greedy).mean <= best.score);
+    }
+
+    #[test]
+    fn test_replanning_beam_search() {
+        let state = MazeState::with_seed(0);
+        let mut searcher = ReplanningBeamSearcher::new(&state, 5, 10);
+        searcher.search();
+        let plan = searcher.plan().to_vec();
+        assert_eq!(plan.len(), 10);
+        // A wall on the second cell of the best line drops it, and every line through that
+        // cell, but not the others.
+        let mut second = state;
+        second.advance(plan[0]);
+        second.advance(plan[1]);
+        let wall = CellChange::Wall {
+            y: second.character.y,
+            x: second.character.x,
+        };
+        let survivors = searcher.notify(&[wall]).unwrap();
+        assert!(survivors < 5);
+        assert_ne!(searcher.plan(), &plan[..]);
+        let mut changed = state;
+        changed.apply_change(wall).unwrap();
+        let mut replayed = changed;
+        for &action in searcher.plan() {
+            assert!(replayed.legal_actions().contains(&action));
+            replayed.advance(action);
+        }
+
+        // A game where a random cell changes every turn.
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut state = MazeState::with_seed(0);
+        let mut searcher = ReplanningBeamSearcher::new(&state, 5, 10);
+        while !state.is_done() {
+            let action = searcher.search().unwrap();
+            assert!(state.legal_actions().contains(&action));
+            state.advance(action);
+            searcher.advance(action);
+            let (y, x) = (rng.gen_range(0..HEIGHT), rng.gen_range(0..WIDTH));
+            if (y, x) == (state.character.y, state.character.x) {
+                continue;
             }
-            mean += state.game_score as f64;
+            let change = if rng.gen_bool(0.2) {
+                CellChange::Wall { y, x }
+            } else {
+                CellChange::Point {
+                    y,
+                    x,
+                    value: rng.gen_range(0..10),
+                }
+            };
+            state.apply_change(change).unwrap();
+            searcher.notify(&[change]).unwrap();
         }
-        mean /= GAME_NUMBER as f64;
-        println!("Beam Search 1ms Score:\t{}", mean)
+        println!("Replanning Beam Search Score:\t{}", state.game_score);
+        assert!(500 < state.game_score);
+    }
+
+    #[test]
+    fn test_endgame_solver_score() {
+        let greedy = GameRunner::new(MazeState::with_seed, greedy_action).run_episodes(10);
+        let solved = GameRunner::new(MazeState::with_seed, with_endgame_solver(greedy_action, 6))
+            .run_episodes(10);
+        for (greedy, solved) in greedy.iter().zip(solved.iter()) {
+            assert!(greedy.score <= solved.score);
+        }
+        println!(
+            "Greedy with Endgame Solver Score:\t{}",
+            Summary::from_results(&solved).mean
+        )
+    }
+
+    #[test]
+    fn test_done_state_has_no_action() {
+        let mut state = MazeState::new();
+        while !state.is_done() {
+            state.advance(greedy_action(&state).unwrap())
+        }
+        assert_eq!(random_action(&state), Outcome::NoAction);
+        assert_eq!(greedy_action(&state), Outcome::NoAction);
+        assert_eq!(beam_search_action(&state, 2, END_TURN), Outcome::NoAction);
+        assert_eq!(
+            chokudai_search_action(&state, 1, END_TURN as usize, 2),
+            Outcome::NoAction
+        );
+    }
+
+    #[test]
+    fn test_beam_search_falls_back_to_greedy() {
+        let state = MazeState::new();
+        assert_ne!(
+            beam_search_with_time_threshold_action(&state, 5, 0),
+            Outcome::NoAction
+        );
+    }
+
+    #[test]
+    fn test_time_manager_budget() {
+        let mut state = MazeState::new();
+        let clock = MockClock::new();
+        let mut time_manager = TimeManager::with_clock(2000, END_TURN, &clock);
+        let first = time_manager.turn_budget();
+        assert!(2000 / END_TURN < first && first <= 2000);
+        for _ in 0..END_TURN / 2 {
+            assert!(time_manager.turn_budget() <= time_manager.remaining_time());
+            state.advance(random_action(&state).unwrap());
+            time_manager.record(&state);
+            clock.advance(Duration::from_millis(15));
+        }
+        assert_eq!(time_manager.remaining_time(), 2000 - 15 * (END_TURN / 2));
+        assert!(time_manager.remaining_time() / (END_TURN / 2) <= time_manager.turn_budget());
+    }
+
+    #[test]
+    fn test_searches_with_mock_clock() {
+        // One millisecond per reading: the searches stop after the same work on every run.
+        for seed in 0..5 {
+            let state = MazeState::with_seed(seed);
+            let beam = |_| {
+                let clock = MockClock::ticking(Duration::from_millis(1));
+                beam_search_with_clock(&state, 5, 30, &clock)
+            };
+            assert_eq!(beam(0), beam(1));
+            let chokudai = |_| {
+                let clock = MockClock::ticking(Duration::from_millis(1));
+                chokudai_search_with_clock(&state, 1, END_TURN as usize, 3, &clock)
+            };
+            assert_eq!(chokudai(0), chokudai(1));
+        }
+        let clock = MockClock::new();
+        let time_keeper = TimeKeeper::with_clock(10, &clock);
+        assert!(!time_keeper.is_time_over());
+        clock.advance(Duration::from_millis(10));
+        assert!(time_keeper.is_time_over());
     }
 
     #[test]
     #[ignore]
-    fn test_chokudai_search_10ms_score() {
+    fn test_chokudai_search_time_manager_score() {
         let mut mean = 0.0;
         for _ in 0..GAME_NUMBER {
             let mut state = MazeState::new();
+            let mut time_manager = TimeManager::new(1000, END_TURN);
             while !state.is_done() {
+                let time_threshold = time_manager.turn_budget();
                 state.advance(
-                    chokudai_search_with_time_threshold_action(&state, 5, END_TURN as usize, 10)
-                        .unwrap(),
-                )
+                    chokudai_search_with_time_threshold_action(
+                        &state,
+                        5,
+                        END_TURN as usize,
+                        time_threshold,
+                    )
+                    .unwrap(),
+                );
+                time_manager.record(&state);
             }
             mean += state.game_score as f64;
         }
         mean /= GAME_NUMBER as f64;
-        println!("Beam Search 10ms Score:\t{}", mean)
+        println!("Chokudai Search TimeManager Score:\t{}", mean)
+    }
+
+    #[test]
+    #[ignore]
+    fn test_chokudai_search_10ms_score() {
+        let results = GameRunner::new(MazeState::with_seed, |state: &MazeState| {
+            chokudai_search_with_time_threshold_action(state, 5, END_TURN as usize, 10)
+        })
+        .run_batch(GAME_NUMBER, THREAD_NUMBER, 0..);
+        let mean = Summary::from_results(&results).mean;
+        println!("Beam Search 10ms Score:\t{}", mean);
+        // Time-limited, so the floor leaves room for slow machines and debug builds.
+        assert!(450.0 <= mean);
     }
 }
@@ -1,8 +1,14 @@
 #![allow(unused)]
 
-use rand::Rng;
+use rand::distributions::{Distribution, WeightedIndex};
+use rand::{rngs::StdRng, Rng, SeedableRng};
 
-type ScoreType = i64;
+use std::{io::Write, ops::ControlFlow};
+
+use crate::game::{add_score, Crossover, ImprovementHook, Progress, SearchLimits};
+use crate::maze_generator::MazeGenerator;
+
+type ScoreType = crate::game::ScoreType;
 
 const HEIGHT: usize = 5;
 const WIDTH: usize = 5;
@@ -28,45 +34,139 @@ impl Coord {
 }
 
 #[derive(Debug, Clone, Copy)]
-struct AutoMoveMazeState {
+pub struct AutoMoveMazeState {
     pub game_score: ScoreType,
     pub evaluated_score: ScoreType,
     points: [[ScoreType; WIDTH]; HEIGHT],
+    walls: [[bool; WIDTH]; HEIGHT],
     turn: usize,
     characters: [Coord; CHARACTER_N],
 }
 
-impl AutoMoveMazeState {
-    fn new() -> AutoMoveMazeState {
-        let mut rng = rand::thread_rng();
-        let mut points = [[0; WIDTH]; HEIGHT];
-        for row in points.iter_mut() {
-            for point in row.iter_mut() {
-                *point = rng.gen_range(0..10);
+// Every character collects at most once per turn, plus its starting cell.
+const COLLECT_CAPACITY: usize = CHARACTER_N * (END_TURN + 1);
+
+#[derive(Debug, Clone, Copy)]
+struct SimulationReport {
+    pub score: ScoreType,
+    // First simulated turn; earlier entries of the trajectory stay empty.
+    pub turns: usize,
+    // Score and positions after each turn.
+    pub scores: [ScoreType; END_TURN],
+    pub positions: [[Coord; CHARACTER_N]; END_TURN],
+    // Points collected by each character.
+    pub gains: [ScoreType; CHARACTER_N],
+}
+
+impl std::fmt::Display for SimulationReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for turn in self.turns..END_TURN {
+            write!(
+                f,
+                "turn:\t{}\tscore:\t{}\tcharacters:",
+                turn + 1,
+                self.scores[turn]
+            )?;
+            for character in self.positions[turn].iter() {
+                write!(f, " ({}, {})", character.y, character.x)?;
             }
+            writeln!(f)?;
         }
+        write!(f, "score:\t{}", self.score)
+    }
+}
+
+impl AutoMoveMazeState {
+    fn new() -> AutoMoveMazeState {
+        Self::generate(&mut rand::thread_rng())
+    }
+
+    pub fn with_seed(seed: u64) -> AutoMoveMazeState {
+        Self::generate(&mut StdRng::seed_from_u64(seed))
+    }
+
+    pub fn with_generator(seed: u64, generator: &MazeGenerator) -> AutoMoveMazeState {
+        Self::generate_with(&mut StdRng::seed_from_u64(seed), generator)
+    }
+
+    fn generate(rng: &mut impl Rng) -> AutoMoveMazeState {
+        Self::generate_with(rng, &MazeGenerator::default())
+    }
+
+    fn generate_with(rng: &mut impl Rng, generator: &MazeGenerator) -> AutoMoveMazeState {
+        let maze = generator.generate(rng, None);
         AutoMoveMazeState {
             game_score: 0,
             evaluated_score: 0,
-            points,
+            points: maze.points,
+            walls: maze.walls,
             turn: 0,
             characters: [Coord::new(); CHARACTER_N],
         }
     }
 
-    fn init_characters(&mut self) {
-        let mut rng = rand::thread_rng();
-        for character in self.characters.iter_mut() {
-            character.y = rng.gen_range(0..HEIGHT);
-            character.x = rng.gen_range(0..WIDTH);
+    // None when every cell is a wall. Draws until it hits an open cell, as before, but gives up
+    // after a board's worth of draws and picks from the open cells instead.
+    fn random_open_cell(&self, rng: &mut impl Rng) -> Option<Coord> {
+        for _ in 0..HEIGHT * WIDTH {
+            let y = rng.gen_range(0..HEIGHT);
+            let x = rng.gen_range(0..WIDTH);
+            if !self.walls[y][x] {
+                return Some(Coord::from_point(x, y));
+            }
+        }
+        let open: Vec<Coord> = (0..HEIGHT)
+            .flat_map(|y| (0..WIDTH).map(move |x| Coord::from_point(x, y)))
+            .filter(|c| !self.walls[c.y][c.x])
+            .collect();
+        (!open.is_empty()).then(|| open[rng.gen_range(0..open.len())])
+    }
+
+    // Characters stay where they are on a board without open cells.
+    fn move_to_open_cell(&mut self, id: usize, rng: &mut impl Rng) {
+        if let Some(cell) = self.random_open_cell(rng) {
+            self.characters[id] = cell;
+        }
+    }
+
+    fn init_characters(&mut self, rng: &mut impl Rng) {
+        for id in 0..CHARACTER_N {
+            self.move_to_open_cell(id, rng);
         }
     }
 
-    fn transition(&mut self) {
-        let mut rng = rand::thread_rng();
-        let character = &mut self.characters[rng.gen_range(0..CHARACTER_N)];
-        character.y = rng.gen_range(0..HEIGHT);
-        character.x = rng.gen_range(0..WIDTH);
+    fn transition(&mut self, rng: &mut impl Rng) {
+        let id = rng.gen_range(0..CHARACTER_N);
+        self.move_to_open_cell(id, rng);
+    }
+
+    fn neighbor(&mut self, neighborhood: Neighborhood, rng: &mut impl Rng) {
+        match neighborhood {
+            Neighborhood::Teleport => self.transition(rng),
+            // Only the move order changes, which decides who gets a contested point.
+            Neighborhood::Swap => {
+                let i = rng.gen_range(0..CHARACTER_N);
+                let j = rng.gen_range(0..CHARACTER_N);
+                self.characters.swap(i, j);
+            }
+            Neighborhood::Nudge => {
+                let dx = [1, -1, 0, 0];
+                let dy = [0, 0, 1, -1];
+                let id = rng.gen_range(0..CHARACTER_N);
+                let character = self.characters[id];
+                let d = rng.gen_range(0..4);
+                let ty = character.y.checked_add_signed(dy[d]).unwrap_or(HEIGHT);
+                let tx = character.x.checked_add_signed(dx[d]).unwrap_or(WIDTH);
+                if ty < HEIGHT && tx < WIDTH && !self.walls[ty][tx] {
+                    self.set_character(id, ty, tx);
+                }
+            }
+            Neighborhood::ReplaceWorst => {
+                let gains = self.simulate().gains;
+                let worst = (0..CHARACTER_N).min_by_key(|&id| gains[id]).unwrap_or(0);
+                self.move_to_open_cell(worst, rng);
+            }
+        }
     }
 
     fn set_character(&mut self, character_id: usize, y: usize, x: usize) {
@@ -79,45 +179,103 @@ impl AutoMoveMazeState {
     }
 
     fn get_score(&self, is_print: bool) -> ScoreType {
-        let mut state = *self;
-        for character in state.characters.iter() {
-            let point = &mut state.points[character.y][character.x];
-            *point = 0;
+        let report = self.simulate();
+        if is_print {
+            println!("{}", report);
         }
-        while !state.is_done() {
-            state.advance();
-            if is_print {
-                println!("{}", state);
+        report.score
+    }
+
+    // Plays the remaining turns without touching `self` or the heap: collected cells go into a
+    // fixed size log that masks `points` instead of a copy of the whole state.
+    fn simulate(&self) -> SimulationReport {
+        self.simulate_active([true; CHARACTER_N])
+    }
+
+    // Same as `simulate` with only the `active` characters on the board.
+    fn simulate_active(&self, active: [bool; CHARACTER_N]) -> SimulationReport {
+        let mut log = [Coord::new(); COLLECT_CAPACITY];
+        let mut log_len = 0;
+        let is_collected =
+            |log: &[Coord], y: usize, x: usize| log.iter().any(|c| c.y == y && c.x == x);
+        // Points under the initial positions are not scored.
+        for (id, &character) in self.characters.iter().enumerate() {
+            if active[id] {
+                log[log_len] = character;
+                log_len += 1;
             }
         }
-        state.game_score
+        let mut report = SimulationReport {
+            score: self.game_score,
+            turns: self.turn,
+            scores: [0; END_TURN],
+            positions: [[Coord::new(); CHARACTER_N]; END_TURN],
+            gains: [0; CHARACTER_N],
+        };
+        let mut characters = self.characters;
+        for turn in self.turn..END_TURN {
+            for character in characters
+                .iter_mut()
+                .zip(active)
+                .filter_map(|(c, a)| a.then_some(c))
+            {
+                let collected = &log[..log_len];
+                *character = self.best_move(*character, |y, x| {
+                    if is_collected(collected, y, x) {
+                        0
+                    } else {
+                        self.points[y][x]
+                    }
+                });
+            }
+            for (id, &character) in characters.iter().enumerate() {
+                if active[id] && !is_collected(&log[..log_len], character.y, character.x) {
+                    let point = self.points[character.y][character.x];
+                    report.score = add_score(report.score, point);
+                    report.gains[id] = add_score(report.gains[id], point);
+                    log[log_len] = character;
+                    log_len += 1;
+                }
+            }
+            report.scores[turn] = report.score;
+            report.positions[turn] = characters;
+        }
+        report
     }
 
-    fn move_player(&mut self, character_id: usize) {
+    // Neighbour with the most points by `point`; stays when walled in on every side.
+    fn best_move(&self, character: Coord, point: impl Fn(usize, usize) -> ScoreType) -> Coord {
         let dx = [1, -1, 0, 0];
         let dy = [0, 0, 1, -1];
-        let character = &mut self.characters[character_id];
         let mut best_point = -INF;
-        let mut best_action_index = 0;
+        let mut best = character;
         for action in 0..4 {
             let ty = character.y.checked_add_signed(dy[action]).unwrap_or(HEIGHT);
             let tx = character.x.checked_add_signed(dx[action]).unwrap_or(WIDTH);
-            if ty < HEIGHT && tx < WIDTH {
-                let point = self.points[ty][tx];
+            if ty < HEIGHT && tx < WIDTH && !self.walls[ty][tx] {
+                let point = point(ty, tx);
                 if best_point < point {
                     best_point = point;
-                    best_action_index = action;
+                    best = Coord::from_point(tx, ty);
                 }
             }
         }
-        character.y = character
-            .y
-            .checked_add_signed(dy[best_action_index])
-            .unwrap();
-        character.x = character
-            .x
-            .checked_add_signed(dx[best_action_index])
-            .unwrap();
+        best
+    }
+
+    fn move_player(&mut self, character_id: usize) {
+        let points = &self.points;
+        self.characters[character_id] =
+            self.best_move(self.characters[character_id], |y, x| points[y][x]);
+    }
+
+    // Pure version of `advance`; the auto-move rule involves no randomness.
+    fn next_state(&self) -> AutoMoveMazeState {
+        let mut next_state = *self;
+        if !next_state.is_done() {
+            next_state.advance();
+        }
+        next_state
     }
 
     pub fn advance(&mut self) {
@@ -126,7 +284,7 @@ impl AutoMoveMazeState {
         }
         for character in self.characters.iter() {
             let point = &mut self.points[character.y][character.x];
-            self.game_score += *point;
+            self.game_score = add_score(self.game_score, *point);
             *point = 0;
         }
         self.turn += 1;
@@ -148,6 +306,8 @@ impl std::fmt::Display for AutoMoveMazeState {
                 }
                 let ch = if is_char {
                     '@'
+                } else if self.walls[h][w] {
+                    '#'
                 } else if self.points[h][w] > 0 {
                     (self.points[h][w] as u8 + b'0') as char
                 } else {
@@ -161,52 +321,994 @@ impl std::fmt::Display for AutoMoveMazeState {
     }
 }
 
-fn random_action(state: &mut AutoMoveMazeState) -> AutoMoveMazeState {
-    let mut rng = rand::thread_rng();
+fn random_action(state: &mut AutoMoveMazeState, rng: &mut impl Rng) -> AutoMoveMazeState {
     for id in 0..CHARACTER_N {
-        let y = rng.gen_range(0..HEIGHT);
-        let x = rng.gen_range(0..WIDTH);
-        state.set_character(id, y, x);
+        state.move_to_open_cell(id, rng);
     }
     *state
 }
 
-fn hill_climb(state: &AutoMoveMazeState, number: usize) -> AutoMoveMazeState {
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Neighborhood {
+    // Moves one character to a random cell, the original `transition`.
+    Teleport,
+    Swap,
+    // Moves one character to an adjacent cell.
+    Nudge,
+    // Teleports the character that collects the least.
+    ReplaceWorst,
+}
+
+const NEIGHBORHOODS: [Neighborhood; 4] = [
+    Neighborhood::Teleport,
+    Neighborhood::Swap,
+    Neighborhood::Nudge,
+    Neighborhood::ReplaceWorst,
+];
+
+// Adaptive pursuit: each operator keeps a running success rate, and the best one is chosen
+// with probability MAX_PROBABILITY while the others keep at least MIN_PROBABILITY.
+pub struct OperatorSelector {
+    quality: [f64; NEIGHBORHOODS.len()],
+    probability: [f64; NEIGHBORHOODS.len()],
+    uses: [usize; NEIGHBORHOODS.len()],
+}
+
+impl OperatorSelector {
+    const MIN_PROBABILITY: f64 = 0.05;
+    const LEARNING_RATE: f64 = 0.1;
+
+    fn new() -> OperatorSelector {
+        let n = NEIGHBORHOODS.len() as f64;
+        OperatorSelector {
+            quality: [0.5; NEIGHBORHOODS.len()],
+            probability: [1.0 / n; NEIGHBORHOODS.len()],
+            uses: [0; NEIGHBORHOODS.len()],
+        }
+    }
+
+    fn select(&mut self, rng: &mut impl Rng) -> usize {
+        let mut r = rng.gen_range(0.0..1.0);
+        let mut index = NEIGHBORHOODS.len() - 1;
+        for (i, p) in self.probability.iter().enumerate() {
+            if r < *p {
+                index = i;
+                break;
+            }
+            r -= p;
+        }
+        self.uses[index] += 1;
+        index
+    }
+
+    fn update(&mut self, index: usize, improved: bool) {
+        let reward = if improved { 1.0 } else { 0.0 };
+        self.quality[index] += Self::LEARNING_RATE * (reward - self.quality[index]);
+        let max_probability = 1.0 - (NEIGHBORHOODS.len() - 1) as f64 * Self::MIN_PROBABILITY;
+        let best = (0..NEIGHBORHOODS.len())
+            .max_by(|&a, &b| self.quality[a].total_cmp(&self.quality[b]))
+            .unwrap_or(0);
+        for (i, p) in self.probability.iter_mut().enumerate() {
+            let target = if i == best {
+                max_probability
+            } else {
+                Self::MIN_PROBABILITY
+            };
+            *p += Self::LEARNING_RATE * (target - *p);
+        }
+    }
+}
+
+// Hill climbing that picks its neighborhood with an `OperatorSelector`.
+fn adaptive_hill_climb(
+    state: &AutoMoveMazeState,
+    number: usize,
+    rng: &mut impl Rng,
+) -> (AutoMoveMazeState, OperatorSelector) {
+    let mut selector = OperatorSelector::new();
     let mut now_state = *state;
-    now_state.init_characters();
+    now_state.init_characters(rng);
     let mut best_score = now_state.get_score(false);
     for _ in 0..number {
+        let index = selector.select(rng);
         let mut next_state = now_state;
-        next_state.transition();
+        next_state.neighbor(NEIGHBORHOODS[index], rng);
         let next_score = next_state.get_score(false);
+        selector.update(index, best_score < next_score);
+        // Equal moves are taken too, to drift across plateaus.
+        if best_score <= next_score {
+            best_score = next_score;
+            now_state = next_state;
+        }
+    }
+    (now_state, selector)
+}
+
+pub fn hill_climb(
+    state: &AutoMoveMazeState,
+    number: usize,
+    rng: &mut impl Rng,
+) -> AutoMoveMazeState {
+    local_search(state, number, &mut Improving, rng)
+}
+
+pub fn hill_climb_with_hook(
+    state: &AutoMoveMazeState,
+    number: usize,
+    on_improvement: &mut ImprovementHook<AutoMoveMazeState>,
+    rng: &mut impl Rng,
+) -> AutoMoveMazeState {
+    local_search_with_hook(state, number, &mut Improving, on_improvement, rng)
+}
+
+// Restrictions on the placement; characters never start on a forbidden cell and stay at least
+// `min_distance` (Manhattan) apart.
+#[derive(Debug, Clone, Copy)]
+struct Constraints {
+    forbidden: [[bool; WIDTH]; HEIGHT],
+    min_distance: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ConstraintHandling {
+    // Every violation costs this many points.
+    Penalty(ScoreType),
+    // Violating characters are moved to a random feasible cell before scoring.
+    Repair,
+}
+
+impl Constraints {
+    fn is_feasible_cell(&self, state: &AutoMoveMazeState, id: usize, y: usize, x: usize) -> bool {
+        !state.walls[y][x]
+            && !self.forbidden[y][x]
+            && state.characters.iter().enumerate().all(|(other, c)| {
+                other == id || self.min_distance <= c.y.abs_diff(y) + c.x.abs_diff(x)
+            })
+    }
+
+    // Characters on forbidden cells plus pairs that are too close.
+    fn violations(&self, state: &AutoMoveMazeState) -> usize {
+        let characters = &state.characters;
+        let forbidden = characters
+            .iter()
+            .filter(|c| self.forbidden[c.y][c.x])
+            .count();
+        let close = (0..CHARACTER_N)
+            .flat_map(|i| (i + 1..CHARACTER_N).map(move |j| (i, j)))
+            .filter(|&(i, j)| {
+                let (a, b) = (characters[i], characters[j]);
+                a.y.abs_diff(b.y) + a.x.abs_diff(b.x) < self.min_distance
+            })
+            .count();
+        forbidden + close
+    }
+
+    fn penalized_score(&self, state: &AutoMoveMazeState, penalty: ScoreType) -> ScoreType {
+        state.get_score(false) - penalty * self.violations(state) as ScoreType
+    }
+
+    // Moves each violating character to a random feasible cell; characters with no such cell
+    // stay put. Returns whether the placement is feasible afterwards.
+    fn repair(&self, state: &mut AutoMoveMazeState, rng: &mut impl Rng) -> bool {
+        for id in 0..CHARACTER_N {
+            let c = state.characters[id];
+            if self.is_feasible_cell(state, id, c.y, c.x) {
+                continue;
+            }
+            let cells: Vec<Coord> = (0..HEIGHT)
+                .flat_map(|y| (0..WIDTH).map(move |x| Coord::from_point(x, y)))
+                .filter(|c| self.is_feasible_cell(state, id, c.y, c.x))
+                .collect();
+            if !cells.is_empty() {
+                state.characters[id] = cells[rng.gen_range(0..cells.len())];
+            }
+        }
+        self.violations(state) == 0
+    }
+}
+
+// Hill climbing under `constraints`; the result is repaired once more so a feasible placement
+// is returned whenever the search found one.
+fn constrained_hill_climb(
+    state: &AutoMoveMazeState,
+    number: usize,
+    constraints: &Constraints,
+    handling: ConstraintHandling,
+    rng: &mut impl Rng,
+) -> AutoMoveMazeState {
+    let score = |state: &mut AutoMoveMazeState, rng: &mut _| match handling {
+        ConstraintHandling::Penalty(penalty) => constraints.penalized_score(state, penalty),
+        ConstraintHandling::Repair => {
+            constraints.repair(state, rng);
+            constraints.penalized_score(state, INF)
+        }
+    };
+    let mut now_state = *state;
+    now_state.init_characters(rng);
+    let mut best_score = score(&mut now_state, rng);
+    for _ in 0..number {
+        let mut next_state = now_state;
+        next_state.transition(rng);
+        let next_score = score(&mut next_state, rng);
         if best_score < next_score {
             best_score = next_score;
             now_state = next_state;
         }
     }
+    if handling != ConstraintHandling::Repair {
+        let mut repaired = now_state;
+        if constraints.violations(&now_state) != 0 && constraints.repair(&mut repaired, rng) {
+            now_state = repaired;
+        }
+    }
     now_state
 }
 
-fn simulated_annealing(
+// Decides whether local search moves from `now_score` to `next_score`. `progress` runs from 0
+// to 1 over the search.
+pub trait Acceptance {
+    fn accept(
+        &mut self,
+        now_score: ScoreType,
+        next_score: ScoreType,
+        progress: f64,
+        rng: &mut impl Rng,
+    ) -> bool;
+}
+
+// Hill climbing: only strict improvements.
+pub struct Improving;
+
+impl Acceptance for Improving {
+    fn accept(
+        &mut self,
+        now_score: ScoreType,
+        next_score: ScoreType,
+        _progress: f64,
+        _rng: &mut impl Rng,
+    ) -> bool {
+        now_score < next_score
+    }
+}
+
+// Simulated annealing with a linearly cooling temperature.
+pub struct Metropolis {
+    pub start_temp: f64,
+    pub end_temp: f64,
+}
+
+impl Metropolis {
+    fn temperature(&self, progress: f64) -> f64 {
+        self.start_temp + (self.end_temp - self.start_temp) * progress
+    }
+}
+
+impl Acceptance for Metropolis {
+    fn accept(
+        &mut self,
+        now_score: ScoreType,
+        next_score: ScoreType,
+        progress: f64,
+        rng: &mut impl Rng,
+    ) -> bool {
+        let probability = ((next_score - now_score) as f64 / self.temperature(progress)).exp();
+        now_score < next_score || probability > rng.gen_range(0.0..1.0)
+    }
+}
+
+// `Metropolis` that writes every `every`-th step to `writer` as a tab-separated line of step,
+// temperature, delta, acceptance probability and whether the move was taken, after a header.
+// Draws the same random numbers, so a seeded run takes the same moves with or without the log.
+// A failing writer only loses log lines.
+struct AnnealingLog<W: Write> {
+    metropolis: Metropolis,
+    writer: W,
+    every: usize,
+    step: usize,
+}
+
+impl<W: Write> AnnealingLog<W> {
+    fn new(metropolis: Metropolis, mut writer: W, every: usize) -> AnnealingLog<W> {
+        writeln!(writer, "step\ttemperature\tdelta\tprobability\taccepted").ok();
+        AnnealingLog {
+            metropolis,
+            writer,
+            every: every.max(1),
+            step: 0,
+        }
+    }
+}
+
+impl<W: Write> Acceptance for AnnealingLog<W> {
+    fn accept(
+        &mut self,
+        now_score: ScoreType,
+        next_score: ScoreType,
+        progress: f64,
+        rng: &mut impl Rng,
+    ) -> bool {
+        let accepted = self.metropolis.accept(now_score, next_score, progress, rng);
+        if self.step.is_multiple_of(self.every) {
+            let temperature = self.metropolis.temperature(progress);
+            let delta = next_score - now_score;
+            let probability = (delta as f64 / temperature).exp().min(1.0);
+            writeln!(
+                self.writer,
+                "{}\t{:.3}\t{}\t{:.4}\t{}",
+                self.step, temperature, delta, probability, accepted
+            )
+            .ok();
+        }
+        self.step += 1;
+        accepted
+    }
+}
+
+// Simulated annealing without temperatures to guess. For the first `warmup` of the search it
+// only climbs and records what the worsening moves would lose; it then starts at the temperature
+// that takes the mean loss with probability `start_acceptance`. After that the rate of taken
+// worsening moves is checked every `window` of them against a target falling linearly to
+// `end_acceptance`: the temperature drops by `cooling` when the rate is above the target and
+// rises by as much when it is below.
+struct AdaptiveAnnealing {
+    warmup: f64,
+    start_acceptance: f64,
+    end_acceptance: f64,
+    window: usize,
+    cooling: f64,
+    losses: Vec<ScoreType>,
+    temperature: Option<f64>,
+    tried: usize,
+    taken: usize,
+}
+
+impl AdaptiveAnnealing {
+    fn new() -> AdaptiveAnnealing {
+        AdaptiveAnnealing {
+            warmup: 0.05,
+            start_acceptance: 0.5,
+            end_acceptance: 0.01,
+            window: 50,
+            cooling: 0.9,
+            losses: vec![],
+            temperature: None,
+            tried: 0,
+            taken: 0,
+        }
+    }
+
+    // Only known once the warm-up is over.
+    fn calibrate(&mut self) -> f64 {
+        *self.temperature.get_or_insert_with(|| {
+            let mean = if self.losses.is_empty() {
+                1.0
+            } else {
+                self.losses.iter().sum::<ScoreType>() as f64 / self.losses.len() as f64
+            };
+            mean / -self.start_acceptance.ln()
+        })
+    }
+}
+
+impl Acceptance for AdaptiveAnnealing {
+    fn accept(
+        &mut self,
+        now_score: ScoreType,
+        next_score: ScoreType,
+        progress: f64,
+        rng: &mut impl Rng,
+    ) -> bool {
+        let loss = now_score - next_score;
+        if loss <= 0 {
+            return true;
+        }
+        if progress < self.warmup {
+            self.losses.push(loss);
+            return false;
+        }
+        let temperature = self.calibrate();
+        let accept = (-loss as f64 / temperature).exp() > rng.gen_range(0.0..1.0);
+        self.tried += 1;
+        self.taken += accept as usize;
+        if self.tried == self.window {
+            let rate = self.taken as f64 / self.tried as f64;
+            let target =
+                self.start_acceptance + (self.end_acceptance - self.start_acceptance) * progress;
+            let factor = if target < rate {
+                self.cooling
+            } else {
+                1.0 / self.cooling
+            };
+            self.temperature = Some(temperature * factor);
+            self.tried = 0;
+            self.taken = 0;
+        }
+        accept
+    }
+}
+
+fn adaptive_annealing(
+    state: &AutoMoveMazeState,
+    number: usize,
+    rng: &mut impl Rng,
+) -> AutoMoveMazeState {
+    local_search(state, number, &mut AdaptiveAnnealing::new(), rng)
+}
+
+// Takes any move that loses at most a linearly shrinking threshold.
+struct ThresholdAccepting {
+    start_threshold: f64,
+    end_threshold: f64,
+}
+
+impl Acceptance for ThresholdAccepting {
+    fn accept(
+        &mut self,
+        now_score: ScoreType,
+        next_score: ScoreType,
+        progress: f64,
+        _rng: &mut impl Rng,
+    ) -> bool {
+        let threshold =
+            self.start_threshold + (self.end_threshold - self.start_threshold) * progress;
+        now_score as f64 - threshold <= next_score as f64
+    }
+}
+
+// Takes any move above a water level that starts at the first score and rises by `rain` on
+// every call.
+struct GreatDeluge {
+    level: Option<f64>,
+    rain: f64,
+}
+
+impl Acceptance for GreatDeluge {
+    fn accept(
+        &mut self,
+        now_score: ScoreType,
+        next_score: ScoreType,
+        _progress: f64,
+        _rng: &mut impl Rng,
+    ) -> bool {
+        let level = self.level.get_or_insert(now_score as f64);
+        let accept = *level <= next_score as f64;
+        *level += self.rain;
+        accept
+    }
+}
+
+// Late acceptance hill climbing: compares with the score from `length` iterations ago.
+struct LateAcceptance {
+    history: Vec<ScoreType>,
+    length: usize,
+    iteration: usize,
+}
+
+impl LateAcceptance {
+    fn new(length: usize) -> LateAcceptance {
+        LateAcceptance {
+            history: vec![],
+            length: length.max(1),
+            iteration: 0,
+        }
+    }
+}
+
+impl Acceptance for LateAcceptance {
+    fn accept(
+        &mut self,
+        now_score: ScoreType,
+        next_score: ScoreType,
+        _progress: f64,
+        _rng: &mut impl Rng,
+    ) -> bool {
+        if self.history.is_empty() {
+            self.history = vec![now_score; self.length];
+        }
+        let slot = self.iteration % self.length;
+        let accept = self.history[slot] <= next_score || now_score <= next_score;
+        self.history[slot] = if accept { next_score } else { now_score };
+        self.iteration += 1;
+        accept
+    }
+}
+
+// A new best placement found by an anytime search.
+#[derive(Debug, Clone, Copy)]
+pub struct Incumbent {
+    pub state: AutoMoveMazeState,
+    pub score: ScoreType,
+    pub iteration: usize,
+}
+
+// The loop shared by every acceptance rule as an iterator over the incumbents: the random
+// start first, then every strict improvement. Ends after `number` iterations, or earlier at the
+// `max_nodes` neighbours or the `stop_at_score` of its limits; placements cost nothing, so
+// `budget` does not apply.
+pub struct LocalSearch<'a, A: Acceptance> {
+    acceptance: &'a mut A,
+    // Owned, seeded by the caller, so the search can move to another thread and replays the
+    // same way from the same seed.
+    rng: StdRng,
+    number: usize,
+    limits: SearchLimits,
+    iteration: usize,
+    now_state: AutoMoveMazeState,
+    now_score: ScoreType,
+    best_score: Option<ScoreType>,
+    progress: Progress,
+}
+
+impl<'a, A: Acceptance> LocalSearch<'a, A> {
+    // Starts from `state` with every character on a random open cell.
+    pub fn new(
+        state: &AutoMoveMazeState,
+        number: usize,
+        acceptance: &'a mut A,
+        mut rng: StdRng,
+    ) -> Self {
+        let mut now_state = *state;
+        now_state.init_characters(&mut rng);
+        let now_score = now_state.get_score(false);
+        LocalSearch {
+            acceptance,
+            rng,
+            number,
+            limits: SearchLimits::default(),
+            iteration: 0,
+            now_state,
+            now_score,
+            best_score: None,
+            progress: Progress::new("local search", number),
+        }
+    }
+
+    pub fn with_limits(mut self, limits: SearchLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    fn stopped(&self) -> bool {
+        self.limits.out_of_nodes(self.iteration)
+            || self
+                .best_score
+                .is_some_and(|score| self.limits.reached(score))
+    }
+}
+
+impl<A: Acceptance> Iterator for LocalSearch<'_, A> {
+    type Item = Incumbent;
+
+    fn next(&mut self) -> Option<Incumbent> {
+        search_span!("local_search", number = self.number);
+        if self.best_score.is_none() {
+            self.best_score = Some(self.now_score);
+            self.progress.set_best(self.now_score);
+            return Some(Incumbent {
+                state: self.now_state,
+                score: self.now_score,
+                iteration: 0,
+            });
+        }
+        while self.iteration < self.number && !self.stopped() {
+            let i = self.iteration;
+            self.iteration += 1;
+            self.progress.inc(1);
+            let mut next_state = self.now_state;
+            next_state.transition(&mut self.rng);
+            let next_score = next_state.get_score(false);
+            let progress = i as f64 / self.number as f64;
+            if self
+                .acceptance
+                .accept(self.now_score, next_score, progress, &mut self.rng)
+            {
+                self.now_score = next_score;
+                self.now_state = next_state;
+            }
+            if self.best_score < Some(next_score) {
+                self.best_score = Some(next_score);
+                search_event!(iteration = i, best_score = next_score);
+                self.progress.set_best(next_score);
+                return Some(Incumbent {
+                    state: next_state,
+                    score: next_score,
+                    iteration: i,
+                });
+            }
+        }
+        self.progress.finish();
+        None
+    }
+}
+
+// Returns the best placement seen.
+pub fn local_search(
+    state: &AutoMoveMazeState,
+    number: usize,
+    acceptance: &mut impl Acceptance,
+    rng: &mut impl Rng,
+) -> AutoMoveMazeState {
+    limited_local_search(state, number, acceptance, SearchLimits::default(), rng)
+}
+
+// `local_search` under `limits`.
+pub fn limited_local_search(
+    state: &AutoMoveMazeState,
+    number: usize,
+    acceptance: &mut impl Acceptance,
+    limits: SearchLimits,
+    rng: &mut impl Rng,
+) -> AutoMoveMazeState {
+    LocalSearch::new(state, number, acceptance, StdRng::seed_from_u64(rng.gen()))
+        .with_limits(limits)
+        .last()
+        .unwrap()
+        .state
+}
+
+// `local_search` that hands every incumbent to `on_improvement`.
+pub fn local_search_with_hook(
+    state: &AutoMoveMazeState,
+    number: usize,
+    acceptance: &mut impl Acceptance,
+    on_improvement: &mut ImprovementHook<AutoMoveMazeState>,
+    rng: &mut impl Rng,
+) -> AutoMoveMazeState {
+    let mut best_state = *state;
+    for incumbent in LocalSearch::new(state, number, acceptance, StdRng::seed_from_u64(rng.gen())) {
+        best_state = incumbent.state;
+        let flow = on_improvement(&incumbent.state, incumbent.score, incumbent.iteration);
+        if flow.is_break() {
+            break;
+        }
+    }
+    best_state
+}
+
+pub fn simulated_annealing(
     state: &AutoMoveMazeState,
     number: usize,
     start_temp: f64,
     end_tmp: f64,
+    rng: &mut impl Rng,
 ) -> AutoMoveMazeState {
-    let mut rng = rand::thread_rng();
+    let mut metropolis = Metropolis {
+        start_temp,
+        end_temp: end_tmp,
+    };
+    local_search(state, number, &mut metropolis, rng)
+}
+
+pub fn simulated_annealing_with_hook(
+    state: &AutoMoveMazeState,
+    number: usize,
+    start_temp: f64,
+    end_temp: f64,
+    on_improvement: &mut ImprovementHook<AutoMoveMazeState>,
+    rng: &mut impl Rng,
+) -> AutoMoveMazeState {
+    let mut metropolis = Metropolis {
+        start_temp,
+        end_temp,
+    };
+    local_search_with_hook(state, number, &mut metropolis, on_improvement, rng)
+}
+
+fn simulated_annealing_with_log(
+    state: &AutoMoveMazeState,
+    number: usize,
+    start_temp: f64,
+    end_temp: f64,
+    writer: impl Write,
+    every: usize,
+    rng: &mut impl Rng,
+) -> AutoMoveMazeState {
+    let metropolis = Metropolis {
+        start_temp,
+        end_temp,
+    };
+    local_search(
+        state,
+        number,
+        &mut AnnealingLog::new(metropolis, writer, every),
+        rng,
+    )
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Restart {
+    Never,
+    // After every n iterations.
+    Every(usize),
+    // After n iterations without a new best of the current run.
+    Stagnation(usize),
+    // After unit * luby(i) iterations for the i-th run: 1, 1, 2, 1, 1, 2, 4, ...
+    Luby(usize),
+}
+
+// The i-th term (1-indexed) of the Luby sequence.
+fn luby(i: usize) -> usize {
+    let mut k = 1;
+    while (1 << k) - 1 < i {
+        k += 1;
+    }
+    if i == (1 << k) - 1 {
+        1 << (k - 1)
+    } else {
+        luby(i - (1 << (k - 1)) + 1)
+    }
+}
+
+struct RestartSchedule {
+    policy: Restart,
+    restarts: usize,
+    since_restart: usize,
+    since_improvement: usize,
+}
+
+impl RestartSchedule {
+    fn new(policy: Restart) -> RestartSchedule {
+        RestartSchedule {
+            policy,
+            restarts: 0,
+            since_restart: 0,
+            since_improvement: 0,
+        }
+    }
+
+    // Called once per iteration; true when the search should start over.
+    fn step(&mut self, improved: bool) -> bool {
+        self.since_restart += 1;
+        self.since_improvement = if improved {
+            0
+        } else {
+            self.since_improvement + 1
+        };
+        let restart = match self.policy {
+            Restart::Never => false,
+            Restart::Every(n) => n <= self.since_restart,
+            Restart::Stagnation(n) => n <= self.since_improvement,
+            Restart::Luby(unit) => unit * luby(self.restarts + 1) <= self.since_restart,
+        };
+        if restart {
+            self.restarts += 1;
+            self.since_restart = 0;
+            self.since_improvement = 0;
+        }
+        restart
+    }
+}
+
+// Hill climbing that starts over from a random placement when `policy` says so. Returns the
+// best placement over all runs and the number of restarts.
+fn hill_climb_with_restarts(
+    state: &AutoMoveMazeState,
+    number: usize,
+    policy: Restart,
+    rng: &mut impl Rng,
+) -> (AutoMoveMazeState, usize) {
+    let mut schedule = RestartSchedule::new(policy);
     let mut now_state = *state;
-    now_state.init_characters();
-    let mut best_score = now_state.get_score(false);
-    let mut now_score = best_score;
+    now_state.init_characters(rng);
+    let mut now_score = now_state.get_score(false);
+    let mut best_state = now_state;
+    let mut best_score = now_score;
+    for _ in 0..number {
+        let mut next_state = now_state;
+        next_state.transition(rng);
+        let next_score = next_state.get_score(false);
+        let improved = now_score < next_score;
+        if improved {
+            now_score = next_score;
+            now_state = next_state;
+        }
+        if best_score < now_score {
+            best_score = now_score;
+            best_state = now_state;
+        }
+        if schedule.step(improved) {
+            now_state.init_characters(rng);
+            now_score = now_state.get_score(false);
+        }
+    }
+    (best_state, schedule.restarts)
+}
+
+// Simulated annealing with restarts. The temperature follows the whole run, so later runs
+// start colder.
+fn simulated_annealing_with_restarts(
+    state: &AutoMoveMazeState,
+    number: usize,
+    start_temp: f64,
+    end_temp: f64,
+    policy: Restart,
+    rng: &mut impl Rng,
+) -> (AutoMoveMazeState, usize) {
+    let mut metropolis = Metropolis {
+        start_temp,
+        end_temp,
+    };
+    let mut schedule = RestartSchedule::new(policy);
+    let mut now_state = *state;
+    now_state.init_characters(rng);
+    let mut now_score = now_state.get_score(false);
+    let mut run_best = now_score;
     let mut best_state = now_state;
+    let mut best_score = now_score;
     for i in 0..number {
         let mut next_state = now_state;
-        next_state.transition();
+        next_state.transition(rng);
+        let next_score = next_state.get_score(false);
+        let progress = i as f64 / number as f64;
+        if metropolis.accept(now_score, next_score, progress, rng) {
+            now_score = next_score;
+            now_state = next_state;
+        }
+        if best_score < next_score {
+            best_score = next_score;
+            best_state = next_state;
+        }
+        let improved = run_best < next_score;
+        run_best = run_best.max(next_score);
+        if schedule.step(improved) {
+            now_state.init_characters(rng);
+            now_score = now_state.get_score(false);
+            run_best = now_score;
+        }
+    }
+    (best_state, schedule.restarts)
+}
+
+// `n` temperatures from `high` down to `low` with a constant ratio between neighbours.
+fn geometric_temperatures(high: f64, low: f64, n: usize) -> Vec<f64> {
+    if n < 2 {
+        return vec![high; n];
+    }
+    let ratio = (low / high).powf(1.0 / (n - 1) as f64);
+    (0..n).map(|i| high * ratio.powi(i as i32)).collect()
+}
+
+// One chain of `parallel_tempering`.
+struct Replica {
+    metropolis: Metropolis,
+    rng: StdRng,
+    state: AutoMoveMazeState,
+    score: ScoreType,
+    best_state: AutoMoveMazeState,
+    best_score: ScoreType,
+}
+
+impl Replica {
+    fn sweep(&mut self, steps: usize) {
+        for _ in 0..steps {
+            let mut next_state = self.state;
+            next_state.transition(&mut self.rng);
+            let next_score = next_state.get_score(false);
+            if self
+                .metropolis
+                .accept(self.score, next_score, 0.0, &mut self.rng)
+            {
+                self.state = next_state;
+                self.score = next_score;
+            }
+            if self.best_score < next_score {
+                self.best_score = next_score;
+                self.best_state = next_state;
+            }
+        }
+    }
+}
+
+// Replica exchange: one Metropolis chain per temperature, hottest first, each on its own
+// thread for `sweep` steps at a time. Between sweeps neighbouring chains swap placements with
+// probability exp((b - a) (1 / t_hot - 1 / t_cold)) for scores a (hot) and b (cold), so good
+// placements sink to the cold chains while the hot ones keep exploring. Every chain takes
+// `number` steps; returns the best placement of any chain.
+pub fn parallel_tempering(
+    state: &AutoMoveMazeState,
+    number: usize,
+    temperatures: &[f64],
+    sweep: usize,
+    rng: &mut impl Rng,
+) -> AutoMoveMazeState {
+    let mut replicas: Vec<Replica> = temperatures
+        .iter()
+        .map(|&temp| {
+            let mut rng = StdRng::seed_from_u64(rng.gen());
+            let mut now_state = *state;
+            now_state.init_characters(&mut rng);
+            let score = now_state.get_score(false);
+            Replica {
+                metropolis: Metropolis {
+                    start_temp: temp,
+                    end_temp: temp,
+                },
+                rng,
+                state: now_state,
+                score,
+                best_state: now_state,
+                best_score: score,
+            }
+        })
+        .collect();
+    let sweep = sweep.max(1);
+    let mut done = 0;
+    while done < number {
+        let steps = sweep.min(number - done);
+        std::thread::scope(|scope| {
+            for replica in replicas.iter_mut() {
+                scope.spawn(move || replica.sweep(steps));
+            }
+        });
+        done += steps;
+        for i in 1..replicas.len() {
+            let (hot, cold) = (&replicas[i - 1], &replicas[i]);
+            let exponent = (cold.score - hot.score) as f64
+                * (1.0 / hot.metropolis.start_temp - 1.0 / cold.metropolis.start_temp);
+            if exponent.exp() > rng.gen_range(0.0..1.0) {
+                let (hot_state, hot_score) = (hot.state, hot.score);
+                replicas[i - 1].state = replicas[i].state;
+                replicas[i - 1].score = replicas[i].score;
+                replicas[i].state = hot_state;
+                replicas[i].score = hot_score;
+            }
+        }
+    }
+    replicas
+        .iter()
+        .max_by_key(|replica| replica.best_score)
+        .map_or(*state, |replica| replica.best_state)
+}
+
+// Destroys `k` random characters and re-inserts them one by one on the open cell that scores
+// best with the characters placed so far. New solutions are accepted by the annealing rule.
+fn large_neighborhood_search(
+    state: &AutoMoveMazeState,
+    number: usize,
+    k: usize,
+    start_temp: f64,
+    end_temp: f64,
+    rng: &mut impl Rng,
+) -> AutoMoveMazeState {
+    let mut metropolis = Metropolis {
+        start_temp,
+        end_temp,
+    };
+    let mut now_state = *state;
+    now_state.init_characters(rng);
+    let mut now_score = now_state.get_score(false);
+    let mut best_score = now_score;
+    let mut best_state = now_state;
+    for i in 0..number {
+        let mut next_state = now_state;
+        let mut active = [true; CHARACTER_N];
+        let mut removed = vec![];
+        while removed.len() < k.min(CHARACTER_N) {
+            let id = rng.gen_range(0..CHARACTER_N);
+            if active[id] {
+                active[id] = false;
+                removed.push(id);
+            }
+        }
+        for id in removed {
+            active[id] = true;
+            let mut best = (-INF, next_state.characters[id]);
+            for y in 0..HEIGHT {
+                for x in 0..WIDTH {
+                    if next_state.walls[y][x] {
+                        continue;
+                    }
+                    next_state.set_character(id, y, x);
+                    let score = next_state.simulate_active(active).score;
+                    if best.0 < score {
+                        best = (score, Coord::from_point(x, y));
+                    }
+                }
+            }
+            next_state.characters[id] = best.1;
+        }
         let next_score = next_state.get_score(false);
-        let temp = start_temp + (end_tmp - start_temp) * (i as f64 / number as f64);
-        let probability = ((next_score - now_score) as f64 / temp).exp();
-        let is_force_next = probability > rng.gen_range(0.0..1.0);
-        if now_score < next_score || is_force_next {
+        if metropolis.accept(now_score, next_score, i as f64 / number as f64, rng) {
             now_score = next_score;
             now_state = next_state;
         }
@@ -218,9 +1320,272 @@ fn simulated_annealing(
     best_state
 }
 
+// Cross-entropy method: every character has its own distribution over the open cells.
+// Each generation samples `population` placements, keeps the `elite` best and moves the
+// distributions towards the cell frequencies of the elites by `smoothing`.
+fn cross_entropy_method(
+    state: &AutoMoveMazeState,
+    population: usize,
+    elite: usize,
+    generation: usize,
+    smoothing: f64,
+    rng: &mut impl Rng,
+) -> AutoMoveMazeState {
+    let open = (0..HEIGHT * WIDTH)
+        .filter(|&cell| !state.walls[cell / WIDTH][cell % WIDTH])
+        .count();
+    let mut probability = [[0.0; HEIGHT * WIDTH]; CHARACTER_N];
+    for row in probability.iter_mut() {
+        for (cell, p) in row.iter_mut().enumerate() {
+            if !state.walls[cell / WIDTH][cell % WIDTH] {
+                *p = 1.0 / open as f64;
+            }
+        }
+    }
+    let mut best_state = *state;
+    best_state.init_characters(rng);
+    let mut best_score = best_state.get_score(false);
+    for _ in 0..generation {
+        let mut samples: Vec<(ScoreType, AutoMoveMazeState)> = (0..population.max(1))
+            .map(|_| {
+                let mut next_state = *state;
+                for (id, row) in probability.iter().enumerate() {
+                    match WeightedIndex::new(row) {
+                        Ok(distribution) => {
+                            let cell = distribution.sample(rng);
+                            next_state.set_character(id, cell / WIDTH, cell % WIDTH);
+                        }
+                        // Every weight is zero on a board without open cells.
+                        Err(_) => next_state.move_to_open_cell(id, rng),
+                    }
+                }
+                (next_state.get_score(false), next_state)
+            })
+            .collect();
+        samples.sort_by_key(|&(score, _)| std::cmp::Reverse(score));
+        let elites = &samples[..elite.clamp(1, samples.len())];
+        if best_score < elites[0].0 {
+            (best_score, best_state) = elites[0];
+        }
+        for (id, row) in probability.iter_mut().enumerate() {
+            let mut frequency = [0.0; HEIGHT * WIDTH];
+            for (_, elite_state) in elites {
+                let character = elite_state.characters[id];
+                frequency[character.y * WIDTH + character.x] += 1.0 / elites.len() as f64;
+            }
+            for (p, f) in row.iter_mut().zip(frequency) {
+                *p = smoothing * f + (1.0 - smoothing) * *p;
+            }
+        }
+    }
+    best_state
+}
+
+// Beam search over placements: keeps the `width` best distinct placements and each generation
+// adds `neighbors` random neighbours of every one of them before cutting back to `width`. The
+// kept placements compete with their own neighbours, so the best never gets worse. A width of
+// one with one neighbour is hill climbing.
+fn population_hill_climb(
+    state: &AutoMoveMazeState,
+    width: usize,
+    neighbors: usize,
+    generation: usize,
+    rng: &mut impl Rng,
+) -> AutoMoveMazeState {
+    let key = |state: &AutoMoveMazeState| state.characters.map(|c| (c.y, c.x));
+    let mut population: Vec<(ScoreType, AutoMoveMazeState)> = (0..width.max(1))
+        .map(|_| {
+            let mut now_state = *state;
+            now_state.init_characters(rng);
+            (now_state.get_score(false), now_state)
+        })
+        .collect();
+    for _ in 0..generation {
+        let mut candidates = population.clone();
+        for (_, now_state) in population.iter() {
+            for _ in 0..neighbors {
+                let mut next_state = *now_state;
+                next_state.transition(rng);
+                candidates.push((next_state.get_score(false), next_state));
+            }
+        }
+        // Stable, so a kept placement stays ahead of an equal newcomer.
+        candidates.sort_by_key(|&(score, _)| std::cmp::Reverse(score));
+        let mut seen = std::collections::HashSet::new();
+        population = candidates
+            .into_iter()
+            .filter(|(_, state)| seen.insert(key(state)))
+            .take(width.max(1))
+            .collect();
+    }
+    population[0].1
+}
+
+// Every character comes from either parent with equal probability.
+pub struct UniformCrossover;
+
+impl Crossover<AutoMoveMazeState> for UniformCrossover {
+    fn crossover(
+        &self,
+        a: &AutoMoveMazeState,
+        b: &AutoMoveMazeState,
+        rng: &mut impl Rng,
+    ) -> AutoMoveMazeState {
+        let mut child = *a;
+        for id in 0..CHARACTER_N {
+            if rng.gen_bool(0.5) {
+                child.characters[id] = b.characters[id];
+            }
+        }
+        child
+    }
+}
+
+// Takes a random rectangle of the board from `b` and the rest from `a`: every character that
+// `b` placed inside the rectangle moves there, the others stay where `a` has them. Keeps
+// characters that work together in one area together, which a uniform crossover breaks up.
+pub struct RegionCrossover;
+
+impl Crossover<AutoMoveMazeState> for RegionCrossover {
+    fn crossover(
+        &self,
+        a: &AutoMoveMazeState,
+        b: &AutoMoveMazeState,
+        rng: &mut impl Rng,
+    ) -> AutoMoveMazeState {
+        let (y0, y1) = (rng.gen_range(0..HEIGHT), rng.gen_range(0..HEIGHT));
+        let (x0, x1) = (rng.gen_range(0..WIDTH), rng.gen_range(0..WIDTH));
+        let inside = |c: &Coord| {
+            (y0.min(y1)..=y0.max(y1)).contains(&c.y) && (x0.min(x1)..=x0.max(x1)).contains(&c.x)
+        };
+        let mut child = *a;
+        for id in 0..CHARACTER_N {
+            if inside(&b.characters[id]) {
+                child.characters[id] = b.characters[id];
+            }
+        }
+        child
+    }
+}
+
+// `crate::game::genetic_algorithm` over placements; `mutation` is the probability that a child's
+// character moves to a random cell.
+pub fn genetic_algorithm(
+    state: &AutoMoveMazeState,
+    population: usize,
+    generation: usize,
+    mutation: f64,
+    crossover: &impl Crossover<AutoMoveMazeState>,
+    rng: &mut impl Rng,
+) -> AutoMoveMazeState {
+    crate::game::genetic_algorithm(
+        population,
+        generation,
+        crossover,
+        |rng| {
+            let mut now_state = *state;
+            now_state.init_characters(rng);
+            now_state
+        },
+        |child, rng| {
+            for id in 0..CHARACTER_N {
+                if rng.gen_bool(mutation.clamp(0.0, 1.0)) {
+                    child.move_to_open_cell(id, rng);
+                }
+            }
+        },
+        |state| state.get_score(false),
+        rng,
+    )
+}
+
+// Objective vectors are compared component-wise, every component maximized.
+type Objectives = [ScoreType; 2];
+
+// `a` is at least as good everywhere and strictly better somewhere.
+fn dominates(a: &Objectives, b: &Objectives) -> bool {
+    a.iter().zip(b).all(|(x, y)| x >= y) && a.iter().zip(b).any(|(x, y)| x > y)
+}
+
+impl AutoMoveMazeState {
+    // Characters walk from a depot at (0, 0) to their cells and then follow the auto-move rule.
+    fn walked_distance(&self) -> ScoreType {
+        let report = self.simulate();
+        let mut previous = self.characters;
+        let mut distance: usize = self.characters.iter().map(|c| c.y + c.x).sum();
+        for positions in &report.positions[report.turns..] {
+            for (before, after) in previous.iter().zip(positions) {
+                distance += before.y.abs_diff(after.y) + before.x.abs_diff(after.x);
+            }
+            previous = *positions;
+        }
+        distance as ScoreType
+    }
+
+    // Maximize the points while minimizing the distance walked.
+    fn objectives(&self) -> Objectives {
+        [self.get_score(false), -self.walked_distance()]
+    }
+}
+
+// Archive of mutually non-dominated placements; one placement per objective vector.
+struct ParetoFront {
+    members: Vec<(Objectives, AutoMoveMazeState)>,
+}
+
+impl ParetoFront {
+    fn new() -> ParetoFront {
+        ParetoFront { members: vec![] }
+    }
+
+    // Returns false when `state` is dominated by or equal to a member.
+    fn insert(&mut self, objectives: Objectives, state: AutoMoveMazeState) -> bool {
+        if self
+            .members
+            .iter()
+            .any(|(member, _)| *member == objectives || dominates(member, &objectives))
+        {
+            return false;
+        }
+        self.members
+            .retain(|(member, _)| !dominates(&objectives, member));
+        self.members.push((objectives, state));
+        true
+    }
+}
+
+// Local search that moves to any neighbour the current placement does not dominate and returns
+// the non-dominated set it came across, best score first.
+fn pareto_local_search(
+    state: &AutoMoveMazeState,
+    number: usize,
+    rng: &mut impl Rng,
+) -> Vec<(Objectives, AutoMoveMazeState)> {
+    let mut now_state = *state;
+    now_state.init_characters(rng);
+    let mut now_objectives = now_state.objectives();
+    let mut front = ParetoFront::new();
+    front.insert(now_objectives, now_state);
+    for _ in 0..number {
+        let mut next_state = now_state;
+        next_state.transition(rng);
+        let next_objectives = next_state.objectives();
+        if !dominates(&now_objectives, &next_objectives) {
+            now_state = next_state;
+            now_objectives = next_objectives;
+        }
+        front.insert(next_objectives, next_state);
+    }
+    front
+        .members
+        .sort_by_key(|&(objectives, _)| std::cmp::Reverse(objectives));
+    front.members
+}
+
 pub fn play_game() {
+    let mut rng = rand::thread_rng();
     let mut state = AutoMoveMazeState::new();
-    let state = random_action(&mut state);
+    let state = random_action(&mut state, &mut rng);
     println!("{}", state);
     let score = state.get_score(true);
     println!("Score of random Action: {}", score);
@@ -229,14 +1594,58 @@ pub fn play_game() {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::maze_generator::{MazeGenerator, PointDistribution};
+    use crate::tuning::{grid, grid_search};
+
     const GAME_NUMBER: usize = 100;
 
+    // The old destructive scoring, kept as the reference for `simulate`.
+    fn reference_score(state: &AutoMoveMazeState) -> ScoreType {
+        let mut state = *state;
+        for character in state.characters {
+            state.points[character.y][character.x] = 0;
+        }
+        while !state.is_done() {
+            state.advance();
+        }
+        state.game_score
+    }
+
+    // Mean score of random placements on the seeds 0..`game_number`: every optimizer starts
+    // from one, so it must end above this.
+    fn random_mean(game_number: usize) -> f64 {
+        let mut rng = StdRng::seed_from_u64(0);
+        let total: f64 = (0..game_number as u64)
+            .map(|seed| {
+                let mut state = AutoMoveMazeState::with_seed(seed);
+                random_action(&mut state, &mut rng).get_score(false) as f64
+            })
+            .sum();
+        total / game_number as f64
+    }
+
+    #[test]
+    fn test_simulate_matches_advance() {
+        let mut rng = StdRng::seed_from_u64(0);
+        for seed in 0..GAME_NUMBER as u64 {
+            let mut state = AutoMoveMazeState::with_seed(seed);
+            state.init_characters(&mut rng);
+            let before = state.to_string();
+            let report = state.simulate();
+            assert_eq!(state.to_string(), before);
+            assert_eq!(report.score, reference_score(&state));
+            assert_eq!(report.scores[END_TURN - 1], report.score);
+            assert!(report.scores.windows(2).all(|w| w[0] <= w[1]));
+        }
+    }
+
     #[test]
     fn test_random_action() {
+        let mut rng = StdRng::seed_from_u64(0);
         let mut mean = 0.0;
-        for _ in 0..GAME_NUMBER {
-            let mut state = AutoMoveMazeState::new();
-            let state = random_action(&mut state);
+        for seed in 0..GAME_NUMBER as u64 {
+            let mut state = AutoMoveMazeState::with_seed(seed);
+            let state = random_action(&mut state, &mut rng);
             let score = state.get_score(false);
             mean += score as f64;
         }
@@ -246,10 +1655,11 @@ mod tests {
 
     #[test]
     fn test_hill_climb_action() {
+        let mut rng = StdRng::seed_from_u64(0);
         let mut mean = 0.0;
-        for _ in 0..GAME_NUMBER {
-            let state = AutoMoveMazeState::new();
-            let state = hill_climb(&state, 10000);
+        for seed in 0..GAME_NUMBER as u64 {
+            let state = AutoMoveMazeState::with_seed(seed);
+            let state = hill_climb(&state, 10000, &mut rng);
             let score = state.get_score(false);
             mean += score as f64;
         }
@@ -257,16 +1667,561 @@ mod tests {
         println!("Score of Hill Climb Action: {}", mean);
     }
 
+    #[test]
+    fn test_adaptive_hill_climb_action() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut mean = 0.0;
+        let mut uses = [0; NEIGHBORHOODS.len()];
+        for seed in 0..GAME_NUMBER as u64 {
+            let state = AutoMoveMazeState::with_seed(seed);
+            let (state, selector) = adaptive_hill_climb(&state, 10000, &mut rng);
+            let total: f64 = selector.probability.iter().sum();
+            assert!((total - 1.0).abs() < 1e-9);
+            for (sum, n) in uses.iter_mut().zip(selector.uses) {
+                *sum += n;
+            }
+            mean += state.get_score(false) as f64;
+        }
+        mean /= GAME_NUMBER as f64;
+        println!("Score of Adaptive Hill Climb Action: {}", mean);
+        println!(
+            "Neighborhood Uses: {:?}",
+            NEIGHBORHOODS.iter().zip(uses).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_large_neighborhood_search_action() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut mean = 0.0;
+        for seed in 0..GAME_NUMBER as u64 {
+            let state = AutoMoveMazeState::with_seed(seed);
+            let state = large_neighborhood_search(&state, 100, 2, 10.0, 1.0, &mut rng);
+            mean += state.get_score(false) as f64;
+        }
+        mean /= GAME_NUMBER as f64;
+        println!("Score of Large Neighborhood Search Action: {}", mean);
+        assert!(random_mean(GAME_NUMBER) < mean);
+    }
+
+    #[test]
+    fn test_simulate_active() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut state = AutoMoveMazeState::with_seed(0);
+        state.init_characters(&mut rng);
+        let report = state.simulate_active([false; CHARACTER_N]);
+        assert_eq!(report.score, 0);
+        let mut only_first = [false; CHARACTER_N];
+        only_first[0] = true;
+        let report = state.simulate_active(only_first);
+        assert_eq!(report.score, report.gains[0]);
+    }
+
+    #[test]
+    fn test_luby() {
+        let expected = [1, 1, 2, 1, 1, 2, 4, 1, 1, 2, 1, 1, 2, 4, 8];
+        for (i, &term) in expected.iter().enumerate() {
+            assert_eq!(luby(i + 1), term);
+        }
+        let mut schedule = RestartSchedule::new(Restart::Luby(2));
+        // Runs of 2, 2, 4, 2, 2, 4 iterations fit into 16.
+        let restarts: Vec<usize> = (1..=20).filter(|_| schedule.step(false)).collect();
+        assert_eq!(restarts, vec![2, 4, 8, 10, 12, 16]);
+    }
+
+    #[test]
+    fn test_restart_policies() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let policies = [
+            Restart::Never,
+            Restart::Every(1000),
+            Restart::Stagnation(500),
+            Restart::Luby(100),
+        ];
+        let baseline = random_mean(GAME_NUMBER / 5);
+        for policy in policies {
+            let (mut hill, mut annealing, mut restarts) = (0.0, 0.0, 0);
+            for seed in 0..GAME_NUMBER as u64 / 5 {
+                let state = AutoMoveMazeState::with_seed(seed);
+                let (best, n) = hill_climb_with_restarts(&state, 5000, policy, &mut rng);
+                hill += best.get_score(false) as f64;
+                restarts += n;
+                let (best, _) =
+                    simulated_annealing_with_restarts(&state, 5000, 500.0, 10.0, policy, &mut rng);
+                annealing += best.get_score(false) as f64;
+            }
+            let n = (GAME_NUMBER / 5) as f64;
+            println!(
+                "{:?}\tHill Climb: {}\tSimulated Annealing: {}\tRestarts: {}",
+                policy,
+                hill / n,
+                annealing / n,
+                restarts as f64 / n
+            );
+            assert!(baseline < hill / n);
+            assert!(baseline < annealing / n);
+        }
+    }
+
+    #[test]
+    fn test_acceptance_criteria() {
+        let mut rng = StdRng::seed_from_u64(0);
+        fn mean(acceptance: &mut dyn FnMut() -> AutoMoveMazeState) -> f64 {
+            (0..GAME_NUMBER / 5)
+                .map(|_| acceptance().get_score(false) as f64)
+                .sum::<f64>()
+                / (GAME_NUMBER / 5) as f64
+        }
+        let state = AutoMoveMazeState::with_seed(0);
+        let number = 5000;
+        let metropolis = mean(&mut || simulated_annealing(&state, number, 500.0, 10.0, &mut rng));
+        let threshold = mean(&mut || {
+            let mut acceptance = ThresholdAccepting {
+                start_threshold: 10.0,
+                end_threshold: 0.0,
+            };
+            local_search(&state, number, &mut acceptance, &mut rng)
+        });
+        let deluge = mean(&mut || {
+            let mut acceptance = GreatDeluge {
+                level: None,
+                rain: 0.01,
+            };
+            local_search(&state, number, &mut acceptance, &mut rng)
+        });
+        let late =
+            mean(&mut || local_search(&state, number, &mut LateAcceptance::new(50), &mut rng));
+        println!("Score of Metropolis: {}", metropolis);
+        println!("Score of Threshold Accepting: {}", threshold);
+        println!("Score of Great Deluge: {}", deluge);
+        println!("Score of Late Acceptance: {}", late);
+        let baseline = mean(&mut || random_action(&mut state.clone(), &mut rng));
+        for score in [metropolis, threshold, deluge, late] {
+            assert!(baseline < score);
+        }
+    }
+
+    #[test]
+    fn test_adaptive_annealing() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut acceptance = AdaptiveAnnealing::new();
+        // Only climbing during the warm-up.
+        assert!(acceptance.accept(10, 11, 0.0, &mut rng));
+        assert!(!acceptance.accept(10, 8, 0.0, &mut rng));
+        assert!(!acceptance.accept(10, 4, 0.01, &mut rng));
+        assert_eq!(acceptance.calibrate(), 4.0 / 2f64.ln());
+        // Taking every worsening move cools, taking none heats.
+        for _ in 0..acceptance.window {
+            acceptance.accept(10, 10 - 1, 0.5, &mut rng);
+        }
+        assert!(acceptance.calibrate() < 4.0 / 2f64.ln());
+        let cooled = acceptance.calibrate();
+        for _ in 0..acceptance.window {
+            acceptance.accept(10, 10 - 1000, 0.5, &mut rng);
+        }
+        assert!(cooled < acceptance.calibrate());
+
+        let (mut metropolis, mut adaptive) = (0.0, 0.0);
+        for seed in 0..GAME_NUMBER as u64 / 5 {
+            let state = AutoMoveMazeState::with_seed(seed);
+            metropolis +=
+                simulated_annealing(&state, 5000, 500.0, 10.0, &mut rng).get_score(false) as f64;
+            adaptive += adaptive_annealing(&state, 5000, &mut rng).get_score(false) as f64;
+        }
+        let n = (GAME_NUMBER / 5) as f64;
+        println!(
+            "Score of Metropolis: {}\tAdaptive: {}",
+            metropolis / n,
+            adaptive / n
+        );
+        assert!(0.9 * metropolis < adaptive);
+    }
+
+    #[test]
+    fn test_parallel_tempering() {
+        let temperatures = geometric_temperatures(500.0, 5.0, 3);
+        assert_eq!(temperatures.len(), 3);
+        assert!((temperatures[1] - 50.0).abs() < 1e-9);
+        assert!((temperatures[2] - 5.0).abs() < 1e-9);
+        assert_eq!(geometric_temperatures(10.0, 1.0, 1), vec![10.0]);
+
+        let mut rng = StdRng::seed_from_u64(0);
+        let temperatures = geometric_temperatures(500.0, 10.0, 4);
+        let (mut annealing, mut tempering) = (0.0, 0.0);
+        for seed in 0..GAME_NUMBER as u64 / 5 {
+            let state = AutoMoveMazeState::with_seed(seed);
+            annealing +=
+                simulated_annealing(&state, 5000, 500.0, 10.0, &mut rng).get_score(false) as f64;
+            let best = parallel_tempering(&state, 5000, &temperatures, 100, &mut rng);
+            tempering += best.get_score(false) as f64;
+        }
+        let n = (GAME_NUMBER / 5) as f64;
+        println!(
+            "Score of Simulated Annealing: {}\tParallel Tempering: {}",
+            annealing / n,
+            tempering / n
+        );
+        assert!(0.98 * annealing < tempering);
+    }
+
+    #[test]
+    fn test_population_hill_climb() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let (mut hill, mut population) = (0.0, 0.0);
+        for seed in 0..GAME_NUMBER as u64 / 5 {
+            let state = AutoMoveMazeState::with_seed(seed);
+            hill += hill_climb(&state, 5000, &mut rng).get_score(false) as f64;
+            // The same 5000 evaluations.
+            let best = population_hill_climb(&state, 10, 5, 100, &mut rng);
+            population += best.get_score(false) as f64;
+        }
+        let n = (GAME_NUMBER / 5) as f64;
+        println!(
+            "Score of Hill Climb: {}\tPopulation Hill Climb: {}",
+            hill / n,
+            population / n
+        );
+        assert!(hill < population);
+    }
+
+    #[test]
+    fn test_crossover() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut a = AutoMoveMazeState::with_seed(0);
+        let mut b = a;
+        a.init_characters(&mut rng);
+        b.init_characters(&mut rng);
+        let from_parent = |child: &AutoMoveMazeState, id: usize| {
+            let c = child.characters[id];
+            [a.characters[id], b.characters[id]]
+                .iter()
+                .any(|p| (p.y, p.x) == (c.y, c.x))
+        };
+        for _ in 0..100 {
+            let uniform = UniformCrossover.crossover(&a, &b, &mut rng);
+            let region = RegionCrossover.crossover(&a, &b, &mut rng);
+            for id in 0..CHARACTER_N {
+                assert!(from_parent(&uniform, id));
+                assert!(from_parent(&region, id));
+            }
+        }
+        // Crossing a placement with itself gives it back.
+        let child = RegionCrossover.crossover(&a, &a, &mut rng);
+        assert_eq!(child.get_score(false), a.get_score(false));
+
+        let (mut uniform, mut region) = (0.0, 0.0);
+        for seed in 0..GAME_NUMBER as u64 / 5 {
+            let state = AutoMoveMazeState::with_seed(seed);
+            uniform += genetic_algorithm(&state, 50, 100, 0.1, &UniformCrossover, &mut rng)
+                .get_score(false) as f64;
+            region += genetic_algorithm(&state, 50, 100, 0.1, &RegionCrossover, &mut rng)
+                .get_score(false) as f64;
+        }
+        let n = (GAME_NUMBER / 5) as f64;
+        println!(
+            "Score of Genetic Algorithm, Uniform: {}\tRegion: {}",
+            uniform / n,
+            region / n
+        );
+        assert!(90.0 < uniform / n && 90.0 < region / n);
+    }
+
+    #[test]
+    fn test_late_acceptance_history() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut acceptance = LateAcceptance::new(2);
+        assert!(!acceptance.accept(10, 9, 0.0, &mut rng));
+        assert!(acceptance.accept(10, 10, 0.0, &mut rng));
+        // The slot of the first call still holds 10.
+        assert!(!acceptance.accept(12, 9, 0.0, &mut rng));
+    }
+
+    #[test]
+    fn test_cross_entropy_method_action() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut cem = 0.0;
+        let mut annealing = 0.0;
+        for seed in 0..(GAME_NUMBER / 5) as u64 {
+            let state = AutoMoveMazeState::with_seed(seed);
+            cem += cross_entropy_method(&state, 100, 10, 50, 0.7, &mut rng).get_score(false) as f64;
+            annealing +=
+                simulated_annealing(&state, 5000, 500.0, 10.0, &mut rng).get_score(false) as f64;
+        }
+        let n = (GAME_NUMBER / 5) as f64;
+        println!("Score of Cross Entropy Method: {}", cem / n);
+        println!(
+            "Score of Simulated Annealing (same budget): {}",
+            annealing / n
+        );
+        assert!(random_mean(GAME_NUMBER / 5) < cem / n);
+    }
+
+    #[test]
+    fn test_pareto_local_search() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let state = AutoMoveMazeState::with_seed(0);
+        let front = pareto_local_search(&state, 5000, &mut rng);
+        for (i, (a, _)) in front.iter().enumerate() {
+            for (b, _) in &front[i + 1..] {
+                assert!(!dominates(a, b) && !dominates(b, a));
+            }
+        }
+        for (objectives, placement) in &front {
+            assert_eq!(*objectives, placement.objectives());
+            println!(
+                "Pareto Score: {}\tDistance: {}",
+                objectives[0], -objectives[1]
+            );
+        }
+    }
+
+    #[test]
+    fn test_constrained_hill_climb() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut forbidden = [[false; WIDTH]; HEIGHT];
+        forbidden[HEIGHT / 2] = [true; WIDTH];
+        let constraints = Constraints {
+            forbidden,
+            min_distance: 3,
+        };
+        for (name, handling) in [
+            ("Penalty", ConstraintHandling::Penalty(10)),
+            ("Repair", ConstraintHandling::Repair),
+        ] {
+            let mut mean = 0.0;
+            for seed in 0..(GAME_NUMBER / 5) as u64 {
+                let state = AutoMoveMazeState::with_seed(seed);
+                let state = constrained_hill_climb(&state, 2000, &constraints, handling, &mut rng);
+                assert_eq!(constraints.violations(&state), 0);
+                mean += state.get_score(false) as f64;
+            }
+            println!(
+                "Score of Constrained Hill Climb ({}): {}",
+                name,
+                mean / (GAME_NUMBER / 5) as f64
+            );
+        }
+    }
+
+    #[test]
+    fn test_local_search_incumbents() {
+        let state = AutoMoveMazeState::with_seed(0);
+        let mut acceptance = Metropolis {
+            start_temp: 500.0,
+            end_temp: 10.0,
+        };
+        let incumbents: Vec<Incumbent> =
+            LocalSearch::new(&state, 5000, &mut acceptance, StdRng::seed_from_u64(0)).collect();
+        for pair in incumbents.windows(2) {
+            assert!(pair[0].score < pair[1].score);
+            assert!(pair[0].iteration <= pair[1].iteration);
+        }
+        for incumbent in &incumbents {
+            assert_eq!(incumbent.score, incumbent.state.get_score(false));
+        }
+        // The seed decides the start too, so a run replays exactly.
+        let replay: Vec<Incumbent> =
+            LocalSearch::new(&state, 5000, &mut acceptance, StdRng::seed_from_u64(0)).collect();
+        let key = |i: &Incumbent| (i.score, i.iteration, i.state.to_string());
+        assert_eq!(
+            incumbents.iter().map(key).collect::<Vec<_>>(),
+            replay.iter().map(key).collect::<Vec<_>>()
+        );
+        // Stopping on a target score.
+        let mut acceptance = LateAcceptance::new(50);
+        let target = LocalSearch::new(&state, 5000, &mut acceptance, StdRng::seed_from_u64(0))
+            .find(|i| 80 <= i.score);
+        println!(
+            "Incumbents: {}\tFirst Iteration Reaching 80: {:?}",
+            incumbents.len(),
+            target.map(|i| i.iteration)
+        );
+    }
+
+    #[test]
+    fn test_local_search_limits() {
+        let state = AutoMoveMazeState::with_seed(0);
+        let run = |limits: SearchLimits| -> Vec<Incumbent> {
+            let mut acceptance = Improving;
+            LocalSearch::new(&state, 5000, &mut acceptance, StdRng::seed_from_u64(0))
+                .with_limits(limits)
+                .collect()
+        };
+        let full = run(SearchLimits::default());
+        let target = full[full.len() / 2].score;
+        let stopped = run(SearchLimits {
+            stop_at_score: Some(target),
+            ..SearchLimits::default()
+        });
+        assert_eq!(stopped.last().unwrap().score, target);
+        assert_eq!(stopped.len(), full.len() / 2 + 1);
+        let limited = run(SearchLimits {
+            max_nodes: Some(100),
+            ..SearchLimits::default()
+        });
+        assert!(limited.iter().all(|i| i.iteration < 100));
+        assert_eq!(
+            limited.last().unwrap().score,
+            full.iter().rfind(|i| i.iteration < 100).unwrap().score
+        );
+    }
+
+    #[test]
+    fn test_improvement_hooks() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let state = AutoMoveMazeState::with_seed(0);
+        let mut log = vec![];
+        let best = hill_climb_with_hook(
+            &state,
+            5000,
+            &mut |_, score, iteration| {
+                log.push((score, iteration));
+                ControlFlow::Continue(())
+            },
+            &mut rng,
+        );
+        assert_eq!(log.last().unwrap().0, best.get_score(false));
+        assert!(log.windows(2).all(|pair| pair[0].0 < pair[1].0));
+        let mut last = None;
+        let best = simulated_annealing_with_hook(
+            &state,
+            5000,
+            500.0,
+            10.0,
+            &mut |_, score, _| {
+                last = Some(score);
+                if 70 <= score {
+                    ControlFlow::Break(())
+                } else {
+                    ControlFlow::Continue(())
+                }
+            },
+            &mut rng,
+        );
+        // Aborting keeps the incumbent the hook saw last.
+        assert_eq!(last, Some(best.get_score(false)));
+        println!(
+            "Hill Climb Improvements: {}\tSA Stop Score: {}",
+            log.len(),
+            best.get_score(false)
+        );
+    }
+
+    #[test]
+    fn test_annealing_log() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let state = AutoMoveMazeState::with_seed(0);
+        let mut log = vec![];
+        simulated_annealing_with_log(&state, 1000, 500.0, 10.0, &mut log, 100, &mut rng);
+        let log = String::from_utf8(log).unwrap();
+        let lines: Vec<&str> = log.lines().collect();
+        assert_eq!(lines[0], "step\ttemperature\tdelta\tprobability\taccepted");
+        assert_eq!(lines.len(), 11);
+        let mut temperatures = vec![];
+        for (i, line) in lines[1..].iter().enumerate() {
+            let fields: Vec<&str> = line.split('\t').collect();
+            assert_eq!(fields[0].parse::<usize>().unwrap(), 100 * i);
+            temperatures.push(fields[1].parse::<f64>().unwrap());
+            let delta: ScoreType = fields[2].parse().unwrap();
+            let probability: f64 = fields[3].parse().unwrap();
+            assert!((0.0..=1.0).contains(&probability));
+            let accepted: bool = fields[4].parse().unwrap();
+            assert!(delta <= 0 || accepted);
+        }
+        assert_eq!(temperatures[0], 500.0);
+        assert!(temperatures.windows(2).all(|pair| pair[1] < pair[0]));
+
+        // Logging does not change the moves of a seeded run.
+        let metropolis = || Metropolis {
+            start_temp: 500.0,
+            end_temp: 10.0,
+        };
+        let scores =
+            |acceptance: &mut dyn FnMut(ScoreType, ScoreType, f64, &mut StdRng) -> bool| {
+                let mut rng = StdRng::seed_from_u64(0);
+                let mut now_score = 0;
+                (0..200)
+                    .map(|i| {
+                        let next_score = now_score + (i * 7 % 11) as ScoreType - 5;
+                        if acceptance(now_score, next_score, i as f64 / 200.0, &mut rng) {
+                            now_score = next_score;
+                        }
+                        now_score
+                    })
+                    .collect::<Vec<_>>()
+            };
+        let mut plain = metropolis();
+        let mut logged = AnnealingLog::new(metropolis(), std::io::sink(), 3);
+        assert_eq!(
+            scores(&mut |a, b, p, rng| plain.accept(a, b, p, rng)),
+            scores(&mut |a, b, p, rng| logged.accept(a, b, p, rng))
+        );
+    }
+
     #[test]
     fn test_simulated_annealing_action() {
+        let mut rng = StdRng::seed_from_u64(0);
         let mut mean = 0.0;
-        for _ in 0..GAME_NUMBER {
-            let state = AutoMoveMazeState::new();
-            let state = simulated_annealing(&state, 10000, 500.0, 10.0);
+        for seed in 0..GAME_NUMBER as u64 {
+            let state = AutoMoveMazeState::with_seed(seed);
+            let state = simulated_annealing(&state, 10000, 500.0, 10.0, &mut rng);
             let score = state.get_score(false);
             mean += score as f64;
         }
         mean /= GAME_NUMBER as f64;
         println!("Score of Simulated Annealing Action: {}", mean);
+        assert!(random_mean(GAME_NUMBER) < mean);
+    }
+
+    #[test]
+    fn test_tune_simulated_annealing() {
+        let space = grid(&[100.0, 500.0], &[1000, 5000]);
+        let trials = grid_search(&space, 10, |&(start_temp, number), seed| {
+            let state = AutoMoveMazeState::with_seed(seed);
+            simulated_annealing(
+                &state,
+                number,
+                start_temp,
+                10.0,
+                &mut StdRng::seed_from_u64(seed),
+            )
+            .get_score(false) as f64
+        });
+        println!("Best Simulated Annealing Params: {}", trials[0]);
+        let baseline = random_mean(10);
+        assert!(trials.iter().all(|trial| baseline < trial.score));
+    }
+
+    #[test]
+    fn test_generated_maze_with_walls() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let generator = MazeGenerator {
+            distribution: PointDistribution::Gaussian {
+                max: 9,
+                mean: 4.5,
+                std_dev: 2.0,
+            },
+            wall_density: 0.2,
+            sparsity: 0.0,
+        };
+        let mut mean = 0.0;
+        for seed in 0..GAME_NUMBER as u64 {
+            let state = AutoMoveMazeState::with_generator(seed, &generator);
+            let state = hill_climb(&state, 1000, &mut rng);
+            assert!(state.characters.iter().all(|c| !state.walls[c.y][c.x]));
+            mean += state.get_score(false) as f64;
+        }
+        mean /= GAME_NUMBER as f64;
+        println!("Score of Hill Climb Action (walls): {}", mean);
+        // A board of walls only has no cell to place on.
+        let walled = MazeGenerator {
+            wall_density: 1.0,
+            ..generator
+        };
+        let state = AutoMoveMazeState::with_generator(0, &walled);
+        assert!(state.random_open_cell(&mut rng).is_none());
+        assert_eq!(hill_climb(&state, 100, &mut rng).get_score(false), 0);
+        let best = cross_entropy_method(&state, 10, 2, 5, 0.7, &mut rng);
+        assert_eq!(best.get_score(false), 0);
     }
 }
@@ -0,0 +1,403 @@
+#![allow(unused)]
+
+use std::io::{self, BufRead, Write};
+
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use crate::game::{add_score, ActionList, Outcome, TwoPlayerState, WinningStatus};
+use crate::hashing::{Fnv, StateHash};
+use crate::learning::Features;
+
+pub type ScoreType = crate::game::ScoreType;
+pub type Action = usize;
+
+pub const HEIGHT: usize = 3;
+pub const WIDTH: usize = 3;
+pub const END_TURN: usize = 4;
+const INF: ScoreType = 1_000_000_000;
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Character {
+    pub y: usize,
+    pub x: usize,
+    pub game_score: ScoreType,
+}
+
+impl Character {
+    pub fn new(y: usize, x: usize) -> Character {
+        Character {
+            y,
+            x,
+            game_score: 0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct AlternateMazeState {
+    points: [[ScoreType; WIDTH]; HEIGHT],
+    turn: usize,
+    characters: [Character; 2],
+}
+
+impl AlternateMazeState {
+    pub fn new() -> AlternateMazeState {
+        Self::generate(&mut rand::thread_rng())
+    }
+
+    pub fn with_seed(seed: u64) -> AlternateMazeState {
+        Self::generate(&mut StdRng::seed_from_u64(seed))
+    }
+
+    fn generate(rng: &mut impl Rng) -> AlternateMazeState {
+        let characters = [
+            Character::new(HEIGHT / 2, WIDTH / 2 - 1),
+            Character::new(HEIGHT / 2, WIDTH / 2 + 1),
+        ];
+        let mut points = [[0; WIDTH]; HEIGHT];
+        for (y, row) in points.iter_mut().enumerate() {
+            for (x, point) in row.iter_mut().enumerate() {
+                if characters.iter().any(|c| c.y == y && c.x == x) {
+                    continue;
+                }
+                *point = rng.gen_range(0..10);
+            }
+        }
+        AlternateMazeState {
+            points,
+            turn: 0,
+            characters,
+        }
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.turn == END_TURN
+    }
+
+    pub fn advance(&mut self, action: Action) {
+        let dx = [1, -1, 0, 0];
+        let dy = [0, 0, 1, -1];
+        let character = &mut self.characters[0];
+        character.x = character.x.checked_add_signed(dx[action]).unwrap_or(0);
+        character.y = character.y.checked_add_signed(dy[action]).unwrap_or(0);
+        let point = &mut self.points[character.y][character.x];
+        if 0 < *point {
+            character.game_score = add_score(character.game_score, *point);
+            *point = 0;
+        }
+        self.turn += 1;
+        self.characters.swap(0, 1);
+    }
+
+    pub fn legal_actions(&self) -> ActionList {
+        let dx = [1, -1, 0, 0];
+        let dy = [0, 0, 1, -1];
+        let character = &self.characters[0];
+        let mut actions = ActionList::new();
+        for act in 0..4 {
+            let ty = character.y.checked_add_signed(dy[act]).unwrap_or(HEIGHT);
+            let tx = character.x.checked_add_signed(dx[act]).unwrap_or(WIDTH);
+            if ty < HEIGHT && tx < WIDTH {
+                actions.push(act);
+            }
+        }
+        actions
+    }
+
+    pub fn winning_status(&self) -> WinningStatus {
+        if !self.is_done() {
+            return WinningStatus::None;
+        }
+        let (me, other) = (self.characters[0].game_score, self.characters[1].game_score);
+        match me.cmp(&other) {
+            std::cmp::Ordering::Greater => WinningStatus::Win,
+            std::cmp::Ordering::Less => WinningStatus::Lose,
+            std::cmp::Ordering::Equal => WinningStatus::Draw,
+        }
+    }
+
+    pub fn score(&self) -> ScoreType {
+        self.characters[0].game_score - self.characters[1].game_score
+    }
+}
+
+impl Default for AlternateMazeState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TwoPlayerState for AlternateMazeState {
+    fn is_done(&self) -> bool {
+        AlternateMazeState::is_done(self)
+    }
+
+    fn advance(&mut self, action: Action) {
+        AlternateMazeState::advance(self, action)
+    }
+
+    fn legal_actions(&self) -> ActionList {
+        AlternateMazeState::legal_actions(self)
+    }
+
+    fn winning_status(&self) -> WinningStatus {
+        AlternateMazeState::winning_status(self)
+    }
+}
+
+impl Features for AlternateMazeState {
+    fn grid_size(&self) -> (usize, usize) {
+        (HEIGHT, WIDTH)
+    }
+
+    fn point(&self, y: usize, x: usize) -> f64 {
+        self.points[y][x] as f64
+    }
+
+    fn position(&self) -> (usize, usize) {
+        (self.characters[0].y, self.characters[0].x)
+    }
+
+    fn remaining_turns(&self) -> usize {
+        END_TURN - self.turn
+    }
+}
+
+// `Features` only sees the player to move, so both characters and the turn go in as well.
+impl StateHash for AlternateMazeState {
+    fn state_hash(&self) -> u64 {
+        let mut hasher = Fnv::new();
+        for &point in self.points.iter().flatten() {
+            hasher = hasher.write(point as u64);
+        }
+        hasher = hasher.write(self.turn as u64);
+        for character in &self.characters {
+            hasher = hasher
+                .write(character.y as u64)
+                .write(character.x as u64)
+                .write(character.game_score as u64);
+        }
+        hasher.finish()
+    }
+}
+
+impl std::fmt::Display for AlternateMazeState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "turn:\t{}", self.turn)?;
+        // characters[0] is always the player to move, so map back to fixed labels.
+        let (a, b) = if self.turn.is_multiple_of(2) {
+            (0, 1)
+        } else {
+            (1, 0)
+        };
+        writeln!(f, "score(A):\t{}", self.characters[a].game_score)?;
+        writeln!(f, "score(B):\t{}", self.characters[b].game_score)?;
+        for h in 0..HEIGHT {
+            for w in 0..WIDTH {
+                let ch = if self.characters[a].y == h && self.characters[a].x == w {
+                    'A'
+                } else if self.characters[b].y == h && self.characters[b].x == w {
+                    'B'
+                } else if self.points[h][w] > 0 {
+                    (self.points[h][w] as u8 + b'0') as char
+                } else {
+                    '.'
+                };
+                write!(f, "{}", ch)?;
+            }
+            writeln!(f)?
+        }
+        Ok(())
+    }
+}
+
+pub fn random_action(state: &AlternateMazeState) -> Outcome {
+    random_action_with(state, &mut rand::thread_rng())
+}
+
+pub fn random_action_with(state: &AlternateMazeState, rng: &mut impl Rng) -> Outcome {
+    let legal_actions = state.legal_actions();
+    if state.is_done() || legal_actions.is_empty() {
+        return Outcome::NoAction;
+    }
+    Outcome::Action(legal_actions[rng.gen_range(0..legal_actions.len())])
+}
+
+pub fn greedy_action(state: &AlternateMazeState) -> Outcome {
+    if state.is_done() {
+        return Outcome::NoAction;
+    }
+    let mut best_score = -INF;
+    let mut best_action = None;
+    for act in state.legal_actions() {
+        let mut next_state = *state;
+        next_state.advance(act);
+        // After advancing, the score is seen from the opponent's side.
+        let score = -next_state.score();
+        if best_score < score {
+            best_score = score;
+            best_action = Some(act);
+        }
+    }
+    best_action.into()
+}
+
+pub fn play_game() {
+    let mut state = AlternateMazeState::new();
+    println!("{}", state);
+    while !state.is_done() {
+        let Outcome::Action(action) = greedy_action(&state) else {
+            break;
+        };
+        state.advance(action);
+        println!("{}", state);
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Command {
+    Move(Action),
+    Undo,
+    Quit,
+}
+
+// d/a/s/w move right/left/down/up like the actions 0..4.
+fn parse_command(line: &str) -> Option<Command> {
+    match line.trim() {
+        "d" | "right" => Some(Command::Move(0)),
+        "a" | "left" => Some(Command::Move(1)),
+        "s" | "down" => Some(Command::Move(2)),
+        "w" | "up" => Some(Command::Move(3)),
+        "u" | "undo" => Some(Command::Undo),
+        "q" | "quit" => Some(Command::Quit),
+        _ => None,
+    }
+}
+
+// Reads the human's moves line by line from `input`; `undo` takes back the last human move
+// together with the agent's reply. Stops at the end of the game, on `quit` or at the end of
+// the input, and returns the state reached.
+pub fn play_against_human<A: Fn(&AlternateMazeState) -> Outcome>(
+    mut state: AlternateMazeState,
+    agent: A,
+    human_first: bool,
+    input: impl BufRead,
+    mut output: impl Write,
+) -> io::Result<AlternateMazeState> {
+    let mut history = vec![];
+    let mut lines = input.lines();
+    while !state.is_done() {
+        if state.turn.is_multiple_of(2) != human_first {
+            let Outcome::Action(action) = agent(&state) else {
+                break;
+            };
+            writeln!(output, "agent:\t{}", action)?;
+            state.advance(action);
+            continue;
+        }
+        writeln!(output, "{}", state)?;
+        write!(output, "move (w/a/s/d, undo, quit): ")?;
+        output.flush()?;
+        let Some(line) = lines.next() else {
+            break;
+        };
+        match parse_command(&line?) {
+            Some(Command::Move(action)) if state.legal_actions().contains(&action) => {
+                history.push(state);
+                state.advance(action);
+            }
+            Some(Command::Undo) => match history.pop() {
+                Some(previous) => state = previous,
+                None => writeln!(output, "nothing to undo")?,
+            },
+            Some(Command::Quit) => break,
+            _ => writeln!(output, "illegal command")?,
+        }
+    }
+    writeln!(output, "{}", state)?;
+    if state.is_done() {
+        // characters[0] is the first player again once END_TURN (even) turns are played.
+        let status = state.winning_status();
+        let status = match (human_first, status) {
+            (false, WinningStatus::Win) => WinningStatus::Lose,
+            (false, WinningStatus::Lose) => WinningStatus::Win,
+            _ => status,
+        };
+        writeln!(output, "result:\t{:?}", status)?;
+    }
+    Ok(state)
+}
+
+pub fn play_human_game() {
+    let state = AlternateMazeState::new();
+    play_against_human(state, greedy_action, true, io::stdin().lock(), io::stdout()).unwrap();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_game_ends_with_status() {
+        let mut state = AlternateMazeState::with_seed(0);
+        assert_eq!(state.winning_status(), WinningStatus::None);
+        while !state.is_done() {
+            state.advance(random_action(&state).unwrap());
+        }
+        assert_ne!(state.winning_status(), WinningStatus::None);
+        assert_eq!(random_action(&state), Outcome::NoAction);
+    }
+
+    #[test]
+    fn test_play_against_human() {
+        let state = AlternateMazeState::with_seed(0);
+        let mut output = vec![];
+        let input = "s\nundo\nundo\nbogus\nquit\n".as_bytes();
+        let result = play_against_human(state, greedy_action, true, input, &mut output).unwrap();
+        assert_eq!(result.turn, 0);
+        let output = String::from_utf8(output).unwrap();
+        assert_eq!(output.matches("agent:").count(), 1);
+        assert!(output.contains("nothing to undo"));
+        assert!(output.contains("illegal command"));
+
+        let mut output = vec![];
+        let input = "a\na\n".as_bytes();
+        let result = play_against_human(state, greedy_action, false, input, &mut output).unwrap();
+        assert!(result.is_done());
+        assert!(String::from_utf8(output).unwrap().contains("result:"));
+    }
+
+    // Walks every line of play through the pure transition, illegal actions included.
+    #[test]
+    fn test_model_check_next_state() {
+        fn visit(state: &AlternateMazeState, visited: &mut usize) {
+            *visited += 1;
+            let total = state
+                .characters
+                .iter()
+                .map(|c| c.game_score)
+                .sum::<ScoreType>()
+                + state.points.iter().flatten().sum::<ScoreType>();
+            assert_eq!(
+                total,
+                AlternateMazeState::with_seed(0)
+                    .points
+                    .iter()
+                    .flatten()
+                    .sum()
+            );
+            for action in 0..8 {
+                let next_state = state.next_state(action);
+                if state.is_done() || !state.legal_actions().contains(&action) {
+                    assert_eq!(next_state.turn, state.turn);
+                    continue;
+                }
+                assert_eq!(next_state.turn, state.turn + 1);
+                visit(&next_state, visited);
+            }
+        }
+        let mut visited = 0;
+        visit(&AlternateMazeState::with_seed(0), &mut visited);
+        assert!(visited > 1);
+    }
+}
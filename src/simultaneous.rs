@@ -0,0 +1,323 @@
+#![allow(unused)]
+
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use crate::game::{add_score, ActionList, Outcome, WinningStatus};
+use crate::maze_generator::sample_point;
+
+pub type ScoreType = crate::game::ScoreType;
+pub type Action = usize;
+
+pub const HEIGHT: usize = 5;
+pub const WIDTH: usize = 5;
+pub const END_TURN: usize = 20;
+
+const DX: [isize; 4] = [1, -1, 0, 0];
+const DY: [isize; 4] = [0, 0, 1, -1];
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Character {
+    pub y: usize,
+    pub x: usize,
+    pub game_score: ScoreType,
+}
+
+// The maze of section5 where both players move at the same time. Players keep their index,
+// unlike `AlternateMazeState`, since neither of them is "to move".
+#[derive(Debug, Clone, Copy)]
+pub struct SimultaneousMazeState {
+    points: [[ScoreType; WIDTH]; HEIGHT],
+    turn: usize,
+    characters: [Character; 2],
+}
+
+impl SimultaneousMazeState {
+    pub fn new() -> SimultaneousMazeState {
+        Self::generate(&mut rand::thread_rng())
+    }
+
+    pub fn with_seed(seed: u64) -> SimultaneousMazeState {
+        Self::generate(&mut StdRng::seed_from_u64(seed))
+    }
+
+    // Symmetric left to right, so that neither player starts ahead.
+    fn generate(rng: &mut impl Rng) -> SimultaneousMazeState {
+        let y = rng.gen_range(0..HEIGHT);
+        let x = rng.gen_range(0..WIDTH / 2);
+        let characters = [
+            Character {
+                y,
+                x,
+                game_score: 0,
+            },
+            Character {
+                y,
+                x: WIDTH - 1 - x,
+                game_score: 0,
+            },
+        ];
+        let mut points = [[0; WIDTH]; HEIGHT];
+        for (y, row) in points.iter_mut().enumerate() {
+            for x in 0..WIDTH.div_ceil(2) {
+                if characters[0].y == y && characters[0].x == x {
+                    continue;
+                }
+                let point = sample_point(rng, 9);
+                row[x] = point;
+                row[WIDTH - 1 - x] = point;
+            }
+        }
+        SimultaneousMazeState {
+            points,
+            turn: 0,
+            characters,
+        }
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.turn == END_TURN
+    }
+
+    // Both characters move, then collect; two characters on the same point both get it.
+    pub fn advance(&mut self, action0: Action, action1: Action) {
+        for (character, action) in self.characters.iter_mut().zip([action0, action1]) {
+            character.x = character.x.checked_add_signed(DX[action]).unwrap_or(0);
+            character.y = character.y.checked_add_signed(DY[action]).unwrap_or(0);
+        }
+        for character in self.characters.iter_mut() {
+            let point = self.points[character.y][character.x];
+            character.game_score = add_score(character.game_score, point);
+        }
+        for character in self.characters.iter() {
+            self.points[character.y][character.x] = 0;
+        }
+        self.turn += 1;
+    }
+
+    pub fn next_state(&self, action0: Action, action1: Action) -> SimultaneousMazeState {
+        let mut next_state = *self;
+        next_state.advance(action0, action1);
+        next_state
+    }
+
+    pub fn legal_actions(&self, player_id: usize) -> ActionList {
+        let character = &self.characters[player_id];
+        let mut actions = ActionList::new();
+        for act in 0..4 {
+            let ty = character.y.checked_add_signed(DY[act]).unwrap_or(HEIGHT);
+            let tx = character.x.checked_add_signed(DX[act]).unwrap_or(WIDTH);
+            if ty < HEIGHT && tx < WIDTH {
+                actions.push(act);
+            }
+        }
+        actions
+    }
+
+    pub fn winning_status(&self, player_id: usize) -> WinningStatus {
+        if !self.is_done() {
+            return WinningStatus::None;
+        }
+        match self.score(player_id).cmp(&0) {
+            std::cmp::Ordering::Greater => WinningStatus::Win,
+            std::cmp::Ordering::Less => WinningStatus::Lose,
+            std::cmp::Ordering::Equal => WinningStatus::Draw,
+        }
+    }
+
+    pub fn score(&self, player_id: usize) -> ScoreType {
+        self.characters[player_id].game_score - self.characters[1 - player_id].game_score
+    }
+}
+
+impl Default for SimultaneousMazeState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl std::fmt::Display for SimultaneousMazeState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "turn:\t{}", self.turn)?;
+        writeln!(f, "score(A):\t{}", self.characters[0].game_score)?;
+        writeln!(f, "score(B):\t{}", self.characters[1].game_score)?;
+        for h in 0..HEIGHT {
+            for w in 0..WIDTH {
+                let here = |id: usize| self.characters[id].y == h && self.characters[id].x == w;
+                let ch = match (here(0), here(1)) {
+                    (true, true) => '*',
+                    (true, false) => 'A',
+                    (false, true) => 'B',
+                    _ if self.points[h][w] > 0 => (self.points[h][w] as u8 + b'0') as char,
+                    _ => '.',
+                };
+                write!(f, "{}", ch)?;
+            }
+            writeln!(f)?
+        }
+        Ok(())
+    }
+}
+
+pub fn random_action(state: &SimultaneousMazeState, player_id: usize) -> Outcome {
+    random_action_with(state, player_id, &mut rand::thread_rng())
+}
+
+pub fn random_action_with(
+    state: &SimultaneousMazeState,
+    player_id: usize,
+    rng: &mut impl Rng,
+) -> Outcome {
+    let legal_actions = state.legal_actions(player_id);
+    if state.is_done() || legal_actions.is_empty() {
+        return Outcome::NoAction;
+    }
+    Outcome::Action(legal_actions[rng.gen_range(0..legal_actions.len())])
+}
+
+// The payoff of every joint action for player 0, the row player; player 1 gets the opposite.
+// Rows follow `legal_actions(0)` and columns `legal_actions(1)`.
+pub fn payoff_matrix(
+    state: &SimultaneousMazeState,
+    evaluate: impl Fn(&SimultaneousMazeState) -> f64,
+) -> Vec<Vec<f64>> {
+    let columns = state.legal_actions(1);
+    state
+        .legal_actions(0)
+        .into_iter()
+        .map(|action0| {
+            columns
+                .iter()
+                .map(|&action1| evaluate(&state.next_state(action0, action1)))
+                .collect()
+        })
+        .collect()
+}
+
+// A mixed strategy for each player and what the row player expects to win with them.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Equilibrium {
+    pub row: Vec<f64>,
+    pub column: Vec<f64>,
+    pub value: f64,
+}
+
+// Fictitious play on a zero-sum matrix game: every round each player best-responds to how
+// often the other has played each action so far. The frequencies approach a Nash equilibrium,
+// with an error that shrinks like 1/sqrt(iterations).
+pub fn fictitious_play(matrix: &[Vec<f64>], iterations: usize) -> Equilibrium {
+    let rows = matrix.len();
+    let columns = matrix.first().map_or(0, Vec::len);
+    if rows == 0 || columns == 0 {
+        return Equilibrium {
+            row: vec![],
+            column: vec![],
+            value: 0.0,
+        };
+    }
+    let argmax = |values: &[f64]| {
+        (0..values.len())
+            .max_by(|&a, &b| values[a].total_cmp(&values[b]).then(b.cmp(&a)))
+            .unwrap()
+    };
+    let mut row_counts = vec![0usize; rows];
+    let mut column_counts = vec![0usize; columns];
+    // What every row earns against the column player's history, and what every column loses
+    // against the row player's.
+    let mut row_payoffs = vec![0.0; rows];
+    let mut column_payoffs = vec![0.0; columns];
+    let (mut row, mut column) = (0, 0);
+    for _ in 0..iterations.max(1) {
+        row_counts[row] += 1;
+        column_counts[column] += 1;
+        for (i, payoff) in row_payoffs.iter_mut().enumerate() {
+            *payoff += matrix[i][column];
+        }
+        for (j, payoff) in column_payoffs.iter_mut().enumerate() {
+            *payoff -= matrix[row][j];
+        }
+        row = argmax(&row_payoffs);
+        column = argmax(&column_payoffs);
+    }
+    let total = iterations.max(1) as f64;
+    let row: Vec<f64> = row_counts.iter().map(|&n| n as f64 / total).collect();
+    let column: Vec<f64> = column_counts.iter().map(|&n| n as f64 / total).collect();
+    let value = (0..rows)
+        .flat_map(|i| (0..columns).map(move |j| (i, j)))
+        .map(|(i, j)| row[i] * column[j] * matrix[i][j])
+        .sum();
+    Equilibrium { row, column, value }
+}
+
+// Samples the action of `player_id` from the equilibrium of the one-step payoff matrix, an
+// alternative to a tree search like DUCT that needs no playouts but only sees one turn ahead.
+pub fn nash_action_with(
+    state: &SimultaneousMazeState,
+    player_id: usize,
+    iterations: usize,
+    rng: &mut impl Rng,
+) -> Outcome {
+    if state.is_done() {
+        return Outcome::NoAction;
+    }
+    let matrix = payoff_matrix(state, |next_state| next_state.score(0) as f64);
+    let equilibrium = fictitious_play(&matrix, iterations);
+    let strategy = if player_id == 0 {
+        equilibrium.row
+    } else {
+        equilibrium.column
+    };
+    let legal_actions = state.legal_actions(player_id);
+    let mut sample = rng.gen::<f64>();
+    for (&action, &p) in legal_actions.iter().zip(strategy.iter()) {
+        if sample < p {
+            return Outcome::Action(action);
+        }
+        sample -= p;
+    }
+    legal_actions.last().copied().into()
+}
+
+pub fn nash_action(state: &SimultaneousMazeState, player_id: usize) -> Outcome {
+    nash_action_with(state, player_id, 1000, &mut rand::thread_rng())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fictitious_play() {
+        // Matching pennies: both players mix half and half and the game is worth nothing.
+        let pennies = vec![vec![1.0, -1.0], vec![-1.0, 1.0]];
+        let equilibrium = fictitious_play(&pennies, 10000);
+        for p in equilibrium.row.iter().chain(equilibrium.column.iter()) {
+            assert!((p - 0.5).abs() < 0.02, "{:?}", equilibrium);
+        }
+        assert!(equilibrium.value.abs() < 0.02);
+        // The second row dominates, and against it the column player picks the second column,
+        // which pays the row player less.
+        let dominated = vec![vec![1.0, 0.0], vec![3.0, 2.0]];
+        let equilibrium = fictitious_play(&dominated, 1000);
+        assert!(0.99 < equilibrium.row[1] && 0.99 < equilibrium.column[1]);
+        assert!((equilibrium.value - 2.0).abs() < 0.02);
+    }
+
+    #[test]
+    fn test_nash_action_against_random() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut wins = 0;
+        for seed in 0..50 {
+            let mut state = SimultaneousMazeState::with_seed(seed);
+            while !state.is_done() {
+                let action0 = nash_action_with(&state, 0, 200, &mut rng).unwrap();
+                let action1 = random_action_with(&state, 1, &mut rng).unwrap();
+                state.advance(action0, action1);
+            }
+            if state.winning_status(0) == WinningStatus::Win {
+                wins += 1;
+            }
+        }
+        println!("Nash Win Rate:\t{}", wins as f64 / 50.0);
+        assert!(35 <= wins);
+    }
+}
@@ -0,0 +1,271 @@
+#![allow(unused)]
+
+use rand::Rng;
+
+use crate::game::{GameRunner, Outcome, ScoreType, SinglePlayerState};
+
+#[derive(Debug, Clone, Copy)]
+pub struct Comparison {
+    pub games: usize,
+    pub mean_difference: f64,
+    pub wins: usize,
+    pub losses: usize,
+    pub ties: usize,
+    pub win_rate: f64,
+    pub sign_test_p_value: f64,
+    pub wilcoxon_p_value: f64,
+}
+
+impl Comparison {
+    pub fn from_scores(a: &[ScoreType], b: &[ScoreType]) -> Comparison {
+        assert_eq!(a.len(), b.len());
+        let differences: Vec<f64> = a.iter().zip(b).map(|(a, b)| (a - b) as f64).collect();
+        let games = differences.len();
+        let wins = differences.iter().filter(|&&d| d > 0.0).count();
+        let losses = differences.iter().filter(|&&d| d < 0.0).count();
+        let ties = games - wins - losses;
+        Comparison {
+            games,
+            mean_difference: differences.iter().sum::<f64>() / games.max(1) as f64,
+            wins,
+            losses,
+            ties,
+            win_rate: (wins as f64 + 0.5 * ties as f64) / games.max(1) as f64,
+            sign_test_p_value: sign_test(wins, losses),
+            wilcoxon_p_value: wilcoxon_signed_rank_test(&differences),
+        }
+    }
+}
+
+impl std::fmt::Display for Comparison {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "games: {}\tdiff: {:.3}\tW/L/T: {}/{}/{}\twin rate: {:.3}\tsign p: {:.4}\twilcoxon p: {:.4}",
+            self.games,
+            self.mean_difference,
+            self.wins,
+            self.losses,
+            self.ties,
+            self.win_rate,
+            self.sign_test_p_value,
+            self.wilcoxon_p_value
+        )
+    }
+}
+
+pub fn compare_agents<S, E, A, B>(
+    environment: E,
+    agent_a: A,
+    agent_b: B,
+    seeds: impl IntoIterator<Item = u64>,
+) -> Comparison
+where
+    S: SinglePlayerState,
+    E: Fn(u64) -> S,
+    A: Fn(&S) -> Outcome,
+    B: Fn(&S) -> Outcome,
+{
+    let mut runner_a = GameRunner::new(&environment, agent_a);
+    let mut runner_b = GameRunner::new(&environment, agent_b);
+    let (scores_a, scores_b): (Vec<_>, Vec<_>) = seeds
+        .into_iter()
+        .map(|seed| (runner_a.run(seed).score, runner_b.run(seed).score))
+        .unzip();
+    Comparison::from_scores(&scores_a, &scores_b)
+}
+
+// A sample mean with its 95% confidence interval, from the normal approximation.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Estimate {
+    pub samples: usize,
+    pub mean: f64,
+    pub std_error: f64,
+}
+
+impl Estimate {
+    pub fn from_samples(samples: &[f64]) -> Estimate {
+        let n = samples.len();
+        let mean = samples.iter().sum::<f64>() / n.max(1) as f64;
+        let variance = samples.iter().map(|x| (x - mean).powi(2)).sum::<f64>()
+            / n.saturating_sub(1).max(1) as f64;
+        Estimate {
+            samples: n,
+            mean,
+            std_error: (variance / n.max(1) as f64).sqrt(),
+        }
+    }
+
+    pub fn from_scores(scores: &[ScoreType]) -> Estimate {
+        let samples: Vec<f64> = scores.iter().map(|&score| score as f64).collect();
+        Estimate::from_samples(&samples)
+    }
+
+    pub fn low(&self) -> f64 {
+        self.mean - 1.96 * self.std_error
+    }
+
+    pub fn high(&self) -> f64 {
+        self.mean + 1.96 * self.std_error
+    }
+
+    // Whether the intervals are disjoint with this one above, i.e. this mean is larger beyond
+    // the noise of both.
+    pub fn clearly_above(&self, other: &Estimate) -> bool {
+        other.high() < self.low()
+    }
+}
+
+impl std::fmt::Display for Estimate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{:.3} +/- {:.3} (n = {})",
+            self.mean,
+            1.96 * self.std_error,
+            self.samples
+        )
+    }
+}
+
+// Final scores of `n_playouts` uniformly random playouts from `state`, as an estimate of its
+// value. A playout ends when the game does or no action is left.
+pub fn mc_evaluate<S: SinglePlayerState>(state: &S, n_playouts: usize) -> Estimate {
+    mc_evaluate_with(state, n_playouts, &mut rand::thread_rng())
+}
+
+pub fn mc_evaluate_with<S: SinglePlayerState>(
+    state: &S,
+    n_playouts: usize,
+    rng: &mut impl Rng,
+) -> Estimate {
+    let scores: Vec<ScoreType> = (0..n_playouts)
+        .map(|_| {
+            let mut state = state.clone();
+            while !state.is_done() {
+                let legal_actions = state.legal_actions();
+                if legal_actions.is_empty() {
+                    break;
+                }
+                state.advance(legal_actions[rng.gen_range(0..legal_actions.len())]);
+            }
+            state.score()
+        })
+        .collect();
+    Estimate::from_scores(&scores)
+}
+
+pub fn sign_test(wins: usize, losses: usize) -> f64 {
+    let n = wins + losses;
+    if n == 0 {
+        return 1.0;
+    }
+    let k = wins.min(losses);
+    let mut term = 0.5_f64.powi(n as i32);
+    let mut tail = 0.0;
+    for i in 0..=k {
+        tail += term;
+        term *= (n - i) as f64 / (i + 1) as f64;
+    }
+    (2.0 * tail).min(1.0)
+}
+
+// Normal approximation; zero differences are dropped, and tied magnitudes share their mean
+// rank and shrink the variance by (t^3 - t) / 48 for every group of t ties.
+pub fn wilcoxon_signed_rank_test(differences: &[f64]) -> f64 {
+    let mut nonzero: Vec<f64> = differences.iter().copied().filter(|&d| d != 0.0).collect();
+    let n = nonzero.len();
+    if n == 0 {
+        return 1.0;
+    }
+    nonzero.sort_by(|a, b| a.abs().total_cmp(&b.abs()));
+    let mut w_plus = 0.0;
+    let mut ties = 0.0;
+    let mut i = 0;
+    while i < n {
+        let mut j = i;
+        while j + 1 < n && nonzero[j + 1].abs() == nonzero[i].abs() {
+            j += 1;
+        }
+        let rank = (i + j) as f64 / 2.0 + 1.0;
+        w_plus += rank * nonzero[i..=j].iter().filter(|&&d| d > 0.0).count() as f64;
+        let t = (j - i + 1) as f64;
+        ties += t * t * t - t;
+        i = j + 1;
+    }
+    let n = n as f64;
+    let mean = n * (n + 1.0) / 4.0;
+    let sd = (n * (n + 1.0) * (2.0 * n + 1.0) / 24.0 - ties / 48.0).sqrt();
+    if sd == 0.0 {
+        return 1.0;
+    }
+    let z = (w_plus - mean).abs() / sd;
+    (2.0 * (1.0 - normal_cdf(z))).min(1.0)
+}
+
+pub fn normal_cdf(x: f64) -> f64 {
+    0.5 * erfc(-x / std::f64::consts::SQRT_2)
+}
+
+// Abramowitz and Stegun 7.1.26.
+fn erfc(x: f64) -> f64 {
+    let z = x.abs();
+    let t = 1.0 / (1.0 + 0.3275911 * z);
+    let poly = t
+        * (0.254829592
+            + t * (-0.284496736 + t * (1.421413741 + t * (-1.453152027 + t * 1.061405429))));
+    let r = poly * (-z * z).exp();
+    if x >= 0.0 {
+        r
+    } else {
+        2.0 - r
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sign_test() {
+        assert_eq!(sign_test(10, 0), 2.0 / 1024.0);
+        assert_eq!(sign_test(5, 5), 1.0);
+        assert_eq!(sign_test(0, 0), 1.0);
+    }
+
+    #[test]
+    fn test_wilcoxon_signed_rank_test() {
+        let positive: Vec<f64> = (1..=20).map(|d| d as f64).collect();
+        assert!(wilcoxon_signed_rank_test(&positive) < 0.001);
+        let symmetric: Vec<f64> = (-10..=10).map(|d| d as f64).collect();
+        assert!(wilcoxon_signed_rank_test(&symmetric) > 0.99);
+        // With every magnitude tied it is the sign test's normal approximation:
+        // z = (6 - 4) / sqrt(8 / 4).
+        let tied = [1.0, 1.0, 1.0, 1.0, 1.0, 1.0, -1.0, -1.0];
+        let expected = 2.0 * (1.0 - normal_cdf(2.0_f64.sqrt()));
+        assert!((wilcoxon_signed_rank_test(&tied) - expected).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_estimate() {
+        let estimate = Estimate::from_samples(&[1.0, 2.0, 3.0, 4.0]);
+        assert_eq!(estimate.mean, 2.5);
+        assert!((estimate.std_error - (5.0_f64 / 12.0).sqrt()).abs() < 1e-12);
+        assert!(estimate.low() < 2.5 && 2.5 < estimate.high());
+        let higher = Estimate::from_samples(&[10.0, 11.0, 12.0, 13.0]);
+        assert!(higher.clearly_above(&estimate));
+        assert!(!estimate.clearly_above(&higher));
+        assert_eq!(Estimate::from_samples(&[]).mean, 0.0);
+    }
+
+    #[test]
+    fn test_comparison_from_scores() {
+        let comparison = Comparison::from_scores(&[3, 5, 2, 7], &[1, 5, 4, 2]);
+        assert_eq!(
+            (comparison.wins, comparison.losses, comparison.ties),
+            (2, 1, 1)
+        );
+        assert_eq!(comparison.mean_difference, 1.25);
+        assert_eq!(comparison.win_rate, 0.625);
+    }
+}
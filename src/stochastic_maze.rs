@@ -0,0 +1,262 @@
+#![allow(unused)]
+
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use crate::expectimax::StochasticState;
+use crate::game::{add_score, ActionList, Outcome, RngStreams};
+
+pub type ScoreType = crate::game::ScoreType;
+pub type Action = usize;
+
+pub const HEIGHT: usize = 5;
+pub const WIDTH: usize = 5;
+pub const END_TURN: usize = 12;
+const MAX_POINT: ScoreType = 9;
+// The character stays in place.
+pub const FAIL_PROBABILITY: f64 = 0.2;
+// A collected point stays on its cell.
+pub const RESPAWN_PROBABILITY: f64 = 0.3;
+
+const DX: [isize; 4] = [1, -1, 0, 0];
+const DY: [isize; 4] = [0, 0, 1, -1];
+
+#[derive(Debug, Clone, Copy)]
+pub struct StochasticMazeState {
+    pub y: usize,
+    pub x: usize,
+    pub game_score: ScoreType,
+    points: [[ScoreType; WIDTH]; HEIGHT],
+    turn: usize,
+}
+
+impl StochasticMazeState {
+    pub fn new() -> StochasticMazeState {
+        Self::generate(&mut rand::thread_rng())
+    }
+
+    pub fn with_seed(seed: u64) -> StochasticMazeState {
+        Self::generate(&mut StdRng::seed_from_u64(seed))
+    }
+
+    fn generate(rng: &mut impl Rng) -> StochasticMazeState {
+        let y = rng.gen_range(0..HEIGHT);
+        let x = rng.gen_range(0..WIDTH);
+        let mut points = [[0; WIDTH]; HEIGHT];
+        for (py, row) in points.iter_mut().enumerate() {
+            for (px, point) in row.iter_mut().enumerate() {
+                if py == y && px == x {
+                    continue;
+                }
+                *point = rng.gen_range(0..=MAX_POINT);
+            }
+        }
+        StochasticMazeState {
+            y,
+            x,
+            game_score: 0,
+            points,
+            turn: 0,
+        }
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.turn == END_TURN
+    }
+
+    pub fn legal_actions(&self) -> ActionList {
+        (0..4)
+            .filter(|&action| {
+                let ty = self.y.checked_add_signed(DY[action]).unwrap_or(HEIGHT);
+                let tx = self.x.checked_add_signed(DX[action]).unwrap_or(WIDTH);
+                ty < HEIGHT && tx < WIDTH
+            })
+            .collect()
+    }
+
+    // Point on the cell `action` leads to.
+    pub fn target_point(&self, action: Action) -> ScoreType {
+        let y = self.y.checked_add_signed(DY[action]).unwrap_or(0);
+        let x = self.x.checked_add_signed(DX[action]).unwrap_or(0);
+        self.points[y][x]
+    }
+
+    fn moved(&self, action: Action, respawn: bool) -> StochasticMazeState {
+        let mut state = *self;
+        state.y = state.y.checked_add_signed(DY[action]).unwrap_or(0);
+        state.x = state.x.checked_add_signed(DX[action]).unwrap_or(0);
+        state.game_score = add_score(state.game_score, state.points[state.y][state.x]);
+        if !respawn {
+            state.points[state.y][state.x] = 0;
+        }
+        state.turn += 1;
+        state
+    }
+
+    fn failed(&self) -> StochasticMazeState {
+        let mut state = *self;
+        state.turn += 1;
+        state
+    }
+
+    // Samples one of `outcomes(action)`.
+    pub fn advance(&mut self, action: Action, rng: &mut impl Rng) {
+        *self = if rng.gen_bool(FAIL_PROBABILITY) {
+            self.failed()
+        } else {
+            self.moved(action, rng.gen_bool(RESPAWN_PROBABILITY))
+        };
+    }
+}
+
+impl std::fmt::Display for StochasticMazeState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "turn:\t{}", self.turn)?;
+        writeln!(f, "score:\t{}", self.game_score)?;
+        for h in 0..HEIGHT {
+            for w in 0..WIDTH {
+                let ch = if self.y == h && self.x == w {
+                    '@'
+                } else if self.points[h][w] > 0 {
+                    (self.points[h][w] as u8 + b'0') as char
+                } else {
+                    '.'
+                };
+                write!(f, "{}", ch)?;
+            }
+            writeln!(f)?
+        }
+        Ok(())
+    }
+}
+
+impl Default for StochasticMazeState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl StochasticState for StochasticMazeState {
+    fn is_done(&self) -> bool {
+        StochasticMazeState::is_done(self)
+    }
+
+    fn legal_actions(&self) -> ActionList {
+        StochasticMazeState::legal_actions(self)
+    }
+
+    fn outcomes(&self, action: Action) -> Vec<(f64, Self)> {
+        let success = 1.0 - FAIL_PROBABILITY;
+        if self.target_point(action) == 0 {
+            return vec![
+                (success, self.moved(action, false)),
+                (FAIL_PROBABILITY, self.failed()),
+            ];
+        }
+        vec![
+            (success * RESPAWN_PROBABILITY, self.moved(action, true)),
+            (
+                success * (1.0 - RESPAWN_PROBABILITY),
+                self.moved(action, false),
+            ),
+            (FAIL_PROBABILITY, self.failed()),
+        ]
+    }
+
+    fn evaluate(&self) -> f64 {
+        self.game_score as f64
+    }
+
+    // No line can gain more than MAX_POINT per remaining turn.
+    fn value_bounds(&self) -> (f64, f64) {
+        let rest = (END_TURN - self.turn) as ScoreType;
+        (
+            self.game_score as f64,
+            (self.game_score + MAX_POINT * rest) as f64,
+        )
+    }
+}
+
+// Deterministic planner: takes the largest point next to the character as if moves never fail.
+pub fn greedy_action(state: &StochasticMazeState) -> Outcome {
+    state
+        .legal_actions()
+        .into_iter()
+        .max_by_key(|&action| state.target_point(action))
+        .into()
+}
+
+pub fn random_action(state: &StochasticMazeState) -> Outcome {
+    random_action_with(state, &mut rand::thread_rng())
+}
+
+pub fn random_action_with(state: &StochasticMazeState, rng: &mut impl Rng) -> Outcome {
+    let legal_actions = state.legal_actions();
+    if legal_actions.is_empty() {
+        return Outcome::NoAction;
+    }
+    Outcome::Action(legal_actions[rng.gen_range(0..legal_actions.len())])
+}
+
+// The substream of a game's seed its chance events are drawn from. The map comes from the seed
+// itself, so changing how the dice are rolled never changes the board.
+pub const CHANCE_STREAM: u64 = 1;
+
+// Final score of one game on the map of `seed`, with chance events drawn from its
+// `CHANCE_STREAM`.
+pub fn play<A: Fn(&StochasticMazeState) -> Outcome>(seed: u64, agent: A) -> ScoreType {
+    let mut rng = RngStreams::new(seed).stream(CHANCE_STREAM).rng();
+    let mut state = StochasticMazeState::with_seed(seed);
+    while !state.is_done() {
+        let Outcome::Action(action) = agent(&state) else {
+            break;
+        };
+        state.advance(action, &mut rng);
+    }
+    state.game_score
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::expectimax::{expectimax_action, Pruning};
+
+    const GAME_NUMBER: u64 = 50;
+
+    fn mean_score<A: Fn(&StochasticMazeState) -> Outcome>(agent: A) -> f64 {
+        (0..GAME_NUMBER)
+            .map(|seed| play(seed, &agent))
+            .sum::<ScoreType>() as f64
+            / GAME_NUMBER as f64
+    }
+
+    #[test]
+    fn test_outcomes_are_distribution() {
+        let state = StochasticMazeState::with_seed(0);
+        for action in state.legal_actions() {
+            let total: f64 = state.outcomes(action).iter().map(|(p, _)| p).sum();
+            assert!((total - 1.0).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn test_chance_stream_is_not_the_map_stream() {
+        // The dice do not replay the draws that laid out the map.
+        let mut map = StdRng::seed_from_u64(0);
+        let mut chance = RngStreams::new(0).stream(CHANCE_STREAM).rng();
+        let map_draws: Vec<u64> = (0..8).map(|_| map.gen()).collect();
+        let chance_draws: Vec<u64> = (0..8).map(|_| chance.gen()).collect();
+        assert_ne!(map_draws, chance_draws);
+        assert_eq!(play(3, greedy_action), play(3, greedy_action));
+    }
+
+    #[test]
+    fn test_expectimax_vs_greedy() {
+        let greedy = mean_score(greedy_action);
+        let expectimax = mean_score(|s| expectimax_action(s, 3, Pruning::Star1));
+        let random = mean_score(random_action);
+        println!("Stochastic Random Score:\t{}", random);
+        println!("Stochastic Greedy Score:\t{}", greedy);
+        println!("Stochastic Expectimax Score:\t{}", expectimax);
+        assert!(greedy <= expectimax);
+    }
+}
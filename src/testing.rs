@@ -0,0 +1,241 @@
+#![allow(unused)]
+
+use proptest::prelude::*;
+use proptest::test_runner::TestCaseError;
+
+use smallvec::smallvec;
+
+use crate::game::{Action, ActionList, ScoreType, SinglePlayerState};
+use crate::learning::Features;
+use crate::maze_generator::MazeGenerator;
+use crate::section3::MazeState;
+
+// Section3 mazes from any seed, with up to 30% walls.
+pub fn arbitrary_maze_state() -> impl Strategy<Value = MazeState> {
+    (any::<u64>(), 0.0..0.3).prop_map(|(seed, wall_density)| {
+        let generator = MazeGenerator {
+            wall_density,
+            ..Default::default()
+        };
+        MazeState::with_generator(seed, &generator).unwrap()
+    })
+}
+
+// Any state built by a seeded constructor such as `with_seed`.
+pub fn arbitrary_seeded_state<S: std::fmt::Debug>(
+    environment: fn(u64) -> S,
+) -> impl Strategy<Value = S> {
+    any::<u64>().prop_map(environment)
+}
+
+// Raw action indices; `play_sequence` maps each one onto the legal actions of the moment, so
+// every sequence is playable on every state.
+pub fn arbitrary_action_sequence(max_len: usize) -> impl Strategy<Value = Vec<Action>> {
+    prop::collection::vec(0..16usize, 0..=max_len)
+}
+
+// Plays `actions` and calls `check(before, after)` after every turn.
+pub fn play_sequence<S: SinglePlayerState>(
+    state: &S,
+    actions: &[Action],
+    mut check: impl FnMut(&S, &S) -> Result<(), TestCaseError>,
+) -> Result<S, TestCaseError> {
+    let mut state = state.clone();
+    for &index in actions {
+        let legal_actions = state.legal_actions();
+        if state.is_done() || legal_actions.is_empty() {
+            break;
+        }
+        let mut next_state = state.clone();
+        next_state.advance(legal_actions[index % legal_actions.len()]);
+        check(&state, &next_state)?;
+        state = next_state;
+    }
+    Ok(state)
+}
+
+pub fn check_score_never_decreases<S: SinglePlayerState>(
+    state: &S,
+    actions: &[Action],
+) -> Result<(), TestCaseError> {
+    play_sequence(state, actions, |before, after| {
+        prop_assert!(
+            before.score() <= after.score(),
+            "score went from {} to {}",
+            before.score(),
+            after.score()
+        );
+        Ok(())
+    })
+    .map(|_| ())
+}
+
+pub fn check_stays_in_bounds<S: SinglePlayerState + Features>(
+    state: &S,
+    actions: &[Action],
+) -> Result<(), TestCaseError> {
+    play_sequence(state, actions, |_, after| {
+        let (height, width) = after.grid_size();
+        let (y, x) = after.position();
+        prop_assert!(y < height && x < width, "({}, {}) is off the grid", y, x);
+        Ok(())
+    })
+    .map(|_| ())
+}
+
+// Every check above that applies to grid states.
+pub fn check_invariants<S: SinglePlayerState + Features>(
+    state: &S,
+    actions: &[Action],
+) -> Result<(), TestCaseError> {
+    check_score_never_decreases(state, actions)?;
+    check_stays_in_bounds(state, actions)
+}
+
+// A game of `rest` turns for checking exact searches by hand. Action 1 pays 1, or 5 once action
+// 0 has been played; action 0 pays nothing.
+#[derive(Debug, Clone)]
+pub struct Ladder {
+    pub rest: usize,
+    pub bonus: bool,
+    pub score: ScoreType,
+}
+
+impl Ladder {
+    pub fn new(rest: usize) -> Ladder {
+        Ladder {
+            rest,
+            bonus: false,
+            score: 0,
+        }
+    }
+}
+
+impl SinglePlayerState for Ladder {
+    fn is_done(&self) -> bool {
+        self.rest == 0
+    }
+
+    fn advance(&mut self, action: Action) {
+        self.rest -= 1;
+        if action == 0 {
+            self.bonus = true;
+        } else {
+            self.score += if self.bonus { 5 } else { 1 };
+        }
+    }
+
+    fn legal_actions(&self) -> ActionList {
+        smallvec![0, 1]
+    }
+
+    fn score(&self) -> ScoreType {
+        self.score
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cooperative_maze::CooperativeMazeState;
+    use crate::expectimax::StochasticState;
+    use crate::game::TwoPlayerState;
+    use crate::section5::AlternateMazeState;
+    use crate::stochastic_maze::StochasticMazeState;
+
+    proptest! {
+        #[test]
+        fn maze_state_invariants(
+            state in arbitrary_maze_state(),
+            actions in arbitrary_action_sequence(120),
+        ) {
+            check_invariants(&state, &actions)?;
+        }
+
+        #[test]
+        fn maze_state_text_round_trip(state in arbitrary_maze_state()) {
+            let loaded: MazeState = state.to_string().parse().unwrap();
+            prop_assert_eq!(loaded.to_string(), state.to_string());
+        }
+
+        #[test]
+        fn cooperative_maze_state_score(
+            state in arbitrary_seeded_state(CooperativeMazeState::with_seed),
+            actions in arbitrary_action_sequence(20),
+        ) {
+            check_score_never_decreases(&state, &actions)?;
+        }
+
+        #[test]
+        fn next_state_accepts_any_action(
+            state in arbitrary_maze_state(),
+            seed in any::<u64>(),
+            actions in prop::collection::vec(any::<usize>(), 0..50),
+            chances in prop::collection::vec(0.0..1.0, 50),
+        ) {
+            let mut maze = state;
+            let mut alternate = AlternateMazeState::with_seed(seed);
+            let mut stochastic = StochasticMazeState::with_seed(seed);
+            for (&action, &chance) in actions.iter().zip(&chances) {
+                let next_maze = SinglePlayerState::next_state(&maze, action);
+                prop_assert!(maze.game_score <= next_maze.game_score);
+                maze = next_maze;
+                alternate = TwoPlayerState::next_state(&alternate, action);
+                stochastic = StochasticState::next_state(&stochastic, action, chance);
+            }
+        }
+    }
+
+    // Compiles only while the type can move to and be shared between threads.
+    fn assert_send_sync<T: Send + Sync>() {}
+
+    #[test]
+    fn test_send_sync() {
+        use crate::alpha_beta::{AlphaBetaConfig, AlphaBetaResult};
+        use crate::arena::Arena;
+        use crate::grid::{
+            CowGrid, DenseGrid, GridMazeState, PackedGrid, PersistentGrid, SparseGrid,
+        };
+        use crate::mcts::{MctsConfig, PonderingMcts, Tree};
+        use crate::opening_book::OpeningBook;
+        use crate::partial_maze::{Belief, PartialMazeState, SoloMaze};
+        use crate::search_tree::SearchTree;
+        use crate::section3::{
+            AutoBeamSearcher, BeamStepper, BucketQueue, ChokudaiSearch, TieBreakQueue,
+        };
+        use crate::section4::{AutoMoveMazeState, LocalSearch, Metropolis, OperatorSelector};
+        use crate::simultaneous::SimultaneousMazeState;
+        use crate::tsp::TspState;
+
+        assert_send_sync::<MazeState>();
+        assert_send_sync::<AlternateMazeState>();
+        assert_send_sync::<CooperativeMazeState>();
+        assert_send_sync::<StochasticMazeState>();
+        assert_send_sync::<PartialMazeState>();
+        assert_send_sync::<Belief>();
+        assert_send_sync::<SoloMaze>();
+        assert_send_sync::<SimultaneousMazeState>();
+        assert_send_sync::<TspState>();
+        assert_send_sync::<GridMazeState<DenseGrid>>();
+        assert_send_sync::<GridMazeState<SparseGrid>>();
+        assert_send_sync::<GridMazeState<PackedGrid>>();
+        assert_send_sync::<GridMazeState<CowGrid>>();
+        assert_send_sync::<GridMazeState<PersistentGrid>>();
+        assert_send_sync::<BucketQueue>();
+        assert_send_sync::<TieBreakQueue>();
+        assert_send_sync::<BeamStepper>();
+        assert_send_sync::<ChokudaiSearch>();
+        assert_send_sync::<AutoBeamSearcher>();
+        assert_send_sync::<SearchTree>();
+        assert_send_sync::<Arena<MazeState>>();
+        assert_send_sync::<MctsConfig>();
+        assert_send_sync::<Tree<AlternateMazeState>>();
+        assert_send_sync::<PonderingMcts<AlternateMazeState>>();
+        assert_send_sync::<AlphaBetaConfig>();
+        assert_send_sync::<AlphaBetaResult>();
+        assert_send_sync::<OpeningBook>();
+        assert_send_sync::<AutoMoveMazeState>();
+        assert_send_sync::<LocalSearch<'static, Metropolis>>();
+        assert_send_sync::<OperatorSelector>();
+    }
+}
@@ -0,0 +1,402 @@
+#![allow(unused)]
+
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex,
+    },
+    thread,
+    time::Duration,
+};
+
+use crate::game::{parallel_map, Clock, FischerClock, Outcome, TwoPlayerState, WinningStatus};
+use crate::learning::Features;
+
+type Agent<'a, S> = Box<dyn Fn(&S) -> Outcome + 'a>;
+
+// Returns the points of the first player: 1.0 for a win, 0.5 for a draw and 0.0 for a loss.
+// A player who has no action to play loses the game.
+pub fn play_match<S: TwoPlayerState>(
+    mut state: S,
+    first: &dyn Fn(&S) -> Outcome,
+    second: &dyn Fn(&S) -> Outcome,
+) -> f64 {
+    let mut first_to_move = true;
+    while !state.is_done() {
+        let agent = if first_to_move { first } else { second };
+        let Outcome::Action(action) = agent(&state) else {
+            return if first_to_move { 0.0 } else { 1.0 };
+        };
+        state.advance(action);
+        first_to_move = !first_to_move;
+    }
+    let points = match state.winning_status() {
+        WinningStatus::Win => 1.0,
+        WinningStatus::Lose => 0.0,
+        WinningStatus::Draw | WinningStatus::None => 0.5,
+    };
+    if first_to_move {
+        points
+    } else {
+        1.0 - points
+    }
+}
+
+// An agent that is told how long it may think about the move.
+pub type TimedAgent<'a, S> = dyn Fn(&S, Duration) -> Outcome + 'a;
+
+// `play_match` where each player has its own `control` clock, read from `clock`. Every move is
+// given the clock's budget for the moves the player has left, and a player who runs out of
+// time loses.
+pub fn play_timed_match<S: TwoPlayerState + Features>(
+    mut state: S,
+    first: &TimedAgent<S>,
+    second: &TimedAgent<S>,
+    control: FischerClock,
+    clock: &impl Clock,
+) -> f64 {
+    let mut controls = [control; 2];
+    let mut first_to_move = true;
+    while !state.is_done() {
+        let (agent, control) = if first_to_move {
+            (first, &mut controls[0])
+        } else {
+            (second, &mut controls[1])
+        };
+        let budget = control.move_budget(state.remaining_turns().div_ceil(2));
+        let start = clock.now();
+        let outcome = agent(&state, budget);
+        let Outcome::Action(action) = outcome else {
+            return if first_to_move { 0.0 } else { 1.0 };
+        };
+        if !control.punch(clock.now() - start) {
+            return if first_to_move { 0.0 } else { 1.0 };
+        }
+        state.advance(action);
+        first_to_move = !first_to_move;
+    }
+    let points = match state.winning_status() {
+        WinningStatus::Win => 1.0,
+        WinningStatus::Lose => 0.0,
+        WinningStatus::Draw | WinningStatus::None => 0.5,
+    };
+    if first_to_move {
+        points
+    } else {
+        1.0 - points
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Record {
+    pub wins: usize,
+    pub draws: usize,
+    pub losses: usize,
+}
+
+impl Record {
+    fn add(&mut self, points: f64) {
+        if 0.5 < points {
+            self.wins += 1;
+        } else if points < 0.5 {
+            self.losses += 1;
+        } else {
+            self.draws += 1;
+        }
+    }
+
+    pub fn games(&self) -> usize {
+        self.wins + self.draws + self.losses
+    }
+
+    // Points per game, a draw counting half.
+    pub fn score_rate(&self) -> f64 {
+        (self.wins as f64 + 0.5 * self.draws as f64) / self.games().max(1) as f64
+    }
+}
+
+// One agent's results against another, split by the seat it played.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SelfPlayResult {
+    pub moving_first: Record,
+    pub moving_second: Record,
+}
+
+impl SelfPlayResult {
+    pub fn total(&self) -> Record {
+        Record {
+            wins: self.moving_first.wins + self.moving_second.wins,
+            draws: self.moving_first.draws + self.moving_second.draws,
+            losses: self.moving_first.losses + self.moving_second.losses,
+        }
+    }
+}
+
+impl std::fmt::Display for SelfPlayResult {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "seat\twin\tdraw\tloss\trate")?;
+        for (seat, record) in [
+            ("first", self.moving_first),
+            ("second", self.moving_second),
+            ("total", self.total()),
+        ] {
+            writeln!(
+                f,
+                "{}\t{}\t{}\t{}\t{:.3}",
+                seat,
+                record.wins,
+                record.draws,
+                record.losses,
+                record.score_rate()
+            )?;
+        }
+        Ok(())
+    }
+}
+
+// Plays `agent` against `opponent` twice from every seed, once in each seat, so that neither
+// gains from the start position. The games are spread over `thread_number` threads.
+pub fn self_play<S, E>(
+    environment: &E,
+    agent: &(dyn Fn(&S) -> Outcome + Sync),
+    opponent: &(dyn Fn(&S) -> Outcome + Sync),
+    seeds: impl IntoIterator<Item = u64>,
+    thread_number: usize,
+) -> SelfPlayResult
+where
+    S: TwoPlayerState,
+    E: Fn(u64) -> S + Sync,
+{
+    let seeds: Vec<u64> = seeds.into_iter().collect();
+    let points = parallel_map(seeds.len(), thread_number, |i| {
+        let first = play_match(environment(seeds[i]), agent, opponent);
+        let second = 1.0 - play_match(environment(seeds[i]), opponent, agent);
+        (first, second)
+    });
+    let mut result = SelfPlayResult::default();
+    for (first, second) in points {
+        result.moving_first.add(first);
+        result.moving_second.add(second);
+    }
+    result
+}
+
+#[derive(Debug, Clone)]
+pub struct Rating {
+    pub name: String,
+    pub elo: f64,
+    // Half width of the 95% confidence interval.
+    pub error: f64,
+    pub games: usize,
+    pub points: f64,
+}
+
+#[derive(Debug, Clone)]
+pub struct Standings {
+    pub ratings: Vec<Rating>,
+    // points[i][j] is what agent i scored against agent j.
+    pub points: Vec<Vec<f64>>,
+    pub games: Vec<Vec<usize>>,
+}
+
+impl std::fmt::Display for Standings {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "name\telo\tgames\tpoints")?;
+        for rating in self.ratings.iter() {
+            writeln!(
+                f,
+                "{}\t{:.0} +/- {:.0}\t{}\t{}",
+                rating.name, rating.elo, rating.error, rating.games, rating.points
+            )?;
+        }
+        Ok(())
+    }
+}
+
+pub struct Tournament<'a, S, E> {
+    environment: E,
+    agents: Vec<(String, Agent<'a, S>)>,
+}
+
+impl<'a, S, E> Tournament<'a, S, E>
+where
+    S: TwoPlayerState,
+    E: Fn(u64) -> S,
+{
+    pub fn new(environment: E) -> Self {
+        Tournament {
+            environment,
+            agents: vec![],
+        }
+    }
+
+    pub fn add_agent(mut self, name: &str, agent: impl Fn(&S) -> Outcome + 'a) -> Self {
+        self.agents.push((name.to_string(), Box::new(agent)));
+        self
+    }
+
+    // Every pair plays `game_number` games in each seat on the same seeds.
+    pub fn run(&self, game_number: usize) -> Standings {
+        let n = self.agents.len();
+        let mut points = vec![vec![0.0; n]; n];
+        let mut games = vec![vec![0; n]; n];
+        for i in 0..n {
+            for j in 0..n {
+                if i == j {
+                    continue;
+                }
+                for seed in 0..game_number as u64 {
+                    let state = (self.environment)(seed);
+                    let result = play_match(state, &self.agents[i].1, &self.agents[j].1);
+                    points[i][j] += result;
+                    points[j][i] += 1.0 - result;
+                    games[i][j] += 1;
+                    games[j][i] += 1;
+                }
+            }
+        }
+        let names: Vec<String> = self.agents.iter().map(|(name, _)| name.clone()).collect();
+        let mut ratings = elo_ratings(&names, &points, &games);
+        ratings.sort_by(|a, b| b.elo.total_cmp(&a.elo));
+        Standings {
+            ratings,
+            points,
+            games,
+        }
+    }
+}
+
+// Bradley-Terry fit by the MM algorithm. One virtual draw is added per pair so that
+// agents without any win still get a finite rating.
+pub fn elo_ratings(names: &[String], points: &[Vec<f64>], games: &[Vec<usize>]) -> Vec<Rating> {
+    const ITERATION: usize = 1000;
+    let n = names.len();
+    let played = |i: usize, j: usize| i != j && games[i][j] > 0;
+    let wins = |i: usize, j: usize| points[i][j] + 0.5;
+    let count = |i: usize, j: usize| games[i][j] as f64 + 1.0;
+    let mut gamma = vec![1.0; n];
+    for _ in 0..ITERATION {
+        for i in 0..n {
+            let (mut w, mut d) = (0.0, 0.0);
+            for j in (0..n).filter(|&j| played(i, j)) {
+                w += wins(i, j);
+                d += count(i, j) / (gamma[i] + gamma[j]);
+            }
+            if 0.0 < d {
+                gamma[i] = w / d;
+            }
+        }
+        let log_mean = gamma.iter().map(|g: &f64| g.ln()).sum::<f64>() / n.max(1) as f64;
+        for g in gamma.iter_mut() {
+            *g /= log_mean.exp();
+        }
+    }
+    let scale = 400.0 / std::f64::consts::LN_10;
+    (0..n)
+        .map(|i| {
+            let mut information = 0.0;
+            for j in (0..n).filter(|&j| played(i, j)) {
+                let p = gamma[i] / (gamma[i] + gamma[j]);
+                information += count(i, j) * p * (1.0 - p);
+            }
+            Rating {
+                name: names[i].clone(),
+                elo: scale * gamma[i].ln(),
+                error: 1.96 * scale / information.sqrt(),
+                games: games[i].iter().sum(),
+                points: points[i].iter().sum(),
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::MockClock;
+    use crate::mcts::{mcts_action_with_clock, MctsConfig};
+    use crate::section5::{greedy_action, random_action, AlternateMazeState};
+
+    #[test]
+    fn test_play_match_no_action_loses() {
+        let state = AlternateMazeState::with_seed(0);
+        assert_eq!(
+            play_match(state, &|_| Outcome::NoAction, &random_action),
+            0.0
+        );
+        assert_eq!(
+            play_match(state, &random_action, &|_| Outcome::NoAction),
+            1.0
+        );
+    }
+
+    #[test]
+    fn test_tournament() {
+        let standings = Tournament::new(AlternateMazeState::with_seed)
+            .add_agent("random", random_action)
+            .add_agent("greedy", greedy_action)
+            .run(50);
+        println!("{}", standings);
+        assert_eq!(standings.ratings[0].name, "greedy");
+        assert_eq!(standings.ratings[0].games, 100);
+        let total: f64 = standings.ratings.iter().map(|r| r.points).sum();
+        assert_eq!(total, 100.0);
+    }
+
+    #[test]
+    fn test_self_play() {
+        let result = self_play(
+            &AlternateMazeState::with_seed,
+            &greedy_action,
+            &random_action,
+            0..50,
+            4,
+        );
+        println!("{}", result);
+        assert_eq!(result.moving_first.games(), 50);
+        assert_eq!(result.moving_second.games(), 50);
+        assert!(0.5 < result.total().score_rate());
+        // A deterministic agent against itself wins exactly the games it loses in the other seat.
+        let mirror = self_play(
+            &AlternateMazeState::with_seed,
+            &greedy_action,
+            &greedy_action,
+            0..20,
+            2,
+        );
+        assert_eq!(mirror.moving_first.wins, mirror.moving_second.losses);
+        assert_eq!(mirror.total().wins, mirror.total().losses);
+    }
+
+    #[test]
+    fn test_play_timed_match() {
+        let state = AlternateMazeState::with_seed(0);
+        let control = FischerClock::new(Duration::from_millis(100), Duration::from_millis(10));
+        let clock = MockClock::new();
+        let mcts = |s: &AlternateMazeState, budget: Duration| {
+            let thinking = MockClock::ticking(Duration::from_millis(1));
+            let outcome = mcts_action_with_clock(s, &MctsConfig::default(), budget, &thinking);
+            clock.advance(thinking.now());
+            outcome
+        };
+        let slow = |s: &AlternateMazeState, budget: Duration| {
+            clock.advance(budget + Duration::from_millis(100));
+            random_action(s)
+        };
+        assert_eq!(play_timed_match(state, &mcts, &slow, control, &clock), 1.0);
+        assert_eq!(play_timed_match(state, &slow, &mcts, control, &clock), 0.0);
+    }
+
+    #[test]
+    fn test_elo_ratings_symmetric() {
+        let names = vec!["a".to_string(), "b".to_string()];
+        let ratings = elo_ratings(
+            &names,
+            &[vec![0.0, 5.0], vec![5.0, 0.0]],
+            &[vec![0, 10], vec![10, 0]],
+        );
+        assert!(ratings[0].elo.abs() < 1e-6);
+        assert!(ratings[1].elo.abs() < 1e-6);
+        assert!(0.0 < ratings[0].error);
+    }
+}
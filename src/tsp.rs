@@ -0,0 +1,150 @@
+#![allow(unused)]
+
+use std::{fmt, sync::Arc};
+
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use crate::game::{Action, ActionList, ScoreType, SinglePlayerState};
+
+pub const CITY_NUMBER: usize = 20;
+// Cities sit on a SIZE x SIZE square.
+pub const SIZE: i64 = 100;
+// Longer than any leg on the square, so every leg scores something.
+pub const MAX_LEG: ScoreType = 142;
+
+// The travelling salesman as a single player game: the tour starts at city 0, every action is
+// the next city to visit, and the game ends when the last city closes the tour back to city 0.
+// A leg of length d scores `MAX_LEG - d`, so the score of a finished tour is
+// `MAX_LEG * cities - length` and the shortest tour scores most. Lengths are Euclidean, rounded
+// to the nearest integer as in TSPLIB.
+#[derive(Debug, Clone)]
+pub struct TspState {
+    cities: Arc<[(i64, i64)]>,
+    tour: Vec<usize>,
+    visited: Vec<bool>,
+    pub game_score: ScoreType,
+}
+
+impl TspState {
+    // At least one city, the start.
+    pub fn new(cities: Vec<(i64, i64)>) -> TspState {
+        assert!(!cities.is_empty(), "a tour needs a city to start from");
+        let mut visited = vec![false; cities.len()];
+        visited[0] = true;
+        TspState {
+            cities: cities.into(),
+            tour: vec![0],
+            visited,
+            game_score: 0,
+        }
+    }
+
+    pub fn with_seed(seed: u64) -> TspState {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let cities = (0..CITY_NUMBER)
+            .map(|_| (rng.gen_range(0..SIZE), rng.gen_range(0..SIZE)))
+            .collect();
+        TspState::new(cities)
+    }
+
+    pub fn distance(&self, from: usize, to: usize) -> ScoreType {
+        let (dy, dx) = (
+            self.cities[from].0 - self.cities[to].0,
+            self.cities[from].1 - self.cities[to].1,
+        );
+        ((dy * dy + dx * dx) as f64).sqrt().round() as ScoreType
+    }
+
+    pub fn current(&self) -> usize {
+        *self.tour.last().unwrap()
+    }
+
+    pub fn tour(&self) -> &[usize] {
+        &self.tour
+    }
+
+    // Length of the legs so far, and of the way back to city 0 once the tour is done.
+    pub fn length(&self) -> ScoreType {
+        let legs: ScoreType = self
+            .tour
+            .windows(2)
+            .map(|leg| self.distance(leg[0], leg[1]))
+            .sum();
+        if self.is_done() && 1 < self.tour.len() {
+            legs + self.distance(self.current(), 0)
+        } else {
+            legs
+        }
+    }
+}
+
+impl SinglePlayerState for TspState {
+    fn is_done(&self) -> bool {
+        self.tour.len() == self.cities.len()
+    }
+
+    fn advance(&mut self, action: Action) {
+        self.game_score += MAX_LEG - self.distance(self.current(), action);
+        self.tour.push(action);
+        self.visited[action] = true;
+        if self.is_done() {
+            self.game_score += MAX_LEG - self.distance(action, 0);
+        }
+    }
+
+    fn legal_actions(&self) -> ActionList {
+        (0..self.cities.len())
+            .filter(|&city| !self.visited[city])
+            .collect()
+    }
+
+    fn score(&self) -> ScoreType {
+        self.game_score
+    }
+}
+
+impl fmt::Display for TspState {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "length:\t{}", self.length())?;
+        let tour: Vec<String> = self.tour.iter().map(usize::to_string).collect();
+        writeln!(f, "tour:\t{}", tour.join(" "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tsp_state() {
+        // A 3-4-5 triangle: 3 + 4 + 5 = 12, from either direction.
+        let cities = vec![(0, 0), (3, 0), (3, 4)];
+        for order in [[1, 2], [2, 1]] {
+            let mut state = TspState::new(cities.clone());
+            assert_eq!(state.legal_actions().len(), 2);
+            for city in order {
+                state.advance(city);
+            }
+            assert!(state.is_done());
+            assert!(state.legal_actions().is_empty());
+            assert_eq!(state.length(), 12);
+            assert_eq!(state.score(), 3 * MAX_LEG - 12);
+        }
+        assert!(TspState::new(vec![(5, 5)]).is_done());
+    }
+
+    #[test]
+    fn test_with_seed() {
+        let mut state = TspState::with_seed(0);
+        assert_eq!(state.legal_actions().len(), CITY_NUMBER - 1);
+        while !state.is_done() {
+            let next = state.legal_actions()[0];
+            state.advance(next);
+        }
+        assert_eq!(state.tour().len(), CITY_NUMBER);
+        assert_eq!(
+            state.score(),
+            MAX_LEG * CITY_NUMBER as ScoreType - state.length()
+        );
+    }
+}
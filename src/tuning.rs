@@ -0,0 +1,277 @@
+#![allow(unused)]
+
+use rand::Rng;
+
+use crate::game::parallel_map;
+
+#[derive(Debug, Clone)]
+pub struct Trial<P> {
+    pub params: P,
+    pub score: f64,
+    pub games: usize,
+}
+
+impl<P: std::fmt::Debug> std::fmt::Display for Trial<P> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{:?}\tscore: {}\tgames: {}",
+            self.params, self.score, self.games
+        )
+    }
+}
+
+pub fn grid<A: Clone, B: Clone>(a: &[A], b: &[B]) -> Vec<(A, B)> {
+    a.iter()
+        .flat_map(|a| b.iter().map(move |b| (a.clone(), b.clone())))
+        .collect()
+}
+
+// Every configuration is played on the same seeds so that they are compared on equal terms.
+fn evaluate_trial<P>(
+    params: P,
+    game_number: usize,
+    evaluate: &impl Fn(&P, u64) -> f64,
+) -> Trial<P> {
+    let score = (0..game_number as u64)
+        .map(|seed| evaluate(&params, seed))
+        .sum::<f64>()
+        / game_number.max(1) as f64;
+    Trial {
+        params,
+        score,
+        games: game_number,
+    }
+}
+
+fn sort_trials<P>(trials: &mut [Trial<P>]) {
+    trials.sort_by(|a, b| b.score.total_cmp(&a.score));
+}
+
+// Returns all trials, best first.
+pub fn grid_search<P: Clone>(
+    grid: &[P],
+    game_number: usize,
+    evaluate: impl Fn(&P, u64) -> f64,
+) -> Vec<Trial<P>> {
+    let mut trials: Vec<Trial<P>> = grid
+        .iter()
+        .map(|params| evaluate_trial(params.clone(), game_number, &evaluate))
+        .collect();
+    sort_trials(&mut trials);
+    trials
+}
+
+pub fn random_search<P, R: Rng>(
+    rng: &mut R,
+    sample: impl Fn(&mut R) -> P,
+    trial_number: usize,
+    game_number: usize,
+    evaluate: impl Fn(&P, u64) -> f64,
+) -> Vec<Trial<P>> {
+    let mut trials: Vec<Trial<P>> = (0..trial_number)
+        .map(|_| evaluate_trial(sample(rng), game_number, &evaluate))
+        .collect();
+    sort_trials(&mut trials);
+    trials
+}
+
+// Evaluates all candidates on `min_games` games, keeps the better half and doubles the
+// games until one candidate is left.
+pub fn successive_halving<P: Clone>(
+    candidates: &[P],
+    min_games: usize,
+    evaluate: impl Fn(&P, u64) -> f64,
+) -> Option<Trial<P>> {
+    let mut survivors: Vec<P> = candidates.to_vec();
+    let mut game_number = min_games.max(1);
+    loop {
+        let trials = grid_search(&survivors, game_number, &evaluate);
+        if trials.len() <= 1 {
+            return trials.into_iter().next();
+        }
+        survivors = trials
+            .into_iter()
+            .take(survivors.len().div_ceil(2))
+            .map(|trial| trial.params)
+            .collect();
+        game_number *= 2;
+    }
+}
+
+// One dimension of a search space.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Param {
+    Int { low: i64, high: i64 },
+    // 0 or 1.
+    Switch,
+}
+
+impl Param {
+    fn range(&self) -> (i64, i64) {
+        match *self {
+            Param::Int { low, high } => (low, high),
+            Param::Switch => (0, 1),
+        }
+    }
+}
+
+// The mean of `evaluate` over the seeds 0..`game_number`, played on `thread_number` threads.
+fn parallel_mean(
+    params: &[i64],
+    game_number: usize,
+    thread_number: usize,
+    evaluate: &(impl Fn(&[i64], u64) -> f64 + Sync),
+) -> f64 {
+    // Summed in seed order once every game is in, so the mean does not depend on which thread
+    // finishes first.
+    let scores = parallel_map(game_number, thread_number, |seed| {
+        evaluate(params, seed as u64)
+    });
+    scores.iter().sum::<f64>() / game_number.max(1) as f64
+}
+
+// What a black-box tuner may spend: `points` distinct configurations, each played on the seeds
+// 0..`game_number` spread over `thread_number` threads.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Budget {
+    pub points: usize,
+    pub game_number: usize,
+    pub thread_number: usize,
+}
+
+// Black-box tuning by simulated annealing over `space`, from a random point. A neighbour moves
+// one integer up or down by up to a quarter of its range, or flips one switch. Every point is
+// scored once, like `grid_search` does but in parallel, until the budget is spent or the walk
+// stops finding new points. Returns their trials, best first.
+pub fn anneal_params(
+    rng: &mut impl Rng,
+    space: &[Param],
+    budget: Budget,
+    start_temp: f64,
+    end_temp: f64,
+    evaluate: impl Fn(&[i64], u64) -> f64 + Sync,
+) -> Vec<Trial<Vec<i64>>> {
+    let Budget {
+        points,
+        game_number,
+        thread_number,
+    } = budget;
+    let mut trials: Vec<Trial<Vec<i64>>> = vec![];
+    let mut scores: std::collections::HashMap<Vec<i64>, f64> = Default::default();
+    let mut score = |params: &Vec<i64>, trials: &mut Vec<Trial<Vec<i64>>>| {
+        *scores.entry(params.clone()).or_insert_with(|| {
+            let score = parallel_mean(params, game_number, thread_number, &evaluate);
+            trials.push(Trial {
+                params: params.clone(),
+                score,
+                games: game_number,
+            });
+            score
+        })
+    };
+    let mut now: Vec<i64> = space
+        .iter()
+        .map(|param| {
+            let (low, high) = param.range();
+            rng.gen_range(low..=high)
+        })
+        .collect();
+    let mut now_score = score(&now, &mut trials);
+    // Revisits cost nothing, so the loop is also bounded by attempts in tiny spaces.
+    let mut attempts = 0;
+    while trials.len() < points && attempts < 100 * points.max(1) && !space.is_empty() {
+        attempts += 1;
+        let mut next = now.clone();
+        let i = rng.gen_range(0..space.len());
+        let (low, high) = space[i].range();
+        next[i] = match space[i] {
+            Param::Switch => 1 - next[i],
+            Param::Int { .. } => {
+                let step = ((high - low) / 4).max(1);
+                (next[i] + rng.gen_range(-step..=step)).clamp(low, high)
+            }
+        };
+        let next_score = score(&next, &mut trials);
+        let progress = trials.len() as f64 / points.max(1) as f64;
+        let temp = start_temp + (end_temp - start_temp) * progress;
+        if now_score < next_score || ((next_score - now_score) / temp).exp() > rng.gen() {
+            now = next;
+            now_score = next_score;
+        }
+    }
+    sort_trials(&mut trials);
+    trials
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    fn noisy_quadratic(params: &(i32, i32), seed: u64) -> f64 {
+        let noise = StdRng::seed_from_u64(seed).gen_range(-1.0..1.0);
+        -(((params.0 - 3).pow(2) + (params.1 - 5).pow(2)) as f64) + noise
+    }
+
+    #[test]
+    fn test_grid() {
+        assert_eq!(grid(&[1, 2], &['a', 'b']).len(), 4);
+    }
+
+    #[test]
+    fn test_grid_search() {
+        let space = grid(&[1, 2, 3, 4], &[4, 5, 6]);
+        let trials = grid_search(&space, 10, noisy_quadratic);
+        assert_eq!(trials.len(), 12);
+        assert_eq!(trials[0].params, (3, 5));
+    }
+
+    #[test]
+    fn test_random_search() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let trials = random_search(
+            &mut rng,
+            |rng| (rng.gen_range(0..7), rng.gen_range(0..10)),
+            50,
+            5,
+            noisy_quadratic,
+        );
+        assert_eq!(trials.len(), 50);
+        assert!(trials.windows(2).all(|w| w[0].score >= w[1].score));
+    }
+
+    #[test]
+    fn test_anneal_params() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let space = [Param::Int { low: 0, high: 10 }, Param::Switch];
+        let evaluate = |params: &[i64], seed: u64| {
+            let bonus = if params[1] == 1 { 2.0 } else { 0.0 };
+            noisy_quadratic(&(params[0] as i32, 5), seed) + bonus
+        };
+        let budget = Budget {
+            points: 15,
+            game_number: 10,
+            thread_number: 4,
+        };
+        let trials = anneal_params(&mut rng, &space, budget, 5.0, 0.1, evaluate);
+        assert_eq!(trials.len(), 15);
+        assert!(trials.windows(2).all(|w| w[0].score >= w[1].score));
+        assert_eq!(trials[0].params, vec![3, 1]);
+        // A budget larger than the 22 points of the space still ends.
+        let budget = Budget {
+            points: 100,
+            ..budget
+        };
+        let trials = anneal_params(&mut rng, &space, budget, 5.0, 0.1, evaluate);
+        assert!(trials.len() <= 22);
+    }
+
+    #[test]
+    fn test_successive_halving() {
+        let space = grid(&[1, 2, 3, 4, 5], &[3, 4, 5, 6, 7]);
+        let best = successive_halving(&space, 2, noisy_quadratic).unwrap();
+        assert_eq!(best.params, (3, 5));
+        assert!(successive_halving(&[] as &[(i32, i32)], 2, noisy_quadratic).is_none());
+    }
+}
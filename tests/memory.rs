@@ -0,0 +1,50 @@
+// The counting allocator replaces the global one for the whole binary, so it gets a test
+// binary of its own and a single test: the counters are shared by every test thread.
+use lean_search::mcts::{MctsConfig, Tree, UniformPolicy};
+use lean_search::memory::{measure_peak, CountingAllocator};
+use lean_search::section3::{beam_search_with_nodes, MazeState};
+use lean_search::section5::AlternateMazeState;
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+// Off by at most `factor` either way.
+fn assert_close(estimated: usize, measured: usize, factor: usize) {
+    assert!(
+        measured <= estimated * factor && estimated <= measured * factor,
+        "estimated {} bytes, measured {}",
+        estimated,
+        measured
+    );
+}
+
+#[test]
+fn test_estimates_track_measured_peak() {
+    let (_, peak) = measure_peak(|| vec![1u8; 64 << 20].len());
+    assert!(32 << 20 <= peak);
+
+    let state = MazeState::with_seed(0);
+    let ((_, count), beam) = measure_peak(|| beam_search_with_nodes(&state, 100, 20));
+    println!(
+        "Beam Search Bytes:\tmeasured {}\testimated {}",
+        beam, count.peak_bytes
+    );
+    assert_close(count.peak_bytes, beam, 2);
+
+    let config = MctsConfig::default();
+    let (tree, measured) = measure_peak(|| {
+        let mut tree = Tree::new(AlternateMazeState::with_seed(0));
+        tree.expand(tree.root(), &UniformPolicy);
+        for _ in 0..1000 {
+            tree.evaluate(&config, &UniformPolicy);
+        }
+        tree
+    });
+    println!(
+        "MCTS Tree Bytes:\tmeasured {}\testimated {}\tnodes {}",
+        measured,
+        tree.tree_bytes(),
+        tree.tree_size()
+    );
+    assert_close(tree.tree_bytes(), measured, 2);
+}